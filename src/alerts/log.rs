@@ -0,0 +1,108 @@
+//! 提醒日志：每条触发的提醒以 JSON 行追加到 `alerts.log_path`，供 `qtrade alerts-history` 复盘
+//!
+//! 每行是一个完整的 `AlertEvent`；文件只追加不改写，跨多次运行累积。
+
+use std::collections::VecDeque;
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use tracing::warn;
+
+use crate::models::AlertEvent;
+
+/// 追加写入一批提醒（追加模式打开，文件不存在时创建）
+pub fn append(path: &Path, events: &[AlertEvent]) -> Result<()> {
+    if events.is_empty() {
+        return Ok(());
+    }
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("无法打开提醒日志 {}", path.display()))?;
+    // 整批拼好后一次写入，避免半行
+    let mut buf = String::new();
+    for event in events {
+        buf.push_str(&serde_json::to_string(event)?);
+        buf.push('\n');
+    }
+    file.write_all(buf.as_bytes())
+        .with_context(|| format!("写入提醒日志 {} 失败", path.display()))
+}
+
+/// 读取最近 `count` 条提醒（按时间正序）；文件不存在返回 `None`，无法解析的行跳过
+pub fn read_last(path: &Path, count: usize) -> Result<Option<Vec<AlertEvent>>> {
+    let file = match std::fs::File::open(path) {
+        Ok(f) => f,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e).with_context(|| format!("无法读取提醒日志 {}", path.display())),
+    };
+
+    let mut last: VecDeque<AlertEvent> = VecDeque::with_capacity(count.min(1024));
+    for (i, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<AlertEvent>(&line) {
+            Ok(event) => {
+                if last.len() == count {
+                    last.pop_front();
+                }
+                if count > 0 {
+                    last.push_back(event);
+                }
+            }
+            Err(e) => warn!("提醒日志第 {} 行无法解析，已跳过: {}", i + 1, e),
+        }
+    }
+    Ok(Some(last.into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{AlertSeverity, Market, Sentiment, StockCode};
+
+    fn event(message: &str) -> AlertEvent {
+        AlertEvent {
+            code: StockCode::new(Market::HK, "00700"),
+            name: "腾讯控股".to_string(),
+            rule_name: "涨跌幅3%".to_string(),
+            message: message.to_string(),
+            triggered_at: chrono::Local::now(),
+            severity: AlertSeverity::Warning,
+            sentiment: Some(Sentiment::Bullish),
+        }
+    }
+
+    #[test]
+    fn test_append_and_read_last() {
+        let path = std::env::temp_dir().join(format!("qtrade-test-alerts-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        // 文件不存在
+        assert!(read_last(&path, 5).unwrap().is_none());
+
+        // 两次追加累积，损坏行跳过
+        append(&path, &[event("a"), event("b")]).unwrap();
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(b"not json\n")
+            .unwrap();
+        append(&path, &[event("c")]).unwrap();
+
+        let last = read_last(&path, 2).unwrap().unwrap();
+        let messages: Vec<&str> = last.iter().map(|e| e.message.as_str()).collect();
+        assert_eq!(messages, ["b", "c"]);
+        assert_eq!(last[1].severity, AlertSeverity::Warning);
+        assert_eq!(last[1].sentiment, Some(Sentiment::Bullish));
+        assert_eq!(read_last(&path, 10).unwrap().unwrap().len(), 3);
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
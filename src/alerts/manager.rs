@@ -1,16 +1,20 @@
-//! 提醒管理器：穿越检测 + 日内去重 + 通知
+//! 提醒管理器：穿越检测 + 日内去重 + 冷却 + 多渠道通知
 //!
-//! 只在 change_pct 从 < 阈值 穿越到 >= 阈值时触发，
-//! 同股票 + 同规则 + 同方向一天只报一次，不会反复报警。
+//! 只在规则从上一次快照不命中（或命中方向相反）变为本次命中时触发（如 change_pct 从 < 阈值 穿越到 >= 阈值），
+//! 同股票 + 同规则 + 同方向一天只报一次，不会反复报警（`AlertRule::daily_dedup` 为 false 的规则除外）。
+//! 同股票 + 同规则在冷却时间（`alerts.cooldown_secs`）内最多报一次，抑制行情快速来回穿越时的连发。
+//! 触发的提醒追加写入 JSONL 日志（`alerts.log_path`），写入失败只记日志。
 
 use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
 
 use chrono::NaiveDate;
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 
 use crate::models::{AlertEvent, QuoteSnapshot, StockCode};
 
-use super::notify::Notifier;
+use super::notify::NotifyChannel;
 use super::rules::AlertRule;
 
 /// 最大提醒历史记录数
@@ -20,33 +24,52 @@ const MAX_HISTORY: usize = 1000;
 pub struct AlertManager {
     /// 注册的规则
     rules: Vec<Box<dyn AlertRule>>,
-    /// 每只股票上一次的 change_pct（用于穿越检测）
-    prev_change_pct: HashMap<StockCode, f64>,
+    /// 每只股票上一次的行情快照（用于穿越检测）
+    prev_quotes: HashMap<StockCode, QuoteSnapshot>,
     /// 日内去重：(股票, "规则名_方向") → 已触发日期
     fired_today: HashMap<(StockCode, String), NaiveDate>,
-    /// 通知器
-    notifier: Notifier,
+    /// 冷却：(股票, 规则名) → 最近触发时刻
+    last_fired: HashMap<(StockCode, String), Instant>,
+    /// 冷却时间，0 表示不限制
+    cooldown: Duration,
+    /// 通知渠道（每条提醒分发给所有渠道）
+    channels: Vec<Box<dyn NotifyChannel>>,
     /// 提醒历史（循环缓冲区，最多保留 MAX_HISTORY 条）
     history: VecDeque<AlertEvent>,
+    /// 提醒日志文件（JSONL），None 表示不落盘
+    log_path: Option<PathBuf>,
     /// 是否启用
     enabled: bool,
 }
 
 impl AlertManager {
-    pub fn new(notifier: Notifier) -> Self {
+    pub fn new(channels: Vec<Box<dyn NotifyChannel>>) -> Self {
         Self {
             rules: Vec::new(),
-            prev_change_pct: HashMap::new(),
+            prev_quotes: HashMap::new(),
             fired_today: HashMap::new(),
-            notifier,
+            last_fired: HashMap::new(),
+            cooldown: Duration::ZERO,
+            channels,
             history: VecDeque::with_capacity(MAX_HISTORY),
+            log_path: None,
             enabled: true,
         }
     }
 
+    /// 并发发送到所有通知渠道，单个渠道失败只记录日志
+    async fn notify(&self, event: &AlertEvent) {
+        let results = futures::future::join_all(self.channels.iter().map(|ch| ch.send(event))).await;
+        for (ch, result) in self.channels.iter().zip(results) {
+            if let Err(e) = result {
+                warn!(channel = ch.name(), "Notify failed: {:#}", e);
+            }
+        }
+    }
+
     /// 清理过期的 fired_today 条目（非今日日期）
     fn cleanup_old_entries(&mut self) {
-        let today = chrono::Local::now().date_naive();
+        let today = crate::models::app_today();
         self.fired_today.retain(|_, date| *date == today);
     }
 
@@ -55,6 +78,28 @@ impl AlertManager {
         self.rules.push(rule);
     }
 
+    /// 批量添加规则（通常来自 `RuleRegistry::build`）
+    pub fn add_rules(&mut self, rules: Vec<Box<dyn AlertRule>>) {
+        for rule in rules {
+            self.add_rule(rule);
+        }
+    }
+
+    /// 替换全部规则（配置热加载），已触发/冷却记录按规则名保留
+    pub fn replace_rules(&mut self, rules: Vec<Box<dyn AlertRule>>) {
+        self.rules = rules;
+    }
+
+    /// 设置冷却时间（同股票 + 同规则在此时间内最多触发一次）
+    pub fn set_cooldown(&mut self, cooldown: Duration) {
+        self.cooldown = cooldown;
+    }
+
+    /// 设置提醒日志文件（每条触发的提醒追加一行 JSON），None 不落盘
+    pub fn set_log_path(&mut self, path: Option<PathBuf>) {
+        self.log_path = path;
+    }
+
     /// 设置启用/禁用
     pub fn set_enabled(&mut self, enabled: bool) {
         self.enabled = enabled;
@@ -71,20 +116,18 @@ impl AlertManager {
             self.cleanup_old_entries();
         }
 
-        let prev = self.prev_change_pct.insert(quote.code.clone(), quote.change_pct);
+        let prev = self.prev_quotes.insert(quote.code.clone(), quote.clone());
         let mut events = Vec::new();
 
         for rule in &self.rules {
             if let Some((message, severity, sentiment)) = rule.evaluate(quote) {
                 // 穿越检测：首次见到的股票（无 prev）不触发，
-                // 只有上一次 rule 不命中 → 本次命中 才算穿越
-                let was_triggered = match prev {
-                    Some(prev_pct) => {
-                        // 构造一个伪快照用上一次的 change_pct 检测
-                        let mut prev_quote = quote.clone();
-                        prev_quote.change_pct = prev_pct;
-                        rule.evaluate(&prev_quote).is_some()
-                    }
+                // 只有上一次 rule 未按同一方向命中 → 本次命中 才算穿越（一跳从上方价位外跌到下方价位外也算）
+                // 用上一次的完整快照检测（规则可能依赖现价/扩展时段价格，不只是 change_pct）
+                let was_triggered = match &prev {
+                    Some(prev_quote) => rule
+                        .evaluate(prev_quote)
+                        .is_some_and(|(_, _, prev_sentiment)| prev_sentiment == sentiment),
                     None => true, // 首次见到，视为"已在阈值内"，不触发
                 };
 
@@ -94,17 +137,29 @@ impl AlertManager {
                 }
 
                 // 日内去重：同股票 + 同规则 + 同方向，一天只报一次
-                let today = chrono::Local::now().date_naive();
+                let today = crate::models::app_today();
                 let direction = match &sentiment {
                     Some(s) => format!("{}", s),
                     None => "none".to_string(),
                 };
                 let fire_key = (quote.code.clone(), format!("{}_{}", rule.name(), direction));
-                if self.fired_today.get(&fire_key) == Some(&today) {
+                if rule.daily_dedup() && self.fired_today.get(&fire_key) == Some(&today) {
                     debug!("日内已报过 {} / {}，跳过", quote.code, fire_key.1);
                     continue;
                 }
 
+                // 冷却：同股票 + 同规则在冷却时间内已触发过
+                let cooldown_key = (quote.code.clone(), rule.name());
+                let now = Instant::now();
+                if self
+                    .last_fired
+                    .get(&cooldown_key)
+                    .is_some_and(|at| now.duration_since(*at) < self.cooldown)
+                {
+                    debug!("冷却中 {} / {}，跳过", quote.code, cooldown_key.1);
+                    continue;
+                }
+
                 // 穿越确认：上次未命中 → 本次命中 → 触发
                 let event = AlertEvent {
                     code: quote.code.clone(),
@@ -116,13 +171,21 @@ impl AlertManager {
                     sentiment,
                 };
 
-                info!("Alert triggered: {} - {}", event.rule_name, event.message);
+                info!(
+                    code = %event.code,
+                    market = %event.code.market,
+                    rule = %event.rule_name,
+                    "Alert triggered: {} - {}",
+                    event.rule_name,
+                    event.message
+                );
 
                 // 发送通知
-                self.notifier.send(&event).await;
+                self.notify(&event).await;
 
-                // 记录历史 + 标记日内已触发
+                // 记录历史 + 标记日内已触发 + 冷却起点
                 self.fired_today.insert(fire_key, today);
+                self.last_fired.insert(cooldown_key, now);
                 // 循环缓冲区：超过容量时移除最旧的
                 if self.history.len() >= MAX_HISTORY {
                     self.history.pop_front();
@@ -132,6 +195,12 @@ impl AlertManager {
             }
         }
 
+        if let Some(path) = &self.log_path {
+            if let Err(e) = super::log::append(path, &events) {
+                warn!("Alert log write failed: {:#}", e);
+            }
+        }
+
         events
     }
 
@@ -142,8 +211,123 @@ impl AlertManager {
 
     /// 移除指定股票的所有数据（watchlist 变更时调用）
     pub fn remove_stock(&mut self, code: &StockCode) {
-        self.prev_change_pct.remove(code);
-        // 同时清理 fired_today 中该股票的所有条目
+        self.prev_quotes.remove(code);
+        // 同时清理 fired_today / 冷却中该股票的所有条目
         self.fired_today.retain(|(c, _), _| c != code);
+        self.last_fired.retain(|(c, _), _| c != code);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alerts::rules::PriceLevelRule;
+    use crate::models::{AlertSeverity, Market, Sentiment};
+
+    /// 现价 >= 10 命中，不做日内去重（只靠穿越 + 冷却）
+    struct AboveTen;
+
+    impl AlertRule for AboveTen {
+        fn name(&self) -> String {
+            "above10".to_string()
+        }
+
+        fn evaluate(&self, quote: &QuoteSnapshot) -> Option<(String, AlertSeverity, Option<Sentiment>)> {
+            (quote.last_price >= 10.0).then(|| ("above".to_string(), AlertSeverity::Warning, None))
+        }
+
+        fn daily_dedup(&self) -> bool {
+            false
+        }
+    }
+
+    fn quote(price: f64) -> QuoteSnapshot {
+        let mut q = QuoteSnapshot::empty(StockCode::new(Market::HK, "00700"), "腾讯控股".to_string());
+        q.last_price = price;
+        q
+    }
+
+    /// 依次喂入价格，返回触发的提醒总数
+    async fn fired_count(manager: &mut AlertManager, prices: &[f64]) -> usize {
+        let mut count = 0;
+        for price in prices {
+            count += manager.evaluate(&quote(*price)).await.len();
+        }
+        count
+    }
+
+    #[tokio::test]
+    async fn test_cooldown_suppresses_repeat_within_window() {
+        // 两次上穿 10：冷却期内第二次被抑制
+        let mut manager = AlertManager::new(Vec::new());
+        manager.add_rule(Box::new(AboveTen));
+        manager.set_cooldown(Duration::from_secs(300));
+        assert_eq!(fired_count(&mut manager, &[9.0, 11.0, 9.0, 11.0]).await, 1);
+        assert_eq!(manager.recent_history(10).len(), 1);
+
+        // 无冷却时两次穿越都触发
+        let mut manager = AlertManager::new(Vec::new());
+        manager.add_rule(Box::new(AboveTen));
+        assert_eq!(fired_count(&mut manager, &[9.0, 11.0, 9.0, 11.0]).await, 2);
+    }
+
+    fn price_level_manager() -> AlertManager {
+        let mut manager = AlertManager::new(Vec::new());
+        manager.add_rule(Box::new(PriceLevelRule::new(
+            StockCode::new(Market::HK, "00700"),
+            Some(400.0),
+            Some(350.0),
+        )));
+        manager
+    }
+
+    #[tokio::test]
+    async fn test_price_level_fires_once_per_cross_and_rearms() {
+        let mut manager = price_level_manager();
+        // 上穿 400 触发一次，停留在上方不重复
+        assert_eq!(fired_count(&mut manager, &[390.0, 401.0, 405.0, 410.0]).await, 1);
+        // 回到价位内再上穿：同日再次触发（不受日内去重限制）
+        assert_eq!(fired_count(&mut manager, &[395.0, 402.0]).await, 1);
+        // 下穿 350 触发，停留在下方不重复
+        assert_eq!(fired_count(&mut manager, &[360.0, 349.0, 340.0]).await, 1);
+
+        let sentiments: Vec<_> = manager.recent_history(10).iter().map(|e| e.sentiment).collect();
+        assert_eq!(
+            sentiments,
+            [
+                Some(Sentiment::Bearish),
+                Some(Sentiment::Bullish),
+                Some(Sentiment::Bullish)
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_price_level_single_tick_jump_across_both_levels() {
+        // 一跳从 400 上方直接跌到 350 下方：下穿提醒照常触发，反向跳回同理
+        let mut manager = price_level_manager();
+        assert_eq!(fired_count(&mut manager, &[390.0, 401.0]).await, 1);
+        let events = manager.evaluate(&quote(340.0)).await;
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].sentiment, Some(Sentiment::Bearish));
+        let events = manager.evaluate(&quote(410.0)).await;
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].sentiment, Some(Sentiment::Bullish));
+    }
+
+    #[tokio::test]
+    async fn test_price_levels_on_same_code_are_independent() {
+        // 同一股票两条上穿价位：名称不同，各自触发，不共用冷却
+        let code = StockCode::new(Market::HK, "00700");
+        let first = PriceLevelRule::new(code.clone(), Some(400.0), None);
+        let second = PriceLevelRule::new(code, Some(420.0), None);
+        assert_ne!(first.name(), second.name());
+
+        let mut manager = AlertManager::new(Vec::new());
+        manager.add_rule(Box::new(first));
+        manager.add_rule(Box::new(second));
+        manager.set_cooldown(Duration::from_secs(300));
+        assert_eq!(fired_count(&mut manager, &[390.0, 405.0]).await, 1);
+        assert_eq!(fired_count(&mut manager, &[425.0]).await, 1);
     }
 }
@@ -1,3 +1,4 @@
+pub mod log;
 pub mod manager;
 pub mod notify;
 pub mod rules;
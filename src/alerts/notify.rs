@@ -1,39 +1,87 @@
-//! 通知渠道：终端输出 + Webhook
+//! 通知渠道：终端/系统通知 + Webhook / Telegram / 邮件
+//!
+//! 每个渠道实现 `NotifyChannel`，`AlertManager` 把提醒并发分发给所有渠道，
+//! 单个渠道失败只记日志，不影响其他渠道。
 
-use tracing::{error, info};
+use anyhow::{Context, Result};
+use futures::future::BoxFuture;
+use lettre::message::header::ContentType;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use tracing::info;
 
+use crate::config::{AlertsConfig, NotifyChannelConfig};
 use crate::models::{AlertEvent, AlertSeverity};
 
-/// 通知器
-pub struct Notifier {
-    /// Webhook URL
-    webhook_url: Option<String>,
-    /// HTTP 客户端
-    http_client: reqwest::Client,
+/// 通知渠道
+pub trait NotifyChannel: Send + Sync {
+    /// 渠道名（用于日志）
+    fn name(&self) -> &str;
+
+    /// 发送一条提醒
+    fn send<'a>(&'a self, event: &'a AlertEvent) -> BoxFuture<'a, Result<()>>;
 }
 
-impl Notifier {
-    pub fn new(webhook_url: Option<String>) -> Self {
-        Self {
-            webhook_url,
-            http_client: reqwest::Client::new(),
-        }
+/// 按配置创建所有通知渠道：终端/系统通知总是启用，`webhook_url` 与 `channels` 依次追加
+pub fn build_channels(config: &AlertsConfig) -> Result<Vec<Box<dyn NotifyChannel>>> {
+    let http_client = reqwest::Client::new();
+    let mut channels: Vec<Box<dyn NotifyChannel>> = vec![Box::new(DesktopChannel)];
+
+    if let Some(url) = &config.webhook_url {
+        channels.push(Box::new(WebhookChannel::new(url.clone(), http_client.clone())));
     }
+    for ch in &config.channels {
+        let channel: Box<dyn NotifyChannel> = match ch.kind.as_str() {
+            "webhook" => Box::new(WebhookChannel::new(
+                ch.url.clone().context("webhook 渠道缺少 url")?,
+                http_client.clone(),
+            )),
+            "telegram" => Box::new(TelegramChannel::new(
+                ch.bot_token.clone().context("telegram 渠道缺少 bot_token")?,
+                ch.chat_id.clone().context("telegram 渠道缺少 chat_id")?,
+                http_client.clone(),
+            )),
+            "email" => Box::new(SmtpEmailChannel::new(ch)?),
+            other => anyhow::bail!("未知通知渠道类型 {:?}", other),
+        };
+        info!("Notify channel enabled: {}", channel.name());
+        channels.push(channel);
+    }
+    Ok(channels)
+}
 
-    /// 发送通知
-    pub async fn send(&self, event: &AlertEvent) {
-        // 终端通知（总是启用）
-        self.send_terminal(event);
+/// 提醒级别中文名
+pub fn severity_text(severity: AlertSeverity) -> &'static str {
+    match severity {
+        AlertSeverity::Info => "信息",
+        AlertSeverity::Warning => "警告",
+        AlertSeverity::Critical => "紧急",
+    }
+}
 
-        // macOS 系统通知
-        self.send_macos_notification(event);
+fn event_time(event: &AlertEvent) -> String {
+    crate::models::to_app_time(&event.triggered_at)
+        .format("%Y-%m-%d %H:%M:%S")
+        .to_string()
+}
 
-        // Webhook 通知
-        if let Some(url) = &self.webhook_url {
-            self.send_webhook(url, event).await;
-        }
-    }
+/// 纯文本消息（飞书 / Telegram / 邮件正文共用）
+fn plain_text(event: &AlertEvent) -> String {
+    format!(
+        "[{}] {} | {} | {}\n时间: {}",
+        severity_text(event.severity),
+        event.code,
+        event.rule_name,
+        event.message,
+        event_time(event)
+    )
+}
+
+/// 终端输出 + macOS 系统通知
+pub struct DesktopChannel;
 
+impl DesktopChannel {
     /// 终端输出通知
     fn send_terminal(&self, event: &AlertEvent) {
         let severity_icon = match event.severity {
@@ -67,54 +115,270 @@ impl Notifier {
 
         let _ = std::process::Command::new("osascript").args(["-e", &script]).spawn();
     }
+}
 
-    /// Webhook 通知（支持飞书/Slack 格式）
-    async fn send_webhook(&self, url: &str, event: &AlertEvent) {
-        let severity_text = match event.severity {
-            AlertSeverity::Info => "信息",
-            AlertSeverity::Warning => "警告",
-            AlertSeverity::Critical => "紧急",
-        };
+impl NotifyChannel for DesktopChannel {
+    fn name(&self) -> &str {
+        "desktop"
+    }
 
-        // 通用 JSON payload（兼容飞书和 Slack）
-        let payload = if url.contains("feishu") || url.contains("lark") {
+    fn send<'a>(&'a self, event: &'a AlertEvent) -> BoxFuture<'a, Result<()>> {
+        self.send_terminal(event);
+        self.send_macos_notification(event);
+        Box::pin(async { Ok(()) })
+    }
+}
+
+/// Webhook 通知（支持飞书/Slack 格式）
+pub struct WebhookChannel {
+    url: String,
+    http_client: reqwest::Client,
+}
+
+impl WebhookChannel {
+    pub fn new(url: String, http_client: reqwest::Client) -> Self {
+        Self { url, http_client }
+    }
+
+    fn payload(&self, event: &AlertEvent) -> serde_json::Value {
+        if self.url.contains("feishu") || self.url.contains("lark") {
             // 飞书格式
             serde_json::json!({
                 "msg_type": "text",
-                "content": {
-                    "text": format!(
-                        "[{}] {} | {} | {}\n时间: {}",
-                        severity_text,
-                        event.code,
-                        event.rule_name,
-                        event.message,
-                        event.triggered_at.format("%Y-%m-%d %H:%M:%S")
-                    )
-                }
+                "content": { "text": plain_text(event) }
             })
         } else {
             // Slack 格式
             serde_json::json!({
                 "text": format!(
                     "*[{}]* `{}` | {} | {}\n_时间: {}_",
-                    severity_text,
+                    severity_text(event.severity),
                     event.code,
                     event.rule_name,
                     event.message,
-                    event.triggered_at.format("%Y-%m-%d %H:%M:%S")
+                    event_time(event)
                 )
             })
-        };
+        }
+    }
+}
+
+impl NotifyChannel for WebhookChannel {
+    fn name(&self) -> &str {
+        "webhook"
+    }
+
+    fn send<'a>(&'a self, event: &'a AlertEvent) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            // URL 可能含 token（飞书/Slack webhook），错误中去掉 URL 以免写进日志
+            let resp = self
+                .http_client
+                .post(&self.url)
+                .json(&self.payload(event))
+                .send()
+                .await
+                .map_err(|e| e.without_url())?;
+            if !resp.status().is_success() {
+                anyhow::bail!("HTTP {}", resp.status());
+            }
+            Ok(())
+        })
+    }
+}
+
+/// Telegram Bot 通知（Bot API sendMessage）
+pub struct TelegramChannel {
+    bot_token: String,
+    chat_id: String,
+    http_client: reqwest::Client,
+}
 
-        match self.http_client.post(url).json(&payload).send().await {
-            Ok(resp) => {
-                if !resp.status().is_success() {
-                    error!("Webhook failed: HTTP {}", resp.status());
-                }
+impl TelegramChannel {
+    pub fn new(bot_token: String, chat_id: String, http_client: reqwest::Client) -> Self {
+        Self {
+            bot_token,
+            chat_id,
+            http_client,
+        }
+    }
+
+    fn payload(&self, event: &AlertEvent) -> serde_json::Value {
+        serde_json::json!({
+            "chat_id": self.chat_id,
+            "text": plain_text(event),
+        })
+    }
+}
+
+impl NotifyChannel for TelegramChannel {
+    fn name(&self) -> &str {
+        "telegram"
+    }
+
+    fn send<'a>(&'a self, event: &'a AlertEvent) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            // bot token 在 URL 路径里，reqwest 错误会带出 URL，必须去掉
+            let url = format!("https://api.telegram.org/bot{}/sendMessage", self.bot_token);
+            let resp = self
+                .http_client
+                .post(&url)
+                .json(&self.payload(event))
+                .send()
+                .await
+                .map_err(|e| e.without_url())?;
+            if !resp.status().is_success() {
+                // 错误原因在响应体 description 字段（如 "chat not found"）
+                let status = resp.status();
+                let body: serde_json::Value = resp.json().await.unwrap_or_default();
+                anyhow::bail!(
+                    "HTTP {}: {}",
+                    status,
+                    body.get("description").and_then(|v| v.as_str()).unwrap_or("")
+                );
             }
-            Err(e) => {
-                error!("Webhook error: {}", e);
+            Ok(())
+        })
+    }
+}
+
+/// SMTP 邮件通知（端口 465 为隐式 TLS，其余端口走 STARTTLS）
+pub struct SmtpEmailChannel {
+    mailer: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+    to: Vec<Mailbox>,
+}
+
+impl SmtpEmailChannel {
+    pub fn new(config: &NotifyChannelConfig) -> Result<Self> {
+        let host = config.smtp_host.as_deref().context("email 渠道缺少 smtp_host")?;
+        let port = config.smtp_port;
+        let builder = if port == 465 {
+            AsyncSmtpTransport::<Tokio1Executor>::relay(host)
+        } else {
+            AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host)
+        }
+        .with_context(|| format!("SMTP 服务器 {} 无效", host))?
+        .port(port);
+        let builder = match (&config.username, &config.password) {
+            (Some(user), Some(pass)) => builder.credentials(Credentials::new(user.clone(), pass.clone())),
+            _ => builder,
+        };
+
+        let from = config.from.as_deref().context("email 渠道缺少 from")?;
+        Ok(Self {
+            mailer: builder.build(),
+            from: from.parse().with_context(|| format!("发件人地址 {:?} 无效", from))?,
+            to: config
+                .to
+                .iter()
+                .map(|addr| addr.parse().with_context(|| format!("收件人地址 {:?} 无效", addr)))
+                .collect::<Result<_>>()?,
+        })
+    }
+}
+
+impl NotifyChannel for SmtpEmailChannel {
+    fn name(&self) -> &str {
+        "email"
+    }
+
+    fn send<'a>(&'a self, event: &'a AlertEvent) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let mut builder = Message::builder()
+                .from(self.from.clone())
+                .subject(format!("qtrade - {} {}", event.rule_name, event.code))
+                .header(ContentType::TEXT_PLAIN);
+            for to in &self.to {
+                builder = builder.to(to.clone());
             }
+            let email = builder.body(plain_text(event))?;
+            self.mailer.send(email).await?;
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Market, StockCode};
+
+    fn event() -> AlertEvent {
+        AlertEvent {
+            code: StockCode::new(Market::HK, "00700"),
+            name: "腾讯控股".to_string(),
+            rule_name: "涨跌幅3%".to_string(),
+            message: "涨幅 3.2%".to_string(),
+            triggered_at: chrono::Local::now(),
+            severity: AlertSeverity::Warning,
+            sentiment: None,
         }
     }
+
+    fn alerts_config(toml_str: &str) -> AlertsConfig {
+        toml::from_str(toml_str).unwrap()
+    }
+
+    fn channel_names(config: &AlertsConfig) -> Vec<String> {
+        build_channels(config)
+            .unwrap()
+            .iter()
+            .map(|ch| ch.name().to_string())
+            .collect()
+    }
+
+    fn build_error(config: &AlertsConfig) -> String {
+        match build_channels(config) {
+            Ok(_) => panic!("expected build_channels to fail"),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_build_channels() {
+        // 终端/系统通知总是启用，webhook_url 与 channels 依次追加
+        assert_eq!(channel_names(&AlertsConfig::default()), ["desktop"]);
+        let config = alerts_config(
+            r#"
+            webhook_url = "https://open.feishu.cn/open-apis/bot/v2/hook/x"
+            [[channels]]
+            type = "telegram"
+            bot_token = "123:abc"
+            chat_id = "42"
+            "#,
+        );
+        assert_eq!(channel_names(&config), ["desktop", "webhook", "telegram"]);
+
+        // 未知类型与缺少必填字段报错
+        let unknown = alerts_config("[[channels]]\ntype = \"sms\"");
+        assert!(build_error(&unknown).contains("未知通知渠道类型"));
+        let no_url = alerts_config("[[channels]]\ntype = \"webhook\"");
+        assert!(build_error(&no_url).contains("缺少 url"));
+        let no_chat = alerts_config("[[channels]]\ntype = \"telegram\"\nbot_token = \"123:abc\"");
+        assert!(build_error(&no_chat).contains("缺少 chat_id"));
+    }
+
+    #[test]
+    fn test_webhook_payload_format() {
+        let client = reqwest::Client::new();
+        let feishu = WebhookChannel::new("https://open.feishu.cn/hook/x".to_string(), client.clone());
+        let payload = feishu.payload(&event());
+        assert_eq!(payload["msg_type"], "text");
+        let text = payload["content"]["text"].as_str().unwrap();
+        assert!(text.starts_with("[警告] HK.00700 | 涨跌幅3% | 涨幅 3.2%"));
+
+        let slack = WebhookChannel::new("https://hooks.slack.com/services/x".to_string(), client);
+        let payload = slack.payload(&event());
+        assert!(payload.get("msg_type").is_none());
+        assert!(payload["text"].as_str().unwrap().starts_with("*[警告]* `HK.00700`"));
+    }
+
+    #[test]
+    fn test_telegram_payload_has_no_token() {
+        let channel = TelegramChannel::new("123:secret".to_string(), "42".to_string(), reqwest::Client::new());
+        let payload = channel.payload(&event());
+        assert_eq!(payload["chat_id"], "42");
+        assert!(payload["text"].as_str().unwrap().contains("涨幅 3.2%"));
+        assert!(!payload.to_string().contains("secret"));
+    }
 }
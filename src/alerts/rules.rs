@@ -1,25 +1,145 @@
 //! 提醒规则定义
+//!
+//! 所有规则统一由 `RuleRegistry` 根据配置构造。自定义规则实现 `AlertRule`，
+//! 再通过 `RuleRegistry::register` 注册一个工厂函数即可，无需修改 `cmd_start`。
 
-use crate::models::{AlertSeverity, QuoteSnapshot, Sentiment};
+use std::collections::HashMap;
 
-/// 提醒规则 trait
+use crate::config::AlertsConfig;
+use crate::data::parser::parse_stock_code;
+use crate::models::{AlertSeverity, QuoteSnapshot, Sentiment, StockCode};
+
+/// 提醒规则 trait（公开扩展点）
+///
+/// `AlertManager` 对每个行情快照调用 `evaluate`，返回 `Some` 表示条件满足。
+/// 管理器负责穿越检测与日内去重：同股票 + 同规则名 + 同方向一天只报一次，
+/// 因此规则本身无需维护状态，`name` 需在所有规则中唯一且稳定。
 pub trait AlertRule: Send + Sync {
-    /// 规则名称
+    /// 规则名称（去重键的一部分）
     fn name(&self) -> String;
 
     /// 评估规则，返回 (消息, 级别, 情绪方向)
     fn evaluate(&self, quote: &QuoteSnapshot) -> Option<(String, AlertSeverity, Option<Sentiment>)>;
+
+    /// 是否日内去重（默认是）；返回 false 时只按穿越触发，回到另一侧后再次穿越可重复提醒
+    fn daily_dedup(&self) -> bool {
+        true
+    }
+}
+
+/// 规则工厂：根据提醒配置构造一组规则
+pub type RuleFactory = Box<dyn Fn(&AlertsConfig) -> Vec<Box<dyn AlertRule>> + Send + Sync>;
+
+/// 提醒规则注册表：集中所有规则的构造
+///
+/// ```ignore
+/// let rules = RuleRegistry::with_builtin()
+///     .register(|_| vec![Box::new(MyRule::new()) as Box<dyn AlertRule>])
+///     .build(&config.alerts);
+/// ```
+pub struct RuleRegistry {
+    factories: Vec<RuleFactory>,
+}
+
+impl RuleRegistry {
+    /// 空注册表（不含内置规则）
+    pub fn new() -> Self {
+        Self { factories: Vec::new() }
+    }
+
+    /// 含内置规则的注册表
+    pub fn with_builtin() -> Self {
+        Self::new().register(builtin_rules)
+    }
+
+    /// 注册规则工厂
+    pub fn register<F>(mut self, factory: F) -> Self
+    where
+        F: Fn(&AlertsConfig) -> Vec<Box<dyn AlertRule>> + Send + Sync + 'static,
+    {
+        self.factories.push(Box::new(factory));
+        self
+    }
+
+    /// 按注册顺序构造所有规则；提醒未启用时返回空
+    pub fn build(&self, config: &AlertsConfig) -> Vec<Box<dyn AlertRule>> {
+        if !config.enabled {
+            return Vec::new();
+        }
+        self.factories.iter().flat_map(|f| f(config)).collect()
+    }
+}
+
+impl Default for RuleRegistry {
+    fn default() -> Self {
+        Self::with_builtin()
+    }
+}
+
+/// 内置规则：每个有效涨跌幅阈值一条 `ChangeThresholdRule`，每个价位配置一条 `PriceLevelRule`
+///
+/// `alerts.thresholds` 的单股阈值替代第一档阈值，其余档位按同一比例放大。
+pub fn builtin_rules(config: &AlertsConfig) -> Vec<Box<dyn AlertRule>> {
+    let thresholds = config.effective_thresholds();
+    let base = thresholds.first().copied().unwrap_or(0.0);
+    // 代码无效的条目已由 `AlertsConfig::validate` 拒绝，这里忽略
+    let stock_thresholds: Vec<(StockCode, f64)> = config
+        .thresholds
+        .iter()
+        .filter_map(|(code, pct)| Some((parse_stock_code(code)?, *pct)))
+        .collect();
+    let change_rules = thresholds.into_iter().map(move |threshold| {
+        let overrides = stock_thresholds
+            .iter()
+            .filter(|_| base > 0.0)
+            .map(|(code, pct)| (code.clone(), pct * threshold / base))
+            .collect();
+        Box::new(
+            ChangeThresholdRule::new(threshold)
+                .with_overrides(overrides)
+                .with_extended_hours(config.include_extended_hours),
+        ) as Box<dyn AlertRule>
+    });
+    // 代码无效的条目已由 `AlertsConfig::validate` 拒绝，这里忽略
+    let level_rules = config.price_levels.iter().filter_map(|level| {
+        let code = parse_stock_code(&level.code)?;
+        Some(Box::new(PriceLevelRule::new(code, level.above, level.below)) as Box<dyn AlertRule>)
+    });
+    change_rules.chain(level_rules).collect()
 }
 
 /// 涨跌幅阈值规则
 pub struct ChangeThresholdRule {
     /// 涨跌幅阈值 (%)
     pub threshold: f64,
+    /// 单只股票的阈值 (%)，优先于 `threshold`
+    pub overrides: HashMap<StockCode, f64>,
+    /// 美股非盘中时段改用扩展时段价格的涨跌幅（`alerts.include_extended_hours`）
+    pub include_extended_hours: bool,
 }
 
 impl ChangeThresholdRule {
     pub fn new(threshold: f64) -> Self {
-        Self { threshold }
+        Self {
+            threshold,
+            overrides: HashMap::new(),
+            include_extended_hours: false,
+        }
+    }
+
+    pub fn with_overrides(mut self, overrides: HashMap<StockCode, f64>) -> Self {
+        self.overrides = overrides;
+        self
+    }
+
+    /// 该股票生效的阈值：单股阈值优先，否则为默认阈值
+    fn threshold_for(&self, code: &StockCode) -> f64 {
+        self.overrides.get(code).copied().unwrap_or(self.threshold)
+    }
+
+    pub fn with_extended_hours(mut self, enabled: bool) -> Self {
+        self.include_extended_hours = enabled;
+        self
     }
 }
 
@@ -29,23 +149,31 @@ impl AlertRule for ChangeThresholdRule {
     }
 
     fn evaluate(&self, quote: &QuoteSnapshot) -> Option<(String, AlertSeverity, Option<Sentiment>)> {
-        let abs_change = quote.change_pct.abs();
-        if abs_change >= self.threshold {
-            let direction = if quote.change_pct > 0.0 { "涨" } else { "跌" };
-            let severity = if abs_change >= self.threshold * 2.0 {
+        // 美股盘前/盘后/夜盘：按扩展价相对收盘价评估（时段按行情时间判断）
+        let session = crate::models::us_market_session_at(&quote.timestamp);
+        let (session_label, price, change_pct) = match quote.extended_change(session) {
+            Some((ext, _, pct)) if self.include_extended_hours => (session.extended_label(), ext, pct),
+            _ => ("", quote.last_price, quote.change_pct),
+        };
+
+        let threshold = self.threshold_for(&quote.code);
+        let abs_change = change_pct.abs();
+        if abs_change >= threshold {
+            let direction = if change_pct > 0.0 { "涨" } else { "跌" };
+            let severity = if abs_change >= threshold * 2.0 {
                 AlertSeverity::Critical
             } else {
                 AlertSeverity::Warning
             };
-            let sentiment = if quote.change_pct > 0.0 {
+            let sentiment = if change_pct > 0.0 {
                 Sentiment::Bullish
             } else {
                 Sentiment::Bearish
             };
             Some((
                 format!(
-                    "{} {} {:.2}% (现价: {:.2})",
-                    quote.name, direction, abs_change, quote.last_price
+                    "{} {}{} {:.2}% (现价: {:.2})",
+                    quote.name, session_label, direction, abs_change, price
                 ),
                 severity,
                 Some(sentiment),
@@ -56,62 +184,110 @@ impl AlertRule for ChangeThresholdRule {
     }
 }
 
-/// 目标价规则
-pub struct TargetPriceRule {
-    /// 股票代码（用 display_code 匹配）
-    pub stock_code: String,
-    /// 上限价格
-    pub upper: Option<f64>,
-    /// 下限价格
-    pub lower: Option<f64>,
+/// 价位穿越规则：现价上穿 `above` 或下穿 `below` 时提醒（`alerts.price_levels`）
+///
+/// 只在穿越瞬间触发；价格停留在价位之外不重复提醒，回到价位另一侧后再次穿越会重新提醒。
+pub struct PriceLevelRule {
+    pub code: StockCode,
+    /// 上方价位：现价 >= 此值
+    pub above: Option<f64>,
+    /// 下方价位：现价 <= 此值
+    pub below: Option<f64>,
 }
 
-impl TargetPriceRule {
-    pub fn new(stock_code: String, upper: Option<f64>, lower: Option<f64>) -> Self {
-        Self {
-            stock_code,
-            upper,
-            lower,
-        }
+impl PriceLevelRule {
+    pub fn new(code: StockCode, above: Option<f64>, below: Option<f64>) -> Self {
+        Self { code, above, below }
     }
 }
 
-impl AlertRule for TargetPriceRule {
+impl AlertRule for PriceLevelRule {
+    /// 名称含价位，同一股票配置多条价位时各自独立冷却与穿越判断
     fn name(&self) -> String {
-        "目标价提醒".to_string()
+        let mut name = format!("价位{}", self.code);
+        if let Some(above) = self.above {
+            name.push_str(&format!("↑{}", above));
+        }
+        if let Some(below) = self.below {
+            name.push_str(&format!("↓{}", below));
+        }
+        name
     }
 
     fn evaluate(&self, quote: &QuoteSnapshot) -> Option<(String, AlertSeverity, Option<Sentiment>)> {
-        if quote.code.display_code() != self.stock_code {
+        if quote.code != self.code || quote.last_price <= 0.0 {
             return None;
         }
 
-        if let Some(upper) = self.upper {
-            if quote.last_price >= upper {
-                return Some((
-                    format!(
-                        "{} 达到目标上限价 {:.2} (现价: {:.2})",
-                        quote.name, upper, quote.last_price
-                    ),
-                    AlertSeverity::Critical,
-                    Some(Sentiment::Bullish),
-                ));
-            }
+        if let Some(above) = self.above.filter(|above| quote.last_price >= *above) {
+            return Some((
+                format!("{} 突破 {:.2} (现价: {:.2})", quote.name, above, quote.last_price),
+                AlertSeverity::Critical,
+                Some(Sentiment::Bullish),
+            ));
         }
 
-        if let Some(lower) = self.lower {
-            if quote.last_price <= lower {
-                return Some((
-                    format!(
-                        "{} 跌破目标下限价 {:.2} (现价: {:.2})",
-                        quote.name, lower, quote.last_price
-                    ),
-                    AlertSeverity::Critical,
-                    Some(Sentiment::Bearish),
-                ));
-            }
+        if let Some(below) = self.below.filter(|below| quote.last_price <= *below) {
+            return Some((
+                format!("{} 跌破 {:.2} (现价: {:.2})", quote.name, below, quote.last_price),
+                AlertSeverity::Critical,
+                Some(Sentiment::Bearish),
+            ));
         }
 
         None
     }
+
+    fn daily_dedup(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Market;
+
+    fn make_quote(code: &str, change_pct: f64) -> QuoteSnapshot {
+        let mut q = QuoteSnapshot::empty(StockCode::new(Market::HK, code), "Test".to_string());
+        q.last_price = 10.0;
+        q.change_pct = change_pct;
+        q
+    }
+
+    #[test]
+    fn test_per_stock_threshold_override() {
+        let config = AlertsConfig {
+            change_threshold_pct: 1.0,
+            thresholds: HashMap::from([("HK.00700".to_string(), 10.0)]),
+            ..AlertsConfig::default()
+        };
+        let rules = builtin_rules(&config);
+        assert_eq!(rules.len(), 1);
+
+        // 单股阈值 10%：涨 5% 不提醒；默认 1% 的股票涨 5% 提醒
+        assert!(rules[0].evaluate(&make_quote("00700", 5.0)).is_none());
+        assert!(rules[0].evaluate(&make_quote("00700", 10.5)).is_some());
+        assert!(rules[0].evaluate(&make_quote("09988", 5.0)).is_some());
+    }
+
+    #[test]
+    fn test_threshold_override_scales_levels() {
+        // 多级阈值 [2, 4]：单股阈值 6% 替代第一档，第二档按比例变为 12%
+        let config = AlertsConfig {
+            change_thresholds: Some(vec![2.0, 4.0]),
+            thresholds: HashMap::from([("HK.00700".to_string(), 6.0)]),
+            ..AlertsConfig::default()
+        };
+        let rules = builtin_rules(&config);
+        let fired = |pct: f64| {
+            rules
+                .iter()
+                .filter(|r| r.evaluate(&make_quote("00700", pct)).is_some())
+                .count()
+        };
+        assert_eq!(fired(5.0), 0);
+        assert_eq!(fired(7.0), 1);
+        assert_eq!(fired(12.0), 2);
+    }
 }
@@ -0,0 +1,97 @@
+//! 双股对比：相对强弱与归一化价格比（配对交易参考）
+
+use crate::models::QuoteSnapshot;
+
+/// 两只股票的对比结果（A 为对比基准，B 为对比目标）
+#[derive(Debug, Clone, PartialEq)]
+pub struct PairComparison {
+    /// A 涨跌幅 (%)
+    pub change_pct_a: f64,
+    /// B 涨跌幅 (%)
+    pub change_pct_b: f64,
+    /// 相对表现：A 涨跌幅 - B 涨跌幅（百分点）
+    pub relative_pct: f64,
+    /// 当前价格比 A/B
+    pub price_ratio: Option<f64>,
+    /// 归一化价格比：(A/A昨收) / (B/B昨收)，昨收时为 1.0，> 1 表示 A 相对走强
+    pub normalized_ratio: Option<f64>,
+}
+
+impl PairComparison {
+    /// 由两只股票的行情计算对比结果
+    ///
+    /// 涨跌幅按昨收重算（缺昨收时采用行情自带的 change_pct），价格为 0 时比值为 None
+    pub fn from_quotes(a: &QuoteSnapshot, b: &QuoteSnapshot) -> Self {
+        let change_pct_a = change_pct(a);
+        let change_pct_b = change_pct(b);
+
+        let price_ratio = (a.last_price > 0.0 && b.last_price > 0.0).then(|| a.last_price / b.last_price);
+        let normalized_ratio = match (normalized(a), normalized(b)) {
+            (Some(na), Some(nb)) if nb > 0.0 => Some(na / nb),
+            _ => None,
+        };
+
+        Self {
+            change_pct_a,
+            change_pct_b,
+            relative_pct: change_pct_a - change_pct_b,
+            price_ratio,
+            normalized_ratio,
+        }
+    }
+
+    /// 归一化价格比相对昨收的变化 (%)
+    pub fn ratio_change_pct(&self) -> Option<f64> {
+        self.normalized_ratio.map(|r| (r - 1.0) * 100.0)
+    }
+}
+
+/// 现价 / 昨收
+fn normalized(q: &QuoteSnapshot) -> Option<f64> {
+    (q.last_price > 0.0 && q.prev_close > 0.0).then(|| q.last_price / q.prev_close)
+}
+
+fn change_pct(q: &QuoteSnapshot) -> f64 {
+    match normalized(q) {
+        Some(n) => (n - 1.0) * 100.0,
+        None => q.change_pct,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Market, StockCode};
+
+    fn make_quote(code: &str, last: f64, prev_close: f64) -> QuoteSnapshot {
+        let mut q = QuoteSnapshot::empty(StockCode::new(Market::HK, code), code.to_string());
+        q.last_price = last;
+        q.prev_close = prev_close;
+        q
+    }
+
+    #[test]
+    fn test_pair_comparison() {
+        let a = make_quote("00700", 110.0, 100.0); // +10%
+        let b = make_quote("09988", 95.0, 100.0); // -5%
+        let cmp = PairComparison::from_quotes(&a, &b);
+        assert!((cmp.change_pct_a - 10.0).abs() < 1e-9);
+        assert!((cmp.change_pct_b + 5.0).abs() < 1e-9);
+        assert!((cmp.relative_pct - 15.0).abs() < 1e-9);
+        assert!((cmp.price_ratio.unwrap() - 110.0 / 95.0).abs() < 1e-9);
+        assert!((cmp.normalized_ratio.unwrap() - 1.1 / 0.95).abs() < 1e-9);
+        assert!(cmp.ratio_change_pct().unwrap() > 15.0);
+    }
+
+    #[test]
+    fn test_pair_comparison_missing_prev_close() {
+        let mut a = make_quote("00700", 110.0, 0.0);
+        a.change_pct = 2.0;
+        let b = make_quote("09988", 0.0, 100.0);
+        let cmp = PairComparison::from_quotes(&a, &b);
+        assert_eq!(cmp.change_pct_a, 2.0);
+        assert!(cmp.price_ratio.is_none());
+        assert!(cmp.normalized_ratio.is_none());
+        assert!(cmp.ratio_change_pct().is_none());
+    }
+}
@@ -3,14 +3,18 @@
 //! 基于历史日K线数据计算技术指标和信号，支持 JSON 缓存
 
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 
-use tracing::{info, warn};
+use tracing::{debug, info, warn};
 
+use crate::config::AnalysisConfig;
 use crate::models::{DailyKline, StockCode, TechnicalIndicators, TimedSignal, Timeframe};
 
 use super::indicators;
-use super::signals;
+use super::signals::{self, DailySignalParams};
 
 /// 单只股票的缓存数据
 #[derive(serde::Serialize, serde::Deserialize)]
@@ -39,22 +43,45 @@ pub struct DailyAnalysisEngine {
     prev_indicators: HashMap<StockCode, TechnicalIndicators>,
     /// 日线信号
     signals: HashMap<StockCode, Vec<TimedSignal>>,
+    /// 信号参数（RSI、均线交叉、缺口、量能、布林带）
+    params: DailySignalParams,
+    /// 缓存写锁（`kline_cache.lock` 上的排他文件锁，进程退出时自动释放）
+    ///
+    /// 未拿到锁说明另一个 qtrade 实例在写缓存，本实例只读不写
+    cache_lock: OnceLock<File>,
 }
 
 /// 最大保留天数
 const MAX_KLINE_DAYS: usize = 150;
 
+/// ADV 统计的交易日数
+const ADV_DAYS: usize = 20;
+
 impl DailyAnalysisEngine {
-    pub fn new() -> Self {
+    /// 按分析配置创建引擎（需在加载缓存/更新K线前确定参数）
+    pub fn new(config: &AnalysisConfig) -> Self {
         Self {
             klines: HashMap::new(),
             last_fetched: HashMap::new(),
             indicators: HashMap::new(),
             prev_indicators: HashMap::new(),
             signals: HashMap::new(),
+            params: DailySignalParams::from_config(config),
+            cache_lock: OnceLock::new(),
         }
     }
 
+    /// 应用新配置（配置热加载）：信号参数变化时按缓存的K线重算全部指标与信号，返回是否有变化
+    pub fn reconfigure(&mut self, config: &AnalysisConfig) -> bool {
+        let params = DailySignalParams::from_config(config);
+        if params == self.params {
+            return false;
+        }
+        self.params = params;
+        self.recompute_all();
+        true
+    }
+
     /// 缓存文件路径
     pub fn cache_path() -> PathBuf {
         let home = std::env::var("HOME").unwrap_or_else(|_| "/tmp".to_string());
@@ -63,12 +90,18 @@ impl DailyAnalysisEngine {
 
     /// 从缓存文件加载K线数据
     pub fn load_cache(&mut self) {
-        let path = Self::cache_path();
+        self.load_cache_from(&Self::cache_path());
+    }
+
+    /// 从指定缓存文件加载K线数据
+    ///
+    /// 写入是原子替换，读到的总是某次完整保存的内容；解析失败时保留现有数据
+    fn load_cache_from(&mut self, path: &Path) {
         if !path.exists() {
             return;
         }
 
-        let content = match std::fs::read_to_string(&path) {
+        let content = match std::fs::read_to_string(path) {
             Ok(c) => c,
             Err(e) => {
                 warn!("Failed to read kline cache: {}", e);
@@ -104,8 +137,11 @@ impl DailyAnalysisEngine {
 
     /// 写入缓存文件
     pub fn save_cache(&self) {
-        let path = Self::cache_path();
+        self.save_cache_to(&Self::cache_path());
+    }
 
+    /// 写入指定缓存文件：先写同目录临时文件再 rename，中途中断不会留下半截文件
+    fn save_cache_to(&self, path: &Path) {
         // 确保目录存在
         if let Some(dir) = path.parent() {
             if let Err(e) = std::fs::create_dir_all(dir) {
@@ -114,6 +150,11 @@ impl DailyAnalysisEngine {
             }
         }
 
+        if !self.hold_cache_lock(path) {
+            debug!("Kline cache locked by another instance, skipping save");
+            return;
+        }
+
         let mut stocks = HashMap::new();
         for (code, klines) in &self.klines {
             let last_fetched = self.last_fetched.get(code).cloned().unwrap_or_default();
@@ -130,7 +171,7 @@ impl DailyAnalysisEngine {
 
         match serde_json::to_string(&cache) {
             Ok(json) => {
-                if let Err(e) = std::fs::write(&path, json) {
+                if let Err(e) = write_atomic(path, json.as_bytes()) {
                     warn!("Failed to write kline cache: {}", e);
                 } else {
                     info!("Saved kline cache to {}", path.display());
@@ -142,6 +183,43 @@ impl DailyAnalysisEngine {
         }
     }
 
+    /// 持有缓存写锁：已持有直接返回 true，否则尝试获取（另一实例退出后可接手）
+    fn hold_cache_lock(&self, cache_path: &Path) -> bool {
+        if self.cache_lock.get().is_some() {
+            return true;
+        }
+        let lock_path = cache_path.with_extension("lock");
+        let file = match File::options()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&lock_path)
+        {
+            Ok(f) => f,
+            Err(e) => {
+                warn!("Failed to open kline cache lock {}: {}", lock_path.display(), e);
+                return false;
+            }
+        };
+        match file.try_lock() {
+            Ok(()) => {
+                let _ = self.cache_lock.set(file);
+                true
+            }
+            Err(std::fs::TryLockError::WouldBlock) => {
+                warn!(
+                    "Kline cache is locked by another qtrade instance ({}), not saving",
+                    lock_path.display()
+                );
+                false
+            }
+            Err(std::fs::TryLockError::Error(e)) => {
+                warn!("Failed to lock kline cache: {}", e);
+                false
+            }
+        }
+    }
+
     /// 合并新K线数据到已有缓存（按日期去重，保留最近 MAX_KLINE_DAYS 天）
     pub fn merge_update(&mut self, new_data: HashMap<StockCode, Vec<DailyKline>>) {
         for (code, new_klines) in new_data {
@@ -188,22 +266,35 @@ impl DailyAnalysisEngine {
             // 用倒数第二根K线的数据计算 prev_indicators
             if close_prices.len() >= 2 {
                 let prev_prices = &close_prices[..close_prices.len() - 1];
-                let prev_ti = Self::compute_indicators(prev_prices);
+                let prev_ti = self.compute_indicators(prev_prices);
                 self.prev_indicators.insert(code.clone(), prev_ti);
             }
 
             // 用全部K线计算当前指标
-            let ti = Self::compute_indicators(&close_prices);
+            let ti = self.compute_indicators(&close_prices);
 
             // 检测信号
             let prev = self.prev_indicators.get(code);
-            let mut raw_signals = signals::detect_signals(&ti, prev, &close_prices, &volumes);
+            let mut raw_signals = signals::detect_signals(&ti, prev, &close_prices, &volumes, &self.params);
 
             // MS-MACD：扫描完整 DIF/DEA 序列，找拐点首日
             let macd_result = indicators::macd(&close_prices, 12, 26, 9);
             let ms_macd_signals = signals::detect_ms_macd_from_series(&macd_result.dif, &macd_result.dea, 5);
             raw_signals.extend(ms_macd_signals);
 
+            // 日线量能：最新K线成交量 vs ADV（与 tick 引擎同一 ADV 口径）
+            if let (Some(last), Some(adv)) = (volumes.last(), adv_of(klines)) {
+                raw_signals.extend(signals::detect_daily_volume(
+                    *last,
+                    adv,
+                    self.params.volume_surge_ratio,
+                    self.params.volume_dryup_ratio,
+                ));
+            }
+
+            // 跳空缺口：信号带缺口日期，放在最后，按数量裁剪时优先保留
+            raw_signals.extend(signals::detect_gap(klines, self.params.gap_threshold_pct));
+
             let timed_signals: Vec<TimedSignal> = raw_signals
                 .into_iter()
                 .map(|signal| TimedSignal {
@@ -260,6 +351,11 @@ impl DailyAnalysisEngine {
         self.last_fetched.get(code).map(|s| s.as_str())
     }
 
+    /// 获取某只股票的日K线（按日期升序）
+    pub fn get_klines(&self, code: &StockCode) -> Option<&[DailyKline]> {
+        self.klines.get(code).map(Vec::as_slice)
+    }
+
     /// 获取所有日线指标
     pub fn get_indicators(&self) -> &HashMap<StockCode, TechnicalIndicators> {
         &self.indicators
@@ -270,25 +366,17 @@ impl DailyAnalysisEngine {
         &self.signals
     }
 
-    /// 计算每只股票的日均成交量（ADV, 近 20 个交易日均值）
+    /// 计算每只股票的日均成交量（ADV, 最新一根之前近 20 个交易日均值）
     pub fn compute_adv(&self) -> HashMap<StockCode, f64> {
-        let adv_days = 20;
-        let mut result = HashMap::new();
-        for (code, klines) in &self.klines {
-            if klines.is_empty() {
-                continue;
-            }
-            let n = klines.len().min(adv_days);
-            let recent = &klines[klines.len() - n..];
-            let total_vol: u64 = recent.iter().map(|k| k.volume).sum();
-            let adv = total_vol as f64 / n as f64;
-            result.insert(code.clone(), adv);
-        }
-        result
+        self.klines
+            .iter()
+            .filter_map(|(code, klines)| Some((code.clone(), adv_of(klines)?)))
+            .collect()
     }
 
     /// 计算技术指标（复用 indicators 模块的纯函数）
-    fn compute_indicators(prices: &[f64]) -> TechnicalIndicators {
+    fn compute_indicators(&self, prices: &[f64]) -> TechnicalIndicators {
+        let boll = indicators::bollinger(prices, self.params.boll_period, self.params.boll_multiplier);
         TechnicalIndicators {
             ma5: indicators::sma(prices, 5),
             ma10: indicators::sma(prices, 10),
@@ -311,31 +399,59 @@ impl DailyAnalysisEngine {
             rsi6: indicators::rsi(prices, 6),
             rsi12: indicators::rsi(prices, 12),
             rsi24: indicators::rsi(prices, 24),
+            rsi: indicators::rsi(prices, self.params.rsi_period),
+
+            boll_upper: boll.map(|(upper, _, _)| upper),
+            boll_mid: boll.map(|(_, mid, _)| mid),
+            boll_lower: boll.map(|(_, _, lower)| lower),
         }
     }
 }
 
+/// 日均成交量（最新一根之前的 `ADV_DAYS` 根K线均值），tick 引擎的放量门槛与日线量能信号共用
+///
+/// 不含最新一根（当日）K线，当日放量/缩量不会拉偏自身的比较基准
+fn adv_of(klines: &[DailyKline]) -> Option<f64> {
+    let (_, prior) = klines.split_last()?;
+    if prior.is_empty() {
+        return None;
+    }
+    let recent = &prior[prior.len().saturating_sub(ADV_DAYS)..];
+    let total_vol: u64 = recent.iter().map(|k| k.volume).sum();
+    Some(total_vol as f64 / recent.len() as f64)
+}
+
 /// 解析 "HK.00700" 格式的 key 为 StockCode
 fn parse_stock_key(key: &str) -> Option<StockCode> {
     let mut parts = key.splitn(2, '.');
     let market_str = parts.next()?;
     let code = parts.next()?;
-    let market = match market_str {
-        "HK" => crate::models::Market::HK,
-        "SH" => crate::models::Market::SH,
-        "SZ" => crate::models::Market::SZ,
-        "US" => crate::models::Market::US,
-        "SG" => crate::models::Market::SG,
-        "FX" => crate::models::Market::FX,
-        _ => return None,
-    };
+    let market = crate::models::Market::from_prefix(market_str)?;
     Some(StockCode::new(market, code))
 }
 
+/// 原子写文件：写入同目录的临时文件并 fsync，再 rename 覆盖目标
+fn write_atomic(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    let mut tmp_name = path.as_os_str().to_owned();
+    tmp_name.push(format!(".tmp.{}", std::process::id()));
+    let tmp_path = PathBuf::from(tmp_name);
+
+    let result = (|| {
+        let mut file = File::create(&tmp_path)?;
+        file.write_all(data)?;
+        file.sync_all()?;
+        std::fs::rename(&tmp_path, path)
+    })();
+    if result.is_err() {
+        let _ = std::fs::remove_file(&tmp_path);
+    }
+    result
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::models::Market;
+    use crate::models::{Market, Sentiment, Signal};
 
     fn make_klines(count: usize, base_price: f64) -> Vec<DailyKline> {
         (0..count)
@@ -356,7 +472,7 @@ mod tests {
 
     #[test]
     fn test_daily_engine_basic() {
-        let mut engine = DailyAnalysisEngine::new();
+        let mut engine = DailyAnalysisEngine::new(&AnalysisConfig::default());
 
         let code = StockCode::new(Market::HK, "00700");
         let klines = make_klines(60, 380.0);
@@ -378,7 +494,7 @@ mod tests {
 
     #[test]
     fn test_daily_signals_tagged() {
-        let mut engine = DailyAnalysisEngine::new();
+        let mut engine = DailyAnalysisEngine::new(&AnalysisConfig::default());
 
         let code = StockCode::new(Market::HK, "00700");
         let klines: Vec<DailyKline> = (0..30)
@@ -409,9 +525,175 @@ mod tests {
         }
     }
 
+    /// 按收盘价序列构造日K线（日期连续递增）
+    fn klines_from_closes(closes: &[f64]) -> Vec<DailyKline> {
+        closes
+            .iter()
+            .enumerate()
+            .map(|(i, &price)| DailyKline {
+                open: price,
+                close: price,
+                high: price + 0.5,
+                low: price - 0.5,
+                volume: 1_000_000,
+                turnover: price * 1_000_000.0,
+                date: format!("2025-{:02}-{:02}", (i / 28) + 1, (i % 28) + 1),
+            })
+            .collect()
+    }
+
+    /// 喂入收盘价序列，返回该股票的日线信号
+    fn daily_signals_for(closes: &[f64]) -> Vec<Signal> {
+        let mut engine = DailyAnalysisEngine::new(&AnalysisConfig::default());
+        let code = StockCode::new(Market::HK, "00700");
+        engine.update(HashMap::from([(code.clone(), klines_from_closes(closes))]));
+        engine.get_signals()[&code].iter().map(|s| s.signal.clone()).collect()
+    }
+
+    #[test]
+    fn test_reconfigure_recomputes_signals() {
+        // 开盘 104 相对前收 100 跳空 4%：默认阈值 3% 触发，热加载为 5% 后不再触发
+        let mut engine = DailyAnalysisEngine::new(&AnalysisConfig::default());
+        let code = StockCode::new(Market::HK, "00700");
+        engine.update(HashMap::from([(code.clone(), klines_from_closes(&[100.0, 104.0]))]));
+        let has_gap = |engine: &DailyAnalysisEngine| {
+            engine.get_signals()[&code]
+                .iter()
+                .any(|s| matches!(s.signal, Signal::Gap { .. }))
+        };
+        assert!(has_gap(&engine));
+
+        // 参数未变不重算
+        assert!(!engine.reconfigure(&AnalysisConfig::default()));
+
+        let config = AnalysisConfig {
+            gap_threshold_pct: 5.0,
+            ..AnalysisConfig::default()
+        };
+        assert!(engine.reconfigure(&config));
+        assert!(!has_gap(&engine));
+    }
+
+    #[test]
+    fn test_daily_macd_golden_cross_on_last_bar() {
+        // 持续下跌（DIF 在 DEA 下方），最后一根大涨 → DIF 上穿 DEA
+        let mut closes: Vec<f64> = (0..60).map(|i| 100.0 - i as f64 * 0.5).collect();
+        assert!(!daily_signals_for(&closes).contains(&Signal::MacdGoldenCross));
+        closes.push(80.0);
+        let signals = daily_signals_for(&closes);
+        assert!(signals.contains(&Signal::MacdGoldenCross), "{:?}", signals);
+        assert!(!signals.contains(&Signal::MacdDeathCross));
+        assert_eq!(Signal::MacdGoldenCross.sentiment(), Sentiment::Bullish);
+    }
+
+    #[test]
+    fn test_daily_macd_death_cross_on_last_bar() {
+        // 持续上涨（DIF 在 DEA 上方），最后一根大跌 → DIF 下穿 DEA
+        let mut closes: Vec<f64> = (0..60).map(|i| 100.0 + i as f64 * 0.5).collect();
+        assert!(!daily_signals_for(&closes).contains(&Signal::MacdDeathCross));
+        closes.push(120.0);
+        let signals = daily_signals_for(&closes);
+        assert!(signals.contains(&Signal::MacdDeathCross), "{:?}", signals);
+        assert!(!signals.contains(&Signal::MacdGoldenCross));
+        assert_eq!(Signal::MacdDeathCross.sentiment(), Sentiment::Bearish);
+    }
+
+    #[test]
+    fn test_daily_rsi_monotonic_climb_approaches_100() {
+        // 连续上涨无回撤 → 可配置周期 RSI 趋近 100，超买信号看空
+        let closes: Vec<f64> = (0..40).map(|i| 100.0 + i as f64).collect();
+        let mut engine = DailyAnalysisEngine::new(&AnalysisConfig {
+            rsi_period: 10,
+            ..AnalysisConfig::default()
+        });
+        let code = StockCode::new(Market::HK, "00700");
+        engine.update(HashMap::from([(code.clone(), klines_from_closes(&closes))]));
+
+        let rsi = engine.get_indicators()[&code].rsi.unwrap();
+        assert!(rsi > 99.0, "rsi = {}", rsi);
+        assert_eq!(
+            Signal::RsiOverbought { period: 10, value: rsi }.sentiment(),
+            Sentiment::Bearish
+        );
+    }
+
+    #[test]
+    fn test_daily_bollinger_breakout_needs_full_window() {
+        // 窄幅震荡后最后一根大涨 → 突破上轨
+        let mut closes: Vec<f64> = (0..30).map(|i| 100.0 + (i % 2) as f64).collect();
+        closes.push(110.0);
+        let signals = daily_signals_for(&closes);
+        assert!(
+            signals.contains(&Signal::BollingerBreakout { upper: true }),
+            "{:?}",
+            signals
+        );
+
+        // 同样的形态但不足 20 根 → 不计算布林带，不触发
+        let short = &closes[closes.len() - 15..];
+        let signals = daily_signals_for(short);
+        assert!(
+            !signals.iter().any(|s| matches!(s, Signal::BollingerBreakout { .. })),
+            "{:?}",
+            signals
+        );
+    }
+
+    #[test]
+    fn test_daily_volume_surge_and_dryup() {
+        let signals_for_volumes = |volumes: &[u64]| {
+            let mut klines = klines_from_closes(&vec![100.0; volumes.len()]);
+            for (k, v) in klines.iter_mut().zip(volumes) {
+                k.volume = *v;
+            }
+            let mut engine = DailyAnalysisEngine::new(&AnalysisConfig::default());
+            let code = StockCode::new(Market::HK, "00700");
+            engine.update(HashMap::from([(code.clone(), klines)]));
+            let adv = engine.compute_adv()[&code];
+            let signals: Vec<Signal> = engine.get_signals()[&code].iter().map(|s| s.signal.clone()).collect();
+            (adv, signals)
+        };
+        let daily_volume = |signals: &[Signal]| {
+            signals.iter().find_map(|s| match s {
+                Signal::DailyVolume { ratio, surge } => Some((*ratio, *surge)),
+                _ => None,
+            })
+        };
+
+        // 19 天 100 万 + 当日 400 万：ADV 不含当日 = 100 万，比值 4 → 放量
+        let mut volumes = vec![1_000_000; 19];
+        volumes.push(4_000_000);
+        let (adv, signals) = signals_for_volumes(&volumes);
+        assert_eq!(adv, 1_000_000.0);
+        let (ratio, surge) = daily_volume(&signals).unwrap();
+        assert!(surge);
+        assert!((ratio - 4.0).abs() < 1e-9);
+
+        // 当日 20 万：ADV = 100 万，比值 0.2 → 缩量
+        *volumes.last_mut().unwrap() = 200_000;
+        let (_, signals) = signals_for_volumes(&volumes);
+        assert!(matches!(daily_volume(&signals), Some((_, false))));
+
+        // 平量不触发
+        let (_, signals) = signals_for_volumes(&[1_000_000; 20]);
+        assert_eq!(daily_volume(&signals), None);
+
+        // 4 天 100 万 + 当日 250 万：若 ADV 含当日为 130 万、比值 ≈ 1.92 不到放量线；
+        // 不含当日 ADV = 100 万，比值 2.5 → 放量
+        let (adv, signals) = signals_for_volumes(&[1_000_000, 1_000_000, 1_000_000, 1_000_000, 2_500_000]);
+        assert_eq!(adv, 1_000_000.0);
+        assert_eq!(daily_volume(&signals), Some((2.5, true)));
+
+        // 只有一根K线，没有历史可比 → 无 ADV
+        let mut engine = DailyAnalysisEngine::new(&AnalysisConfig::default());
+        let code = StockCode::new(Market::HK, "00700");
+        engine.update(HashMap::from([(code.clone(), klines_from_closes(&[100.0]))]));
+        assert!(!engine.compute_adv().contains_key(&code));
+    }
+
     #[test]
     fn test_merge_update() {
-        let mut engine = DailyAnalysisEngine::new();
+        let mut engine = DailyAnalysisEngine::new(&AnalysisConfig::default());
         let code = StockCode::new(Market::HK, "00700");
 
         // 初始数据：10 天
@@ -474,4 +756,59 @@ mod tests {
         let jan10 = klines.iter().find(|k| k.date == "2025-01-10").unwrap();
         assert_eq!(jan10.close, 200.0);
     }
+
+    #[test]
+    fn test_cache_atomic_save_and_lock() {
+        let dir = std::env::temp_dir().join(format!("qtrade-test-kline-cache-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let path = dir.join("kline_cache.json");
+
+        let mut first = DailyAnalysisEngine::new(&AnalysisConfig::default());
+        first.update(HashMap::from([(
+            StockCode::new(Market::HK, "00700"),
+            make_klines(30, 380.0),
+        )]));
+        first.save_cache_to(&path);
+
+        let mut loaded = DailyAnalysisEngine::new(&AnalysisConfig::default());
+        loaded.load_cache_from(&path);
+        assert_eq!(loaded.stock_count(), 1);
+        // 临时文件已 rename 为正式缓存
+        let files: Vec<_> = std::fs::read_dir(&dir)
+            .unwrap()
+            .flatten()
+            .map(|e| e.file_name())
+            .collect();
+        assert!(
+            files.iter().all(|f| !f.to_string_lossy().contains(".tmp.")),
+            "{:?}",
+            files
+        );
+
+        // 另一实例持有锁 → 不写入
+        let mut second = DailyAnalysisEngine::new(&AnalysisConfig::default());
+        second.update(HashMap::from([
+            (StockCode::new(Market::HK, "00700"), make_klines(30, 380.0)),
+            (StockCode::new(Market::SH, "600519"), make_klines(30, 1680.0)),
+        ]));
+        second.save_cache_to(&path);
+        let mut loaded = DailyAnalysisEngine::new(&AnalysisConfig::default());
+        loaded.load_cache_from(&path);
+        assert_eq!(loaded.stock_count(), 1);
+
+        // 持锁实例退出后可接手
+        drop(first);
+        second.save_cache_to(&path);
+        let mut loaded = DailyAnalysisEngine::new(&AnalysisConfig::default());
+        loaded.load_cache_from(&path);
+        assert_eq!(loaded.stock_count(), 2);
+
+        // 损坏的缓存文件不影响已有数据
+        std::fs::write(&path, "{\"stocks\": {").unwrap();
+        loaded.load_cache_from(&path);
+        assert_eq!(loaded.stock_count(), 2);
+
+        drop(second);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
 }
@@ -0,0 +1,126 @@
+//! 自定义信号检测器扩展点
+//!
+//! 下游用户实现 `SignalDetector` 并通过 `AnalysisEngine::register_detector` 注册，
+//! 无需修改引擎即可加入私有信号。检测器与内置检测共享每只股票的 tick 价格窗口，
+//! 在预热结束后与内置检测一同运行。
+
+use crate::analysis::indicators::sma;
+use crate::models::{QuoteSnapshot, Sentiment, Signal, StockCode};
+use std::collections::HashMap;
+
+/// 自定义 tick 信号检测器
+pub trait SignalDetector: Send {
+    /// 所需的价格窗口长度（引擎取所有检测器与内置检测的最大值）
+    fn window_size(&self) -> usize {
+        0
+    }
+
+    /// 处理一个 tick，返回新触发的信号
+    ///
+    /// `window` 为该股票最近的价格序列（按时间升序，最后一个即 `quote.last_price`）
+    fn evaluate(&mut self, quote: &QuoteSnapshot, window: &[f64]) -> Vec<Signal>;
+
+    /// 股票被移除时清理该股票的内部状态
+    fn remove_stock(&mut self, _code: &StockCode) {}
+}
+
+/// 示例检测器：tick 价格短/长均线交叉（动量交叉）
+///
+/// 短均线上穿长均线 → 动量转强（利多），下穿 → 动量转弱（利空）。
+/// 仅在均线相对位置翻转时触发一次，作为实现 `SignalDetector` 的参考。
+#[derive(Debug)]
+pub struct MomentumCrossDetector {
+    short: usize,
+    long: usize,
+    /// 每只股票上一次的状态：true = 短均线在长均线之上
+    last_above: HashMap<StockCode, bool>,
+}
+
+impl MomentumCrossDetector {
+    pub fn new(short: usize, long: usize) -> Self {
+        Self {
+            short,
+            long,
+            last_above: HashMap::new(),
+        }
+    }
+}
+
+impl SignalDetector for MomentumCrossDetector {
+    fn window_size(&self) -> usize {
+        self.long
+    }
+
+    fn evaluate(&mut self, quote: &QuoteSnapshot, window: &[f64]) -> Vec<Signal> {
+        let (Some(short_ma), Some(long_ma)) = (sma(window, self.short), sma(window, self.long)) else {
+            return Vec::new();
+        };
+        // 均线相等时不判定方向，保持上一状态
+        if (short_ma - long_ma).abs() < f64::EPSILON {
+            return Vec::new();
+        }
+
+        let above = short_ma > long_ma;
+        let prev = self.last_above.insert(quote.code.clone(), above);
+        match prev {
+            Some(was_above) if was_above != above => {
+                let (name, sentiment) = if above {
+                    (format!("动量上穿{}/{}", self.short, self.long), Sentiment::Bullish)
+                } else {
+                    (format!("动量下穿{}/{}", self.short, self.long), Sentiment::Bearish)
+                };
+                vec![Signal::Custom { name, sentiment }]
+            }
+            // 首次建立状态或方向未变，不触发
+            _ => Vec::new(),
+        }
+    }
+
+    fn remove_stock(&mut self, code: &StockCode) {
+        self.last_above.remove(code);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{DataSource, Market};
+
+    fn make_quote(price: f64) -> QuoteSnapshot {
+        let mut q = QuoteSnapshot::empty(StockCode::new(Market::HK, "00700"), "腾讯控股".to_string());
+        q.last_price = price;
+        q.source = DataSource::Cache;
+        q
+    }
+
+    #[test]
+    fn test_momentum_cross_fires_once_per_flip() {
+        let mut det = MomentumCrossDetector::new(2, 4);
+        let q = make_quote(100.0);
+
+        // 数据不足
+        assert!(det.evaluate(&q, &[100.0, 101.0]).is_empty());
+        // 首次建立状态（短在下）不触发
+        assert!(det.evaluate(&q, &[104.0, 103.0, 102.0, 101.0]).is_empty());
+        // 上穿 → 利多
+        let sigs = det.evaluate(&q, &[103.0, 102.0, 101.0, 106.0]);
+        assert_eq!(sigs.len(), 1);
+        assert_eq!(sigs[0].sentiment(), Sentiment::Bullish);
+        // 保持在上方不重复触发
+        assert!(det.evaluate(&q, &[102.0, 101.0, 106.0, 107.0]).is_empty());
+        // 下穿 → 利空
+        let sigs = det.evaluate(&q, &[106.0, 107.0, 100.0, 99.0]);
+        assert_eq!(sigs.len(), 1);
+        assert_eq!(sigs[0].sentiment(), Sentiment::Bearish);
+    }
+
+    #[test]
+    fn test_momentum_cross_remove_stock_resets_state() {
+        let mut det = MomentumCrossDetector::new(2, 4);
+        let q = make_quote(100.0);
+        assert!(det.evaluate(&q, &[104.0, 103.0, 102.0, 101.0]).is_empty());
+        det.remove_stock(&q.code);
+        // 状态已清除，重新建立状态不触发
+        assert!(det.evaluate(&q, &[101.0, 102.0, 103.0, 104.0]).is_empty());
+    }
+}
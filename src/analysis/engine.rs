@@ -1,14 +1,18 @@
 //! 分析引擎：事件型 tick 信号检测
 //!
 //! 检测 4 类事件信号（触发一次后保持显示，不频繁翻转）：
-//! - VWAP 偏离：价格偏离成交均价超阈值
+//! - VWAP 偏离：价格偏离成交均价超阈值（当日累计 VWAP 或最近 N 分钟滚动 VWAP）
 //! - 急涨急跌：短窗口内价格剧烈变动
 //! - 振幅突破：日内振幅超阈值
 //! - 量能突变：增量成交量相对窗口均值突增
+//!
+//! 另可通过 `register_detector` 注册自定义检测器（见 `detector` 模块）。
 
-use crate::config::AnalysisConfig;
-use crate::models::{QuoteSnapshot, Signal, StockCode};
+use crate::analysis::detector::SignalDetector;
+use crate::config::{AnalysisConfig, ThresholdOverride};
+use crate::models::{Market, QuoteSnapshot, Signal, StockCode};
 use std::collections::HashMap;
+use tracing::debug;
 
 /// 每只股票的价格历史窗口
 #[derive(Debug)]
@@ -31,6 +35,11 @@ impl PriceWindow {
             self.prices.remove(0);
         }
     }
+
+    /// 数据断档后重新开始：丢弃断档前的价格
+    fn restart(&mut self) {
+        self.prices.clear();
+    }
 }
 
 /// 每只股票的成交量跟踪器（时间戳 + 累计成交量 ring buffer）
@@ -114,6 +123,133 @@ impl VolumeTracker {
     }
 }
 
+/// 滚动 VWAP 跟踪器（时间戳 + 累计成交量 + 累计成交额 ring buffer）
+///
+/// 最近 N 分钟的 VWAP = 区间成交额增量 / 区间成交量增量，
+/// 由累计值差分得到，无需逐笔数据。
+#[derive(Debug)]
+struct RollingVwap {
+    /// (时间戳秒, 累计成交量, 累计成交额)
+    samples: std::collections::VecDeque<(f64, u64, f64)>,
+    /// 窗口长度（秒）
+    window_secs: f64,
+}
+
+impl RollingVwap {
+    fn new(window_secs: f64) -> Self {
+        Self {
+            samples: std::collections::VecDeque::new(),
+            window_secs,
+        }
+    }
+
+    /// 记录一个采样点
+    fn push(&mut self, timestamp: f64, cumulative_volume: u64, cumulative_turnover: f64) {
+        self.samples
+            .push_back((timestamp, cumulative_volume, cumulative_turnover));
+        // 保留一个不晚于窗口起点的锚点样本，其余更早的样本淘汰
+        while self.samples.len() > 2 && timestamp - self.samples[1].0 >= self.window_secs {
+            self.samples.pop_front();
+        }
+    }
+
+    /// 计算滚动 VWAP，历史不足一个完整窗口或区间无成交时返回 None
+    fn vwap(&self) -> Option<f64> {
+        let (cur_time, cur_vol, cur_turnover) = *self.samples.back()?;
+        let (anchor_time, anchor_vol, anchor_turnover) = *self.samples.front()?;
+        if cur_time - anchor_time < self.window_secs {
+            return None;
+        }
+        let delta_vol = cur_vol.saturating_sub(anchor_vol);
+        let delta_turnover = cur_turnover - anchor_turnover;
+        if delta_vol == 0 || delta_turnover <= 0.0 {
+            return None;
+        }
+        Some(delta_turnover / delta_vol as f64)
+    }
+}
+
+/// 滚动 VWAP 窗口（秒）；`vwap_rolling_minutes` 为 0 时返回 None（当日累计 VWAP）
+fn vwap_rolling_secs(config: &AnalysisConfig) -> Option<f64> {
+    (config.vwap_rolling_minutes > 0).then_some(config.vwap_rolling_minutes as f64 * 60.0)
+}
+
+/// tick 信号判定阈值（可按股票覆盖的部分）
+#[derive(Debug, Clone, Copy)]
+struct Thresholds {
+    vwap_deviation_pct: f64,
+    vwap_reset_pct: f64,
+    rapid_move_pct: f64,
+    rapid_move_reset_pct: f64,
+    rapid_move_efficiency: f64,
+    rapid_move_min_change: f64,
+    amplitude_breakout_pct: f64,
+    /// 换手率异动阈值 (%)，0 = 关闭
+    turnover_rate_threshold: f64,
+    volume_spike_ratio: f64,
+    /// 量能突变最低增量成交额（万元）
+    volume_spike_turnover: f64,
+}
+
+impl Thresholds {
+    fn from_config(config: &AnalysisConfig) -> Self {
+        Self {
+            vwap_deviation_pct: config.vwap_deviation_pct,
+            vwap_reset_pct: config.vwap_reset_pct,
+            rapid_move_pct: config.rapid_move_pct,
+            rapid_move_reset_pct: config.rapid_move_reset_pct,
+            rapid_move_efficiency: config.rapid_move_efficiency,
+            rapid_move_min_change: config.rapid_move_min_change,
+            amplitude_breakout_pct: config.amplitude_breakout_pct,
+            turnover_rate_threshold: config.turnover_rate_threshold,
+            volume_spike_ratio: config.volume_spike_ratio,
+            volume_spike_turnover: config.volume_spike_turnover,
+        }
+    }
+
+    /// 叠加覆盖项，未填写的字段保持原值
+    fn with_override(self, o: &ThresholdOverride) -> Self {
+        Self {
+            vwap_deviation_pct: o.vwap_deviation_pct.unwrap_or(self.vwap_deviation_pct),
+            vwap_reset_pct: o.vwap_reset_pct.unwrap_or(self.vwap_reset_pct),
+            rapid_move_pct: o.rapid_move_pct.unwrap_or(self.rapid_move_pct),
+            rapid_move_reset_pct: o.rapid_move_reset_pct.unwrap_or(self.rapid_move_reset_pct),
+            rapid_move_efficiency: o.rapid_move_efficiency.unwrap_or(self.rapid_move_efficiency),
+            rapid_move_min_change: o.rapid_move_min_change.unwrap_or(self.rapid_move_min_change),
+            amplitude_breakout_pct: o.amplitude_breakout_pct.unwrap_or(self.amplitude_breakout_pct),
+            turnover_rate_threshold: o.turnover_rate_threshold.unwrap_or(self.turnover_rate_threshold),
+            volume_spike_ratio: o.volume_spike_ratio.unwrap_or(self.volume_spike_ratio),
+            volume_spike_turnover: o.volume_spike_turnover.unwrap_or(self.volume_spike_turnover),
+        }
+    }
+}
+
+/// 按股票解析生效阈值：有覆盖用覆盖，否则回落到全局
+#[derive(Debug)]
+struct ThresholdResolver {
+    global: Thresholds,
+    per_stock: HashMap<StockCode, Thresholds>,
+}
+
+impl ThresholdResolver {
+    fn new(config: &AnalysisConfig) -> Self {
+        let global = Thresholds::from_config(config);
+        // 无效代码已由 AnalysisConfig::validate 拒绝，这里直接跳过
+        let per_stock = config
+            .overrides
+            .iter()
+            .filter_map(|(key, o)| {
+                crate::data::parser::parse_stock_code(key).map(|code| (code, global.with_override(o)))
+            })
+            .collect();
+        Self { global, per_stock }
+    }
+
+    fn get(&self, code: &StockCode) -> &Thresholds {
+        self.per_stock.get(code).unwrap_or(&self.global)
+    }
+}
+
 /// 每只股票的事件状态（防重复触发）
 #[derive(Debug, Default)]
 struct TickState {
@@ -125,12 +261,16 @@ struct TickState {
     vwap_below_triggered: bool,
     /// 振幅突破已触发（日内仅一次）
     amplitude_triggered: bool,
+    /// 换手率异动已触发（换手率回落到阈值 80% 以下才重新检测，实际即跨日重置）
+    turnover_rate_triggered: bool,
     /// 急涨急跌上涨方向已触发（滞后重置）
     rapid_move_up_triggered: bool,
     /// 急涨急跌下跌方向已触发（滞后重置）
     rapid_move_down_triggered: bool,
     /// 量能突变已触发（滞后重置）
     volume_spike_triggered: bool,
+    /// 上一个行情的时间戳（秒），用于检测数据断档
+    last_tick_secs: Option<f64>,
 }
 
 /// 分析引擎
@@ -139,25 +279,28 @@ pub struct AnalysisEngine {
     windows: HashMap<StockCode, PriceWindow>,
     /// 每只股票的成交量跟踪器
     vol_trackers: HashMap<StockCode, VolumeTracker>,
+    /// 每只股票的滚动 VWAP 跟踪器（仅 rolling 模式）
+    vwap_trackers: HashMap<StockCode, RollingVwap>,
     /// 每只股票的事件状态
     tick_states: HashMap<StockCode, TickState>,
     /// 每只股票的日均成交量（ADV），由外部 daily engine 注入（预留）
     adv_map: HashMap<StockCode, f64>,
-    /// 配置阈值
-    vwap_deviation_pct: f64,
-    vwap_reset_pct: f64,
-    rapid_move_pct: f64,
+    /// 用户注册的自定义检测器
+    detectors: Vec<Box<dyn SignalDetector>>,
+    /// 价格窗口长度（内置急涨急跌与自定义检测器所需的最大值）
+    window_size: usize,
+    /// 判定阈值（全局 + 按股票覆盖）
+    thresholds: ThresholdResolver,
+    /// 滚动 VWAP 窗口（秒），None 表示使用当日累计 VWAP
+    vwap_rolling_secs: Option<f64>,
     rapid_move_window: usize,
-    rapid_move_reset_pct: f64,
-    rapid_move_efficiency: f64,
-    rapid_move_min_change: f64,
-    amplitude_breakout_pct: f64,
-    volume_spike_ratio: f64,
+    /// 数据断档阈值（秒），0 = 关闭
+    rapid_move_max_gap_secs: f64,
     volume_baseline_secs: f64,
     volume_min_baseline_secs: f64,
-    /// 量能突变最低增量成交额（万元）
-    volume_spike_turnover: f64,
     warmup_ticks: u32,
+    /// 仅在常规交易时段产生信号的市场
+    regular_session_markets: Vec<Market>,
 }
 
 impl AnalysisEngine {
@@ -165,21 +308,55 @@ impl AnalysisEngine {
         Self {
             windows: HashMap::new(),
             vol_trackers: HashMap::new(),
+            vwap_trackers: HashMap::new(),
             tick_states: HashMap::new(),
             adv_map: HashMap::new(),
-            vwap_deviation_pct: config.vwap_deviation_pct,
-            vwap_reset_pct: config.vwap_reset_pct,
-            rapid_move_pct: config.rapid_move_pct,
+            detectors: Vec::new(),
+            window_size: config.rapid_move_window as usize + 1,
+            thresholds: ThresholdResolver::new(config),
+            vwap_rolling_secs: vwap_rolling_secs(config),
             rapid_move_window: config.rapid_move_window as usize,
-            rapid_move_reset_pct: config.rapid_move_reset_pct,
-            rapid_move_efficiency: config.rapid_move_efficiency,
-            rapid_move_min_change: config.rapid_move_min_change,
-            amplitude_breakout_pct: config.amplitude_breakout_pct,
-            volume_spike_ratio: config.volume_spike_ratio,
+            rapid_move_max_gap_secs: config.rapid_move_max_gap_secs,
             volume_baseline_secs: config.volume_baseline_secs,
             volume_min_baseline_secs: config.volume_min_baseline_secs,
-            volume_spike_turnover: config.volume_spike_turnover,
             warmup_ticks: config.warmup_ticks,
+            regular_session_markets: config.regular_session_markets(),
+        }
+    }
+
+    /// 应用新配置（配置热加载）：判定阈值、预热与时段门控立即生效
+    ///
+    /// 价格窗口长度、VWAP 口径或量能基线变化时清空对应的逐股缓存重新积累；
+    /// 各股的触发状态保留，改阈值不会让已触发的信号立即重复触发。
+    pub fn reconfigure(&mut self, config: &AnalysisConfig) {
+        self.thresholds = ThresholdResolver::new(config);
+        self.rapid_move_window = config.rapid_move_window as usize;
+        self.rapid_move_max_gap_secs = config.rapid_move_max_gap_secs;
+        self.warmup_ticks = config.warmup_ticks;
+        self.regular_session_markets = config.regular_session_markets();
+
+        let window_size = self
+            .detectors
+            .iter()
+            .map(|d| d.window_size())
+            .fold(config.rapid_move_window as usize + 1, usize::max);
+        if window_size != self.window_size {
+            self.window_size = window_size;
+            self.windows.clear();
+        }
+
+        let vwap_rolling_secs = vwap_rolling_secs(config);
+        if vwap_rolling_secs != self.vwap_rolling_secs {
+            self.vwap_rolling_secs = vwap_rolling_secs;
+            self.vwap_trackers.clear();
+        }
+
+        if config.volume_baseline_secs != self.volume_baseline_secs
+            || config.volume_min_baseline_secs != self.volume_min_baseline_secs
+        {
+            self.volume_baseline_secs = config.volume_baseline_secs;
+            self.volume_min_baseline_secs = config.volume_min_baseline_secs;
+            self.vol_trackers.clear();
         }
     }
 
@@ -188,16 +365,50 @@ impl AnalysisEngine {
         self.adv_map = adv;
     }
 
+    /// 注册自定义检测器，与内置检测共享价格窗口
+    ///
+    /// 应在处理行情前注册；已存在的价格窗口不会扩容。
+    pub fn register_detector(&mut self, detector: Box<dyn SignalDetector>) {
+        self.window_size = self.window_size.max(detector.window_size());
+        self.detectors.push(detector);
+    }
+
     /// 处理新的行情快照，返回新触发的事件型信号
     pub fn process(&mut self, quote: &QuoteSnapshot) -> Vec<Signal> {
+        // 停牌：价格冻结不产生信号；清空价格窗口，复牌后的价格不与停牌前比较（避免复牌跳空误报急涨急跌）
+        if quote.is_suspended {
+            if let Some(window) = self.windows.get_mut(&quote.code) {
+                window.restart();
+            }
+            return Vec::new();
+        }
+
         let mut signals = Vec::new();
+        let ts_secs = quote.timestamp.timestamp() as f64 + quote.timestamp.timestamp_subsec_millis() as f64 / 1000.0;
+
+        // 数据断档（重连、OCR 跳帧）：断档前后的价格不连续，不能放在同一窗口里比较
+        let gap_secs = self
+            .tick_states
+            .get(&quote.code)
+            .and_then(|ts| ts.last_tick_secs)
+            .map(|last| ts_secs - last);
+        let after_gap =
+            self.rapid_move_max_gap_secs > 0.0 && gap_secs.is_some_and(|gap| gap > self.rapid_move_max_gap_secs);
 
         // 更新价格窗口
-        let window_size = self.rapid_move_window + 1;
+        let window_size = self.window_size;
         let window = self
             .windows
             .entry(quote.code.clone())
             .or_insert_with(|| PriceWindow::new(window_size));
+        if after_gap {
+            debug!(
+                "{}: {:.0}s data gap, restarting price window",
+                quote.code.display_code(),
+                gap_secs.unwrap_or_default()
+            );
+            window.restart();
+        }
         window.push_price(quote.last_price);
 
         // 更新成交量跟踪器（时间戳 + 累计量）
@@ -207,10 +418,23 @@ impl AnalysisEngine {
             .vol_trackers
             .entry(quote.code.clone())
             .or_insert_with(|| VolumeTracker::new(baseline_secs, min_baseline_secs));
-        let ts_secs = quote.timestamp.timestamp() as f64 + quote.timestamp.timestamp_subsec_millis() as f64 / 1000.0;
         vol_tracker.push(ts_secs, quote.volume);
 
+        // 滚动 VWAP 模式：记录累计成交量/成交额
+        let rolling_vwap = match self.vwap_rolling_secs {
+            Some(window_secs) => {
+                let tracker = self
+                    .vwap_trackers
+                    .entry(quote.code.clone())
+                    .or_insert_with(|| RollingVwap::new(window_secs));
+                tracker.push(ts_secs, quote.volume, quote.turnover);
+                tracker.vwap()
+            }
+            None => None,
+        };
+
         let ts = self.tick_states.entry(quote.code.clone()).or_default();
+        ts.last_tick_secs = Some(ts_secs);
 
         // 预热：前 N 个 tick 仅记录数据，不产生信号
         ts.tick_count += 1;
@@ -218,17 +442,33 @@ impl AnalysisEngine {
             return signals;
         }
 
+        // 常规时段门控：盘前/盘后等时段外的行情只更新窗口，不产生信号
+        if self.regular_session_markets.contains(&quote.code.market)
+            && !crate::models::is_regular_session_at(quote.code.market, &quote.timestamp)
+        {
+            return signals;
+        }
+        let th = *self.thresholds.get(&quote.code);
+
         // 1. VWAP 偏离（指数的 turnover/volume 与指数点位不可比，跳过）
-        if !quote.code.is_index() && quote.volume > 0 && quote.turnover > 0.0 && quote.last_price > 0.0 {
-            let vwap = quote.turnover / quote.volume as f64;
+        // 累计模式：当日 turnover/volume；滚动模式：最近 N 分钟区间 VWAP（历史不足时跳过）
+        let vwap = if !quote.code.is_index() && quote.volume > 0 && quote.turnover > 0.0 && quote.last_price > 0.0 {
+            match self.vwap_rolling_secs {
+                Some(_) => rolling_vwap,
+                None => Some(quote.turnover / quote.volume as f64),
+            }
+        } else {
+            None
+        };
+        if let Some(vwap) = vwap {
             let deviation = (quote.last_price - vwap) / vwap * 100.0;
 
-            if deviation >= self.vwap_deviation_pct && !ts.vwap_above_triggered {
+            if deviation >= th.vwap_deviation_pct && !ts.vwap_above_triggered {
                 signals.push(Signal::VwapDeviation {
                     deviation_pct: deviation,
                 });
                 ts.vwap_above_triggered = true;
-            } else if deviation <= -self.vwap_deviation_pct && !ts.vwap_below_triggered {
+            } else if deviation <= -th.vwap_deviation_pct && !ts.vwap_below_triggered {
                 signals.push(Signal::VwapDeviation {
                     deviation_pct: deviation,
                 });
@@ -236,7 +476,7 @@ impl AnalysisEngine {
             }
 
             // 滞后重置
-            if deviation.abs() < self.vwap_reset_pct {
+            if deviation.abs() < th.vwap_reset_pct {
                 ts.vwap_above_triggered = false;
                 ts.vwap_below_triggered = false;
             }
@@ -269,16 +509,16 @@ impl AnalysisEngine {
 
                     // 第三层：滞后重置 — 幅度达标 + 效率达标 + 绝对变动达标 + 未被抑制
                     let abs_change = net_change.abs();
-                    if change_pct >= self.rapid_move_pct
-                        && efficiency >= self.rapid_move_efficiency
-                        && abs_change >= self.rapid_move_min_change
+                    if change_pct >= th.rapid_move_pct
+                        && efficiency >= th.rapid_move_efficiency
+                        && abs_change >= th.rapid_move_min_change
                         && !ts.rapid_move_up_triggered
                     {
                         signals.push(Signal::RapidMove { change_pct });
                         ts.rapid_move_up_triggered = true;
-                    } else if change_pct <= -self.rapid_move_pct
-                        && efficiency >= self.rapid_move_efficiency
-                        && abs_change >= self.rapid_move_min_change
+                    } else if change_pct <= -th.rapid_move_pct
+                        && efficiency >= th.rapid_move_efficiency
+                        && abs_change >= th.rapid_move_min_change
                         && !ts.rapid_move_down_triggered
                     {
                         signals.push(Signal::RapidMove { change_pct });
@@ -286,10 +526,10 @@ impl AnalysisEngine {
                     }
 
                     // 重置：变动回落到 reset 阈值内
-                    if change_pct < self.rapid_move_reset_pct {
+                    if change_pct < th.rapid_move_reset_pct {
                         ts.rapid_move_up_triggered = false;
                     }
-                    if change_pct > -self.rapid_move_reset_pct {
+                    if change_pct > -th.rapid_move_reset_pct {
                         ts.rapid_move_down_triggered = false;
                     }
                 }
@@ -297,20 +537,34 @@ impl AnalysisEngine {
         }
 
         // 3. 振幅突破
-        if quote.amplitude >= self.amplitude_breakout_pct && !ts.amplitude_triggered {
+        if quote.amplitude >= th.amplitude_breakout_pct && !ts.amplitude_triggered {
             signals.push(Signal::AmplitudeBreakout {
                 amplitude_pct: quote.amplitude,
             });
             ts.amplitude_triggered = true;
         }
 
+        // 3b. 换手率异动（指数无换手率概念，跳过）
+        if th.turnover_rate_threshold > 0.0 && !quote.code.is_index() {
+            if quote.turnover_rate >= th.turnover_rate_threshold && !ts.turnover_rate_triggered {
+                signals.push(Signal::TurnoverRateSpike {
+                    rate: quote.turnover_rate,
+                });
+                ts.turnover_rate_triggered = true;
+            }
+            // 滞后重置：当日换手率只增不减，回落通常意味着新交易日
+            if quote.turnover_rate < th.turnover_rate_threshold * 0.8 {
+                ts.turnover_rate_triggered = false;
+            }
+        }
+
         // 4. 量能突变：时间归一化量速率 + 增量成交额门槛（指数跳过）
         if !quote.code.is_index() {
             if let Some((ratio, delta)) = vol_tracker.compute_ratio() {
                 // 增量成交额门槛：delta × price >= volume_spike_turnover 万元
-                let turnover_ok = delta as f64 * quote.last_price >= self.volume_spike_turnover * 10000.0;
+                let turnover_ok = delta as f64 * quote.last_price >= th.volume_spike_turnover * 10000.0;
 
-                if ratio >= self.volume_spike_ratio && turnover_ok && !ts.volume_spike_triggered {
+                if ratio >= th.volume_spike_ratio && turnover_ok && !ts.volume_spike_triggered {
                     signals.push(Signal::VolumeSpike {
                         ratio,
                         price: quote.last_price,
@@ -325,6 +579,11 @@ impl AnalysisEngine {
             }
         }
 
+        // 5. 自定义检测器
+        for detector in &mut self.detectors {
+            signals.extend(detector.evaluate(quote, &window.prices));
+        }
+
         signals
     }
 
@@ -332,8 +591,12 @@ impl AnalysisEngine {
     pub fn remove_stock(&mut self, code: &StockCode) {
         self.windows.remove(code);
         self.vol_trackers.remove(code);
+        self.vwap_trackers.remove(code);
         self.tick_states.remove(code);
         self.adv_map.remove(code);
+        for detector in &mut self.detectors {
+            detector.remove_stock(code);
+        }
     }
 }
 
@@ -341,27 +604,44 @@ impl AnalysisEngine {
 mod tests {
     use super::*;
     use crate::config::AnalysisConfig;
-    use crate::models::{DataSource, Market};
+    use crate::models::{DataSource, Market, Sentiment};
 
     fn default_config() -> AnalysisConfig {
         AnalysisConfig {
             daily_kline_enabled: true,
             daily_kline_days: 120,
             daily_kline_refresh_minutes: 30,
+            permission_probe: "once".to_string(),
+            permission_probe_ttl_minutes: 0,
+            rsi_period: 14,
+            rsi_overbought: 70.0,
+            rsi_oversold: 30.0,
+            ma_fast_period: 5,
+            ma_slow_period: 20,
+            gap_threshold_pct: 3.0,
+            daily_volume_surge_ratio: 2.0,
+            daily_volume_dryup_ratio: 0.5,
+            boll_period: 20,
+            boll_std_multiplier: 2.0,
             vwap_deviation_pct: 2.0,
             vwap_reset_pct: 1.0,
+            vwap_rolling_minutes: 0,
             rapid_move_pct: 1.0,
             rapid_move_window: 5,
             rapid_move_reset_pct: 0.5,
             rapid_move_efficiency: 0.6,
             rapid_move_min_change: 0.05,
+            rapid_move_max_gap_secs: 30.0,
             amplitude_breakout_pct: 5.0,
+            turnover_rate_threshold: 10.0,
             volume_spike_ratio: 3.0,
             volume_baseline_secs: 300.0,
             volume_min_baseline_secs: 0.0, // 测试中关闭最短基线要求
             volume_spike_turnover: 0.0,    // 测试中关闭成交额门槛
             tick_signal_display_minutes: 5,
             warmup_ticks: 0, // 测试中默认关闭预热
+            regular_session_only: Vec::new(),
+            overrides: HashMap::new(),
         }
     }
 
@@ -382,6 +662,7 @@ mod tests {
             amplitude: 0.0,
             extended_price: None,
             extended_change_pct: None,
+            is_suspended: false,
             timestamp: chrono::Local::now(),
             source: DataSource::Cache,
         }
@@ -410,6 +691,108 @@ mod tests {
             .any(|s| matches!(s, Signal::RapidMove { change_pct } if *change_pct > 0.0)));
     }
 
+    #[test]
+    fn test_per_stock_threshold_override() {
+        // 全局 1% 即触发急涨；00700 单独放宽到 3%，09988 沿用全局
+        let mut config = AnalysisConfig {
+            rapid_move_window: 2,
+            rapid_move_pct: 1.0,
+            ..default_config()
+        };
+        config.overrides.insert(
+            "HK.00700".to_string(),
+            ThresholdOverride {
+                rapid_move_pct: Some(3.0),
+                ..Default::default()
+            },
+        );
+        let mut engine = AnalysisEngine::new(&config);
+        let is_rapid = |sigs: &[Signal]| sigs.iter().any(|s| matches!(s, Signal::RapidMove { .. }));
+
+        for code in ["00700", "09988"] {
+            for price in [100.0, 100.0, 100.0] {
+                engine.process(&make_quote(code, price));
+            }
+        }
+        // 同样 +2%：被覆盖的股票不触发，其余股票按全局阈值触发
+        assert!(!is_rapid(&engine.process(&make_quote("00700", 102.0))));
+        assert!(is_rapid(&engine.process(&make_quote("09988", 102.0))));
+    }
+
+    #[test]
+    fn test_regular_session_gate() {
+        use chrono::TimeZone;
+
+        let config = AnalysisConfig {
+            regular_session_only: vec!["US".to_string()],
+            ..default_config()
+        };
+        let mut engine = AnalysisEngine::new(&config);
+        // 2024-06-03（周一）开盘前后：09:29:50 ET 盘前 / 09:30:05 ET 盘中
+        let make_us = |min: u32, sec: u32| {
+            let mut q = make_quote("AAPL", 190.0);
+            q.code = StockCode::new(Market::US, "AAPL");
+            q.amplitude = 6.0;
+            q.timestamp = chrono::Utc
+                .with_ymd_and_hms(2024, 6, 3, 13, min, sec)
+                .unwrap()
+                .with_timezone(&chrono::Local);
+            q
+        };
+
+        let pre = make_us(29, 50);
+        assert!(
+            engine.process(&pre).is_empty(),
+            "pre-market quote should not emit signals"
+        );
+        assert_eq!(engine.windows[&pre.code].prices, vec![190.0]);
+
+        let sigs = engine.process(&make_us(30, 5));
+        assert!(sigs.iter().any(|s| matches!(s, Signal::AmplitudeBreakout { .. })));
+        assert_eq!(engine.windows[&pre.code].prices.len(), 2);
+
+        // 未开启门控的市场不受影响
+        let mut q = make_quote("00700", 100.0);
+        q.amplitude = 6.0;
+        q.timestamp = pre.timestamp;
+        assert!(!engine.process(&q).is_empty());
+    }
+
+    #[test]
+    fn test_rapid_move_skipped_after_data_gap() {
+        let config = AnalysisConfig {
+            rapid_move_window: 2,
+            rapid_move_pct: 1.0,
+            ..default_config()
+        };
+        let base_time = chrono::Local::now();
+        let make_timed = |secs: i64, price: f64| {
+            let mut q = make_quote("00700", price);
+            q.timestamp = base_time + chrono::Duration::seconds(secs);
+            q
+        };
+        let is_rapid = |sigs: &[Signal]| sigs.iter().any(|s| matches!(s, Signal::RapidMove { .. }));
+
+        // 稳定窗口（每 2 秒一个 tick），断档 60 秒后价格 +2% → 不视为急涨
+        let mut engine = AnalysisEngine::new(&config);
+        for (secs, price) in [(0, 100.0), (2, 100.0), (4, 100.0)] {
+            engine.process(&make_timed(secs, price));
+        }
+        assert!(!is_rapid(&engine.process(&make_timed(64, 102.0))));
+        // 断档后窗口重新积累，紧随其后的 tick 也不与断档前价格比较
+        assert!(!is_rapid(&engine.process(&make_timed(66, 102.1))));
+
+        // 关闭断档检测 → 同样的序列触发急涨
+        let mut engine = AnalysisEngine::new(&AnalysisConfig {
+            rapid_move_max_gap_secs: 0.0,
+            ..config
+        });
+        for (secs, price) in [(0, 100.0), (2, 100.0), (4, 100.0)] {
+            engine.process(&make_timed(secs, price));
+        }
+        assert!(is_rapid(&engine.process(&make_timed(64, 102.0))));
+    }
+
     #[test]
     fn test_engine_amplitude_breakout() {
         let mut engine = AnalysisEngine::new(&default_config());
@@ -424,6 +807,91 @@ mod tests {
         assert!(sigs.iter().all(|s| !matches!(s, Signal::AmplitudeBreakout { .. })));
     }
 
+    #[test]
+    fn test_engine_reconfigure_applies_new_thresholds() {
+        let config = AnalysisConfig {
+            rapid_move_window: 2,
+            rapid_move_pct: 5.0,
+            ..default_config()
+        };
+        let mut engine = AnalysisEngine::new(&config);
+        let is_rapid = |sigs: &[Signal]| sigs.iter().any(|s| matches!(s, Signal::RapidMove { .. }));
+        for price in [100.0, 100.0, 100.0] {
+            engine.process(&make_quote("00700", price));
+        }
+        assert!(!is_rapid(&engine.process(&make_quote("00700", 102.0))));
+
+        // 调低阈值后立即生效，窗口长度未变，已有价格保留
+        engine.reconfigure(&AnalysisConfig {
+            rapid_move_pct: 1.0,
+            ..config.clone()
+        });
+        assert!(is_rapid(&engine.process(&make_quote("00700", 104.0))));
+
+        // 窗口长度变化时重新积累
+        engine.reconfigure(&AnalysisConfig {
+            rapid_move_window: 3,
+            rapid_move_pct: 1.0,
+            ..config
+        });
+        assert!(engine.windows.is_empty());
+        assert_eq!(engine.window_size, 4);
+    }
+
+    #[test]
+    fn test_engine_skips_suspended() {
+        let config = AnalysisConfig {
+            rapid_move_window: 2,
+            rapid_move_max_gap_secs: 0.0,
+            ..default_config()
+        };
+        let mut engine = AnalysisEngine::new(&config);
+        for price in [100.0, 100.0, 100.0] {
+            engine.process(&make_quote("00700", price));
+        }
+
+        // 停牌期间不产生信号（含振幅等快照字段触发的信号）
+        let mut halted = make_quote("00700", 100.0);
+        halted.is_suspended = true;
+        halted.amplitude = 10.0;
+        assert!(engine.process(&halted).is_empty());
+
+        // 复牌跳空不与停牌前价格比较
+        let sigs = engine.process(&make_quote("00700", 110.0));
+        assert!(sigs.iter().all(|s| !matches!(s, Signal::RapidMove { .. })));
+    }
+
+    #[test]
+    fn test_engine_turnover_rate_spike() {
+        let mut engine = AnalysisEngine::new(&default_config());
+        let is_turnover = |sigs: &[Signal]| sigs.iter().any(|s| matches!(s, Signal::TurnoverRateSpike { .. }));
+
+        let mut q = make_quote("00700", 100.0);
+        q.turnover_rate = 9.0;
+        assert!(!is_turnover(&engine.process(&q)));
+
+        q.turnover_rate = 12.5;
+        let sigs = engine.process(&q);
+        assert!(sigs.contains(&Signal::TurnoverRateSpike { rate: 12.5 }));
+
+        // 仍在阈值附近不重复触发
+        q.turnover_rate = 9.0;
+        assert!(!is_turnover(&engine.process(&q)));
+        q.turnover_rate = 13.0;
+        assert!(!is_turnover(&engine.process(&q)));
+
+        // 回落到阈值 80% 以下（新交易日）后可再次触发
+        q.turnover_rate = 0.5;
+        engine.process(&q);
+        q.turnover_rate = 11.0;
+        assert!(is_turnover(&engine.process(&q)));
+
+        // 指数不检测
+        let mut idx = make_quote("800000", 100.0);
+        idx.turnover_rate = 50.0;
+        assert!(!is_turnover(&engine.process(&idx)));
+    }
+
     #[test]
     fn test_engine_volume_spike() {
         let config = AnalysisConfig {
@@ -794,4 +1262,124 @@ mod tests {
             "index stock should not produce VWAP signal"
         );
     }
+
+    #[test]
+    fn test_rolling_vwap_window() {
+        let mut rv = RollingVwap::new(60.0);
+        rv.push(0.0, 1_000, 100_000.0);
+        rv.push(30.0, 2_000, 195_000.0);
+        // 历史不足一个完整窗口
+        assert_eq!(rv.vwap(), None);
+
+        rv.push(60.0, 3_000, 290_000.0);
+        // [0, 60] 区间：190000 / 2000
+        assert_eq!(rv.vwap(), Some(95.0));
+
+        rv.push(90.0, 4_000, 380_000.0);
+        // 锚点滑动到 30：185000 / 2000
+        assert_eq!(rv.vwap(), Some(92.5));
+    }
+
+    #[test]
+    fn test_engine_rolling_vwap_deviation() {
+        // 当日累计 VWAP ≈ 100，最近 1 分钟以 95 成交 → 现价 98 相对滚动 VWAP 偏离 +3.2%，相对累计 VWAP 仅 -1.9%
+        let base = chrono::Local::now();
+        let ticks = [
+            (0, 100.0, 1_000_000, 100_000_000.0),
+            (30, 95.0, 1_010_000, 100_950_000.0),
+            (60, 98.0, 1_020_000, 101_900_000.0),
+        ];
+        let run = |config: &AnalysisConfig| {
+            let mut engine = AnalysisEngine::new(config);
+            let mut last = Vec::new();
+            for (secs, price, volume, turnover) in ticks {
+                let mut q = make_quote("00700", price);
+                q.volume = volume;
+                q.turnover = turnover;
+                q.timestamp = base + chrono::Duration::seconds(secs);
+                last = engine.process(&q);
+            }
+            last
+        };
+        let is_above = |s: &Signal| matches!(s, Signal::VwapDeviation { deviation_pct } if *deviation_pct > 0.0);
+
+        let rolling = AnalysisConfig {
+            vwap_rolling_minutes: 1,
+            ..default_config()
+        };
+        assert!(run(&rolling).iter().any(is_above));
+        assert!(!run(&default_config()).iter().any(is_above));
+    }
+
+    #[test]
+    fn test_engine_rolling_vwap_late_day_spike() {
+        // 全天在 100 成交 100 万股；尾盘 5 分钟拉到 103 成交 20 万股，随后急跌到 100.5：
+        // 累计 VWAP 被全天成交"粘住"在 100.5 附近（偏离 0），5 分钟滚动 VWAP ≈ 102.85（偏离 -2.3%）
+        let base = chrono::Local::now();
+        let mut ticks = vec![(0, 100.0, 1_000_000u64, 100_000_000.0)];
+        for minute in 1..=5u64 {
+            let volume = 1_000_000 + minute * 40_000;
+            ticks.push((
+                minute as i64 * 60,
+                103.0,
+                volume,
+                100_000_000.0 + (minute * 40_000) as f64 * 103.0,
+            ));
+        }
+        ticks.push((360, 100.5, 1_210_000, 120_600_000.0 + 10_000.0 * 100.5));
+
+        let run = |config: &AnalysisConfig| {
+            let mut engine = AnalysisEngine::new(config);
+            let mut last = Vec::new();
+            for &(secs, price, volume, turnover) in &ticks {
+                let mut q = make_quote("00700", price);
+                q.volume = volume;
+                q.turnover = turnover;
+                q.timestamp = base + chrono::Duration::seconds(secs);
+                last = engine.process(&q);
+            }
+            last
+        };
+        let is_below = |s: &Signal| matches!(s, Signal::VwapDeviation { deviation_pct } if *deviation_pct < -2.0);
+
+        let rolling = AnalysisConfig {
+            vwap_rolling_minutes: 5,
+            ..default_config()
+        };
+        assert!(run(&rolling).iter().any(is_below));
+        // vwap_rolling_minutes = 0 保持当日累计口径，尾盘急跌不触发
+        assert!(!run(&default_config())
+            .iter()
+            .any(|s| matches!(s, Signal::VwapDeviation { .. })));
+    }
+
+    #[test]
+    fn test_registered_detector_shares_window() {
+        use crate::analysis::detector::MomentumCrossDetector;
+
+        // rapid_move_window=2 → 内置窗口 3，注册长周期 4 的检测器后窗口扩到 4
+        let config = AnalysisConfig {
+            rapid_move_window: 2,
+            rapid_move_pct: 50.0, // 关闭急涨急跌，只观察自定义信号
+            ..default_config()
+        };
+        let mut engine = AnalysisEngine::new(&config);
+        engine.register_detector(Box::new(MomentumCrossDetector::new(2, 4)));
+
+        // 下跌段建立"短均线在下"状态
+        for price in [104.0, 103.0, 102.0, 101.0] {
+            let sigs = engine.process(&make_quote("00700", price));
+            assert!(!sigs.iter().any(|s| matches!(s, Signal::Custom { .. })));
+        }
+
+        // 反弹：短均线上穿长均线
+        let mut crossed = Vec::new();
+        for price in [103.0, 106.0] {
+            crossed.extend(engine.process(&make_quote("00700", price)));
+        }
+        let custom: Vec<_> = crossed.iter().filter(|s| matches!(s, Signal::Custom { .. })).collect();
+        assert_eq!(custom.len(), 1);
+        assert_eq!(custom[0].sentiment(), Sentiment::Bullish);
+        assert_eq!(custom[0].to_string(), "动量上穿2/4");
+    }
 }
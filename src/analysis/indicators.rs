@@ -1,6 +1,6 @@
 //! 技术指标纯计算函数
 //!
-//! MA (Simple Moving Average), EMA, MACD, RSI
+//! MA (Simple Moving Average), EMA, MACD, RSI, Bollinger Bands
 
 /// 计算简单移动平均线 (SMA)
 /// 返回最后一个完整周期的 MA 值
@@ -197,6 +197,16 @@ pub fn rsi(data: &[f64], period: usize) -> Option<f64> {
     series.last().copied().flatten()
 }
 
+/// 计算最新布林带 (上轨, 中轨, 下轨)
+/// 中轨 = SMA(period)，上下轨 = 中轨 ± multiplier × 标准差（总体标准差）
+pub fn bollinger(data: &[f64], period: usize, multiplier: f64) -> Option<(f64, f64, f64)> {
+    let mid = sma(data, period)?;
+    let window = &data[data.len() - period..];
+    let variance = window.iter().map(|x| (x - mid).powi(2)).sum::<f64>() / period as f64;
+    let width = multiplier * variance.sqrt();
+    Some((mid + width, mid, mid - width))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -237,6 +247,17 @@ mod tests {
         assert!(hist.is_some());
     }
 
+    #[test]
+    fn test_bollinger() {
+        // 窗口 [2, 4, 4, 4, 5, 5, 7, 9]：均值 5，总体标准差 2
+        let data = vec![100.0, 2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0];
+        assert_eq!(bollinger(&data, 8, 2.0), Some((9.0, 5.0, 1.0)));
+        assert_eq!(bollinger(&data, 10, 2.0), None); // 数据不足
+
+        // 横盘无波动 → 上下轨收敛到中轨
+        assert_eq!(bollinger(&[3.0; 20], 20, 2.0), Some((3.0, 3.0, 3.0)));
+    }
+
     #[test]
     fn test_rsi() {
         // 全部上涨 → RSI 应接近 100
@@ -0,0 +1,313 @@
+//! 信号日志：累积本次运行触发的 tick / 日线信号，导出 CSV 供复盘
+//!
+//! CSV 列固定为：timestamp, code, name, timeframe, signal_type, magnitude, price, description
+//!
+//! 另提供日K缓存的离线导出（`qtrade export-signals`），列为：code, name, signal, sentiment, date
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+
+use super::daily::DailyAnalysisEngine;
+use crate::models::{Signal, StockCode, TimedSignal, Timeframe};
+
+/// CSV 表头（列顺序固定，新增列只能追加在末尾）
+const CSV_HEADER: &str = "timestamp,code,name,timeframe,signal_type,magnitude,price,description";
+
+/// 日线信号导出 CSV 表头
+const DAILY_CSV_HEADER: &str = "code,name,signal,sentiment,date";
+
+/// 一条信号记录
+#[derive(Debug, Clone)]
+pub struct SignalRecord {
+    pub timestamp: DateTime<Local>,
+    pub code: StockCode,
+    pub name: String,
+    /// "tick" | "daily"
+    pub timeframe: &'static str,
+    pub signal: Signal,
+    /// 触发时的价格
+    pub price: f64,
+}
+
+/// 信号日志收集器
+#[derive(Debug, Default)]
+pub struct SignalJournal {
+    records: Vec<SignalRecord>,
+    /// 日线信号去重：(股票, 信号描述)，同一日线信号每次运行只记一次
+    seen_daily: HashSet<(StockCode, String)>,
+}
+
+impl SignalJournal {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 记录一个 tick 信号
+    pub fn record_tick(&mut self, code: &StockCode, name: &str, signal: &Signal, price: f64, at: DateTime<Local>) {
+        self.records.push(SignalRecord {
+            timestamp: at,
+            code: code.clone(),
+            name: name.to_string(),
+            timeframe: "tick",
+            signal: signal.clone(),
+            price,
+        });
+    }
+
+    /// 记录日线信号（日线信号每次刷新都会重复给出，已记录过的跳过），返回新记录数
+    pub fn record_daily(
+        &mut self,
+        code: &StockCode,
+        name: &str,
+        signals: &[TimedSignal],
+        price: f64,
+        at: DateTime<Local>,
+    ) -> usize {
+        let mut added = 0;
+        for ts in signals {
+            if !self.seen_daily.insert((code.clone(), ts.signal.to_string())) {
+                continue;
+            }
+            self.records.push(SignalRecord {
+                timestamp: at,
+                code: code.clone(),
+                name: name.to_string(),
+                timeframe: match ts.timeframe {
+                    Timeframe::Daily => "daily",
+                },
+                signal: ts.signal.clone(),
+                price,
+            });
+            added += 1;
+        }
+        added
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    /// 生成 CSV 文本（含表头）
+    pub fn to_csv(&self) -> String {
+        let mut out = String::from(CSV_HEADER);
+        out.push('\n');
+        for r in &self.records {
+            let magnitude = r.signal.magnitude().map(|m| format!("{:.4}", m)).unwrap_or_default();
+            let fields = [
+                crate::models::to_app_time(&r.timestamp)
+                    .format("%Y-%m-%d %H:%M:%S")
+                    .to_string(),
+                r.code.display_code(),
+                r.name.clone(),
+                r.timeframe.to_string(),
+                r.signal.kind().to_string(),
+                magnitude,
+                format!("{:.4}", r.price),
+                r.signal.to_string(),
+            ];
+            let line: Vec<String> = fields.iter().map(|f| csv_escape(f)).collect();
+            out.push_str(&line.join(","));
+            out.push('\n');
+        }
+        out
+    }
+
+    /// 按股票分组的文字摘要（退出时打印）：股票按首次触发顺序，组内按时间顺序
+    pub fn digest(&self) -> String {
+        let mut groups: Vec<(&StockCode, Vec<&SignalRecord>)> = Vec::new();
+        for r in &self.records {
+            match groups.iter_mut().find(|(code, _)| **code == r.code) {
+                Some((_, records)) => records.push(r),
+                None => groups.push((&r.code, vec![r])),
+            }
+        }
+
+        let mut out = format!(
+            "本次运行信号汇总：{} 只股票，{} 条信号\n",
+            groups.len(),
+            self.records.len()
+        );
+        for (code, records) in &groups {
+            out.push_str(&format!(
+                "\n{} {}（{} 条）\n",
+                code.display_code(),
+                records[0].name,
+                records.len()
+            ));
+            for r in records {
+                out.push_str(&format!(
+                    "  {}  {:<5} [{}]{}  @{:.2}\n",
+                    crate::models::to_app_time(&r.timestamp).format("%H:%M:%S"),
+                    r.timeframe,
+                    r.signal.sentiment(),
+                    r.signal,
+                    r.price
+                ));
+            }
+        }
+        out
+    }
+
+    /// 写入 `<dir>/signals_YYYYMMDD_HHMMSS.csv`，返回文件路径
+    pub fn write_csv(&self, dir: &Path) -> Result<PathBuf> {
+        std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+        let file_name = format!("signals_{}.csv", crate::models::app_now().format("%Y%m%d_%H%M%S"));
+        let path = dir.join(file_name);
+        std::fs::write(&path, self.to_csv()).with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(path)
+    }
+}
+
+/// 日线引擎当前信号 → CSV（含表头），每行一个 (股票, 信号, 日期, 情绪)
+///
+/// 股票按代码排序，组内保持信号顺序；`date` 为该股最新一根日K的日期（日线信号均按最新K线判定）。
+/// `names` 缺少的股票名称留空。
+pub fn daily_signals_csv(engine: &DailyAnalysisEngine, names: &HashMap<StockCode, String>) -> String {
+    let mut codes: Vec<&StockCode> = engine.get_signals().keys().collect();
+    codes.sort_by_key(|code| code.display_code());
+
+    let mut out = String::from(DAILY_CSV_HEADER);
+    out.push('\n');
+    let mut seen = HashSet::new();
+    for code in codes {
+        let name = names.get(code).map(String::as_str).unwrap_or_default();
+        let date = engine.last_kline_date(code).unwrap_or_default();
+        for ts in &engine.get_signals()[code] {
+            let fields = [
+                code.display_code(),
+                name.to_string(),
+                ts.signal.to_string(),
+                ts.signal.sentiment().to_string(),
+                date.clone(),
+            ];
+            let line: Vec<String> = fields.iter().map(|f| csv_escape(f)).collect();
+            let line = line.join(",");
+            if seen.insert(line.clone()) {
+                out.push_str(&line);
+                out.push('\n');
+            }
+        }
+    }
+    out
+}
+
+/// CSV 字段转义：含逗号/引号/换行时加引号，内部引号双写
+fn csv_escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AnalysisConfig;
+    use crate::models::Market;
+
+    #[test]
+    fn test_journal_csv_columns() {
+        let mut journal = SignalJournal::new();
+        let code = StockCode::new(Market::HK, "00700");
+        journal.record_tick(
+            &code,
+            "腾讯控股",
+            &Signal::RapidMove { change_pct: 1.5 },
+            388.2,
+            Local::now(),
+        );
+        let csv = journal.to_csv();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], CSV_HEADER);
+        let cols: Vec<&str> = lines[1].split(',').collect();
+        assert_eq!(cols.len(), 8);
+        assert_eq!(cols[1], "HK.00700");
+        assert_eq!(cols[2], "腾讯控股");
+        assert_eq!(cols[3], "tick");
+        assert_eq!(cols[4], "rapid_move");
+        assert_eq!(cols[5], "1.5000");
+        assert_eq!(cols[6], "388.2000");
+    }
+
+    #[test]
+    fn test_journal_daily_dedup() {
+        let mut journal = SignalJournal::new();
+        let code = StockCode::new(Market::SH, "600519");
+        let sigs = vec![TimedSignal {
+            signal: Signal::MacdGoldenCross,
+            timeframe: Timeframe::Daily,
+        }];
+        assert_eq!(journal.record_daily(&code, "贵州茅台", &sigs, 1688.0, Local::now()), 1);
+        // 日线刷新再次给出相同信号 → 不重复记录
+        assert_eq!(journal.record_daily(&code, "贵州茅台", &sigs, 1690.0, Local::now()), 0);
+        assert_eq!(journal.len(), 1);
+    }
+
+    #[test]
+    fn test_journal_digest_groups_by_stock() {
+        let mut journal = SignalJournal::new();
+        let tencent = StockCode::new(Market::HK, "00700");
+        let moutai = StockCode::new(Market::SH, "600519");
+        let rapid = Signal::RapidMove { change_pct: 1.5 };
+        journal.record_tick(&tencent, "腾讯控股", &rapid, 388.2, Local::now());
+        journal.record_tick(&moutai, "贵州茅台", &rapid, 1688.0, Local::now());
+        journal.record_tick(&tencent, "腾讯控股", &rapid, 390.0, Local::now());
+
+        let digest = journal.digest();
+        assert!(digest.starts_with("本次运行信号汇总：2 只股票，3 条信号"), "{}", digest);
+        // 股票按首次触发顺序，同一股票的信号归在一组
+        let tencent_at = digest.find("HK.00700 腾讯控股（2 条）").unwrap();
+        let moutai_at = digest.find("SH.600519 贵州茅台（1 条）").unwrap();
+        assert!(tencent_at < moutai_at);
+        let tencent_block = &digest[tencent_at..moutai_at];
+        assert!(tencent_block.contains("@388.20") && tencent_block.contains("@390.00"));
+    }
+
+    #[test]
+    fn test_daily_signals_csv() {
+        use crate::models::DailyKline;
+
+        // 第二根K线高开 5% → 跳空信号，日期取最新K线
+        let bar = |date: &str, open: f64, close: f64| DailyKline {
+            open,
+            close,
+            high: open.max(close),
+            low: open.min(close),
+            volume: 1000,
+            turnover: 0.0,
+            date: date.to_string(),
+        };
+        let code = StockCode::new(Market::HK, "00700");
+        let mut engine = DailyAnalysisEngine::new(&AnalysisConfig::default());
+        engine.update(HashMap::from([(
+            code.clone(),
+            vec![bar("2024-01-14", 100.0, 100.0), bar("2024-01-15", 105.0, 106.0)],
+        )]));
+        let names = HashMap::from([(code, "腾讯控股".to_string())]);
+
+        let csv = daily_signals_csv(&engine, &names);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], DAILY_CSV_HEADER);
+        assert_eq!(lines.len(), 2, "{}", csv);
+        let cols: Vec<&str> = lines[1].split(',').collect();
+        assert_eq!(
+            cols,
+            ["HK.00700", "腾讯控股", "跳空高开5.0%(01-15)", "利多", "2024-01-15"]
+        );
+    }
+
+    #[test]
+    fn test_csv_escape() {
+        assert_eq!(csv_escape("plain"), "plain");
+        assert_eq!(csv_escape("a,b"), "\"a,b\"");
+        assert_eq!(csv_escape("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+}
@@ -1,4 +1,8 @@
+pub mod compare;
 pub mod daily;
+pub mod detector;
 pub mod engine;
 pub mod indicators;
+pub mod journal;
 pub mod signals;
+pub mod tick_log;
@@ -1,20 +1,63 @@
-//! 信号检测：金叉/死叉、超买/超卖
+//! 信号检测：金叉/死叉、超买/超卖、布林带突破、跳空缺口
 
-use crate::models::{Signal, TechnicalIndicators};
+use crate::config::AnalysisConfig;
+use crate::models::{DailyKline, Signal, TechnicalIndicators};
+
+use super::indicators;
 
-/// RSI 超买阈值
-const RSI_OVERBOUGHT: f64 = 70.0;
-/// RSI 超卖阈值
-const RSI_OVERSOLD: f64 = 30.0;
 /// 放量倍数阈值
 const VOLUME_SPIKE_RATIO: f64 = 2.0;
 
+/// 日线信号参数，取自 `AnalysisConfig`（默认值以配置为准）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DailySignalParams {
+    /// `TechnicalIndicators::rsi` 的计算周期与超买/超卖线
+    pub rsi_period: usize,
+    pub rsi_overbought: f64,
+    pub rsi_oversold: f64,
+    /// 可配置均线交叉的快/慢线周期
+    pub ma_fast: usize,
+    pub ma_slow: usize,
+    /// 跳空缺口阈值 (%)
+    pub gap_threshold_pct: f64,
+    /// 日线量能放大 / 萎缩倍数（相对 ADV）
+    pub volume_surge_ratio: f64,
+    pub volume_dryup_ratio: f64,
+    /// 布林带窗口（K 线数）与标准差倍数
+    pub boll_period: usize,
+    pub boll_multiplier: f64,
+}
+
+impl DailySignalParams {
+    pub fn from_config(config: &AnalysisConfig) -> Self {
+        Self {
+            rsi_period: config.rsi_period,
+            rsi_overbought: config.rsi_overbought,
+            rsi_oversold: config.rsi_oversold,
+            ma_fast: config.ma_fast_period,
+            ma_slow: config.ma_slow_period,
+            gap_threshold_pct: config.gap_threshold_pct,
+            volume_surge_ratio: config.daily_volume_surge_ratio,
+            volume_dryup_ratio: config.daily_volume_dryup_ratio,
+            boll_period: config.boll_period,
+            boll_multiplier: config.boll_std_multiplier,
+        }
+    }
+}
+
+impl Default for DailySignalParams {
+    fn default() -> Self {
+        Self::from_config(&AnalysisConfig::default())
+    }
+}
+
 /// 检测所有信号
 pub fn detect_signals(
     current: &TechnicalIndicators,
     previous: Option<&TechnicalIndicators>,
-    _prices: &[f64],
+    prices: &[f64],
     volumes: &[u64],
+    params: &DailySignalParams,
 ) -> Vec<Signal> {
     let mut signals = Vec::new();
 
@@ -26,10 +69,19 @@ pub fn detect_signals(
 
         // MACD 金叉/死叉
         detect_macd_cross(current, prev, &mut signals);
+
+        // RSI 进入超买/超卖区
+        detect_rsi_cross(current, prev, params, &mut signals);
+
+        // 布林带突破
+        detect_bollinger_breakout(current, prev, prices, &mut signals);
     }
 
+    // 可配置周期均线交叉（与上面固定组合重复时不再推送）
+    detect_configured_ma_cross(prices, params, &mut signals);
+
     // RSI 超买/超卖
-    detect_rsi_signals(current, &mut signals);
+    detect_rsi_signals(current, params, &mut signals);
 
     // 放量检测
     detect_volume_spike(volumes, &mut signals);
@@ -60,6 +112,35 @@ fn detect_ma_cross(
     }
 }
 
+/// 按收盘价序列检测可配置周期的均线交叉：比较最后两根K线 (快线 - 慢线) 的符号
+fn detect_configured_ma_cross(prices: &[f64], params: &DailySignalParams, signals: &mut Vec<Signal>) {
+    let (fast, slow) = (params.ma_fast, params.ma_slow);
+    let Some(prev_prices) = prices.split_last().map(|(_, rest)| rest) else {
+        return;
+    };
+    let (cur_fast, cur_slow) = (indicators::sma(prices, fast), indicators::sma(prices, slow));
+    let (prev_fast, prev_slow) = (indicators::sma(prev_prices, fast), indicators::sma(prev_prices, slow));
+
+    if let (Some(cf), Some(cs), Some(pf), Some(ps)) = (cur_fast, cur_slow, prev_fast, prev_slow) {
+        let signal = if pf <= ps && cf > cs {
+            Signal::MaGoldenCross {
+                short: fast,
+                long: slow,
+            }
+        } else if pf >= ps && cf < cs {
+            Signal::MaDeathCross {
+                short: fast,
+                long: slow,
+            }
+        } else {
+            return;
+        };
+        if !signals.contains(&signal) {
+            signals.push(signal);
+        }
+    }
+}
+
 /// 获取指定周期的 MA 值对
 fn get_ma_pair(ti: &TechnicalIndicators, short: usize, long: usize) -> (Option<f64>, Option<f64>) {
     let short_val = match short {
@@ -96,30 +177,105 @@ fn detect_macd_cross(current: &TechnicalIndicators, previous: &TechnicalIndicato
 }
 
 /// 检测 RSI 超买/超卖
-fn detect_rsi_signals(current: &TechnicalIndicators, signals: &mut Vec<Signal>) {
-    if let Some(rsi6) = current.rsi6 {
-        if rsi6 >= RSI_OVERBOUGHT {
-            signals.push(Signal::RsiOverbought { period: 6, value: rsi6 });
-        } else if rsi6 <= RSI_OVERSOLD {
-            signals.push(Signal::RsiOversold { period: 6, value: rsi6 });
+fn detect_rsi_signals(current: &TechnicalIndicators, params: &DailySignalParams, signals: &mut Vec<Signal>) {
+    for (period, value) in [(6, current.rsi6), (12, current.rsi12)] {
+        let Some(value) = value else { continue };
+        let signal = if value >= params.rsi_overbought {
+            Signal::RsiOverbought { period, value }
+        } else if value <= params.rsi_oversold {
+            Signal::RsiOversold { period, value }
+        } else {
+            continue;
+        };
+        // 配置周期与 6/12 相同时，穿越信号已推送过
+        if !signals.contains(&signal) {
+            signals.push(signal);
         }
     }
+}
 
-    if let Some(rsi12) = current.rsi12 {
-        if rsi12 >= RSI_OVERBOUGHT {
+/// 检测可配置周期 RSI 穿越超买/超卖线（仅穿越当日触发，区间内不重复）
+fn detect_rsi_cross(
+    current: &TechnicalIndicators,
+    previous: &TechnicalIndicators,
+    params: &DailySignalParams,
+    signals: &mut Vec<Signal>,
+) {
+    if let (Some(cur), Some(prev)) = (current.rsi, previous.rsi) {
+        // 上穿超买线（看空）
+        if prev < params.rsi_overbought && cur >= params.rsi_overbought {
             signals.push(Signal::RsiOverbought {
-                period: 12,
-                value: rsi12,
+                period: params.rsi_period,
+                value: cur,
             });
-        } else if rsi12 <= RSI_OVERSOLD {
+        }
+        // 下穿超卖线（看多）
+        if prev > params.rsi_oversold && cur <= params.rsi_oversold {
             signals.push(Signal::RsiOversold {
-                period: 12,
-                value: rsi12,
+                period: params.rsi_period,
+                value: cur,
             });
         }
     }
 }
 
+/// 检测收盘价突破布林带上/下轨：仅前一根收盘仍在轨道内的突破当日触发，轨道外不重复
+///
+/// K 线不足一个布林窗口时轨道为 None，不触发
+fn detect_bollinger_breakout(
+    current: &TechnicalIndicators,
+    previous: &TechnicalIndicators,
+    prices: &[f64],
+    signals: &mut Vec<Signal>,
+) {
+    let [.., prev_close, close] = prices else {
+        return;
+    };
+    if let (Some(upper), Some(lower), Some(prev_upper), Some(prev_lower)) = (
+        current.boll_upper,
+        current.boll_lower,
+        previous.boll_upper,
+        previous.boll_lower,
+    ) {
+        if *close > upper && *prev_close <= prev_upper {
+            signals.push(Signal::BollingerBreakout { upper: true });
+        } else if *close < lower && *prev_close >= prev_lower {
+            signals.push(Signal::BollingerBreakout { upper: false });
+        }
+    }
+}
+
+/// 检测最后一根K线的跳空缺口：开盘价相对前一根收盘价的幅度超过 `threshold_pct`
+pub fn detect_gap(klines: &[DailyKline], threshold_pct: f64) -> Option<Signal> {
+    let [.., prev, last] = klines else {
+        return None;
+    };
+    if prev.close <= 0.0 || last.open <= 0.0 {
+        return None;
+    }
+    let change_pct = (last.open - prev.close) / prev.close * 100.0;
+    (change_pct.abs() > threshold_pct).then(|| Signal::Gap {
+        pct: change_pct.abs(),
+        up: change_pct > 0.0,
+        date: last.date.clone(),
+    })
+}
+
+/// 检测日线量能：最新成交量 ≥ ADV × surge_ratio 为放量，≤ ADV × dryup_ratio 为缩量
+pub fn detect_daily_volume(last_volume: u64, adv: f64, surge_ratio: f64, dryup_ratio: f64) -> Option<Signal> {
+    if adv <= 0.0 {
+        return None;
+    }
+    let ratio = last_volume as f64 / adv;
+    if ratio >= surge_ratio {
+        Some(Signal::DailyVolume { ratio, surge: true })
+    } else if ratio <= dryup_ratio {
+        Some(Signal::DailyVolume { ratio, surge: false })
+    } else {
+        None
+    }
+}
+
 /// 扫描 MACD DIF/DEA 序列，检测最近一次 MS-MACD 动能拐点首日
 ///
 /// 从后往前扫描最近 `lookback` 根 K 线，找到拐点的**首次发生日**：
@@ -199,6 +355,17 @@ fn detect_volume_spike(volumes: &[u64], signals: &mut Vec<Signal>) {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::models::Sentiment;
+
+    /// 默认参数下检测信号
+    fn detect(
+        current: &TechnicalIndicators,
+        previous: Option<&TechnicalIndicators>,
+        prices: &[f64],
+        volumes: &[u64],
+    ) -> Vec<Signal> {
+        detect_signals(current, previous, prices, volumes, &DailySignalParams::default())
+    }
 
     #[test]
     fn test_ma_golden_cross() {
@@ -213,7 +380,7 @@ mod tests {
             ..Default::default()
         };
 
-        let signals = detect_signals(&current, Some(&prev), &[], &[]);
+        let signals = detect(&current, Some(&prev), &[], &[]);
         assert!(signals
             .iter()
             .any(|s| matches!(s, Signal::MaGoldenCross { short: 5, long: 10 })));
@@ -232,12 +399,41 @@ mod tests {
             ..Default::default()
         };
 
-        let signals = detect_signals(&current, Some(&prev), &[], &[]);
+        let signals = detect(&current, Some(&prev), &[], &[]);
         assert!(signals
             .iter()
             .any(|s| matches!(s, Signal::MaDeathCross { short: 5, long: 10 })));
     }
 
+    #[test]
+    fn test_configured_ma_cross_on_last_bar() {
+        let params = DailySignalParams {
+            ma_fast: 3,
+            ma_slow: 8,
+            ..Default::default()
+        };
+        let ti = TechnicalIndicators::default();
+        let flat = [100.0; 10];
+
+        // 横盘后放量大涨：MA3 上穿 MA8 → 金叉
+        let rising = [&flat[..], &[110.0]].concat();
+        let signals = detect_signals(&ti, Some(&ti), &rising, &[], &params);
+        assert_eq!(signals, vec![Signal::MaGoldenCross { short: 3, long: 8 }]);
+        assert_eq!(signals[0].to_string(), "MA3/8 金叉");
+
+        // 次日快线仍在慢线上方 → 不重复触发
+        let next = [&rising[..], &[111.0]].concat();
+        assert!(detect_signals(&ti, Some(&ti), &next, &[], &params).is_empty());
+
+        // 横盘后大跌 → 死叉
+        let falling = [&flat[..], &[90.0]].concat();
+        let signals = detect_signals(&ti, Some(&ti), &falling, &[], &params);
+        assert_eq!(signals, vec![Signal::MaDeathCross { short: 3, long: 8 }]);
+
+        // K 线不足慢线周期 → 不检测
+        assert!(detect_signals(&ti, Some(&ti), &[100.0, 100.0, 110.0], &[], &params).is_empty());
+    }
+
     #[test]
     fn test_rsi_overbought() {
         let current = TechnicalIndicators {
@@ -245,18 +441,138 @@ mod tests {
             ..Default::default()
         };
 
-        let signals = detect_signals(&current, None, &[], &[]);
+        let signals = detect(&current, None, &[], &[]);
         assert!(signals
             .iter()
             .any(|s| matches!(s, Signal::RsiOverbought { period: 6, .. })));
     }
 
+    #[test]
+    fn test_rsi_cross_fires_only_on_crossing_bar() {
+        let params = DailySignalParams {
+            rsi_overbought: 80.0,
+            rsi_oversold: 20.0,
+            ..Default::default()
+        };
+        let at = |v: f64| TechnicalIndicators {
+            rsi: Some(v),
+            ..Default::default()
+        };
+
+        // 上穿 80 → 超买（看空）；75 未达自定义阈值
+        let signals = detect_signals(&at(82.0), Some(&at(78.0)), &[], &[], &params);
+        assert_eq!(
+            signals,
+            vec![Signal::RsiOverbought {
+                period: 14,
+                value: 82.0
+            }]
+        );
+        assert_eq!(signals[0].sentiment(), Sentiment::Bearish);
+        assert!(detect_signals(&at(75.0), Some(&at(65.0)), &[], &[], &params).is_empty());
+
+        // 已在超买区内不重复触发
+        assert!(detect_signals(&at(85.0), Some(&at(82.0)), &[], &[], &params).is_empty());
+
+        // 下穿 20 → 超卖（看多）
+        let signals = detect_signals(&at(18.0), Some(&at(25.0)), &[], &[], &params);
+        assert_eq!(
+            signals,
+            vec![Signal::RsiOversold {
+                period: 14,
+                value: 18.0
+            }]
+        );
+        assert_eq!(signals[0].sentiment(), Sentiment::Bullish);
+    }
+
+    #[test]
+    fn test_bollinger_breakout() {
+        let bands = TechnicalIndicators {
+            boll_upper: Some(110.0),
+            boll_mid: Some(100.0),
+            boll_lower: Some(90.0),
+            ..Default::default()
+        };
+
+        let signals = detect(&bands, Some(&bands), &[100.0, 111.0], &[]);
+        assert_eq!(signals, vec![Signal::BollingerBreakout { upper: true }]);
+        assert_eq!(signals[0].sentiment(), Sentiment::Bearish);
+
+        let signals = detect(&bands, Some(&bands), &[100.0, 89.0], &[]);
+        assert_eq!(signals, vec![Signal::BollingerBreakout { upper: false }]);
+        assert_eq!(signals[0].sentiment(), Sentiment::Bullish);
+
+        // 轨道内 / 轨道未算出（K 线不足）/ 无前一根均不触发
+        assert!(detect(&bands, Some(&bands), &[100.0, 110.0], &[]).is_empty());
+        let no_bands = TechnicalIndicators::default();
+        assert!(detect(&no_bands, Some(&no_bands), &[100.0, 200.0], &[]).is_empty());
+        assert!(detect(&bands, None, &[100.0, 111.0], &[]).is_empty());
+    }
+
+    #[test]
+    fn test_bollinger_breakout_fires_only_on_crossing_day() {
+        let bands = TechnicalIndicators {
+            boll_upper: Some(110.0),
+            boll_mid: Some(100.0),
+            boll_lower: Some(90.0),
+            ..Default::default()
+        };
+
+        // 前一根已在上轨之上，次日仍在轨外 → 不重复触发
+        assert!(detect(&bands, Some(&bands), &[112.0, 115.0], &[]).is_empty());
+        assert!(detect(&bands, Some(&bands), &[88.0, 85.0], &[]).is_empty());
+
+        // 回到轨道内后再次突破 → 重新触发
+        let signals = detect(&bands, Some(&bands), &[109.0, 115.0], &[]);
+        assert_eq!(signals, vec![Signal::BollingerBreakout { upper: true }]);
+
+        // 前一根轨道按前一日计算：前一日上轨更高、收盘在其内 → 今日突破新上轨算穿越
+        let prev = TechnicalIndicators {
+            boll_upper: Some(113.0),
+            ..bands.clone()
+        };
+        let signals = detect(&bands, Some(&prev), &[112.0, 115.0], &[]);
+        assert_eq!(signals, vec![Signal::BollingerBreakout { upper: true }]);
+    }
+
+    #[test]
+    fn test_gap_uses_last_two_bars() {
+        let bar = |date: &str, open: f64, close: f64| DailyKline {
+            open,
+            close,
+            high: open.max(close),
+            low: open.min(close),
+            volume: 1_000_000,
+            turnover: 0.0,
+            date: date.to_string(),
+        };
+
+        // 100 → 开盘 104：高开 4% > 3%，带缺口日期
+        let klines = [bar("2025-01-14", 99.0, 100.0), bar("2025-01-15", 104.0, 103.0)];
+        let signal = detect_gap(&klines, 3.0).unwrap();
+        assert!(matches!(&signal, Signal::Gap { up: true, date, .. } if date == "2025-01-15"));
+        assert!((signal.magnitude().unwrap() - 4.0).abs() < 1e-9);
+        assert_eq!(signal.sentiment(), Sentiment::Bullish);
+        assert_eq!(signal.to_string(), "跳空高开4.0%(01-15)");
+
+        // 低开 5% → 偏空
+        let klines = [bar("2025-01-14", 99.0, 100.0), bar("2025-01-15", 95.0, 96.0)];
+        let signal = detect_gap(&klines, 3.0).unwrap();
+        assert_eq!(signal.sentiment(), Sentiment::Bearish);
+
+        // 未超过阈值 / 只有一根K线
+        let klines = [bar("2025-01-14", 99.0, 100.0), bar("2025-01-15", 102.0, 103.0)];
+        assert_eq!(detect_gap(&klines, 3.0), None);
+        assert_eq!(detect_gap(&klines[1..], 3.0), None);
+    }
+
     #[test]
     fn test_volume_spike() {
         let volumes = vec![100, 100, 100, 100, 100, 300]; // 3x 放量
         let current = TechnicalIndicators::default();
 
-        let signals = detect_signals(&current, None, &[], &volumes);
+        let signals = detect(&current, None, &[], &volumes);
         assert!(signals.iter().any(|s| matches!(s, Signal::VolumeSpike { .. })));
     }
 
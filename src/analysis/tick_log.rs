@@ -0,0 +1,180 @@
+//! tick 信号日志：每个触发的 tick 信号以 JSON 行追加到 `<export.tick_log_dir>/tick_signals_YYYYMMDD.jsonl`
+//!
+//! 按应用时区的自然日分文件；启动时可读回显示窗口覆盖的日期文件，恢复窗口内的 tick 信号（重启后不丢失）。
+
+use std::collections::BTreeMap;
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::models::{Signal, StockCode};
+
+/// 一条 tick 信号记录
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TickSignalEntry {
+    pub timestamp: DateTime<Local>,
+    pub code: StockCode,
+    pub name: String,
+    pub signal: Signal,
+    /// 触发时的价格
+    pub price: f64,
+}
+
+/// 指定日期的日志文件路径
+pub fn file_path(dir: &Path, date: NaiveDate) -> PathBuf {
+    dir.join(format!("tick_signals_{}.jsonl", date.format("%Y%m%d")))
+}
+
+/// 追加写入一批记录（按各自触发时间的应用时区日期分文件，目录不存在时创建）
+pub fn append(dir: &Path, entries: &[TickSignalEntry]) -> Result<()> {
+    if entries.is_empty() {
+        return Ok(());
+    }
+    std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+
+    // 按日期拼好后每个文件一次写入，避免半行
+    let mut by_date: BTreeMap<NaiveDate, String> = BTreeMap::new();
+    for entry in entries {
+        let buf = by_date
+            .entry(crate::models::to_app_time(&entry.timestamp).date_naive())
+            .or_default();
+        buf.push_str(&serde_json::to_string(entry)?);
+        buf.push('\n');
+    }
+    for (date, buf) in by_date {
+        let path = file_path(dir, date);
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .and_then(|mut f| f.write_all(buf.as_bytes()))
+            .with_context(|| format!("写入 tick 信号日志 {} 失败", path.display()))?;
+    }
+    Ok(())
+}
+
+/// 读取晚于 `since` 的记录：从 `since` 所在日期到今天逐日读取（按日期、写入顺序），
+/// 跨午夜的显示窗口不会漏掉前一天的记录；文件不存在跳过，无法解析的行跳过
+pub fn read_since(dir: &Path, since: DateTime<Local>) -> Result<Vec<TickSignalEntry>> {
+    let first = crate::models::to_app_time(&since).date_naive();
+    let last = crate::models::app_today().max(first);
+
+    let mut entries = Vec::new();
+    for date in first.iter_days().take_while(|date| *date <= last) {
+        read_file_since(&file_path(dir, date), since, &mut entries)?;
+    }
+    Ok(entries)
+}
+
+/// 读取单个日期文件中晚于 `since` 的记录
+fn read_file_since(path: &Path, since: DateTime<Local>, entries: &mut Vec<TickSignalEntry>) -> Result<()> {
+    let file = match std::fs::File::open(path) {
+        Ok(f) => f,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e).with_context(|| format!("无法读取 tick 信号日志 {}", path.display())),
+    };
+
+    for (i, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<TickSignalEntry>(&line) {
+            Ok(entry) if entry.timestamp > since => entries.push(entry),
+            Ok(_) => {}
+            Err(e) => warn!(
+                "tick 信号日志 {} 第 {} 行无法解析，已跳过: {}",
+                path.display(),
+                i + 1,
+                e
+            ),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Market, Sentiment};
+    use chrono::TimeZone;
+
+    fn entry(signal: Signal, at: DateTime<Local>) -> TickSignalEntry {
+        TickSignalEntry {
+            timestamp: at,
+            code: StockCode::new(Market::HK, "00700"),
+            name: "腾讯控股".to_string(),
+            signal,
+            price: 388.2,
+        }
+    }
+
+    #[test]
+    fn test_append_and_read_since() {
+        let dir = std::env::temp_dir().join(format!("qtrade-test-tick-log-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        // 固定在午间，避免跨日分文件影响断言
+        let now = Local.with_ymd_and_hms(2025, 1, 15, 12, 0, 0).unwrap();
+        // 目录/文件不存在
+        assert!(read_since(&dir, now - chrono::Duration::minutes(5)).unwrap().is_empty());
+
+        let old = entry(
+            Signal::RapidMove { change_pct: 1.5 },
+            now - chrono::Duration::minutes(10),
+        );
+        let recent = [
+            entry(
+                Signal::VolumeSpike {
+                    ratio: 12.0,
+                    price: 388.2,
+                    delta: 5000,
+                },
+                now,
+            ),
+            entry(
+                Signal::Custom {
+                    name: "动量".to_string(),
+                    sentiment: Sentiment::Bullish,
+                },
+                now,
+            ),
+        ];
+        append(&dir, std::slice::from_ref(&old)).unwrap();
+        append(&dir, &recent).unwrap();
+
+        // 只读回显示窗口内的记录，信号字段完整往返
+        assert_eq!(read_since(&dir, now - chrono::Duration::minutes(5)).unwrap(), recent);
+        assert_eq!(read_since(&dir, now - chrono::Duration::minutes(30)).unwrap().len(), 3);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_read_since_spans_midnight() {
+        let dir = std::env::temp_dir().join(format!("qtrade-test-tick-log-midnight-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let midnight = Local.with_ymd_and_hms(2025, 1, 16, 0, 0, 0).unwrap();
+        let before = entry(
+            Signal::RapidMove { change_pct: 1.5 },
+            midnight - chrono::Duration::minutes(2),
+        );
+        let after = entry(
+            Signal::RapidMove { change_pct: -2.0 },
+            midnight + chrono::Duration::minutes(2),
+        );
+        append(&dir, &[before.clone(), after.clone()]).unwrap();
+
+        // 窗口从前一天 23:55 开始：两天的文件都要读
+        let since = midnight - chrono::Duration::minutes(5);
+        assert_eq!(read_since(&dir, since).unwrap(), [before, after]);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -1,6 +1,13 @@
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::models::{DataSource, Market, StockCode};
+
+/// 内置交易日历的市场（半日市、常规时段门控、指数排除仅支持这些市场）
+const SESSION_MARKETS: [Market; 4] = [Market::HK, Market::SH, Market::SZ, Market::US];
 
 /// 应用配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -32,40 +39,187 @@ pub struct AppConfig {
     /// MCP 服务器配置
     #[serde(default)]
     pub mcp: McpConfig,
+
+    /// OCR 数据源配置
+    #[serde(default)]
+    pub ocr: OcrConfig,
+
+    /// 导出配置
+    #[serde(default)]
+    pub export: ExportConfig,
+
+    /// 配置文件路径（`load` 时记录，用于热加载；默认配置为 None）
+    #[serde(skip)]
+    pub path: Option<PathBuf>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GeneralConfig {
-    /// 日志级别
+    /// 日志级别，支持 EnvFilter 指令按模块覆盖，如 "info,qtrade::futu::openapi=debug"
     #[serde(default = "default_log_level")]
     pub log_level: String,
+
+    /// 日志格式: "pretty"（默认，人类可读）| "json"（结构化，便于日志管道采集）
+    #[serde(default = "default_log_format")]
+    pub log_format: String,
+
+    /// 时区（IANA 名称，如 "Asia/Hong_Kong"），留空则使用系统本地时区
+    ///
+    /// 影响时间显示、日期计算（日K线截止日、提醒按日去重）。
+    pub timezone: Option<String>,
+
+    /// 半日市日历：提前收盘的交易日（交易时段判断按此提前收市）
+    #[serde(default)]
+    pub half_days: Vec<HalfDayConfig>,
+}
+
+/// 半日市条目，如 `{ market = "HK", date = "2025-12-24", close = "12:00" }`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HalfDayConfig {
+    /// 市场："HK" | "SH" | "SZ"（沪深任一即对整个 A 股生效）| "US"
+    pub market: String,
+    /// 日期（交易所本地），"YYYY-MM-DD"
+    pub date: String,
+    /// 提前收盘时间（交易所本地），"HH:MM"
+    pub close: String,
 }
 
 impl Default for GeneralConfig {
     fn default() -> Self {
         Self {
             log_level: default_log_level(),
+            log_format: default_log_format(),
+            timezone: None,
+            half_days: Vec::new(),
         }
     }
 }
 
+impl GeneralConfig {
+    /// 是否输出 JSON 格式日志，未知格式返回错误
+    pub fn json_logs(&self) -> Result<bool> {
+        match self.log_format.trim() {
+            "" | "pretty" => Ok(false),
+            "json" => Ok(true),
+            other => anyhow::bail!(
+                "无效的日志格式 general.log_format = {:?}（可选 \"pretty\" / \"json\"）",
+                other
+            ),
+        }
+    }
+
+    /// 解析配置的时区，未配置返回 None，名称无效返回错误
+    pub fn parse_timezone(&self) -> Result<Option<chrono_tz::Tz>> {
+        match self.timezone.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+            Some(name) => name
+                .parse::<chrono_tz::Tz>()
+                .map(Some)
+                .map_err(|e| anyhow::anyhow!("无效的时区 general.timezone = {:?}: {}", name, e)),
+            None => Ok(None),
+        }
+    }
+
+    /// 解析半日市日历，市场/日期/时间无效返回错误
+    pub fn parse_half_days(&self) -> Result<Vec<crate::models::HalfDay>> {
+        use chrono::Timelike;
+
+        self.half_days
+            .iter()
+            .map(|d| {
+                let market = Market::from_prefix(d.market.trim())
+                    .filter(|m| SESSION_MARKETS.contains(m))
+                    .ok_or_else(|| {
+                        anyhow::anyhow!(
+                            "general.half_days 市场 {:?} 无效（可选 \"HK\" / \"SH\" / \"SZ\" / \"US\"）",
+                            d.market
+                        )
+                    })?;
+                let date = chrono::NaiveDate::parse_from_str(d.date.trim(), "%Y-%m-%d")
+                    .map_err(|e| anyhow::anyhow!("general.half_days 日期 {:?} 无效: {}", d.date, e))?;
+                let close = chrono::NaiveTime::parse_from_str(d.close.trim(), "%H:%M")
+                    .map_err(|e| anyhow::anyhow!("general.half_days 收盘时间 {:?} 无效: {}", d.close, e))?;
+                Ok(crate::models::HalfDay {
+                    market,
+                    date,
+                    close_hhmm: close.hour() * 100 + close.minute(),
+                })
+            })
+            .collect()
+    }
+}
+
+/// 可作为实时行情来源的数据源（`data_source.source` / `fallback_sources`）
+pub const LIVE_SOURCES: [&str; 3] = ["accessibility", "openapi", "ocr"];
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DataSourceConfig {
     /// 数据源类型: "accessibility" | "openapi"
     #[serde(default = "default_source")]
     pub source: String,
 
+    /// 备用数据源（按顺序），主数据源缺失的股票依次由备用数据源补齐，如 `["ocr"]`
+    ///
+    /// 适合 OpenAPI 无美股行情权限时用 OCR 补齐美股；为空时只用主数据源。
+    #[serde(default)]
+    pub fallback_sources: Vec<String>,
+
     /// 数据刷新间隔（秒）
     #[serde(default = "default_refresh_interval")]
     pub refresh_interval_secs: u64,
+
+    /// 各数据源自身的行情延迟（秒），如 `{ openapi = 0, ocr = 900 }`；非 0 的数据源在现价后标注 "延N分"
+    #[serde(default)]
+    pub latency_secs: BTreeMap<String, u64>,
+
+    /// 行情超过此秒数未刷新时在现价后标注 "滞后N秒"（缓存数据除外），0 关闭
+    #[serde(default = "default_stale_after_secs")]
+    pub stale_after_secs: u64,
 }
 
 impl Default for DataSourceConfig {
     fn default() -> Self {
         Self {
             source: default_source(),
+            fallback_sources: Vec::new(),
             refresh_interval_secs: default_refresh_interval(),
+            latency_secs: BTreeMap::new(),
+            stale_after_secs: default_stale_after_secs(),
+        }
+    }
+}
+
+impl DataSourceConfig {
+    /// 校验数据源配置
+    pub fn validate(&self) -> Result<()> {
+        for key in self.latency_secs.keys() {
+            if DataSource::from_config_key(key).is_none() {
+                anyhow::bail!(
+                    "data_source.latency_secs 数据源 {:?} 无效（可选 \"accessibility\" / \"openapi\" / \"ocr\" / \"cache\" / \"demo\"）",
+                    key
+                );
+            }
+        }
+        for (i, source) in self.fallback_sources.iter().enumerate() {
+            if !LIVE_SOURCES.contains(&source.as_str()) {
+                anyhow::bail!(
+                    "data_source.fallback_sources 数据源 {:?} 无效（可选 \"accessibility\" / \"openapi\" / \"ocr\"）",
+                    source
+                );
+            }
+            if *source == self.source || self.fallback_sources[..i].contains(source) {
+                anyhow::bail!("data_source.fallback_sources 数据源 {:?} 重复", source);
+            }
         }
+        Ok(())
+    }
+
+    /// 声明了非 0 延迟的数据源（仪表盘据此标注延迟行情）
+    pub fn source_latency(&self) -> HashMap<DataSource, Duration> {
+        self.latency_secs
+            .iter()
+            .filter(|(_, secs)| **secs > 0)
+            .filter_map(|(key, secs)| Some((DataSource::from_config_key(key)?, Duration::from_secs(*secs))))
+            .collect()
     }
 }
 
@@ -77,6 +231,10 @@ pub struct FutuConfig {
     /// 指定用户 ID（留空则自动选择最近活跃的）
     pub user_id: Option<String>,
 
+    /// 同时读取多个用户（账户）的自选股并按代码去重合并，`["all"]` 表示所有用户；非空时优先于 `user_id`
+    #[serde(default)]
+    pub user_ids: Vec<String>,
+
     /// FutuOpenD 连接地址
     #[serde(default = "default_opend_host")]
     pub opend_host: String,
@@ -84,6 +242,30 @@ pub struct FutuConfig {
     /// FutuOpenD 连接端口
     #[serde(default = "default_opend_port")]
     pub opend_port: u16,
+
+    /// 无实时行情的股票（如无权限市场）用 plist 缓存价格作为低频兜底，随 plist 重载刷新
+    #[serde(default)]
+    pub cached_price_fallback: bool,
+
+    /// 断线重连的最大退避（秒，从 1s 起翻倍，请求与推送循环共用），0 表示推送循环不重连
+    #[serde(default = "default_push_reconnect_max_secs")]
+    pub push_reconnect_max_secs: u64,
+
+    /// 调试：每个 proto_id 缓存最近 N 个 OpenAPI 原始响应（解码出错时写入日志，`test-api --dump-raw` 打印），0 关闭
+    #[serde(default)]
+    pub raw_response_log: usize,
+
+    /// 不订阅这些市场的指数（按 `StockCode::is_index` 判断），默认排除港股指数（800xxx）；设为 `[]` 可盯指数
+    #[serde(default = "default_exclude_index_markets")]
+    pub exclude_index_markets: Vec<String>,
+
+    /// 不订阅代码以这些前缀开头的股票（不区分市场），默认不排除
+    #[serde(default)]
+    pub exclude_code_prefixes: Vec<String>,
+
+    /// 交易密码的 32 位 MD5（`test-trade --opend-acc` 向真实账户下单前解锁交易），留空则不解锁
+    #[serde(default)]
+    pub trade_pwd_md5: Option<String>,
 }
 
 impl Default for FutuConfig {
@@ -91,9 +273,58 @@ impl Default for FutuConfig {
         Self {
             data_path: None,
             user_id: None,
+            user_ids: Vec::new(),
             opend_host: default_opend_host(),
             opend_port: default_opend_port(),
+            cached_price_fallback: false,
+            push_reconnect_max_secs: default_push_reconnect_max_secs(),
+            raw_response_log: 0,
+            exclude_index_markets: default_exclude_index_markets(),
+            exclude_code_prefixes: Vec::new(),
+            trade_pwd_md5: None,
+        }
+    }
+}
+
+impl FutuConfig {
+    /// 生效的用户选择：`user_ids` 非空时用它，否则退回单个 `user_id`（都为空 = 自动选择最近活跃的用户）
+    pub fn selected_user_ids(&self) -> Vec<String> {
+        if self.user_ids.is_empty() {
+            self.user_id.iter().cloned().collect()
+        } else {
+            self.user_ids.clone()
+        }
+    }
+
+    /// 是否跳过该代码（不订阅）：未知市场、被排除市场的指数、命中排除前缀
+    pub fn is_excluded(&self, code: &StockCode) -> bool {
+        code.market == Market::Unknown
+            || (code.is_index()
+                && self
+                    .exclude_index_markets
+                    .iter()
+                    .any(|m| Market::from_prefix(m.trim()) == Some(code.market)))
+            || self
+                .exclude_code_prefixes
+                .iter()
+                .any(|p| !p.is_empty() && code.code.starts_with(p.as_str()))
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        for m in &self.exclude_index_markets {
+            if Market::from_prefix(m.trim()).is_none_or(|m| !SESSION_MARKETS.contains(&m)) {
+                anyhow::bail!(
+                    "futu.exclude_index_markets 市场 {:?} 无效（可选 \"HK\" / \"SH\" / \"SZ\" / \"US\"）",
+                    m
+                );
+            }
         }
+        if let Some(pwd) = &self.trade_pwd_md5 {
+            if pwd.len() != 32 || !pwd.chars().all(|c| c.is_ascii_hexdigit()) {
+                anyhow::bail!("futu.trade_pwd_md5 须为 32 位十六进制 MD5");
+            }
+        }
+        Ok(())
     }
 }
 
@@ -114,8 +345,72 @@ pub struct AlertsConfig {
     /// 多级涨跌幅阈值 (%)，如 [3.0, 5.0, 7.0, 10.0]
     pub change_thresholds: Option<Vec<f64>>,
 
-    /// Webhook URL（可选）
+    /// 美股非盘中时段是否按盘前/盘后/夜盘价相对收盘价的涨跌幅评估阈值（与仪表盘现价列一致）
+    #[serde(default)]
+    pub include_extended_hours: bool,
+
+    /// Webhook URL（可选，等价于 `channels` 中的一个 webhook 渠道）
     pub webhook_url: Option<String>,
+
+    /// 通知渠道（webhook / telegram / email），每条提醒发送到所有渠道
+    #[serde(default)]
+    pub channels: Vec<NotifyChannelConfig>,
+
+    /// 单只股票的涨跌幅阈值 (%)，键为 "HK.00700" 形式的代码；替代第一档阈值，其余档位按比例放大
+    #[serde(default)]
+    pub thresholds: HashMap<String, f64>,
+
+    /// 价位穿越提醒，如 `{ code = "HK.00700", above = 400.0, below = 350.0 }`
+    #[serde(default)]
+    pub price_levels: Vec<PriceLevelConfig>,
+
+    /// 提醒日志（JSONL，每条提醒追加一行，`qtrade alerts-history` 查看），留空不落盘
+    #[serde(default = "default_alert_log_path")]
+    pub log_path: String,
+}
+
+/// 单只股票的价位提醒（上穿 `above` / 下穿 `below`，至少填一个）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriceLevelConfig {
+    /// 股票代码，如 "HK.00700"
+    pub code: String,
+    pub above: Option<f64>,
+    pub below: Option<f64>,
+}
+
+/// 通知渠道，如 `{ type = "telegram", bot_token = "123:abc", chat_id = "42" }`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NotifyChannelConfig {
+    /// 渠道类型："webhook" | "telegram" | "email"
+    #[serde(rename = "type")]
+    pub kind: String,
+
+    /// webhook：URL（飞书/Slack 格式按 URL 自动选择）
+    pub url: Option<String>,
+
+    /// telegram：Bot token（@BotFather 获取）
+    pub bot_token: Option<String>,
+
+    /// telegram：接收消息的 chat id
+    pub chat_id: Option<String>,
+
+    /// email：SMTP 服务器
+    pub smtp_host: Option<String>,
+
+    /// email：SMTP 端口，465 为隐式 TLS，其余走 STARTTLS
+    #[serde(default = "default_smtp_port")]
+    pub smtp_port: u16,
+
+    /// email：SMTP 登录用户名与密码（留空则不认证）
+    pub username: Option<String>,
+    pub password: Option<String>,
+
+    /// email：发件人，如 "qtrade <bot@example.com>"
+    pub from: Option<String>,
+
+    /// email：收件人列表
+    #[serde(default)]
+    pub to: Vec<String>,
 }
 
 impl Default for AlertsConfig {
@@ -125,7 +420,12 @@ impl Default for AlertsConfig {
             cooldown_secs: default_cooldown(),
             change_threshold_pct: default_change_threshold(),
             change_thresholds: None,
+            include_extended_hours: false,
             webhook_url: None,
+            channels: Vec::new(),
+            thresholds: HashMap::new(),
+            price_levels: Vec::new(),
+            log_path: default_alert_log_path(),
         }
     }
 }
@@ -137,6 +437,69 @@ impl AlertsConfig {
             .clone()
             .unwrap_or_else(|| vec![self.change_threshold_pct])
     }
+
+    /// 提醒日志路径（`log_path` 为空表示不落盘）
+    pub fn log_file(&self) -> Option<std::path::PathBuf> {
+        let path = self.log_path.trim();
+        (!path.is_empty()).then(|| std::path::PathBuf::from(path))
+    }
+
+    /// 校验单股阈值、价位提醒与通知渠道的必填字段
+    pub fn validate(&self) -> Result<()> {
+        let mut codes: Vec<&String> = self.thresholds.keys().collect();
+        codes.sort();
+        for code in codes {
+            if crate::data::parser::parse_stock_code(code).is_none() {
+                anyhow::bail!("alerts.thresholds 股票代码 {:?} 无效（应为 \"HK.00700\" 形式）", code);
+            }
+            if self.thresholds[code] <= 0.0 {
+                anyhow::bail!("alerts.thresholds.{:?} ({}) 必须大于 0", code, self.thresholds[code]);
+            }
+        }
+        for level in &self.price_levels {
+            if crate::data::parser::parse_stock_code(&level.code).is_none() {
+                anyhow::bail!(
+                    "alerts.price_levels 股票代码 {:?} 无效（应为 \"HK.00700\" 形式）",
+                    level.code
+                );
+            }
+            match (level.above, level.below) {
+                (None, None) => anyhow::bail!("alerts.price_levels {:?} 需至少设置 above / below 之一", level.code),
+                (Some(above), Some(below)) if above <= below => anyhow::bail!(
+                    "alerts.price_levels {:?} above ({}) 必须大于 below ({})",
+                    level.code,
+                    above,
+                    below
+                ),
+                _ => {}
+            }
+        }
+        for (i, ch) in self.channels.iter().enumerate() {
+            let required: Vec<(&str, bool)> = match ch.kind.as_str() {
+                "webhook" => vec![("url", ch.url.is_some())],
+                "telegram" => vec![("bot_token", ch.bot_token.is_some()), ("chat_id", ch.chat_id.is_some())],
+                "email" => vec![
+                    ("smtp_host", ch.smtp_host.is_some()),
+                    ("from", ch.from.is_some()),
+                    ("to", !ch.to.is_empty()),
+                ],
+                other => anyhow::bail!(
+                    "alerts.channels[{}].type = {:?} 无效（可选 \"webhook\" / \"telegram\" / \"email\"）",
+                    i,
+                    other
+                ),
+            };
+            let missing: Vec<&str> = required
+                .into_iter()
+                .filter(|(_, present)| !present)
+                .map(|(field, _)| field)
+                .collect();
+            if !missing.is_empty() {
+                anyhow::bail!("alerts.channels[{}]（{}）缺少 {}", i, ch.kind, missing.join(" / "));
+            }
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -148,6 +511,30 @@ pub struct UiConfig {
     /// 是否显示技术指标列
     #[serde(default = "default_true")]
     pub show_indicators: bool,
+
+    /// 美股非盘中时段是否以盘前/盘后/夜盘价替代现价列（运行时按 `e` 切换）
+    #[serde(default = "default_true")]
+    pub show_extended_hours: bool,
+
+    /// 选中行自动跟随："off" | "signal"（最近触发 tick 信号的股票）| "mover"（本次更新涨跌幅变动最大的股票）
+    #[serde(default = "default_follow_action")]
+    pub follow_action: String,
+
+    /// 手动按键后暂停自动跟随的秒数
+    #[serde(default = "default_follow_pause_secs")]
+    pub follow_pause_secs: u64,
+
+    /// 每只股票保留的日线信号上限（超出的旧信号以 "+N" 提示），0 表示与 analysis.daily_kline_days 一致
+    #[serde(default)]
+    pub max_daily_signals_per_stock: usize,
+
+    /// 涨跌幅显示的小数位数（0-4）
+    #[serde(default = "default_change_pct_decimals")]
+    pub change_pct_decimals: usize,
+
+    /// 行情表格显示的列及顺序，可选值见 `UI_COLUMNS`（窄终端可去掉换手率/振幅等列）
+    #[serde(default = "default_ui_columns")]
+    pub columns: Vec<String>,
 }
 
 impl Default for UiConfig {
@@ -155,7 +542,45 @@ impl Default for UiConfig {
         Self {
             page_size: default_page_size(),
             show_indicators: true,
+            show_extended_hours: true,
+            follow_action: default_follow_action(),
+            follow_pause_secs: default_follow_pause_secs(),
+            max_daily_signals_per_stock: 0,
+            change_pct_decimals: default_change_pct_decimals(),
+            columns: default_ui_columns(),
+        }
+    }
+}
+
+impl UiConfig {
+    /// 校验 UI 配置
+    pub fn validate(&self) -> Result<()> {
+        match self.follow_action.as_str() {
+            "off" | "signal" | "mover" => {}
+            other => anyhow::bail!(
+                "ui.follow_action = {:?} 无效（可选 \"off\" / \"signal\" / \"mover\"）",
+                other
+            ),
+        }
+        if self.change_pct_decimals > MAX_CHANGE_PCT_DECIMALS {
+            anyhow::bail!(
+                "ui.change_pct_decimals = {} 无效（可选 0-{}）",
+                self.change_pct_decimals,
+                MAX_CHANGE_PCT_DECIMALS
+            );
+        }
+        if self.columns.is_empty() {
+            anyhow::bail!("ui.columns 不能为空");
         }
+        for (i, col) in self.columns.iter().enumerate() {
+            if !UI_COLUMNS.contains(&col.as_str()) {
+                anyhow::bail!("ui.columns 列 {:?} 无效（可选 {}）", col, UI_COLUMNS.join(" / "));
+            }
+            if self.columns[..i].contains(col) {
+                anyhow::bail!("ui.columns 列 {:?} 重复", col);
+            }
+        }
+        Ok(())
     }
 }
 
@@ -173,6 +598,54 @@ pub struct AnalysisConfig {
     #[serde(default = "default_daily_kline_refresh_minutes")]
     pub daily_kline_refresh_minutes: u64,
 
+    /// 市场权限探测策略："once"（探测结果在本次运行内复用）| "per_cycle"（每轮日K拉取前都探测）
+    #[serde(default = "default_permission_probe")]
+    pub permission_probe: String,
+
+    /// "once" 模式下探测结果的有效期（分钟），0 表示整个会话有效
+    #[serde(default)]
+    pub permission_probe_ttl_minutes: u64,
+
+    /// 日线 RSI 周期（超买/超卖穿越信号）
+    #[serde(default = "default_rsi_period")]
+    pub rsi_period: usize,
+
+    /// RSI 超买线，上穿时产生看空信号
+    #[serde(default = "default_rsi_overbought")]
+    pub rsi_overbought: f64,
+
+    /// RSI 超卖线，下穿时产生看多信号
+    #[serde(default = "default_rsi_oversold")]
+    pub rsi_oversold: f64,
+
+    /// 日线均线交叉的快线周期
+    #[serde(default = "default_ma_fast_period")]
+    pub ma_fast_period: usize,
+
+    /// 日线均线交叉的慢线周期（快线上穿为金叉，下穿为死叉）
+    #[serde(default = "default_ma_slow_period")]
+    pub ma_slow_period: usize,
+
+    /// 日线跳空缺口阈值 (%)：当日开盘相对前一日收盘的幅度超过此值时产生信号
+    #[serde(default = "default_gap_threshold_pct")]
+    pub gap_threshold_pct: f64,
+
+    /// 日线放量倍数：最新日K成交量 ≥ ADV × 此值时产生放量信号
+    #[serde(default = "default_daily_volume_surge_ratio")]
+    pub daily_volume_surge_ratio: f64,
+
+    /// 日线缩量倍数：最新日K成交量 ≤ ADV × 此值时产生缩量信号
+    #[serde(default = "default_daily_volume_dryup_ratio")]
+    pub daily_volume_dryup_ratio: f64,
+
+    /// 日线布林带窗口（K 线数），缓存不足此数量的股票不产生突破信号
+    #[serde(default = "default_boll_period")]
+    pub boll_period: usize,
+
+    /// 布林带标准差倍数（上下轨 = 中轨 ± 倍数 × 标准差）
+    #[serde(default = "default_boll_std_multiplier")]
+    pub boll_std_multiplier: f64,
+
     /// VWAP 偏离触发阈值 (%)
     #[serde(default = "default_vwap_deviation_pct")]
     pub vwap_deviation_pct: f64,
@@ -181,6 +654,10 @@ pub struct AnalysisConfig {
     #[serde(default = "default_vwap_reset_pct")]
     pub vwap_reset_pct: f64,
 
+    /// 滚动 VWAP 窗口（分钟）：>0 时 VWAP 偏离按最近 N 分钟的区间 VWAP 计算，0 = 当日累计 VWAP（默认）
+    #[serde(default)]
+    pub vwap_rolling_minutes: u64,
+
     /// 急涨急跌阈值 (%)
     #[serde(default = "default_rapid_move_pct")]
     pub rapid_move_pct: f64,
@@ -201,10 +678,19 @@ pub struct AnalysisConfig {
     #[serde(default = "default_rapid_move_min_change")]
     pub rapid_move_min_change: f64,
 
+    /// 数据断档阈值（秒）：同一股票相邻两个行情的时间间隔超过此值时清空价格窗口，
+    /// 断档前的价格不参与急涨急跌计算（重连、OCR 跳帧后的正常波动不会被误判为急涨急跌）。0 = 关闭
+    #[serde(default = "default_rapid_move_max_gap_secs")]
+    pub rapid_move_max_gap_secs: f64,
+
     /// 振幅突破阈值 (%)
     #[serde(default = "default_amplitude_breakout_pct")]
     pub amplitude_breakout_pct: f64,
 
+    /// 换手率异动阈值 (%)：当日累计换手率超过此值时产生信号（小盘股高换手往往意味着异常关注），0 = 关闭；指数不检测
+    #[serde(default = "default_turnover_rate_threshold")]
+    pub turnover_rate_threshold: f64,
+
     /// 量能突变倍数阈值（当前 tick 量速率 / 基线量速率）
     #[serde(default = "default_volume_spike_ratio")]
     pub volume_spike_ratio: f64,
@@ -228,6 +714,33 @@ pub struct AnalysisConfig {
     /// 信号检测预热 tick 数（启动后前 N 个 tick 不产生信号）
     #[serde(default = "default_warmup_ticks")]
     pub warmup_ticks: u32,
+
+    /// 仅在常规交易时段产生 tick 信号的市场（如 ["US"] 屏蔽盘前/盘后/夜盘的稀疏成交噪声），为空不限制
+    ///
+    /// 时段外的行情仍计入价格窗口与量能基线，只是不产生信号。
+    #[serde(default)]
+    pub regular_session_only: Vec<String>,
+
+    /// 单只股票的阈值覆盖，键为 "HK.00700" 形式的代码，未填写的阈值沿用上面的全局值
+    #[serde(default)]
+    pub overrides: std::collections::HashMap<String, ThresholdOverride>,
+}
+
+/// 单只股票的 tick 信号阈值覆盖（字段含义同 `AnalysisConfig` 同名字段）
+///
+/// 只覆盖判定阈值；窗口长度、基线时长等决定数据结构的参数仍为全局配置。
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ThresholdOverride {
+    pub vwap_deviation_pct: Option<f64>,
+    pub vwap_reset_pct: Option<f64>,
+    pub rapid_move_pct: Option<f64>,
+    pub rapid_move_reset_pct: Option<f64>,
+    pub rapid_move_efficiency: Option<f64>,
+    pub rapid_move_min_change: Option<f64>,
+    pub amplitude_breakout_pct: Option<f64>,
+    pub turnover_rate_threshold: Option<f64>,
+    pub volume_spike_ratio: Option<f64>,
+    pub volume_spike_turnover: Option<f64>,
 }
 
 impl Default for AnalysisConfig {
@@ -236,21 +749,161 @@ impl Default for AnalysisConfig {
             daily_kline_enabled: true,
             daily_kline_days: default_daily_kline_days(),
             daily_kline_refresh_minutes: default_daily_kline_refresh_minutes(),
+            permission_probe: default_permission_probe(),
+            permission_probe_ttl_minutes: 0,
+            rsi_period: default_rsi_period(),
+            rsi_overbought: default_rsi_overbought(),
+            rsi_oversold: default_rsi_oversold(),
+            ma_fast_period: default_ma_fast_period(),
+            ma_slow_period: default_ma_slow_period(),
+            gap_threshold_pct: default_gap_threshold_pct(),
+            daily_volume_surge_ratio: default_daily_volume_surge_ratio(),
+            daily_volume_dryup_ratio: default_daily_volume_dryup_ratio(),
+            boll_period: default_boll_period(),
+            boll_std_multiplier: default_boll_std_multiplier(),
             vwap_deviation_pct: default_vwap_deviation_pct(),
             vwap_reset_pct: default_vwap_reset_pct(),
+            vwap_rolling_minutes: 0,
             rapid_move_pct: default_rapid_move_pct(),
             rapid_move_window: default_rapid_move_window(),
             rapid_move_reset_pct: default_rapid_move_reset_pct(),
             rapid_move_efficiency: default_rapid_move_efficiency(),
             rapid_move_min_change: default_rapid_move_min_change(),
+            rapid_move_max_gap_secs: default_rapid_move_max_gap_secs(),
             amplitude_breakout_pct: default_amplitude_breakout_pct(),
+            turnover_rate_threshold: default_turnover_rate_threshold(),
             volume_spike_ratio: default_volume_spike_ratio(),
             volume_baseline_secs: default_volume_baseline_secs(),
             volume_min_baseline_secs: default_volume_min_baseline_secs(),
             volume_spike_turnover: default_volume_spike_turnover(),
             tick_signal_display_minutes: default_tick_signal_display_minutes(),
             warmup_ticks: default_warmup_ticks(),
+            regular_session_only: Vec::new(),
+            overrides: std::collections::HashMap::new(),
+        }
+    }
+}
+
+impl AnalysisConfig {
+    /// 开启常规时段门控的市场（无效项已由 `validate` 拒绝，这里忽略）
+    pub fn regular_session_markets(&self) -> Vec<Market> {
+        self.regular_session_only
+            .iter()
+            .filter_map(|m| Market::from_prefix(m.trim()))
+            .filter(|m| SESSION_MARKETS.contains(m))
+            .collect()
+    }
+
+    /// 校验阈值对的大小关系（滞后重置依赖 reset < trigger，倒置时信号永不触发或反复触发）
+    pub fn validate(&self) -> Result<()> {
+        let mut errors = Vec::new();
+        if self.vwap_reset_pct >= self.vwap_deviation_pct {
+            errors.push(format!(
+                "analysis.vwap_reset_pct ({}) 必须小于 analysis.vwap_deviation_pct ({})",
+                self.vwap_reset_pct, self.vwap_deviation_pct
+            ));
+        }
+        if !matches!(self.permission_probe.as_str(), "once" | "per_cycle") {
+            errors.push(format!(
+                "analysis.permission_probe = {:?} 无效（可选 \"once\" / \"per_cycle\"）",
+                self.permission_probe
+            ));
+        }
+        if self.rsi_period < 2 {
+            errors.push(format!("analysis.rsi_period ({}) 必须 >= 2", self.rsi_period));
+        }
+        if !(0.0 < self.rsi_oversold && self.rsi_oversold < self.rsi_overbought && self.rsi_overbought < 100.0) {
+            errors.push(format!(
+                "analysis.rsi_oversold ({}) / rsi_overbought ({}) 需满足 0 < 超卖 < 超买 < 100",
+                self.rsi_oversold, self.rsi_overbought
+            ));
+        }
+        if self.ma_fast_period == 0 || self.ma_fast_period >= self.ma_slow_period {
+            errors.push(format!(
+                "analysis.ma_fast_period ({}) 必须大于 0 且小于 analysis.ma_slow_period ({})",
+                self.ma_fast_period, self.ma_slow_period
+            ));
+        }
+        if self.gap_threshold_pct <= 0.0 {
+            errors.push(format!(
+                "analysis.gap_threshold_pct ({}) 必须大于 0",
+                self.gap_threshold_pct
+            ));
+        }
+        if !(0.0 < self.daily_volume_dryup_ratio && self.daily_volume_dryup_ratio < self.daily_volume_surge_ratio) {
+            errors.push(format!(
+                "analysis.daily_volume_dryup_ratio ({}) 必须大于 0 且小于 analysis.daily_volume_surge_ratio ({})",
+                self.daily_volume_dryup_ratio, self.daily_volume_surge_ratio
+            ));
+        }
+        if self.boll_period < 2 {
+            errors.push(format!("analysis.boll_period ({}) 必须 >= 2", self.boll_period));
+        }
+        if self.boll_std_multiplier <= 0.0 {
+            errors.push(format!(
+                "analysis.boll_std_multiplier ({}) 必须大于 0",
+                self.boll_std_multiplier
+            ));
+        }
+        if self.turnover_rate_threshold < 0.0 {
+            errors.push(format!(
+                "analysis.turnover_rate_threshold ({}) 不能为负数",
+                self.turnover_rate_threshold
+            ));
+        }
+        if self.rapid_move_reset_pct >= self.rapid_move_pct {
+            errors.push(format!(
+                "analysis.rapid_move_reset_pct ({}) 必须小于 analysis.rapid_move_pct ({})",
+                self.rapid_move_reset_pct, self.rapid_move_pct
+            ));
+        }
+        if self.volume_min_baseline_secs > self.volume_baseline_secs {
+            errors.push(format!(
+                "analysis.volume_min_baseline_secs ({}) 不能大于 analysis.volume_baseline_secs ({})",
+                self.volume_min_baseline_secs, self.volume_baseline_secs
+            ));
+        }
+        for m in &self.regular_session_only {
+            if Market::from_prefix(m.trim()).is_none_or(|m| !SESSION_MARKETS.contains(&m)) {
+                errors.push(format!(
+                    "analysis.regular_session_only 市场 {:?} 无效（可选 \"HK\" / \"SH\" / \"SZ\" / \"US\"）",
+                    m
+                ));
+            }
+        }
+        let mut override_keys: Vec<&String> = self.overrides.keys().collect();
+        override_keys.sort();
+        for key in override_keys {
+            let o = &self.overrides[key];
+            if crate::data::parser::parse_stock_code(key).is_none() {
+                errors.push(format!(
+                    "analysis.overrides 股票代码 {:?} 无效（应为 \"HK.00700\" 形式）",
+                    key
+                ));
+                continue;
+            }
+            // 覆盖后的阈值对同样需要 reset < trigger（可能只覆盖了其中一个）
+            let vwap_deviation = o.vwap_deviation_pct.unwrap_or(self.vwap_deviation_pct);
+            let vwap_reset = o.vwap_reset_pct.unwrap_or(self.vwap_reset_pct);
+            if vwap_reset >= vwap_deviation {
+                errors.push(format!(
+                    "analysis.overrides.{:?} vwap_reset_pct ({}) 必须小于 vwap_deviation_pct ({})",
+                    key, vwap_reset, vwap_deviation
+                ));
+            }
+            let rapid_move = o.rapid_move_pct.unwrap_or(self.rapid_move_pct);
+            let rapid_reset = o.rapid_move_reset_pct.unwrap_or(self.rapid_move_reset_pct);
+            if rapid_reset >= rapid_move {
+                errors.push(format!(
+                    "analysis.overrides.{:?} rapid_move_reset_pct ({}) 必须小于 rapid_move_pct ({})",
+                    key, rapid_reset, rapid_move
+                ));
+            }
+        }
+        if !errors.is_empty() {
+            anyhow::bail!("配置阈值不一致：{}", errors.join("；"));
         }
+        Ok(())
     }
 }
 
@@ -263,6 +916,14 @@ pub struct McpConfig {
     /// MCP 服务器端口
     #[serde(default = "default_mcp_port")]
     pub port: u16,
+
+    /// 模拟下单：完成导航、填单与验价后取消确认弹窗，不真正提交（`--dry-run` 亦可开启）
+    #[serde(default)]
+    pub dry_run: bool,
+
+    /// 委托日志（JSONL，每次委托的请求与结果追加一行，`qtrade orders` 查看），留空不落盘
+    #[serde(default = "default_order_log_path")]
+    pub order_log_path: String,
 }
 
 impl Default for McpConfig {
@@ -270,10 +931,167 @@ impl Default for McpConfig {
         Self {
             host: default_mcp_host(),
             port: default_mcp_port(),
+            dry_run: false,
+            order_log_path: default_order_log_path(),
         }
     }
 }
 
+impl McpConfig {
+    /// 委托日志路径（`order_log_path` 为空表示不落盘）
+    pub fn order_log_file(&self) -> Option<std::path::PathBuf> {
+        let path = self.order_log_path.trim();
+        (!path.is_empty()).then(|| std::path::PathBuf::from(path))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OcrConfig {
+    /// OCR 轮询间隔（秒），独立于 data_source.refresh_interval_secs
+    ///
+    /// OCR 每轮需截图 + Vision 识别，开销远高于 API 轮询，默认放慢。
+    /// 画面静止时截图哈希未变会跳过识别，实际识别频率低于此间隔对应的频率。
+    #[serde(default = "default_ocr_interval")]
+    pub interval_secs: u64,
+    /// 截图后端："cgwindow"（CGWindowListCreateImage，默认）| "screencapturekit"（macOS 14+）
+    ///
+    /// screencapturekit 在低于 macOS 14 的系统或截图失败时自动回退 cgwindow。
+    #[serde(default = "default_capture_backend")]
+    pub capture_backend: String,
+    /// 富途窗口被完全遮挡时，截图前先将其激活到前台（会抢占焦点，默认关闭）
+    ///
+    /// 适合专用盯盘机器；日常使用时每轮 OCR 都可能打断当前输入。
+    #[serde(default)]
+    pub raise_before_capture: bool,
+    /// raise_before_capture 截图完成后将焦点还给原前台 App
+    #[serde(default = "default_true")]
+    pub restore_focus_after_capture: bool,
+    /// 精确识别（Pass 2）前将自选股裁剪图放大的倍数，1.0 = 不放大
+    ///
+    /// 富途窗口较小、字号过小时 Vision 识别率明显下降，放大 2 倍通常可改善；
+    /// 布局检测（Pass 1 快速识别）不受影响。
+    #[serde(default = "default_ocr_upscale")]
+    pub upscale: f64,
+    /// 富途主窗口的最小面积（逻辑 point²，与 Retina 缩放无关），更小的窗口视为菜单/浮层/工具窗口
+    ///
+    /// 紧凑布局下主窗口很小、报「未找到富途牛牛窗口」时可调低。
+    #[serde(default = "default_min_window_area")]
+    pub min_window_area: f64,
+    /// Vision 识别语言（BCP 47，按优先级排列），默认 ["zh-Hans", "en-US"]
+    ///
+    /// 富途繁体界面（港版）可改为 ["zh-Hant", "en-US"]，提升「長和」「滙豐」等繁体名称识别率。
+    #[serde(default = "default_ocr_languages")]
+    pub languages: Vec<String>,
+}
+
+/// `ocr.upscale` 上限（再大识别率不再提升，只增加耗时与内存）
+pub const MAX_OCR_UPSCALE: f64 = 4.0;
+
+impl Default for OcrConfig {
+    fn default() -> Self {
+        Self {
+            interval_secs: default_ocr_interval(),
+            capture_backend: default_capture_backend(),
+            raise_before_capture: false,
+            restore_focus_after_capture: true,
+            upscale: default_ocr_upscale(),
+            min_window_area: default_min_window_area(),
+            languages: default_ocr_languages(),
+        }
+    }
+}
+
+impl OcrConfig {
+    pub fn validate(&self) -> Result<()> {
+        if !(1.0..=MAX_OCR_UPSCALE).contains(&self.upscale) {
+            anyhow::bail!("ocr.upscale = {} 超出范围（1.0-{}）", self.upscale, MAX_OCR_UPSCALE);
+        }
+        if self.min_window_area < 0.0 || self.min_window_area.is_nan() {
+            anyhow::bail!("ocr.min_window_area = {} 不能为负数", self.min_window_area);
+        }
+        if self.languages.is_empty() {
+            anyhow::bail!("ocr.languages 不能为空");
+        }
+        if let Some(lang) = self.languages.iter().find(|l| l.trim().is_empty()) {
+            anyhow::bail!("ocr.languages 含空语言代码 {:?}", lang);
+        }
+        match self.capture_backend.as_str() {
+            "cgwindow" | "screencapturekit" => Ok(()),
+            other => anyhow::bail!(
+                "ocr.capture_backend = {:?} 无效（可选 \"cgwindow\" / \"screencapturekit\"）",
+                other
+            ),
+        }
+    }
+}
+
+fn default_ocr_interval() -> u64 {
+    5
+}
+
+fn default_capture_backend() -> String {
+    "cgwindow".to_string()
+}
+
+fn default_ocr_upscale() -> f64 {
+    1.0
+}
+
+fn default_min_window_area() -> f64 {
+    10000.0
+}
+
+fn default_ocr_languages() -> Vec<String> {
+    vec!["zh-Hans".to_string(), "en-US".to_string()]
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportConfig {
+    /// 信号 CSV 导出目录（按 x 键或退出时写入）
+    #[serde(default = "default_signals_dir")]
+    pub signals_dir: String,
+
+    /// 退出时自动导出本次运行的信号
+    #[serde(default)]
+    pub signals_on_exit: bool,
+
+    /// 退出时在终端打印按股票分组的信号摘要
+    #[serde(default)]
+    pub digest_on_exit: bool,
+
+    /// tick 信号日志目录（每日一个 `tick_signals_YYYYMMDD.jsonl`，逐条追加），留空不落盘
+    #[serde(default = "default_signals_dir")]
+    pub tick_log_dir: String,
+
+    /// 启动时读回 tick 信号日志，恢复显示窗口（`analysis.tick_signal_display_minutes`）内的信号
+    #[serde(default = "default_true")]
+    pub reload_tick_signals: bool,
+}
+
+impl Default for ExportConfig {
+    fn default() -> Self {
+        Self {
+            signals_dir: default_signals_dir(),
+            signals_on_exit: false,
+            digest_on_exit: false,
+            tick_log_dir: default_signals_dir(),
+            reload_tick_signals: true,
+        }
+    }
+}
+
+impl ExportConfig {
+    /// tick 信号日志目录（`tick_log_dir` 为空表示不落盘）
+    pub fn tick_log_dir(&self) -> Option<PathBuf> {
+        let dir = self.tick_log_dir.trim();
+        (!dir.is_empty()).then(|| PathBuf::from(dir))
+    }
+}
+
+fn default_signals_dir() -> String {
+    "exports".to_string()
+}
+
 fn default_mcp_host() -> String {
     "127.0.0.1".to_string()
 }
@@ -282,6 +1100,10 @@ fn default_mcp_port() -> u16 {
     8900
 }
 
+fn default_order_log_path() -> String {
+    "orders.jsonl".to_string()
+}
+
 fn default_daily_kline_days() -> u32 {
     120
 }
@@ -290,6 +1112,10 @@ fn default_daily_kline_refresh_minutes() -> u64 {
     30
 }
 
+fn default_permission_probe() -> String {
+    "once".to_string()
+}
+
 fn default_vwap_deviation_pct() -> f64 {
     2.0
 }
@@ -318,10 +1144,18 @@ fn default_rapid_move_min_change() -> f64 {
     0.05
 }
 
+fn default_rapid_move_max_gap_secs() -> f64 {
+    30.0
+}
+
 fn default_amplitude_breakout_pct() -> f64 {
     5.0
 }
 
+fn default_turnover_rate_threshold() -> f64 {
+    10.0
+}
+
 fn default_volume_spike_ratio() -> f64 {
     1000.0
 }
@@ -346,6 +1180,54 @@ fn default_warmup_ticks() -> u32 {
     3
 }
 
+fn default_rsi_period() -> usize {
+    14
+}
+
+fn default_rsi_overbought() -> f64 {
+    70.0
+}
+
+fn default_rsi_oversold() -> f64 {
+    30.0
+}
+
+fn default_ma_fast_period() -> usize {
+    5
+}
+
+fn default_ma_slow_period() -> usize {
+    20
+}
+
+fn default_gap_threshold_pct() -> f64 {
+    3.0
+}
+
+fn default_daily_volume_surge_ratio() -> f64 {
+    2.0
+}
+
+fn default_daily_volume_dryup_ratio() -> f64 {
+    0.5
+}
+
+fn default_boll_period() -> usize {
+    20
+}
+
+fn default_boll_std_multiplier() -> f64 {
+    2.0
+}
+
+fn default_smtp_port() -> u16 {
+    465
+}
+
+fn default_log_format() -> String {
+    "pretty".to_string()
+}
+
 fn default_log_level() -> String {
     "info".to_string()
 }
@@ -358,6 +1240,10 @@ fn default_refresh_interval() -> u64 {
     2
 }
 
+fn default_stale_after_secs() -> u64 {
+    60
+}
+
 fn default_opend_host() -> String {
     "127.0.0.1".to_string()
 }
@@ -366,6 +1252,14 @@ fn default_opend_port() -> u16 {
     11111
 }
 
+fn default_push_reconnect_max_secs() -> u64 {
+    60
+}
+
+fn default_exclude_index_markets() -> Vec<String> {
+    vec!["HK".to_string()]
+}
+
 fn default_true() -> bool {
     true
 }
@@ -374,6 +1268,10 @@ fn default_cooldown() -> u64 {
     300
 }
 
+fn default_alert_log_path() -> String {
+    "alerts.jsonl".to_string()
+}
+
 fn default_change_threshold() -> f64 {
     3.0
 }
@@ -382,12 +1280,45 @@ fn default_page_size() -> usize {
     50
 }
 
+fn default_follow_action() -> String {
+    "off".to_string()
+}
+
+fn default_follow_pause_secs() -> u64 {
+    5
+}
+
+/// 涨跌幅小数位数上限
+const MAX_CHANGE_PCT_DECIMALS: usize = 4;
+
+fn default_change_pct_decimals() -> usize {
+    2
+}
+
+/// 行情表格可选列（默认全部显示，按此顺序）
+pub const UI_COLUMNS: [&str; 9] = [
+    "code",
+    "name",
+    "price",
+    "change_pct",
+    "change",
+    "volume",
+    "turnover_rate",
+    "amplitude",
+    "signal",
+];
+
+fn default_ui_columns() -> Vec<String> {
+    UI_COLUMNS.iter().map(|c| c.to_string()).collect()
+}
+
 impl AppConfig {
     /// 从文件加载配置
     pub fn load(path: &Path) -> Result<Self> {
         let content =
             std::fs::read_to_string(path).with_context(|| format!("Failed to read config file: {}", path.display()))?;
-        let config: AppConfig = toml::from_str(&content).with_context(|| "Failed to parse config TOML")?;
+        let mut config: AppConfig = toml::from_str(&content).with_context(|| "Failed to parse config TOML")?;
+        config.path = Some(path.to_path_buf());
         Ok(config)
     }
 
@@ -416,6 +1347,44 @@ impl AppConfig {
         tracing::info!("Using default configuration");
         Self::default()
     }
+
+    /// 校验配置的跨字段约束
+    pub fn validate(&self) -> Result<()> {
+        self.futu.validate()?;
+        self.data_source.validate()?;
+        self.alerts.validate()?;
+        self.analysis.validate()?;
+        self.ui.validate()?;
+        self.ocr.validate()
+    }
+
+    /// 返回脱敏后的副本（用于打印/导出），敏感字段替换为占位符
+    pub fn redacted(&self) -> Self {
+        let mut config = self.clone();
+        // webhook URL 的路径/查询参数通常含 access_token，仅保留协议与主机
+        config.alerts.webhook_url = config.alerts.webhook_url.as_deref().map(redact_url);
+        for ch in &mut config.alerts.channels {
+            ch.url = ch.url.as_deref().map(redact_url);
+            for secret in [&mut ch.bot_token, &mut ch.password] {
+                if secret.is_some() {
+                    *secret = Some("***".to_string());
+                }
+            }
+        }
+        if config.futu.trade_pwd_md5.is_some() {
+            config.futu.trade_pwd_md5 = Some("***".to_string());
+        }
+        config
+    }
+}
+
+/// 脱敏 URL："https://host/path?token=x" → "https://host/***"
+fn redact_url(url: &str) -> String {
+    let host_start = url.find("://").map(|i| i + 3).unwrap_or(0);
+    match url[host_start..].find('/') {
+        Some(i) => format!("{}/***", &url[..host_start + i]),
+        None => url.to_string(),
+    }
 }
 
 impl Default for AppConfig {
@@ -428,6 +1397,9 @@ impl Default for AppConfig {
             ui: UiConfig::default(),
             analysis: AnalysisConfig::default(),
             mcp: McpConfig::default(),
+            ocr: OcrConfig::default(),
+            export: ExportConfig::default(),
+            path: None,
         }
     }
 }
@@ -441,3 +1413,302 @@ fn dirs_home() -> PathBuf {
         .map(PathBuf::from)
         .unwrap_or_else(|_| PathBuf::from("/tmp"))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_config_is_valid() {
+        assert!(AppConfig::default().validate().is_ok());
+    }
+
+    #[test]
+    fn test_inverted_threshold_pair_fails_validation() {
+        let mut config = AppConfig::default();
+        config.analysis.vwap_deviation_pct = 1.0;
+        config.analysis.vwap_reset_pct = 2.0;
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains("vwap_reset_pct"), "{}", err);
+
+        let mut config = AppConfig::default();
+        config.analysis.volume_baseline_secs = 60.0;
+        config.analysis.volume_min_baseline_secs = 120.0;
+        config.analysis.rapid_move_reset_pct = config.analysis.rapid_move_pct;
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains("volume_min_baseline_secs"), "{}", err);
+        assert!(err.contains("rapid_move_reset_pct"), "{}", err);
+    }
+
+    #[test]
+    fn test_vwap_rolling_minutes_default_cumulative() {
+        // 缺省为 0（当日累计），0 与正数都合法
+        let config: AppConfig = toml::from_str("[analysis]").unwrap();
+        assert_eq!(config.analysis.vwap_rolling_minutes, 0);
+        assert!(config.validate().is_ok());
+        let config: AppConfig = toml::from_str("[analysis]\nvwap_rolling_minutes = 15").unwrap();
+        assert_eq!(config.analysis.vwap_rolling_minutes, 15);
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_permission_probe_validation() {
+        let mut config = AppConfig::default();
+        config.analysis.permission_probe = "per_cycle".to_string();
+        assert!(config.validate().is_ok());
+        config.analysis.permission_probe = "never".to_string();
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains("analysis.permission_probe"), "{}", err);
+    }
+
+    #[test]
+    fn test_follow_action_validation() {
+        let mut config = AppConfig::default();
+        config.ui.follow_action = "mover".to_string();
+        assert!(config.validate().is_ok());
+        config.ui.follow_action = "latest".to_string();
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains("ui.follow_action"), "{}", err);
+    }
+
+    #[test]
+    fn test_change_pct_decimals_validation() {
+        let mut config = AppConfig::default();
+        assert_eq!(config.ui.change_pct_decimals, 2);
+        config.ui.change_pct_decimals = 0;
+        assert!(config.validate().is_ok());
+        config.ui.change_pct_decimals = 5;
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains("ui.change_pct_decimals"), "{}", err);
+    }
+
+    #[test]
+    fn test_ui_columns_validation() {
+        let mut config = AppConfig::default();
+        assert_eq!(config.ui.columns.len(), UI_COLUMNS.len());
+        config.ui.columns = vec!["code".into(), "name".into(), "price".into(), "signal".into()];
+        assert!(config.validate().is_ok());
+        for bad in [
+            vec![],
+            vec!["code".into(), "vwap".into()],
+            vec!["code".into(), "code".into()],
+        ] {
+            config.ui.columns = bad;
+            let err = config.validate().unwrap_err().to_string();
+            assert!(err.contains("ui.columns"), "{}", err);
+        }
+    }
+
+    #[test]
+    fn test_parse_half_days() {
+        let config: AppConfig = toml::from_str(
+            r#"
+            [general]
+            half_days = [
+                { market = "HK", date = "2025-12-24", close = "12:00" },
+                { market = "us", date = "2025-11-28", close = "13:00" },
+            ]
+            "#,
+        )
+        .unwrap();
+        let days = config.general.parse_half_days().unwrap();
+        assert_eq!(days.len(), 2);
+        assert_eq!(days[0].market, crate::models::Market::HK);
+        assert_eq!(days[0].date, chrono::NaiveDate::from_ymd_opt(2025, 12, 24).unwrap());
+        assert_eq!(days[0].close_hhmm, 1200);
+        assert_eq!(days[1].market, crate::models::Market::US);
+        assert_eq!(days[1].close_hhmm, 1300);
+
+        let mut bad = config.clone();
+        bad.general.half_days[0].market = "SG".to_string();
+        assert!(bad.general.parse_half_days().is_err());
+        let mut bad = config;
+        bad.general.half_days[0].close = "noon".to_string();
+        assert!(bad.general.parse_half_days().is_err());
+    }
+
+    #[test]
+    fn test_alert_channels_validation() {
+        let config: AppConfig = toml::from_str(
+            r#"
+            [alerts]
+            channels = [
+                { type = "telegram", bot_token = "123:abc", chat_id = "42" },
+                { type = "email", smtp_host = "smtp.example.com", from = "bot@example.com", to = ["me@example.com"] },
+            ]
+            "#,
+        )
+        .unwrap();
+        assert!(config.validate().is_ok());
+        assert_eq!(config.alerts.channels[1].smtp_port, 465);
+
+        let mut bad = config.clone();
+        bad.alerts.channels[0].chat_id = None;
+        let err = bad.validate().unwrap_err().to_string();
+        assert!(err.contains("alerts.channels[0]") && err.contains("chat_id"), "{}", err);
+
+        let mut bad = config;
+        bad.alerts.channels[1].kind = "sms".to_string();
+        let err = bad.validate().unwrap_err().to_string();
+        assert!(err.contains("alerts.channels[1].type"), "{}", err);
+    }
+
+    #[test]
+    fn test_price_levels_validation() {
+        let config: AppConfig = toml::from_str(
+            r#"
+            [alerts]
+            price_levels = [
+                { code = "HK.00700", above = 400.0, below = 350.0 },
+                { code = "US.AAPL", below = 180.0 },
+            ]
+            "#,
+        )
+        .unwrap();
+        assert!(config.validate().is_ok());
+
+        let mut bad = config.clone();
+        bad.alerts.price_levels[0].below = Some(420.0);
+        let err = bad.validate().unwrap_err().to_string();
+        assert!(err.contains("above"), "{}", err);
+
+        let mut bad = config;
+        bad.alerts.price_levels[1].below = None;
+        let err = bad.validate().unwrap_err().to_string();
+        assert!(err.contains("US.AAPL"), "{}", err);
+    }
+
+    #[test]
+    fn test_threshold_override_validation() {
+        let config: AppConfig = toml::from_str(
+            r#"
+            [analysis.overrides."HK.02800"]
+            rapid_move_pct = 0.3
+            rapid_move_reset_pct = 0.1
+            "#,
+        )
+        .unwrap();
+        assert!(config.validate().is_ok());
+        let o = &config.analysis.overrides["HK.02800"];
+        assert_eq!(o.rapid_move_pct, Some(0.3));
+        assert_eq!(o.vwap_deviation_pct, None);
+
+        // 只覆盖触发阈值，低于全局 reset 阈值 → 倒置
+        let mut bad = config.clone();
+        bad.analysis.overrides.get_mut("HK.02800").unwrap().rapid_move_reset_pct = None;
+        bad.analysis.overrides.get_mut("HK.02800").unwrap().rapid_move_pct = Some(0.2);
+        let err = bad.validate().unwrap_err().to_string();
+        assert!(err.contains("rapid_move_reset_pct"), "{}", err);
+
+        let mut bad = config;
+        bad.analysis
+            .overrides
+            .insert("腾讯".to_string(), ThresholdOverride::default());
+        let err = bad.validate().unwrap_err().to_string();
+        assert!(err.contains("analysis.overrides"), "{}", err);
+    }
+
+    #[test]
+    fn test_subscription_exclusion() {
+        let futu = FutuConfig::default();
+        // 默认：只排除港股指数和 Unknown 市场
+        assert!(futu.is_excluded(&StockCode::new(Market::HK, "800000")));
+        assert!(futu.is_excluded(&StockCode::new(Market::Unknown, "CNmain")));
+        assert!(!futu.is_excluded(&StockCode::new(Market::HK, "00700")));
+        assert!(!futu.is_excluded(&StockCode::new(Market::SH, "000001")));
+
+        let config: AppConfig = toml::from_str(
+            r#"
+            [futu]
+            exclude_index_markets = ["sh", "US"]
+            exclude_code_prefixes = ["09"]
+            "#,
+        )
+        .unwrap();
+        assert!(config.validate().is_ok());
+        let futu = &config.futu;
+        assert!(!futu.is_excluded(&StockCode::new(Market::HK, "800000")));
+        assert!(futu.is_excluded(&StockCode::new(Market::SH, "000001")));
+        assert!(futu.is_excluded(&StockCode::new(Market::US, ".DJI")));
+        assert!(futu.is_excluded(&StockCode::new(Market::HK, "09988")));
+        assert!(!futu.is_excluded(&StockCode::new(Market::US, "AAPL")));
+
+        let mut bad = config;
+        bad.futu.exclude_index_markets = vec!["SG".to_string()];
+        let err = bad.validate().unwrap_err().to_string();
+        assert!(err.contains("futu.exclude_index_markets"), "{}", err);
+
+        bad.futu.exclude_index_markets = vec!["HK".to_string()];
+        bad.futu.trade_pwd_md5 = Some("e10adc3949ba59abbe56e057f20f883e".to_string());
+        assert!(bad.validate().is_ok());
+        bad.futu.trade_pwd_md5 = Some("123456".to_string());
+        let err = bad.validate().unwrap_err().to_string();
+        assert!(err.contains("futu.trade_pwd_md5"), "{}", err);
+    }
+
+    #[test]
+    fn test_capture_backend_validation() {
+        let mut config = AppConfig::default();
+        config.ocr.capture_backend = "screencapturekit".to_string();
+        assert!(config.validate().is_ok());
+        config.ocr.capture_backend = "sck".to_string();
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains("ocr.capture_backend"), "{}", err);
+
+        let mut config = AppConfig::default();
+        config.ocr.upscale = 2.0;
+        assert!(config.validate().is_ok());
+        config.ocr.upscale = 0.5;
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains("ocr.upscale"), "{}", err);
+
+        let mut config = AppConfig::default();
+        assert_eq!(config.ocr.languages, ["zh-Hans", "en-US"]);
+        config.ocr.languages = vec!["zh-Hant".to_string(), "en-US".to_string()];
+        assert!(config.validate().is_ok());
+        config.ocr.languages.clear();
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains("ocr.languages"), "{}", err);
+    }
+
+    #[test]
+    fn test_fallback_sources_validation() {
+        let config: AppConfig = toml::from_str(
+            r#"
+            [data_source]
+            source = "openapi"
+            fallback_sources = ["ocr"]
+            "#,
+        )
+        .unwrap();
+        assert!(config.validate().is_ok());
+
+        for bad in [vec!["openapi"], vec!["ocr", "ocr"], vec!["cache"]] {
+            let mut config = config.clone();
+            config.data_source.fallback_sources = bad.iter().map(|s| s.to_string()).collect();
+            let err = config.validate().unwrap_err().to_string();
+            assert!(err.contains("data_source.fallback_sources"), "{}", err);
+        }
+    }
+
+    #[test]
+    fn test_source_latency() {
+        let config: AppConfig = toml::from_str(
+            r#"
+            [data_source]
+            latency_secs = { openapi = 0, ocr = 900 }
+            "#,
+        )
+        .unwrap();
+        assert!(config.validate().is_ok());
+        assert_eq!(config.data_source.stale_after_secs, 60);
+        let latency = config.data_source.source_latency();
+        assert_eq!(latency.len(), 1);
+        assert_eq!(latency[&DataSource::Ocr], Duration::from_secs(900));
+
+        let mut bad = config;
+        bad.data_source.latency_secs.insert("yahoo".to_string(), 900);
+        let err = bad.validate().unwrap_err().to_string();
+        assert!(err.contains("data_source.latency_secs"), "{}", err);
+    }
+}
@@ -0,0 +1,248 @@
+//! 演示数据源：为几只示例股票生成随机游走行情
+//!
+//! 不依赖富途 App / FutuOpenD，供 `qtrade demo` 在任意机器上驱动完整的
+//! 分析 → 提醒 → 仪表盘链路（冒烟测试、演示、截图）。随机游走中会不定期插入
+//! 几个 tick 的单边行情并伴随放量，使急涨急跌、量能突变、涨跌幅提醒等能够触发。
+
+use anyhow::Result;
+use tracing::info;
+
+use crate::models::{DataSource, Market, QuoteSnapshot, StockCode, WatchlistEntry};
+
+/// 演示股票：(市场, 代码, 名称, 昨收, 每 tick 基准成交量)
+const DEMO_STOCKS: &[(Market, &str, &str, f64, u64)] = &[
+    (Market::HK, "00700", "腾讯控股", 380.0, 20_000),
+    (Market::HK, "09988", "阿里巴巴-W", 85.0, 60_000),
+    (Market::SH, "600519", "贵州茅台", 1680.0, 2_000),
+    (Market::SZ, "300750", "宁德时代", 190.0, 30_000),
+    (Market::US, "AAPL", "苹果", 190.0, 50_000),
+];
+
+/// 每个 tick 开始一段单边行情的概率
+const BURST_PROBABILITY: f64 = 0.03;
+/// 单边行情持续的 tick 数
+const BURST_TICKS: u32 = 4;
+/// 单边行情期间的成交量倍数
+const BURST_VOLUME_MULTIPLIER: f64 = 6.0;
+
+/// 演示用自选股列表
+pub fn demo_watchlist() -> Vec<WatchlistEntry> {
+    DEMO_STOCKS
+        .iter()
+        .enumerate()
+        .map(|(i, &(market, code, name, prev_close, _))| WatchlistEntry {
+            code: StockCode::new(market, code),
+            stock_id: 0,
+            name: name.to_string(),
+            cached_price: Some(prev_close),
+            prev_close: Some(prev_close),
+            sort_index: i,
+        })
+        .collect()
+}
+
+/// xorshift64* 伪随机数（演示用，无需引入 rand 依赖）
+#[derive(Debug)]
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        // 种子不能为 0
+        Self(seed.max(1))
+    }
+
+    /// [0, 1) 均匀分布
+    fn next_f64(&mut self) -> f64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        let x = self.0.wrapping_mul(0x2545_F491_4F6C_DD1D);
+        (x >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// [lo, hi) 均匀分布
+    fn range(&mut self, lo: f64, hi: f64) -> f64 {
+        lo + (hi - lo) * self.next_f64()
+    }
+}
+
+/// 单只演示股票的日内状态
+#[derive(Debug)]
+struct DemoStock {
+    code: StockCode,
+    name: String,
+    prev_close: f64,
+    open: f64,
+    high: f64,
+    low: f64,
+    last: f64,
+    /// 累计成交量
+    volume: u64,
+    /// 累计成交额
+    turnover: f64,
+    base_volume: u64,
+    /// 单边行情剩余 tick 数
+    burst_left: u32,
+    /// 单边行情方向（+1 / -1）
+    burst_dir: f64,
+}
+
+impl DemoStock {
+    fn new(entry: &WatchlistEntry, base_volume: u64) -> Self {
+        let prev_close = entry.prev_close.unwrap_or(100.0);
+        Self {
+            code: entry.code.clone(),
+            name: entry.name.clone(),
+            prev_close,
+            open: prev_close,
+            high: prev_close,
+            low: prev_close,
+            last: prev_close,
+            volume: 0,
+            turnover: 0.0,
+            base_volume,
+            burst_left: 0,
+            burst_dir: 1.0,
+        }
+    }
+
+    /// 推进一个 tick，返回最新快照
+    fn tick(&mut self, rng: &mut Rng) -> QuoteSnapshot {
+        if self.burst_left == 0 && rng.next_f64() < BURST_PROBABILITY {
+            self.burst_left = BURST_TICKS;
+            self.burst_dir = if rng.next_f64() < 0.5 { 1.0 } else { -1.0 };
+        }
+
+        let bursting = self.burst_left > 0;
+        let step_pct = if bursting {
+            self.burst_left -= 1;
+            self.burst_dir * rng.range(0.4, 0.8)
+        } else {
+            rng.range(-0.15, 0.15)
+        };
+        self.last = ((self.last * (1.0 + step_pct / 100.0)) * 100.0).round().max(1.0) / 100.0;
+        self.high = self.high.max(self.last);
+        self.low = self.low.min(self.last);
+
+        let multiplier = if bursting { BURST_VOLUME_MULTIPLIER } else { 1.0 };
+        let delta = (self.base_volume as f64 * rng.range(0.5, 1.5) * multiplier) as u64;
+        self.volume = self.volume.saturating_add(delta);
+        self.turnover += delta as f64 * self.last;
+
+        let change = self.last - self.prev_close;
+        QuoteSnapshot {
+            code: self.code.clone(),
+            name: self.name.clone(),
+            last_price: self.last,
+            prev_close: self.prev_close,
+            open_price: self.open,
+            high_price: self.high,
+            low_price: self.low,
+            volume: self.volume,
+            turnover: self.turnover,
+            change,
+            change_pct: change / self.prev_close * 100.0,
+            turnover_rate: 0.0,
+            amplitude: (self.high - self.low) / self.prev_close * 100.0,
+            extended_price: None,
+            extended_change_pct: None,
+            is_suspended: false,
+            timestamp: chrono::Local::now(),
+            source: DataSource::Demo,
+        }
+    }
+}
+
+/// 演示数据提供者：每次轮询为每只股票生成一个随机游走 tick
+pub struct DemoProvider {
+    stocks: Vec<DemoStock>,
+    rng: Rng,
+    connected: bool,
+}
+
+impl DemoProvider {
+    /// 以当前时间为随机种子
+    pub fn new(entries: &[WatchlistEntry]) -> Self {
+        let seed = chrono::Local::now().timestamp_nanos_opt().unwrap_or_default() as u64;
+        Self::with_seed(entries, seed)
+    }
+
+    /// 固定随机种子（可复现的行情序列）
+    pub fn with_seed(entries: &[WatchlistEntry], seed: u64) -> Self {
+        let stocks = entries
+            .iter()
+            .map(|e| {
+                let base_volume = DEMO_STOCKS
+                    .iter()
+                    .find(|(m, c, ..)| *m == e.code.market && *c == e.code.code)
+                    .map(|s| s.4)
+                    .unwrap_or(10_000);
+                DemoStock::new(e, base_volume)
+            })
+            .collect();
+        Self {
+            stocks,
+            rng: Rng::new(seed),
+            connected: false,
+        }
+    }
+
+    pub async fn connect(&mut self) -> Result<()> {
+        self.connected = true;
+        info!("Demo provider ready: {} synthetic stocks", self.stocks.len());
+        Ok(())
+    }
+
+    pub async fn get_quotes(&mut self, codes: &[StockCode]) -> Result<Vec<QuoteSnapshot>> {
+        Ok(self.next_quotes(codes))
+    }
+
+    /// 为 `codes` 中的演示股票各生成一个 tick
+    fn next_quotes(&mut self, codes: &[StockCode]) -> Vec<QuoteSnapshot> {
+        let rng = &mut self.rng;
+        self.stocks
+            .iter_mut()
+            .filter(|s| codes.contains(&s.code))
+            .map(|s| s.tick(rng))
+            .collect()
+    }
+
+    pub fn name(&self) -> &str {
+        "Demo"
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.connected
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_demo_random_walk() {
+        let watchlist = demo_watchlist();
+        let codes: Vec<StockCode> = watchlist.iter().map(|e| e.code.clone()).collect();
+        let mut provider = DemoProvider::with_seed(&watchlist, 42);
+
+        let mut last_volume = vec![0u64; codes.len()];
+        for _ in 0..500 {
+            let quotes = provider.next_quotes(&codes);
+            assert_eq!(quotes.len(), codes.len());
+            for (i, q) in quotes.iter().enumerate() {
+                assert!(q.last_price > 0.0);
+                assert!(q.low_price <= q.last_price && q.last_price <= q.high_price);
+                // 累计成交量单调递增
+                assert!(q.volume >= last_volume[i]);
+                last_volume[i] = q.volume;
+                assert_eq!(q.source, DataSource::Demo);
+            }
+        }
+
+        // 只生成请求的股票
+        let quotes = provider.next_quotes(&codes[..1]);
+        assert_eq!(quotes.len(), 1);
+        assert_eq!(quotes[0].code, codes[0]);
+    }
+}
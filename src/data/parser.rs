@@ -67,8 +67,8 @@ fn try_parse_parts(parts: &[&str]) -> Option<QuoteSnapshot> {
             }
         } else if let Some(pct) = parse_percentage(part) {
             change_pct = Some(pct);
-        } else if let Some(vol) = parse_volume(part) {
-            volume = Some(vol);
+        } else if let Some((vol, unit)) = parse_volume_with_unit(part) {
+            volume = volume_to_shares(vol, unit, code.market);
         } else if name.is_none() && !looks_like_number(part) {
             name = Some(part.to_string());
         }
@@ -96,6 +96,7 @@ fn try_parse_parts(parts: &[&str]) -> Option<QuoteSnapshot> {
         amplitude: 0.0,
         extended_price: None,
         extended_change_pct: None,
+        is_suspended: false,
         timestamp: Local::now(),
         source: DataSource::Accessibility,
     })
@@ -111,35 +112,40 @@ fn find_stock_code(parts: &[&str]) -> Option<(usize, StockCode)> {
     None
 }
 
+/// 零宽字符（OCR / 富文本复制常夹带，肉眼不可见）
+const ZERO_WIDTH_CHARS: [char; 5] = ['\u{200B}', '\u{200C}', '\u{200D}', '\u{2060}', '\u{FEFF}'];
+
+/// 代码首尾的杂散标点（括号、引号、分隔符）；`.` `+` `-` `%` 有含义，不在此列
+const STRAY_PUNCTUATION: &[char] = &['(', ')', '[', ']', '<', '>', '"', '\'', ',', ':', ';', '|'];
+
+/// 规整代码文本：全角字母数字/标点转半角，去掉空白与零宽字符，再去掉首尾杂散标点
+///
+/// CJK 渲染下 OCR 常输出全角代码（"００７００"、"ＨＫ．００７００"）。
+fn normalize_code_text(s: &str) -> String {
+    let halfwidth: String = s
+        .chars()
+        .filter(|c| !c.is_whitespace() && !ZERO_WIDTH_CHARS.contains(c))
+        .map(|c| match c {
+            '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0).unwrap_or(c),
+            _ => c,
+        })
+        .collect();
+    halfwidth.trim_matches(STRAY_PUNCTUATION).to_string()
+}
+
 /// 解析股票代码字符串
-/// 支持格式：00700, HK.00700, 600519, SH.600519, 000001.SZ 等
+/// 支持格式：00700, HK.00700, 600519, SH.600519, 000001.SZ 等（全角、夹带零宽字符也可识别）
 pub fn parse_stock_code(s: &str) -> Option<StockCode> {
-    let s = s.trim();
+    let normalized = normalize_code_text(s);
+    let s = normalized.as_str();
 
     // 带市场前缀：HK.00700, SH.600519, SZ.000001
     if let Some((market_str, code)) = s.split_once('.') {
-        let market = match market_str.to_uppercase().as_str() {
-            "HK" => Some(Market::HK),
-            "SH" => Some(Market::SH),
-            "SZ" => Some(Market::SZ),
-            "US" => Some(Market::US),
-            "SG" => Some(Market::SG),
-            "FX" => Some(Market::FX),
-            _ => None,
-        };
+        let market = Market::from_prefix(market_str);
 
         // 也可能是 600519.SH 格式
         if market.is_none() {
-            let market2 = match code.to_uppercase().as_str() {
-                "HK" => Some(Market::HK),
-                "SH" => Some(Market::SH),
-                "SZ" => Some(Market::SZ),
-                "US" => Some(Market::US),
-                "SG" => Some(Market::SG),
-                "FX" => Some(Market::FX),
-                _ => None,
-            };
-            if let Some(m) = market2 {
+            if let Some(m) = Market::from_prefix(code) {
                 if market_str.chars().all(|c| c.is_ascii_digit()) {
                     return Some(StockCode::new(m, market_str));
                 }
@@ -166,13 +172,13 @@ pub fn parse_stock_code(s: &str) -> Option<StockCode> {
 
     // 美股字母代码：1-5 个字母（可带前导点号如 .DJI/.IXIC 表示指数，尾随点号为 OCR 噪声）
     // OCR 可能输出混合大小写（如 "Li" → "LI"），统一转大写
-    // 排除市场前缀 HK/SH/SZ/US，这些是市场标识不是股票代码
+    // 排除市场前缀 HK/SH/SZ/US/SG/FX，这些是市场标识不是股票代码
     {
         let stripped = s.trim_end_matches('.');
         let alpha_part = stripped.trim_start_matches('.');
         if !alpha_part.is_empty() && alpha_part.len() <= 5 && alpha_part.chars().all(|c| c.is_ascii_alphabetic()) {
             let upper = alpha_part.to_ascii_uppercase();
-            if !matches!(upper.as_str(), "HK" | "SH" | "SZ" | "US" | "SG" | "FX") {
+            if Market::from_prefix(&upper).is_none() {
                 // 保留前导点号（.DJI/.IXIC 等指数代码）
                 let code = if stripped.starts_with('.') {
                     format!(".{}", upper)
@@ -256,6 +262,44 @@ fn parse_volume(s: &str) -> Option<u64> {
     s.replace(',', "").parse::<u64>().ok()
 }
 
+/// A 股每手股数
+pub const A_SHARE_LOT_SIZE: u64 = 100;
+
+/// 成交量单位
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VolumeUnit {
+    /// 股（FutuOpenD 与 QuoteSnapshot 统一使用）
+    Shares,
+    /// 手（富途 UI 对 A 股常以手显示）
+    Lots,
+}
+
+/// 解析带单位的成交量字符串
+///
+/// "12.3万手" → (123000, Lots)，"4567股" → (4567, Shares)，无单位按股处理
+pub fn parse_volume_with_unit(s: &str) -> Option<(u64, VolumeUnit)> {
+    let s = s.trim();
+    if let Some(num) = s.strip_suffix('手') {
+        return parse_volume(num).map(|v| (v, VolumeUnit::Lots));
+    }
+    let num = s.strip_suffix('股').unwrap_or(s);
+    parse_volume(num).map(|v| (v, VolumeUnit::Shares))
+}
+
+/// 将成交量统一换算为股
+///
+/// 手 → 股仅对 A 股（每手 100 股）换算；港股等市场每手股数因股而异，
+/// 无法换算时返回 None，宁缺毋错。
+pub fn volume_to_shares(value: u64, unit: VolumeUnit, market: Market) -> Option<u64> {
+    match unit {
+        VolumeUnit::Shares => Some(value),
+        VolumeUnit::Lots => match market {
+            Market::SH | Market::SZ => value.checked_mul(A_SHARE_LOT_SIZE),
+            _ => None,
+        },
+    }
+}
+
 /// 判断字符串是否看起来像数字
 fn looks_like_number(s: &str) -> bool {
     let s = s.trim().trim_start_matches('+').trim_start_matches('-');
@@ -330,6 +374,40 @@ mod tests {
         assert!(parse_stock_code("SG").is_none());
     }
 
+    #[test]
+    fn test_parse_stock_code_normalizes_ocr_text() {
+        // 全角数字
+        assert_eq!(
+            parse_stock_code("００７００"),
+            Some(StockCode::new(Market::HK, "00700"))
+        );
+        // 全角市场前缀 + 全角点号
+        assert_eq!(
+            parse_stock_code("ＳＨ．６００５１９"),
+            Some(StockCode::new(Market::SH, "600519"))
+        );
+        // 尾随零宽空格 / 前导 BOM / 全角空格
+        assert_eq!(
+            parse_stock_code("00700\u{200B}"),
+            Some(StockCode::new(Market::HK, "00700"))
+        );
+        assert_eq!(
+            parse_stock_code("\u{FEFF}AAPL"),
+            Some(StockCode::new(Market::US, "AAPL"))
+        );
+        assert_eq!(
+            parse_stock_code("\u{3000}09988 "),
+            Some(StockCode::new(Market::HK, "09988"))
+        );
+        // 首尾括号等杂散标点
+        assert_eq!(parse_stock_code("(00700)"), Some(StockCode::new(Market::HK, "00700")));
+        assert_eq!(parse_stock_code("「"), None);
+        // 百分号、正负号有含义，不剥离
+        assert!(parse_stock_code("12345%").is_none());
+        assert!(parse_stock_code("").is_none());
+        assert!(parse_stock_code("\u{200B}").is_none());
+    }
+
     #[test]
     fn test_parse_percentage() {
         assert_eq!(parse_percentage("+0.67%"), Some(0.67));
@@ -346,6 +424,37 @@ mod tests {
         assert_eq!(parse_volume("1,234,567"), Some(1_234_567));
     }
 
+    #[test]
+    fn test_parse_volume_with_unit() {
+        assert_eq!(parse_volume_with_unit("12.3万手"), Some((123_000, VolumeUnit::Lots)));
+        assert_eq!(parse_volume_with_unit("856手"), Some((856, VolumeUnit::Lots)));
+        assert_eq!(parse_volume_with_unit("4567股"), Some((4567, VolumeUnit::Shares)));
+        assert_eq!(parse_volume_with_unit("1.2亿"), Some((120_000_000, VolumeUnit::Shares)));
+        assert_eq!(parse_volume_with_unit("手"), None);
+    }
+
+    #[test]
+    fn test_volume_to_shares_a_share_lots() {
+        // A 股：1 手 = 100 股
+        assert_eq!(
+            volume_to_shares(123_000, VolumeUnit::Lots, Market::SH),
+            Some(12_300_000)
+        );
+        assert_eq!(volume_to_shares(856, VolumeUnit::Lots, Market::SZ), Some(85_600));
+        // 股不换算
+        assert_eq!(volume_to_shares(4567, VolumeUnit::Shares, Market::SH), Some(4567));
+        assert_eq!(volume_to_shares(4567, VolumeUnit::Shares, Market::HK), Some(4567));
+        // 港股每手股数不定，不猜测
+        assert_eq!(volume_to_shares(100, VolumeUnit::Lots, Market::HK), None);
+    }
+
+    #[test]
+    fn test_try_parse_quote_text_lots_volume() {
+        let q = try_parse_quote_text("贵州茅台 600519 1688.00 +12.50 +0.75% 2.5万手").unwrap();
+        assert_eq!(q.code.market, Market::SH);
+        assert_eq!(q.volume, 2_500_000);
+    }
+
     #[test]
     fn test_try_parse_quote_text() {
         let text = "腾讯控股\t00700\t388.00\t+2.60\t+0.67%";
@@ -1,6 +1,7 @@
 //! 数据提供者 trait 与调度
 //!
-//! 抽象数据源，支持 Accessibility API 和 FutuOpenD OpenAPI 两种实现
+//! 抽象数据源，支持 Accessibility API、FutuOpenD OpenAPI 与截图 OCR 等实现；
+//! 配置 `data_source.fallback_sources` 时由 `ChainedProvider` 按顺序降级补齐
 
 use anyhow::Result;
 use tokio::sync::mpsc;
@@ -8,13 +9,14 @@ use tracing::{debug, info, warn};
 
 use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
-use std::time::SystemTime;
+use std::time::{Duration, SystemTime};
 
-use crate::config::AppConfig;
-use crate::futu::accessibility::{AccessibilityReader, GridFrame};
+use crate::config::{AppConfig, OcrConfig};
+use crate::data::demo::DemoProvider;
+use crate::futu::accessibility::{AccessibilityReader, GridFrame, GridFrameCache};
 use crate::futu::ocr;
 use crate::futu::openapi::OpenApiClient;
-use crate::models::{DailyKline, Market, QuoteSnapshot, StockCode};
+use crate::models::{ConnectionState, DailyKline, Market, QuoteSnapshot, StockCode};
 
 /// Accessibility API 数据提供者
 pub struct AccessibilityProvider {
@@ -52,21 +54,17 @@ impl AccessibilityProvider {
 /// OpenAPI 数据提供者
 pub struct OpenApiProvider {
     client: OpenApiClient,
-    connected: bool,
 }
 
 impl OpenApiProvider {
     pub fn new(host: &str, port: u16) -> Self {
         Self {
             client: OpenApiClient::new(host, port),
-            connected: false,
         }
     }
 
     pub async fn connect(&mut self) -> Result<()> {
-        self.client.connect().await?;
-        self.connected = true;
-        Ok(())
+        self.client.connect().await
     }
 
     /// 订阅行情（必须在 get_quotes 之前调用）
@@ -90,8 +88,8 @@ impl OpenApiProvider {
         stocks: &[StockCode],
         days: u32,
     ) -> Result<HashMap<StockCode, Vec<DailyKline>>> {
-        let end = chrono::Local::now().format("%Y-%m-%d").to_string();
-        let begin = (chrono::Local::now() - chrono::Duration::days(days as i64 * 2))
+        let end = crate::models::app_today().format("%Y-%m-%d").to_string();
+        let begin = (crate::models::app_today() - chrono::Duration::days(days as i64 * 2))
             .format("%Y-%m-%d")
             .to_string();
         self.client
@@ -104,7 +102,12 @@ impl OpenApiProvider {
     }
 
     pub fn is_connected(&self) -> bool {
-        self.connected
+        self.client.connection_state() == ConnectionState::Connected
+    }
+
+    /// 连接状态（断线后客户端按退避自动重连并恢复订阅）
+    pub fn connection_state(&self) -> ConnectionState {
+        self.client.connection_state()
     }
 
     pub fn set_quote_channel(&mut self, tx: mpsc::Sender<QuoteSnapshot>) {
@@ -128,16 +131,32 @@ pub struct OcrProvider {
     last_image_hash: String,
     /// 上一轮有效 quotes 缓存（图像未变化时复用）
     last_quotes: Vec<QuoteSnapshot>,
-    /// 白名单缓存：(plist 路径, mtime, 精确白名单, Unknown 市场的 code 字符串集)
-    whitelist_cache: Option<(PathBuf, SystemTime, HashSet<StockCode>, HashSet<String>)>,
-    /// AX API 检测到的自选股表格区域（归一化坐标），用于跳过 Pass 1 快速 OCR
-    cached_grid_frame: Option<GridFrame>,
-    /// 用户 ID（用于加载正确的自选股白名单）
-    user_id: Option<String>,
+    /// 上一帧 OCR 解析结果（白名单过滤前，保留行顺序），用于帧间行身份稳定
+    last_parsed: Vec<QuoteSnapshot>,
+    /// 白名单缓存：(各 plist 路径及 mtime, 精确白名单, Unknown 市场的 code 字符串集)
+    whitelist_cache: Option<(Vec<(PathBuf, SystemTime)>, HashSet<StockCode>, HashSet<String>)>,
+    /// AX API 检测到的自选股表格区域（归一化坐标），用于跳过 Pass 1 快速 OCR；窗口尺寸变化时才重新检测
+    grid_cache: GridFrameCache,
+    /// 截图后端（ocr.capture_backend）
+    capture_backend: ocr::CaptureBackend,
+    /// 窗口被完全遮挡时截图前激活富途（ocr.raise_before_capture）
+    raise_before_capture: bool,
+    /// 截图后恢复原前台 App（ocr.restore_focus_after_capture）
+    restore_focus_after_capture: bool,
+    /// Pass 2 精确识别前裁剪图的放大倍数（ocr.upscale）
+    upscale: f64,
+    /// 富途窗口最小逻辑面积（ocr.min_window_area）
+    min_window_area: f64,
+    /// Vision 识别语言（ocr.languages）
+    languages: Vec<String>,
+    /// 裁剪后解析出 0 条行情而被作废的 grid frame；AX 返回相同区域时不再采用，直到区域变化
+    rejected_grid_frame: Option<GridFrame>,
+    /// 用户 ID 列表（用于加载正确的自选股白名单，规则见 `watchlist::find_user_dirs`）
+    user_ids: Vec<String>,
 }
 
 impl OcrProvider {
-    pub fn new(user_id: Option<String>) -> Self {
+    pub fn new(user_ids: Vec<String>, config: &OcrConfig) -> Self {
         Self {
             futu_pid: None,
             gui_pid: None,
@@ -145,9 +164,17 @@ impl OcrProvider {
             last_window_size: None,
             last_image_hash: String::new(),
             last_quotes: Vec::new(),
+            last_parsed: Vec::new(),
             whitelist_cache: None,
-            cached_grid_frame: None,
-            user_id,
+            grid_cache: GridFrameCache::default(),
+            capture_backend: ocr::CaptureBackend::from_config(&config.capture_backend),
+            raise_before_capture: config.raise_before_capture,
+            restore_focus_after_capture: config.restore_focus_after_capture,
+            upscale: config.upscale,
+            min_window_area: config.min_window_area,
+            languages: config.languages.clone(),
+            rejected_grid_frame: None,
+            user_ids,
         }
     }
 
@@ -158,29 +185,31 @@ impl OcrProvider {
         info!("OCR provider connected to Futu app (PID: {})", pid);
 
         // 通过 CGWindowList 获取实际 GUI PID（可能与 pgrep 找到的 PID 不同）
-        match ocr::find_futu_window(pid) {
+        let window_size = match ocr::find_futu_window(pid, self.min_window_area) {
             Ok(win) => {
                 self.gui_pid = Some(win.owner_pid);
                 if win.owner_pid != pid {
                     info!("GUI PID differs from pgrep PID: {} vs {}", win.owner_pid, pid);
                 }
+                Some((win.width, win.height))
             }
             Err(e) => {
                 warn!("find_futu_window failed: {}, using pgrep PID for AX", e);
                 self.gui_pid = Some(pid);
+                None
             }
-        }
+        };
 
         // 尝试通过 AX API 检测自选股表格区域
         if let Some(gp) = self.gui_pid {
-            self.detect_grid_frame(gp);
+            self.detect_grid_frame(gp, window_size);
         }
 
         Ok(())
     }
 
-    /// 通过 AX API 检测自选股表格区域
-    fn detect_grid_frame(&mut self, pid: i32) {
+    /// 通过 AX API 检测自选股表格区域（`window_size` 为检测时的窗口尺寸，未知时下一轮重新检测）
+    fn detect_grid_frame(&mut self, pid: i32, window_size: Option<(f64, f64)>) {
         match crate::futu::accessibility::find_watchlist_grid_frame(pid) {
             Ok(frame) => {
                 info!(
@@ -190,11 +219,11 @@ impl OcrProvider {
                     frame.width * 100.0,
                     frame.height * 100.0,
                 );
-                self.cached_grid_frame = Some(frame);
+                self.grid_cache.set(Some(frame), window_size);
             }
             Err(e) => {
                 warn!("AX grid detection failed, will use Pass 1 OCR: {}", e);
-                self.cached_grid_frame = None;
+                self.grid_cache.set(None, window_size);
             }
         }
     }
@@ -204,22 +233,91 @@ impl OcrProvider {
             .futu_pid
             .ok_or_else(|| anyhow::anyhow!("Not connected. Call connect() first."))?;
 
-        // 每轮重新探测 GridFrame（用户可能拖动了内部面板分割线）
-        if let Some(gp) = self.gui_pid {
+        // 富途窗口最小化/隐藏时跳过本轮：截图无有效内容，只会白耗 CPU
+        let win = ocr::find_futu_window(pid, self.min_window_area)?;
+        if !win.is_onscreen {
+            debug!("Futu window {} not on screen, skipping OCR cycle", win.id);
+            anyhow::bail!("富途窗口不可见（已最小化或隐藏），OCR 暂停");
+        }
+        // 被其他窗口完全遮挡时 macOS 会暂停其绘制，截图可能是过期画面
+        // 开启 raise_before_capture 时改为截图前激活富途
+        let raise = win.is_occluded() && self.raise_before_capture;
+        if win.is_occluded() && !raise {
+            debug!(
+                "Futu window {} occluded ({:.0}%), skipping OCR cycle",
+                win.id,
+                win.occluded_ratio * 100.0
+            );
+            anyhow::bail!("富途窗口被遮挡（{:.0}%），OCR 暂停", win.occluded_ratio * 100.0);
+        }
+
+        // 窗口尺寸变化或缓存过期时才重新探测 GridFrame（AX 树遍历开销大）
+        let window_size = (win.width, win.height);
+        if let Some(gp) = self
+            .gui_pid
+            .filter(|_| self.grid_cache.needs_refresh(win.width, win.height))
+        {
+            if self.grid_cache.window_changed(win.width, win.height) {
+                debug!(
+                    "Window size changed to {:.0}x{:.0}, re-detecting GridFrame",
+                    win.width, win.height
+                );
+            }
             match crate::futu::accessibility::find_watchlist_grid_frame(gp) {
-                Ok(frame) => self.cached_grid_frame = Some(frame),
+                Ok(frame) if self.rejected_grid_frame == Some(frame) => {
+                    debug!("GridFrame unchanged since rejected, keep using Pass 1");
+                    self.grid_cache.set(None, Some(window_size));
+                }
+                Ok(frame) => {
+                    self.rejected_grid_frame = None;
+                    self.grid_cache.set(Some(frame), Some(window_size));
+                }
                 Err(e) => {
                     debug!("GridFrame refresh failed, using cached: {}", e);
+                    self.grid_cache.set(self.grid_cache.frame(), Some(window_size));
                 }
             }
         }
 
         // CG 截图和 Vision OCR 都是同步 API，放到阻塞线程池
         let prev_hash = self.last_image_hash.clone();
-        let grid_frame = self.cached_grid_frame;
-        let result = tokio::task::spawn_blocking(move || ocr::ocr_capture_and_parse(pid, &prev_hash, grid_frame))
-            .await
-            .map_err(|e| anyhow::anyhow!("spawn_blocking failed: {}", e))??;
+        let grid_frame = self.grid_cache.frame();
+        let backend = self.capture_backend;
+        let restore_focus = self.restore_focus_after_capture;
+        let upscale = self.upscale;
+        let min_window_area = self.min_window_area;
+        let languages = self.languages.clone();
+        let owner_pid = win.owner_pid;
+        let result = tokio::task::spawn_blocking(move || {
+            let previous_front = if raise {
+                debug!("Futu window occluded, raising before capture");
+                crate::futu::window_focus::bring_to_front(owner_pid)
+            } else {
+                None
+            };
+            let result = ocr::ocr_capture_and_parse(
+                pid,
+                &prev_hash,
+                grid_frame,
+                backend,
+                upscale,
+                min_window_area,
+                &languages,
+            );
+            if let Some(front) = previous_front.filter(|_| restore_focus) {
+                crate::futu::window_focus::activate_app(front);
+            }
+            result
+        })
+        .await
+        .map_err(|e| anyhow::anyhow!("spawn_blocking failed: {}", e))??;
+
+        // grid frame 裁剪解析不出行情（已在同一截图降级到 Pass 1）→ 作废缓存的 frame
+        if result.grid_frame_rejected {
+            warn!("AX grid frame yielded no quotes, invalidating cached frame");
+            self.rejected_grid_frame = grid_frame;
+            self.grid_cache.invalidate();
+        }
 
         // 图像未变化 → 直接返回缓存
         self.last_image_hash = result.image_hash;
@@ -242,13 +340,22 @@ impl OcrProvider {
         self.last_window_size = Some(new_size);
 
         // Layer 2: 自选股白名单过滤（plist 变化时才重新加载）
+        let prev_parsed = std::mem::take(&mut self.last_parsed);
         let (whitelist, unknown_codes) = self.get_whitelist();
-        let ocr_total = result.quotes.len();
-        let accepted: Vec<_> = result
-            .quotes
-            .into_iter()
-            .filter(|q| whitelist.contains(&q.code) || unknown_codes.contains(&q.code.code))
-            .collect();
+        let is_known = |c: &StockCode| whitelist.contains(c) || unknown_codes.contains(&c.code);
+
+        // 帧间行身份稳定：代码误识别的行按位置 + 名称沿用上一帧代码，避免行闪烁
+        let mut parsed = result.quotes;
+        let stabilized = ocr::stabilize_row_codes(&prev_parsed, &mut parsed, is_known);
+        if stabilized > 0 {
+            debug!(
+                "Row tracking: {} misread codes restored from previous frame",
+                stabilized
+            );
+        }
+
+        let ocr_total = parsed.len();
+        let accepted: Vec<_> = parsed.iter().filter(|q| is_known(&q.code)).cloned().collect();
         let filtered_count = ocr_total - accepted.len();
         if filtered_count > 0 {
             info!(
@@ -259,28 +366,31 @@ impl OcrProvider {
             );
         }
 
+        self.last_parsed = parsed;
         self.last_quotes = accepted.clone();
         Ok(accepted)
     }
 
     /// 获取白名单（plist mtime 未变时返回缓存）
     fn get_whitelist(&mut self) -> (&HashSet<StockCode>, &HashSet<String>) {
+        let mtime_of = |path: &PathBuf| path.metadata().and_then(|m| m.modified());
         let need_reload = match &self.whitelist_cache {
-            Some((path, cached_mtime, _, _)) => path
-                .metadata()
-                .and_then(|m| m.modified())
-                .map(|mtime| mtime != *cached_mtime)
-                .unwrap_or(true),
+            Some((stamps, _, _)) => stamps
+                .iter()
+                .any(|(path, cached_mtime)| mtime_of(path).map(|mtime| mtime != *cached_mtime).unwrap_or(true)),
             None => true,
         };
 
         if need_reload {
-            match crate::futu::watchlist::load_watchlist_codes(self.user_id.as_deref()) {
-                Ok((path, codes)) => {
-                    let mtime = path
-                        .metadata()
-                        .and_then(|m| m.modified())
-                        .unwrap_or(SystemTime::UNIX_EPOCH);
+            match crate::futu::watchlist::load_watchlist_codes(&self.user_ids) {
+                Ok((paths, codes)) => {
+                    let stamps = paths
+                        .into_iter()
+                        .map(|path| {
+                            let mtime = mtime_of(&path).unwrap_or(SystemTime::UNIX_EPOCH);
+                            (path, mtime)
+                        })
+                        .collect();
                     let unknown_codes: HashSet<String> = codes
                         .iter()
                         .filter(|c| c.market == Market::Unknown)
@@ -288,20 +398,23 @@ impl OcrProvider {
                         .collect();
                     let whitelist: HashSet<StockCode> = codes.into_iter().collect();
                     info!("Whitelist reloaded: {} codes", whitelist.len());
-                    self.whitelist_cache = Some((path, mtime, whitelist, unknown_codes));
+                    self.whitelist_cache = Some((stamps, whitelist, unknown_codes));
                 }
                 Err(e) => {
                     warn!("Failed to load watchlist for whitelist: {}", e);
                     if self.whitelist_cache.is_none() {
-                        self.whitelist_cache =
-                            Some((PathBuf::new(), SystemTime::UNIX_EPOCH, HashSet::new(), HashSet::new()));
+                        self.whitelist_cache = Some((
+                            vec![(PathBuf::new(), SystemTime::UNIX_EPOCH)],
+                            HashSet::new(),
+                            HashSet::new(),
+                        ));
                     }
                 }
             }
         }
 
         let cache = self.whitelist_cache.as_ref().unwrap();
-        (&cache.2, &cache.3)
+        (&cache.1, &cache.2)
     }
 
     pub fn name(&self) -> &str {
@@ -313,24 +426,195 @@ impl OcrProvider {
     }
 }
 
+/// 多数据源降级链：按顺序拉取，后面的数据源只补齐前面缺失的股票
+///
+/// 典型用法 OpenAPI → OCR：港股/A 股走 OpenAPI，美股无行情权限（或盘前盘后无配额）时由 OCR 补齐。
+/// 全部数据源都拿不到的股票仍由 `futu.cached_price_fallback` 兜底。
+pub struct ChainedProvider {
+    providers: Vec<DataProviderKind>,
+    /// 最近一次提供了行情的数据源（如 "OpenAPI+OCR"），尚未拉取时为整条链
+    active_name: String,
+}
+
+impl ChainedProvider {
+    pub fn new(providers: Vec<DataProviderKind>) -> Self {
+        let active_name = providers.iter().map(|p| p.name()).collect::<Vec<_>>().join("→");
+        Self { providers, active_name }
+    }
+
+    /// 依次连接所有数据源，至少一个成功即可（连接失败的数据源拉取时报错并跳过）
+    pub async fn connect(&mut self) -> Result<()> {
+        let mut connected = false;
+        let mut last_err = None;
+        for provider in self.providers.iter_mut() {
+            match provider.connect().await {
+                Ok(()) => connected = true,
+                Err(e) => {
+                    warn!("数据源 [{}] 连接失败: {}", provider.name(), e);
+                    last_err = Some(e);
+                }
+            }
+        }
+        match last_err {
+            Some(e) if !connected => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    /// 订阅 / 退订转发给所有数据源，单个失败只记日志
+    async fn for_each_subscription(&mut self, codes: &[StockCode], subscribe: bool) -> Result<()> {
+        for provider in self.providers.iter_mut() {
+            let result = if subscribe {
+                provider.subscribe(codes).await
+            } else {
+                provider.unsubscribe(codes).await
+            };
+            if let Err(e) = result {
+                warn!(
+                    "数据源 [{}] {}失败: {}",
+                    provider.name(),
+                    if subscribe { "订阅" } else { "退订" },
+                    e
+                );
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn subscribe(&mut self, codes: &[StockCode]) -> Result<()> {
+        self.for_each_subscription(codes, true).await
+    }
+
+    pub async fn unsubscribe(&mut self, codes: &[StockCode]) -> Result<()> {
+        self.for_each_subscription(codes, false).await
+    }
+
+    /// 按顺序拉取，后面的数据源只请求前面缺失的股票，结果按 `StockCode` 合并（先到先得）
+    ///
+    /// 请求的股票全部拿到后不再调用后续数据源；单个数据源失败只记日志，
+    /// 所有数据源都没有返回行情时才返回最后一个错误
+    pub async fn get_quotes(&mut self, codes: &[StockCode]) -> Result<Vec<QuoteSnapshot>> {
+        if codes.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut merged: Vec<QuoteSnapshot> = Vec::new();
+        let mut seen: HashSet<StockCode> = HashSet::new();
+        let mut sources: Vec<String> = Vec::new();
+        let mut last_err = None;
+
+        for provider in self.providers.iter_mut() {
+            let missing: Vec<StockCode> = codes.iter().filter(|c| !seen.contains(*c)).cloned().collect();
+            if !merged.is_empty() && missing.is_empty() {
+                break;
+            }
+            match provider.get_quotes(&missing).await {
+                Ok(quotes) => {
+                    let before = merged.len();
+                    for quote in quotes {
+                        if seen.insert(quote.code.clone()) {
+                            merged.push(quote);
+                        }
+                    }
+                    if merged.len() > before {
+                        sources.push(provider.name().to_string());
+                    }
+                }
+                Err(e) => {
+                    debug!("数据源 [{}] 拉取失败，尝试下一个: {}", provider.name(), e);
+                    last_err = Some(e);
+                }
+            }
+        }
+
+        if !sources.is_empty() {
+            self.active_name = sources.join("+");
+        }
+        match last_err {
+            Some(e) if merged.is_empty() => Err(e),
+            _ => Ok(merged),
+        }
+    }
+
+    /// 第一个返回非空结果的数据源的日K线
+    ///
+    /// 单个数据源失败只记日志并尝试下一个，都没有返回日K线时才返回最后一个错误
+    pub async fn get_daily_klines(
+        &mut self,
+        stocks: &[StockCode],
+        days: u32,
+    ) -> Result<HashMap<StockCode, Vec<DailyKline>>> {
+        let mut last_err = None;
+        for provider in self.providers.iter_mut() {
+            match provider.get_daily_klines(stocks, days).await {
+                Ok(klines) if !klines.is_empty() => return Ok(klines),
+                Ok(_) => {}
+                Err(e) => {
+                    warn!("数据源 [{}] 日K线拉取失败，尝试下一个: {}", provider.name(), e);
+                    last_err = Some(e);
+                }
+            }
+        }
+        match last_err {
+            Some(e) => Err(e),
+            None => Ok(HashMap::new()),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.active_name
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.providers.iter().any(|p| p.is_connected())
+    }
+}
+
 /// 数据源类型（枚举分发，无需 async_trait）
 pub enum DataProviderKind {
     Accessibility(AccessibilityProvider),
     OpenApi(OpenApiProvider),
     Ocr(OcrProvider),
+    /// 合成行情（`qtrade demo`）
+    Demo(DemoProvider),
+    /// 主数据源 + 备用数据源（`data_source.fallback_sources`）
+    Chained(ChainedProvider),
 }
 
 impl DataProviderKind {
-    /// 根据配置创建数据提供者
+    /// 根据配置创建数据提供者（配置了备用数据源时组成降级链）
     pub fn from_config(config: &AppConfig) -> Self {
-        match config.data_source.source.as_str() {
+        let primary = Self::from_source(&config.data_source.source, config);
+        if config.data_source.fallback_sources.is_empty() {
+            return primary;
+        }
+        let mut providers = vec![primary];
+        providers.extend(
+            config
+                .data_source
+                .fallback_sources
+                .iter()
+                .map(|source| Self::from_source(source, config)),
+        );
+        let chain = ChainedProvider::new(providers);
+        info!("Data source fallback chain: {}", chain.name());
+        DataProviderKind::Chained(chain)
+    }
+
+    /// 按数据源名创建单个数据提供者（未知名称按 accessibility 处理）
+    fn from_source(source: &str, config: &AppConfig) -> Self {
+        match source {
             "openapi" => {
                 info!("Using FutuOpenD OpenAPI data source");
-                DataProviderKind::OpenApi(OpenApiProvider::new(&config.futu.opend_host, config.futu.opend_port))
+                let mut provider = OpenApiProvider::new(&config.futu.opend_host, config.futu.opend_port);
+                provider
+                    .client
+                    .set_push_reconnect_max(Duration::from_secs(config.futu.push_reconnect_max_secs));
+                provider.client.enable_raw_log(config.futu.raw_response_log);
+                DataProviderKind::OpenApi(provider)
             }
             "ocr" => {
                 info!("Using window screenshot + Vision OCR data source");
-                DataProviderKind::Ocr(OcrProvider::new(config.futu.user_id.clone()))
+                DataProviderKind::Ocr(OcrProvider::new(config.futu.selected_user_ids(), &config.ocr))
             }
             _ => {
                 info!("Using macOS Accessibility API data source");
@@ -344,6 +628,9 @@ impl DataProviderKind {
             DataProviderKind::Accessibility(p) => p.connect().await,
             DataProviderKind::OpenApi(p) => p.connect().await,
             DataProviderKind::Ocr(p) => p.connect().await,
+            DataProviderKind::Demo(p) => p.connect().await,
+            // 降级链内部递归分发，需装箱
+            DataProviderKind::Chained(p) => Box::pin(p.connect()).await,
         }
     }
 
@@ -353,6 +640,8 @@ impl DataProviderKind {
             DataProviderKind::Accessibility(_) => Ok(()),
             DataProviderKind::OpenApi(p) => p.subscribe(codes).await,
             DataProviderKind::Ocr(_) => Ok(()),
+            DataProviderKind::Demo(_) => Ok(()),
+            DataProviderKind::Chained(p) => Box::pin(p.subscribe(codes)).await,
         }
     }
 
@@ -362,6 +651,8 @@ impl DataProviderKind {
             DataProviderKind::Accessibility(_) => Ok(()),
             DataProviderKind::OpenApi(p) => p.unsubscribe(codes).await,
             DataProviderKind::Ocr(_) => Ok(()),
+            DataProviderKind::Demo(_) => Ok(()),
+            DataProviderKind::Chained(p) => Box::pin(p.unsubscribe(codes)).await,
         }
     }
 
@@ -370,10 +661,12 @@ impl DataProviderKind {
             DataProviderKind::Accessibility(p) => p.get_quotes(codes).await,
             DataProviderKind::OpenApi(p) => p.get_quotes(codes).await,
             DataProviderKind::Ocr(p) => p.get_quotes(codes).await,
+            DataProviderKind::Demo(p) => p.get_quotes(codes).await,
+            DataProviderKind::Chained(p) => Box::pin(p.get_quotes(codes)).await,
         }
     }
 
-    /// 获取历史日K线数据（Accessibility/OCR 模式返回空 Map）
+    /// 获取历史日K线数据（Accessibility/OCR/Demo 模式返回空 Map）
     pub async fn get_daily_klines(
         &mut self,
         stocks: &[StockCode],
@@ -383,6 +676,26 @@ impl DataProviderKind {
             DataProviderKind::Accessibility(_) => Ok(HashMap::new()),
             DataProviderKind::OpenApi(p) => p.get_daily_klines(stocks, days).await,
             DataProviderKind::Ocr(_) => Ok(HashMap::new()),
+            DataProviderKind::Demo(_) => Ok(HashMap::new()),
+            DataProviderKind::Chained(p) => Box::pin(p.get_daily_klines(stocks, days)).await,
+        }
+    }
+
+    /// 轮询间隔：OCR 使用 `ocr.interval_secs`，其余使用 `data_source.refresh_interval_secs`
+    ///
+    /// 降级链取所有数据源中最长的间隔：备用数据源每轮都可能被调用（补齐缺失股票或主数据源断开），
+    /// 含 OCR 时不能按主数据源的短间隔反复截图识别
+    pub fn poll_interval(&self, config: &AppConfig) -> Duration {
+        let refresh = Duration::from_secs(config.data_source.refresh_interval_secs);
+        match self {
+            DataProviderKind::Ocr(_) => Duration::from_secs(config.ocr.interval_secs.max(1)),
+            DataProviderKind::Chained(p) => p
+                .providers
+                .iter()
+                .map(|provider| provider.poll_interval(config))
+                .max()
+                .unwrap_or(refresh),
+            _ => refresh,
         }
     }
 
@@ -391,6 +704,8 @@ impl DataProviderKind {
             DataProviderKind::Accessibility(p) => p.name(),
             DataProviderKind::OpenApi(p) => p.name(),
             DataProviderKind::Ocr(p) => p.name(),
+            DataProviderKind::Demo(p) => p.name(),
+            DataProviderKind::Chained(p) => p.name(),
         }
     }
 
@@ -399,6 +714,21 @@ impl DataProviderKind {
             DataProviderKind::Accessibility(p) => p.is_connected(),
             DataProviderKind::OpenApi(p) => p.is_connected(),
             DataProviderKind::Ocr(p) => p.is_connected(),
+            DataProviderKind::Demo(p) => p.is_connected(),
+            DataProviderKind::Chained(p) => p.is_connected(),
+        }
+    }
+
+    /// 连接状态（仅 OpenAPI 有重连中状态；降级链任一数据源已连接即视为已连接，否则取主数据源状态）
+    pub fn connection_state(&self) -> ConnectionState {
+        match self {
+            DataProviderKind::OpenApi(p) => p.connection_state(),
+            DataProviderKind::Chained(p) if !p.is_connected() => p
+                .providers
+                .first()
+                .map_or(ConnectionState::Disconnected, |primary| primary.connection_state()),
+            _ if self.is_connected() => ConnectionState::Connected,
+            _ => ConnectionState::Disconnected,
         }
     }
 
@@ -408,6 +738,54 @@ impl DataProviderKind {
             DataProviderKind::Accessibility(_) => HashSet::new(),
             DataProviderKind::OpenApi(p) => p.subscribed_markets(),
             DataProviderKind::Ocr(_) => HashSet::new(),
+            DataProviderKind::Demo(_) => HashSet::new(),
+            DataProviderKind::Chained(p) => p.providers.iter().flat_map(|p| p.subscribed_markets()).collect(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::demo::demo_watchlist;
+
+    #[tokio::test]
+    async fn test_chained_provider_fills_missing_codes() {
+        let watchlist = demo_watchlist();
+        let codes: Vec<StockCode> = watchlist.iter().map(|e| e.code.clone()).collect();
+        // 主数据源只覆盖前两只股票，备用数据源覆盖全部
+        let primary = DataProviderKind::Demo(DemoProvider::with_seed(&watchlist[..2], 1));
+        let fallback = DataProviderKind::Demo(DemoProvider::with_seed(&watchlist, 2));
+        let mut chain = DataProviderKind::Chained(ChainedProvider::new(vec![primary, fallback]));
+        assert_eq!(chain.name(), "Demo→Demo");
+        chain.connect().await.unwrap();
+
+        let quotes = chain.get_quotes(&codes).await.unwrap();
+        assert_eq!(quotes.len(), codes.len());
+        let unique: HashSet<&StockCode> = quotes.iter().map(|q| &q.code).collect();
+        assert_eq!(unique.len(), codes.len());
+        assert_eq!(chain.name(), "Demo+Demo");
+
+        // 主数据源已覆盖全部请求 → 不再调用备用数据源
+        let quotes = chain.get_quotes(&codes[..2]).await.unwrap();
+        assert_eq!(quotes.len(), 2);
+        assert_eq!(chain.name(), "Demo");
+    }
+
+    #[test]
+    fn test_chain_poll_interval_uses_slowest_source() {
+        let mut config = AppConfig::default();
+        config.data_source.refresh_interval_secs = 2;
+        config.ocr.interval_secs = 5;
+        let demo = || DataProviderKind::Demo(DemoProvider::with_seed(&demo_watchlist(), 1));
+        assert_eq!(demo().poll_interval(&config), Duration::from_secs(2));
+
+        // OpenAPI → OCR 这类链按 OCR 间隔轮询，避免每 2s 截图识别一次
+        let ocr = DataProviderKind::Ocr(OcrProvider::new(Vec::new(), &config.ocr));
+        let chain = DataProviderKind::Chained(ChainedProvider::new(vec![demo(), ocr]));
+        assert_eq!(chain.poll_interval(&config), Duration::from_secs(5));
+
+        let chain = DataProviderKind::Chained(ChainedProvider::new(vec![demo(), demo()]));
+        assert_eq!(chain.poll_interval(&config), Duration::from_secs(2));
+    }
+}
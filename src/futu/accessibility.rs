@@ -3,6 +3,8 @@
 //! 通过 AXUIElement API 从富途牛牛 App 窗口直接读取行情数据。
 //! 需要用户在 系统偏好设置 → 隐私与安全性 → 辅助功能 中授权。
 
+use std::time::{Duration, Instant};
+
 use anyhow::{Context, Result};
 use tracing::{debug, info, warn};
 
@@ -12,7 +14,7 @@ use crate::models::QuoteSnapshot;
 use crate::futu::ax::{Application, Element, Rect};
 
 /// 自选股表格区域（归一化坐标 0.0-1.0，相对于窗口）
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct GridFrame {
     pub x: f64,
     pub y: f64,
@@ -31,6 +33,55 @@ impl From<Rect> for GridFrame {
     }
 }
 
+/// GridFrame 缓存：窗口尺寸不变时复用上次 AX 检测结果，避免每轮 OCR 都遍历 AX 树
+///
+/// 窗口尺寸变化超过 `SIZE_TOLERANCE` 或缓存超过 `MAX_AGE` 时需要重新检测；
+/// 后者兜底窗口尺寸不变、但用户拖动了内部面板分割线的情况。
+#[derive(Debug, Clone, Default)]
+pub struct GridFrameCache {
+    frame: Option<GridFrame>,
+    /// 检测时的窗口尺寸（逻辑 point）
+    window_size: Option<(f64, f64)>,
+    checked_at: Option<Instant>,
+}
+
+impl GridFrameCache {
+    /// 窗口尺寸容差（逻辑 point）
+    pub const SIZE_TOLERANCE: f64 = 1.0;
+    /// 缓存最长有效期
+    pub const MAX_AGE: Duration = Duration::from_secs(60);
+
+    /// 当前缓存的 frame
+    pub fn frame(&self) -> Option<GridFrame> {
+        self.frame
+    }
+
+    /// 窗口尺寸相对上次检测是否变化（无记录时视为变化）
+    pub fn window_changed(&self, width: f64, height: f64) -> bool {
+        match self.window_size {
+            Some((w, h)) => (w - width).abs() > Self::SIZE_TOLERANCE || (h - height).abs() > Self::SIZE_TOLERANCE,
+            None => true,
+        }
+    }
+
+    /// 是否需要重新做 AX 检测
+    pub fn needs_refresh(&self, width: f64, height: f64) -> bool {
+        self.window_changed(width, height) || self.checked_at.is_none_or(|t| t.elapsed() >= Self::MAX_AGE)
+    }
+
+    /// 记录一次检测结果（`frame` 为 None 表示当前不可用）及检测时的窗口尺寸
+    pub fn set(&mut self, frame: Option<GridFrame>, window_size: Option<(f64, f64)>) {
+        self.frame = frame;
+        self.window_size = window_size;
+        self.checked_at = Some(Instant::now());
+    }
+
+    /// 作废缓存，下一轮强制重新检测
+    pub fn invalidate(&mut self) {
+        *self = Self::default();
+    }
+}
+
 /// Accessibility API 数据提取器
 pub struct AccessibilityReader {
     /// 富途 App 连接
@@ -318,6 +369,29 @@ mod tests {
         assert_eq!(grid.height, 0.4);
     }
 
+    #[test]
+    fn test_grid_frame_cache_refresh_on_resize() {
+        let frame = GridFrame::from(Rect::new(0.0, 0.1, 0.3, 0.8));
+        let mut cache = GridFrameCache::default();
+        assert!(cache.needs_refresh(1200.0, 800.0));
+
+        cache.set(Some(frame), Some((1200.0, 800.0)));
+        assert_eq!(cache.frame(), Some(frame));
+        assert!(!cache.needs_refresh(1200.0, 800.0));
+        // 容差内的亚像素抖动不触发重新检测
+        assert!(!cache.needs_refresh(1200.5, 800.0));
+        assert!(cache.needs_refresh(1000.0, 800.0));
+
+        // 未记录窗口尺寸（连接时窗口查找失败）→ 下一轮重新检测
+        cache.set(Some(frame), None);
+        assert!(cache.needs_refresh(1200.0, 800.0));
+
+        cache.set(Some(frame), Some((1200.0, 800.0)));
+        cache.invalidate();
+        assert_eq!(cache.frame(), None);
+        assert!(cache.needs_refresh(1200.0, 800.0));
+    }
+
     #[test]
     fn test_accessibility_reader_new() {
         let reader = AccessibilityReader::new();
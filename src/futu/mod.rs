@@ -2,7 +2,11 @@
 
 pub mod accessibility;
 pub mod ax;
+#[cfg(feature = "trading")]
 pub mod ax_action;
 pub mod ocr;
 pub mod openapi;
+#[cfg(feature = "trading")]
+pub mod trade;
 pub mod watchlist;
+pub mod window_focus;
@@ -1,26 +1,32 @@
 //! 窗口截图 + Apple Vision OCR 数据获取
 //!
-//! 通过 CGWindowListCreateImage 截取富途牛牛窗口，
+//! 通过 CGWindowListCreateImage（或 macOS 14+ 的 ScreenCaptureKit）截取富途牛牛窗口，
 //! 使用 Vision 框架的 VNRecognizeTextRequest 识别文字，
 //! 按 Y 坐标聚类成行后复用现有 parser 解析为 QuoteSnapshot。
 
 #![allow(deprecated)] // CGWindowListCreateImage / CGWindowListCopyWindowInfo
 
+use std::ptr::NonNull;
+
 use anyhow::{Context, Result};
+use block2::RcBlock;
 use objc2::rc::Retained;
 use objc2::AnyThread;
 use objc2_core_foundation::{CFRetained, CGPoint, CGRect, CGSize};
 use objc2_core_graphics::{
-    CGImage, CGImageCreateWithImageInRect, CGWindowID, CGWindowImageOption, CGWindowListCopyWindowInfo,
+    CGBitmapContextCreate, CGBitmapContextCreateImage, CGColorSpace, CGContext, CGImage, CGImageAlphaInfo,
+    CGImageCreateWithImageInRect, CGInterpolationQuality, CGWindowID, CGWindowImageOption, CGWindowListCopyWindowInfo,
     CGWindowListCreateImage, CGWindowListOption,
 };
-use objc2_foundation::{NSArray, NSDictionary, NSString};
+use objc2_foundation::{NSArray, NSDictionary, NSError, NSOperatingSystemVersion, NSProcessInfo, NSString};
+use objc2_screen_capture_kit::{SCContentFilter, SCScreenshotManager, SCShareableContent, SCStreamConfiguration};
 use objc2_vision::{
     VNImageRequestHandler, VNRecognizeTextRequest, VNRecognizedTextObservation, VNRequest,
     VNRequestTextRecognitionLevel,
 };
 use tracing::{debug, info, warn};
 
+use crate::data::parser::{parse_volume_with_unit, VolumeUnit};
 use crate::models::{DataSource, Market, QuoteSnapshot, StockCode};
 
 /// OCR 识别出的文字块
@@ -32,21 +38,39 @@ pub struct OcrTextBlock {
     pub bbox: (f64, f64, f64, f64),
 }
 
-/// 窗口信息（ID + 尺寸 + 所属进程 PID）
+/// 窗口信息（ID + 尺寸 + 所属进程 PID + 可见性）
 #[derive(Debug, Clone, Copy)]
 pub struct WindowInfo {
     pub id: u32,
     pub width: f64,
     pub height: f64,
     pub owner_pid: i32,
+    /// 窗口是否在屏幕上（kCGWindowIsOnscreen）；最小化、App 隐藏、位于其他桌面时为 false
+    pub is_onscreen: bool,
+    /// 被其他 App 的普通窗口遮挡的面积比例（0.0-1.0），窗口不在屏幕上时为 0.0
+    pub occluded_ratio: f64,
+}
+
+/// 遮挡比例达到此值视为完全遮挡：macOS 会暂停被完全遮挡窗口的绘制，截图可能是过期画面
+pub const OCCLUDED_SKIP_RATIO: f64 = 0.95;
+
+impl WindowInfo {
+    /// 窗口是否（几乎）完全被遮挡
+    pub fn is_occluded(&self) -> bool {
+        self.occluded_ratio >= OCCLUDED_SKIP_RATIO
+    }
 }
 
 /// 查找富途牛牛 App 的主窗口 ID 和尺寸
 ///
 /// 通过 CGWindowListCopyWindowInfo 获取所有窗口，
-/// 按 owner name 匹配 "Futu" / "Niuniu" / "牛牛"，选面积最大的。
+/// 按 owner name 匹配 "Futu" / "Niuniu" / "牛牛"，优先选在屏幕上的窗口，其次面积最大的。
 /// 不依赖单一 PID，避免多进程场景找不到窗口。
-pub fn find_futu_window(pid: i32) -> Result<WindowInfo> {
+/// 返回的 `is_onscreen` 供调用方判断窗口是否最小化/隐藏，`occluded_ratio` 判断是否被遮挡。
+///
+/// `min_area` 为逻辑面积（point²，与 Retina 缩放无关）：kCGWindowBounds 以 point 为单位，
+/// 小于此值的窗口（菜单、浮层、工具窗口）不参与选择（`ocr.min_window_area`）。
+pub fn find_futu_window(pid: i32, min_area: f64) -> Result<WindowInfo> {
     let info_list = CGWindowListCopyWindowInfo(
         CGWindowListOption::OptionAll,
         0, // kCGNullWindowID
@@ -57,8 +81,8 @@ pub fn find_futu_window(pid: i32) -> Result<WindowInfo> {
     let count = unsafe { core_foundation::array::CFArrayGetCount(cf_arr_ptr) };
     debug!("CGWindowListCopyWindowInfo: {} windows total", count);
 
-    let mut best: Option<(u32, f64, f64, i32)> = None;
-    let mut best_area: f64 = 0.0;
+    let mut best: Option<WindowInfo> = None;
+    let mut best_key: (bool, f64) = (false, 0.0);
 
     for i in 0..count {
         let dict_ptr = unsafe { core_foundation::array::CFArrayGetValueAtIndex(cf_arr_ptr, i) };
@@ -83,40 +107,145 @@ pub fn find_futu_window(pid: i32) -> Result<WindowInfo> {
         // 读取 kCGWindowNumber
         let window_id = unsafe { dict_get_i32(dict_ptr, "kCGWindowNumber") };
 
-        // 读取 kCGWindowBounds
-        let (w, h) = unsafe { dict_get_window_bounds(dict_ptr) }.unwrap_or((0.0, 0.0));
+        // 读取 kCGWindowBounds（point，非像素）
+        let (_, _, w, h) = unsafe { dict_get_window_bounds(dict_ptr) }.unwrap_or_default();
         let area = w * h;
 
         // 过滤太小的窗口（菜单、浮层等）
-        if area < 10000.0 {
+        if area < min_area {
+            debug!(
+                "  skip small window: owner={:?} window_id={:?} size={}x{} area={} < {}",
+                owner_name, window_id, w, h, area, min_area
+            );
             continue;
         }
 
+        // kCGWindowIsOnscreen 仅在窗口可见时存在且为 true
+        let is_onscreen = unsafe { dict_get_bool(dict_ptr, "kCGWindowIsOnscreen") }.unwrap_or(false);
+
         debug!(
-            "  owner={:?} pid={:?} window_id={:?} size={}x{} area={}",
-            owner_name, owner_pid, window_id, w, h, area
+            "  owner={:?} pid={:?} window_id={:?} size={}x{} area={} onscreen={}",
+            owner_name, owner_pid, window_id, w, h, area, is_onscreen
         );
 
-        if area > best_area {
-            best_area = area;
+        // 可见窗口优先，其次面积大者
+        let key = (is_onscreen, area);
+        if best.is_none() || key > best_key {
             if let (Some(wid), Some(opid)) = (window_id, owner_pid) {
-                best = Some((wid as u32, w, h, opid));
+                best_key = key;
+                best = Some(WindowInfo {
+                    id: wid as u32,
+                    width: w,
+                    height: h,
+                    owner_pid: opid,
+                    is_onscreen,
+                    occluded_ratio: 0.0,
+                });
             }
         }
     }
 
-    best.map(|(id, width, height, owner_pid)| WindowInfo {
-        id,
-        width,
-        height,
-        owner_pid,
-    })
-    .context("未找到富途牛牛窗口。请确认 App 已启动且窗口未最小化。")
+    let mut win = best.context("未找到富途牛牛窗口。请确认 App 已启动且窗口未最小化。")?;
+    if win.is_onscreen {
+        win.occluded_ratio = window_occluded_ratio(win.id, win.owner_pid);
+    }
+    Ok(win)
+}
+
+/// 计算窗口被其他 App 遮挡的面积比例
+///
+/// 通过 `OnScreenAboveWindow` 列出位于该窗口之上的屏幕窗口，只统计 layer 0（普通窗口）、
+/// 不透明度 > 0、且不属于富途自身进程的窗口（排除富途自己的弹窗/浮层、菜单栏、Dock）。
+fn window_occluded_ratio(window_id: u32, owner_pid: i32) -> f64 {
+    let Some(info_list) = CGWindowListCopyWindowInfo(CGWindowListOption::OptionOnScreenAboveWindow, window_id) else {
+        return 0.0;
+    };
+    let cf_arr_ptr = CFRetained::as_ptr(&info_list).as_ptr() as *const _;
+    let count = unsafe { core_foundation::array::CFArrayGetCount(cf_arr_ptr) };
+
+    // 目标窗口自身的 bounds：OnScreenAboveWindow 不含目标窗口，单独查询
+    let Some(target_list) = CGWindowListCopyWindowInfo(CGWindowListOption::OptionIncludingWindow, window_id) else {
+        return 0.0;
+    };
+    let target_ptr = CFRetained::as_ptr(&target_list).as_ptr() as *const _;
+    if unsafe { core_foundation::array::CFArrayGetCount(target_ptr) } == 0 {
+        return 0.0;
+    }
+    let target_dict = unsafe { core_foundation::array::CFArrayGetValueAtIndex(target_ptr, 0) };
+    let Some(target) = (unsafe { dict_get_window_bounds(target_dict) }) else {
+        return 0.0;
+    };
+
+    let mut occluders = Vec::new();
+    for i in 0..count {
+        let dict_ptr = unsafe { core_foundation::array::CFArrayGetValueAtIndex(cf_arr_ptr, i) };
+        if dict_ptr.is_null() {
+            continue;
+        }
+        let layer = unsafe { dict_get_i32(dict_ptr, "kCGWindowLayer") }.unwrap_or(0);
+        let alpha = unsafe { dict_get_f64(dict_ptr, "kCGWindowAlpha") }.unwrap_or(1.0);
+        let pid = unsafe { dict_get_i32(dict_ptr, "kCGWindowOwnerPID") };
+        if layer != 0 || alpha <= 0.0 || pid == Some(owner_pid) {
+            continue;
+        }
+        if let Some(bounds) = unsafe { dict_get_window_bounds(dict_ptr) } {
+            debug!(
+                "  occluder owner={:?} bounds={:?}",
+                unsafe { dict_get_string(dict_ptr, "kCGWindowOwnerName") },
+                bounds
+            );
+            occluders.push(bounds);
+        }
+    }
+
+    covered_ratio(target, &occluders)
+}
+
+/// 目标矩形被一组矩形覆盖的面积比例（0.0-1.0），矩形格式 (x, y, w, h)
+///
+/// 按所有矩形边坐标切分网格，逐格判断是否被覆盖，重叠部分不重复计算。
+fn covered_ratio(target: (f64, f64, f64, f64), rects: &[(f64, f64, f64, f64)]) -> f64 {
+    let (tx, ty, tw, th) = target;
+    if tw <= 0.0 || th <= 0.0 {
+        return 0.0;
+    }
+
+    // 裁剪到目标矩形内，丢弃不相交的矩形
+    let clipped: Vec<(f64, f64, f64, f64)> = rects
+        .iter()
+        .map(|&(x, y, w, h)| (x.max(tx), y.max(ty), (x + w).min(tx + tw), (y + h).min(ty + th)))
+        .filter(|&(x0, y0, x1, y1)| x1 > x0 && y1 > y0)
+        .collect();
+    if clipped.is_empty() {
+        return 0.0;
+    }
+
+    let mut xs: Vec<f64> = clipped.iter().flat_map(|r| [r.0, r.2]).collect();
+    let mut ys: Vec<f64> = clipped.iter().flat_map(|r| [r.1, r.3]).collect();
+    xs.sort_by(|a, b| a.total_cmp(b));
+    xs.dedup();
+    ys.sort_by(|a, b| a.total_cmp(b));
+    ys.dedup();
+
+    let mut covered = 0.0;
+    for xw in xs.windows(2) {
+        for yw in ys.windows(2) {
+            let (cx, cy) = ((xw[0] + xw[1]) / 2.0, (yw[0] + yw[1]) / 2.0);
+            if clipped
+                .iter()
+                .any(|&(x0, y0, x1, y1)| cx > x0 && cx < x1 && cy > y0 && cy < y1)
+            {
+                covered += (xw[1] - xw[0]) * (yw[1] - yw[0]);
+            }
+        }
+    }
+
+    (covered / (tw * th)).min(1.0)
 }
 
-/// 兼容旧接口：只返回窗口 ID
+/// 兼容旧接口：只返回窗口 ID（使用默认最小窗口面积）
 pub fn find_futu_window_id(pid: i32) -> Result<u32> {
-    find_futu_window(pid).map(|w| w.id)
+    find_futu_window(pid, crate::config::OcrConfig::default().min_window_area).map(|w| w.id)
 }
 
 /// 检查是否拥有屏幕录制权限
@@ -135,8 +264,137 @@ pub fn request_screen_capture_permission() -> bool {
     unsafe { CGRequestScreenCaptureAccess() }
 }
 
+/// 截图后端
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CaptureBackend {
+    /// CGWindowListCreateImage（已弃用，macOS 14+ 上可能弹出授权提示或返回降采样图像）
+    #[default]
+    CgWindow,
+    /// ScreenCaptureKit `SCScreenshotManager`（macOS 14+），系统不支持或截图失败时回退 CGWindowList
+    ScreenCaptureKit,
+}
+
+impl CaptureBackend {
+    /// 由 `ocr.capture_backend` 配置值解析（取值已在配置校验中检查）
+    pub fn from_config(value: &str) -> Self {
+        match value {
+            "screencapturekit" => Self::ScreenCaptureKit,
+            _ => Self::CgWindow,
+        }
+    }
+}
+
 /// 截取指定窗口的截图
-pub fn capture_window(window_id: u32) -> Result<CFRetained<CGImage>> {
+///
+/// `backend` 为 ScreenCaptureKit 时优先走 SCK（仅 macOS 14+），不可用或失败时回退 CGWindowListCreateImage。
+pub fn capture_window(window_id: u32, backend: CaptureBackend) -> Result<CFRetained<CGImage>> {
+    if backend == CaptureBackend::ScreenCaptureKit {
+        if screencapturekit_available() {
+            match capture_window_sck(window_id) {
+                Ok(image) => return Ok(image),
+                Err(e) => warn!(
+                    "ScreenCaptureKit capture failed, falling back to CGWindowListCreateImage: {}",
+                    e
+                ),
+            }
+        } else {
+            debug!("ScreenCaptureKit screenshot requires macOS 14+, using CGWindowListCreateImage");
+        }
+    }
+    capture_window_cg(window_id)
+}
+
+/// SCScreenshotManager 需要 macOS 14+
+fn screencapturekit_available() -> bool {
+    let sonoma = NSOperatingSystemVersion {
+        majorVersion: 14,
+        minorVersion: 0,
+        patchVersion: 0,
+    };
+    NSProcessInfo::processInfo().isOperatingSystemAtLeastVersion(sonoma)
+}
+
+/// 通过 ScreenCaptureKit 截取指定窗口（macOS 14+）
+///
+/// SCK 是回调式异步 API：在当前线程阻塞等待回调结果，调用方需在 spawn_blocking 中运行。
+fn capture_window_sck(window_id: u32) -> Result<CFRetained<CGImage>> {
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    const SCK_TIMEOUT: Duration = Duration::from_secs(3);
+
+    // 1. 枚举可共享窗口（含屏幕外窗口），按 window ID 找到富途窗口
+    let (tx, rx) = mpsc::channel();
+    let content_handler = RcBlock::new(move |content: *mut SCShareableContent, error: *mut NSError| {
+        let result = unsafe { Retained::retain(content) }.ok_or_else(|| sck_error_message(error));
+        let _ = tx.send(result);
+    });
+    unsafe {
+        SCShareableContent::getShareableContentExcludingDesktopWindows_onScreenWindowsOnly_completionHandler(
+            true,
+            false,
+            &content_handler,
+        );
+    }
+    let content = rx
+        .recv_timeout(SCK_TIMEOUT)
+        .context("SCShareableContent timed out")?
+        .map_err(|e| anyhow::anyhow!("SCShareableContent failed: {}", e))?;
+
+    let window = unsafe { content.windows() }
+        .iter()
+        .find(|w| unsafe { w.windowID() } == window_id)
+        .with_context(|| format!("ScreenCaptureKit: window {} not shareable", window_id))?;
+
+    // 2. 单窗口 filter + 按像素尺寸截图（pointPixelScale 保留 Retina 分辨率）
+    let filter = unsafe { SCContentFilter::initWithDesktopIndependentWindow(SCContentFilter::alloc(), &window) };
+    let frame = unsafe { window.frame() };
+    let scale = f64::from(unsafe { filter.pointPixelScale() });
+    let config = unsafe { SCStreamConfiguration::new() };
+    unsafe {
+        config.setWidth((frame.size.width * scale) as usize);
+        config.setHeight((frame.size.height * scale) as usize);
+        config.setShowsCursor(false);
+    }
+
+    let (tx, rx) = mpsc::channel();
+    let image_handler = RcBlock::new(move |image: *mut CGImage, error: *mut NSError| {
+        // 回调中的 CGImage 不归调用方所有，需 retain 后再传出
+        let result = NonNull::new(image)
+            .map(|ptr| unsafe { CFRetained::retain(ptr) })
+            .ok_or_else(|| sck_error_message(error));
+        let _ = tx.send(result);
+    });
+    unsafe {
+        SCScreenshotManager::captureImageWithFilter_configuration_completionHandler(
+            &filter,
+            &config,
+            Some(&image_handler),
+        );
+    }
+    let image = rx
+        .recv_timeout(SCK_TIMEOUT)
+        .context("SCScreenshotManager timed out")?
+        .map_err(|e| anyhow::anyhow!("SCScreenshotManager failed: {}", e))?;
+
+    debug!(
+        "Captured window {} (ScreenCaptureKit) -> image {}x{}",
+        window_id,
+        CGImage::width(Some(&image)),
+        CGImage::height(Some(&image)),
+    );
+    Ok(image)
+}
+
+/// SCK 回调的 NSError 描述
+fn sck_error_message(error: *mut NSError) -> String {
+    unsafe { error.as_ref() }
+        .map(|e| e.localizedDescription().to_string())
+        .unwrap_or_else(|| "unknown error".to_string())
+}
+
+/// 通过 CGWindowListCreateImage 截取指定窗口
+fn capture_window_cg(window_id: u32) -> Result<CFRetained<CGImage>> {
     // CGRectNull: origin=(inf,inf), size=(0,0) — 表示自动适配窗口边界
     let null_rect = CGRect {
         origin: CGPoint {
@@ -189,8 +447,9 @@ pub fn capture_window(window_id: u32) -> Result<CFRetained<CGImage>> {
 
 /// 使用 Apple Vision 框架识别图片中的文字
 ///
-/// `accurate` 为 true 时使用精确模式（慢），false 时使用快速模式（用于布局检测）
-fn recognize_text_with_level(image: &CGImage, accurate: bool) -> Result<Vec<OcrTextBlock>> {
+/// `accurate` 为 true 时使用精确模式（慢），false 时使用快速模式（用于布局检测）；
+/// `languages` 为识别语言（BCP 47，按优先级排列，见 `ocr.languages`）
+fn recognize_text_with_level(image: &CGImage, accurate: bool, languages: &[String]) -> Result<Vec<OcrTextBlock>> {
     // 使用 autoreleasepool 包裹所有 Objective-C 对象操作
     // 防止 Vision 框架内部对象（如 CRImageReaderOutput）累积导致内存泄漏
     objc2::rc::autoreleasepool(|_pool| {
@@ -205,10 +464,8 @@ fn recognize_text_with_level(image: &CGImage, accurate: bool) -> Result<Vec<OcrT
             let level = if accurate { 0 } else { 1 };
             request.setRecognitionLevel(VNRequestTextRecognitionLevel(level));
 
-            let zh = NSString::from_str("zh-Hans");
-            let en = NSString::from_str("en-US");
-            let languages = NSArray::from_retained_slice(&[zh, en]);
-            request.setRecognitionLanguages(&languages);
+            let languages: Vec<Retained<NSString>> = languages.iter().map(|l| NSString::from_str(l)).collect();
+            request.setRecognitionLanguages(&NSArray::from_retained_slice(&languages));
 
             let request_as_vn: Retained<VNRequest> = Retained::cast_unchecked(request.clone());
             let requests = NSArray::from_retained_slice(&[request_as_vn]);
@@ -245,13 +502,13 @@ fn recognize_text_with_level(image: &CGImage, accurate: bool) -> Result<Vec<OcrT
 }
 
 /// 精确模式 OCR（用于最终识别）
-pub fn recognize_text(image: &CGImage) -> Result<Vec<OcrTextBlock>> {
-    recognize_text_with_level(image, true)
+pub fn recognize_text(image: &CGImage, languages: &[String]) -> Result<Vec<OcrTextBlock>> {
+    recognize_text_with_level(image, true, languages)
 }
 
 /// 快速模式 OCR（用于布局检测）
-pub fn recognize_text_fast(image: &CGImage) -> Result<Vec<OcrTextBlock>> {
-    recognize_text_with_level(image, false)
+pub fn recognize_text_fast(image: &CGImage, languages: &[String]) -> Result<Vec<OcrTextBlock>> {
+    recognize_text_with_level(image, false, languages)
 }
 
 /// 窗口布局信息（归一化坐标 0.0-1.0）
@@ -378,10 +635,45 @@ pub fn crop_image_xy(image: &CGImage, x_range: (f64, f64), y_range: Option<(f64,
     CGImageCreateWithImageInRect(Some(image), rect).context("CGImageCreateWithImageInRect returned null")
 }
 
+/// 按倍数放大图像（高质量插值重绘到更大的位图），小字号下提升 Vision 识别率
+///
+/// Vision 返回归一化坐标，放大不影响后续分行与解析。
+pub fn upscale_image(image: &CGImage, factor: f64) -> Result<CFRetained<CGImage>> {
+    let w = (CGImage::width(Some(image)) as f64 * factor).round() as usize;
+    let h = (CGImage::height(Some(image)) as f64 * factor).round() as usize;
+    let space = CGColorSpace::new_device_rgb().context("CGColorSpaceCreateDeviceRGB returned null")?;
+    // data 传 null 由 CoreGraphics 分配缓冲区，bytes_per_row 传 0 自动计算
+    let ctx = unsafe {
+        CGBitmapContextCreate(
+            std::ptr::null_mut(),
+            w,
+            h,
+            8,
+            0,
+            Some(&space),
+            CGImageAlphaInfo::PremultipliedLast.0,
+        )
+    }
+    .context("CGBitmapContextCreate returned null")?;
+    CGContext::set_interpolation_quality(Some(&ctx), CGInterpolationQuality::High);
+    let rect = CGRect {
+        origin: CGPoint { x: 0.0, y: 0.0 },
+        size: CGSize {
+            width: w as f64,
+            height: h as f64,
+        },
+    };
+    CGContext::draw_image(Some(&ctx), rect, Some(image));
+
+    debug!("Upscaled image {:.1}x to {}x{}", factor, w, h);
+    CGBitmapContextCreateImage(Some(&ctx)).context("CGBitmapContextCreateImage returned null")
+}
+
 /// 将 OCR 文字块按 Y 坐标聚类成行
 ///
 /// Vision 坐标原点在左下角，y=1.0 是顶部。
-/// 转换为从上到下排序，Y 容差 0.5% 内归为同一行。
+/// 转换为从上到下排序，顶边 Y 差小于容差的归为同一行。
+/// 容差随字号缩放：取文字块高度中位数的 40%（高 DPI / 小窗口下固定容差会误合并或误拆分行）。
 pub fn group_into_rows(blocks: &[OcrTextBlock]) -> Vec<Vec<&OcrTextBlock>> {
     if blocks.is_empty() {
         return Vec::new();
@@ -395,7 +687,7 @@ pub fn group_into_rows(blocks: &[OcrTextBlock]) -> Vec<Vec<&OcrTextBlock>> {
         by.partial_cmp(&ay).unwrap_or(std::cmp::Ordering::Equal)
     });
 
-    let tolerance = 0.005; // 0.5% 容差
+    let tolerance = row_tolerance(blocks);
     let mut rows: Vec<Vec<&OcrTextBlock>> = Vec::new();
 
     for block in sorted {
@@ -425,30 +717,61 @@ pub fn group_into_rows(blocks: &[OcrTextBlock]) -> Vec<Vec<&OcrTextBlock>> {
     rows
 }
 
+/// 行聚类的 Y 容差（归一化坐标）：文字块高度中位数 × 0.4，无有效高度时退回 0.5%
+fn row_tolerance(blocks: &[OcrTextBlock]) -> f64 {
+    const ROW_TOLERANCE_FALLBACK: f64 = 0.005;
+    let mut heights: Vec<f64> = blocks.iter().map(|b| b.bbox.3).filter(|h| *h > 0.0).collect();
+    if heights.is_empty() {
+        return ROW_TOLERANCE_FALLBACK;
+    }
+    heights.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    heights[heights.len() / 2] * 0.4
+}
+
 /// 从 OCR 行中解析自选股行情（两行配对格式）
 ///
 /// 富途牛牛自选股每条目占两行：
 ///   第一行: `[HK/SH/SZ] [名称]  [价格]  [涨跌%]`
 ///   第二行: `[股票代码]`
 ///
+/// 大字体布局下价格会换到独立一行（`[市场] [名称]` / `[价格] [涨跌%]` / `[股票代码]` 三行），
+/// 代码行之前的价格行同样计入当前条目。
+///
 /// 遇到股票代码时，将前面积累的名称/价格组装为 QuoteSnapshot。
+/// 同一代码出现多次（滚动残影/重影行）时只保留置信度最高的一条，同置信度保留首次出现；
+/// 条目置信度取其各行文本块置信度的最小值（选中行高亮会拉低整行置信度）。
+///
+/// 选中行的价格/代码还可能重复出现在详情区，缺少市场前缀时按代码格式推断出错误市场，
+/// 产生幽灵行情：同一 `code.code` 已有明确识别市场的条目时，丢弃市场仅靠推断（或为 Unknown）的条目。
+/// 两条目市场都明确识别时视为不同股票（如 US C6L 与 SG C6L）。
 pub fn parse_watchlist_from_ocr(rows: &[Vec<&OcrTextBlock>]) -> Vec<QuoteSnapshot> {
-    use crate::data::parser::parse_stock_code;
-
-    let mut quotes = Vec::new();
+    use crate::data::parser::{parse_stock_code, volume_to_shares};
+    use std::collections::HashMap;
+
+    let mut quotes: Vec<QuoteSnapshot> = Vec::new();
+    // 与 quotes 一一对应：市场是否由市场前缀明确识别（而非按代码格式推断）
+    let mut market_confirmed: Vec<bool> = Vec::new();
+    // 代码 → (quotes 下标, 条目最小块置信度)，用于单次解析内去重
+    let mut seen: HashMap<StockCode, (usize, f32)> = HashMap::new();
     let mut pending_market: Option<Market> = None;
     let mut pending_name: Option<String> = None;
     let mut pending_price: Option<f64> = None;
     let mut pending_change_pct: Option<f64> = None;
     let mut pending_change_amt: Option<f64> = None;
+    let mut pending_volume: Option<(u64, VolumeUnit)> = None;
+    // 已积累 pending 行的最小块置信度
+    let mut pending_confidence: f32 = 1.0;
 
     for row in rows {
+        let row_confidence = row.iter().map(|b| b.confidence).fold(1.0_f32, f32::min);
         let mut row_code: Option<StockCode> = None;
         let mut row_price: Option<f64> = None;
         let mut row_change_pct: Option<f64> = None;
         let mut row_change_amt: Option<f64> = None;
+        let mut row_volume: Option<(u64, VolumeUnit)> = None;
         let mut row_market: Option<Market> = None;
         let mut row_name: Option<String> = None;
+        let mut row_bare_name: Option<String> = None;
 
         for block in row {
             let text = block.text.trim();
@@ -479,6 +802,14 @@ pub fn parse_watchlist_from_ocr(rows: &[Vec<&OcrTextBlock>]) -> Vec<QuoteSnapsho
                 }
             }
 
+            // 尝试解析为成交量（必须带 手/股 单位，避免与价格、成交额混淆）
+            if row_volume.is_none() {
+                if let Some(vol) = ocr_parse_volume(text) {
+                    row_volume = Some(vol);
+                    continue;
+                }
+            }
+
             // 尝试解析为 "HK 名称" / "SH 名称" / "SZ 名称" / "us 波音"
             if row_market.is_none() {
                 if let Some((m, n)) = ocr_parse_market_name(text) {
@@ -498,16 +829,8 @@ pub fn parse_watchlist_from_ocr(rows: &[Vec<&OcrTextBlock>]) -> Vec<QuoteSnapsho
                     .to_uppercase()
                     .trim_end_matches(|c: char| !c.is_alphanumeric())
                     .to_string();
-                if matches!(cleaned.as_str(), "HK" | "SH" | "SZ" | "US" | "SG" | "FX") {
-                    row_market = Some(match cleaned.as_str() {
-                        "HK" => Market::HK,
-                        "SH" => Market::SH,
-                        "SZ" => Market::SZ,
-                        "US" => Market::US,
-                        "SG" => Market::SG,
-                        "FX" => Market::FX,
-                        _ => unreachable!(),
-                    });
+                if let Some(m) = Market::from_prefix(&cleaned) {
+                    row_market = Some(m);
                     continue;
                 }
             }
@@ -533,6 +856,11 @@ pub fn parse_watchlist_from_ocr(rows: &[Vec<&OcrTextBlock>]) -> Vec<QuoteSnapsho
                     continue;
                 }
             }
+
+            // 无市场前缀的中文名称（前缀漏识别），仅用于判断新条目开始
+            if row_market.is_none() && row_bare_name.is_none() && text.chars().filter(|c| *c > '\x7f').count() >= 2 {
+                row_bare_name = Some(text.to_string());
+            }
         }
 
         // SG/FX 等市场的代码格式特殊（如 D05, CNmain, USDCNH），
@@ -554,8 +882,17 @@ pub fn parse_watchlist_from_ocr(rows: &[Vec<&OcrTextBlock>]) -> Vec<QuoteSnapsho
 
         // 如果本行有股票代码 → 与 pending 信息配对，生成 QuoteSnapshot
         if let Some(code) = row_code {
-            let market = pending_market.take().or(row_market.take()).unwrap_or(code.market);
+            let explicit_market = pending_market.take().or(row_market.take());
+            let market = explicit_market.unwrap_or(code.market);
+            let confirmed = explicit_market.is_some() && market != Market::Unknown;
+            let confidence = row_confidence.min(std::mem::replace(&mut pending_confidence, 1.0));
             let name = pending_name.take().or(row_name.take()).unwrap_or_default();
+            // 成交量统一为股：A 股显示为手时按每手 100 股换算
+            let volume = pending_volume
+                .take()
+                .or(row_volume.take())
+                .and_then(|(v, unit)| volume_to_shares(v, unit, market))
+                .unwrap_or(0);
 
             // 主价格优先用 pending（上一行的名称行数据）
             // 代码行自身的价格/涨跌幅作为盘前/盘后扩展数据（美股）
@@ -605,7 +942,7 @@ pub fn parse_watchlist_from_ocr(rows: &[Vec<&OcrTextBlock>]) -> Vec<QuoteSnapsho
                     (0.0, 0.0)
                 };
 
-                quotes.push(QuoteSnapshot {
+                let quote = QuoteSnapshot {
                     code: StockCode::new(market, &code.code),
                     name,
                     last_price: price,
@@ -613,7 +950,7 @@ pub fn parse_watchlist_from_ocr(rows: &[Vec<&OcrTextBlock>]) -> Vec<QuoteSnapsho
                     open_price: 0.0,
                     high_price: 0.0,
                     low_price: 0.0,
-                    volume: 0,
+                    volume,
                     turnover: 0.0,
                     change: final_change,
                     change_pct: final_pct,
@@ -621,9 +958,31 @@ pub fn parse_watchlist_from_ocr(rows: &[Vec<&OcrTextBlock>]) -> Vec<QuoteSnapsho
                     amplitude: 0.0,
                     extended_price: ext_price,
                     extended_change_pct: ext_pct,
+                    is_suspended: false,
                     timestamp: chrono::Local::now(),
                     source: DataSource::Ocr,
-                });
+                };
+
+                match seen.get(&quote.code) {
+                    Some(&(idx, prev_confidence)) => {
+                        debug!(
+                            "Duplicate OCR row for {} (confidence {:.2} vs {:.2})",
+                            quote.code.display_code(),
+                            confidence,
+                            prev_confidence
+                        );
+                        if confidence > prev_confidence {
+                            seen.insert(quote.code.clone(), (idx, confidence));
+                            quotes[idx] = quote;
+                            market_confirmed[idx] = confirmed;
+                        }
+                    }
+                    None => {
+                        seen.insert(quote.code.clone(), (quotes.len(), confidence));
+                        quotes.push(quote);
+                        market_confirmed.push(confirmed);
+                    }
+                }
             }
         } else {
             // 本行无代码 → 积累信息供下一行配对
@@ -634,19 +993,123 @@ pub fn parse_watchlist_from_ocr(rows: &[Vec<&OcrTextBlock>]) -> Vec<QuoteSnapsho
                 pending_price = row_price;
                 pending_change_pct = row_change_pct;
                 pending_change_amt = row_change_amt;
+                pending_volume = row_volume;
+                pending_confidence = row_confidence;
             } else if row_price.is_some() && (row_change_pct.is_some() || row_change_amt.is_some()) {
                 // 价格+涨跌信息同行 → 可信的价格行（选中股价格可能单独一行）
+                // 同行有中文名称 → 市场前缀漏识别的新条目，不沿用上一条目的市场/名称
+                if row_bare_name.is_some() {
+                    pending_market = None;
+                    pending_name = row_bare_name;
+                    pending_confidence = 1.0;
+                }
                 pending_price = row_price;
                 pending_change_pct = row_change_pct;
                 pending_change_amt = row_change_amt;
+                pending_volume = row_volume.or(pending_volume);
+                pending_confidence = pending_confidence.min(row_confidence);
+            } else if row_price.is_some() && pending_market.is_some() && pending_price.is_none() {
+                // 三行布局（大字体时价格换行）："市场 名称" 行之后、代码行之前的独立价格行，
+                // 涨跌幅 badge 未识别时只有价格；仅在条目已开始且尚无价格时采用
+                pending_price = row_price;
+                pending_volume = row_volume.or(pending_volume);
+                pending_confidence = pending_confidence.min(row_confidence);
             }
-            // 忽略只有价格没有涨跌信息的独立数字行（避免图表噪声覆盖正确价格）
+            // 忽略其余只有价格没有涨跌信息的独立数字行（避免图表噪声覆盖正确价格）
         }
     }
 
+    // 选中行幽灵行情：同代码已有明确市场的条目时，丢弃市场靠推断的条目
+    let confirmed: Vec<StockCode> = quotes
+        .iter()
+        .zip(&market_confirmed)
+        .filter(|(_, c)| **c)
+        .map(|(q, _)| q.code.clone())
+        .collect();
+    let mut flags = market_confirmed.iter();
+    quotes.retain(|q| {
+        let keep = *flags.next().unwrap_or(&true)
+            || !confirmed
+                .iter()
+                .any(|c| c.code == q.code.code && c.market != q.code.market);
+        if !keep {
+            debug!(
+                "Dropping phantom OCR quote {} (market inferred from code)",
+                q.code.display_code()
+            );
+        }
+        keep
+    });
+
     quotes
 }
 
+/// 帧间行身份稳定：代码疑似误识别时，按位置 + 名称相似度沿用上一帧的代码
+///
+/// 保守策略，仅当以下条件全部满足时才修正：
+/// - 本帧代码不被 `is_known` 认可（不在自选股中，多为 OCR 误读）
+/// - 上一帧同一行位置的代码已知，且本帧没有其他行占用该代码
+/// - 名称相似（共有字符 >= 60%）且价格接近（偏差 < 10%）
+///
+/// 本帧代码被认可时一律不改，保证真实的代码变化（调整自选股顺序、增删股票）正常生效。
+/// 返回修正的行数。
+pub fn stabilize_row_codes(
+    prev: &[QuoteSnapshot],
+    quotes: &mut [QuoteSnapshot],
+    is_known: impl Fn(&StockCode) -> bool,
+) -> usize {
+    use std::collections::HashSet;
+
+    let mut present: HashSet<StockCode> = quotes
+        .iter()
+        .filter(|q| is_known(&q.code))
+        .map(|q| q.code.clone())
+        .collect();
+    let mut fixed = 0;
+
+    for (i, q) in quotes.iter_mut().enumerate() {
+        if is_known(&q.code) {
+            continue;
+        }
+        let p = match prev.get(i) {
+            Some(p) => p,
+            None => continue,
+        };
+        if !is_known(&p.code) || present.contains(&p.code) {
+            continue;
+        }
+        let price_close = p.last_price > 0.0 && (q.last_price - p.last_price).abs() / p.last_price < 0.1;
+        if !price_close || name_similarity(&p.name, &q.name) < 0.6 {
+            continue;
+        }
+
+        debug!(
+            "Row {}: code {} looks misread, keeping {} ({})",
+            i,
+            q.code.display_code(),
+            p.code.display_code(),
+            q.name
+        );
+        q.code = p.code.clone();
+        present.insert(p.code.clone());
+        fixed += 1;
+    }
+
+    fixed
+}
+
+/// 名称相似度（0.0-1.0）：共有字符数 / 较长名称的字符数
+fn name_similarity(a: &str, b: &str) -> f64 {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let max_len = a.len().max(b.len());
+    if max_len == 0 {
+        return 0.0;
+    }
+    let common = a.iter().filter(|c| b.contains(c)).count();
+    common as f64 / max_len as f64
+}
+
 /// 解析价格文本（正数）
 /// 直接解析，失败则按空格分割取第一个 token（兼容 OCR 合并列 "6.93007 -0.01081"）
 /// 过滤 sidebar 噪声小整数：小于 100 的价格必须含小数点
@@ -671,6 +1134,17 @@ fn try_parse_price(s: &str) -> Option<f64> {
     None
 }
 
+/// 解析成交量文本: "12.3万手" → (123000, 手), "4567股" → (4567, 股)
+///
+/// 仅识别带 `手`/`股` 单位的文本：无单位数字无法与价格、成交额区分。
+fn ocr_parse_volume(s: &str) -> Option<(u64, VolumeUnit)> {
+    let s = s.trim();
+    if !s.ends_with('手') && !s.ends_with('股') {
+        return None;
+    }
+    parse_volume_with_unit(s)
+}
+
 /// 解析涨跌额文本: "+153" → 153.0, "-2.50" → -2.50, "+0.01081" → 0.01081
 ///
 /// 必须以 `+` 或 `-` 开头（无符号数字视为价格），且不以 `%` 结尾（那是百分比）。
@@ -710,20 +1184,12 @@ fn ocr_parse_market_name(s: &str) -> Option<(Market, String)> {
     let s = s.trim();
     let upper = s.to_uppercase();
 
-    // 在文本中查找市场前缀
-    let markets: &[(&str, Market)] = &[
-        ("HK ", Market::HK),
-        ("SH ", Market::SH),
-        ("SZ ", Market::SZ),
-        ("US ", Market::US),
-        ("SG ", Market::SG),
-        ("FX ", Market::FX),
-    ];
-
-    for (marker, market) in markets {
+    // 在文本中查找市场前缀（"HK " / "SH " ...）
+    for market in Market::all() {
+        let marker = format!("{} ", market);
         // 开头匹配（可能有 OCR 噪声字符在前缀和名称之间）
         // "US：金龙中国" / "US）蔚来" → 清理前缀后的非字母数字/非中文字符
-        if upper.starts_with(marker) {
+        if upper.starts_with(&marker) {
             let rest = s[marker.len()..].trim();
             // 跳过前导标点噪声（OCR 产物：：、）、|、.、，等）
             let name = rest
@@ -731,7 +1197,7 @@ fn ocr_parse_market_name(s: &str) -> Option<(Market, String)> {
                 .trim()
                 .to_string();
             if !name.is_empty() && name.chars().any(|c| c > '\x7f') {
-                return Some((*market, name));
+                return Some((market, name));
             }
         }
         // 内部匹配："牛牛圈 SH 中国建筑" → 找到 " SH " 后的中文名
@@ -740,7 +1206,7 @@ fn ocr_parse_market_name(s: &str) -> Option<(Market, String)> {
             let name_start = pos + search.len();
             let name = s[name_start..].trim().to_string();
             if !name.is_empty() && name.chars().any(|c| c > '\x7f') {
-                return Some((*market, name));
+                return Some((market, name));
             }
         }
     }
@@ -748,16 +1214,7 @@ fn ocr_parse_market_name(s: &str) -> Option<(Market, String)> {
     // "US：" / "US）" 开头但无空格：OCR 将 "US 蔚来" 识别为 "US）蔚来"
     // 也处理 "HK名称"（无空格，但名称以中文开头）
     if let Some(prefix) = s.get(..2) {
-        let market = match prefix.to_uppercase().as_str() {
-            "HK" => Some(Market::HK),
-            "SH" => Some(Market::SH),
-            "SZ" => Some(Market::SZ),
-            "US" => Some(Market::US),
-            "SG" => Some(Market::SG),
-            "FX" => Some(Market::FX),
-            _ => None,
-        };
-        if let Some(m) = market {
+        if let Some(m) = Market::from_prefix(prefix) {
             let rest = &s[2..];
             // 跳过前导标点噪声
             let name = rest
@@ -780,6 +1237,8 @@ pub struct OcrResult {
     pub image_hash: String,
     /// 是否因图像未变化而跳过了 OCR（复用上一轮结果）
     pub skipped: bool,
+    /// 传入的 AX grid frame 裁剪解析出 0 条行情（已降级到 Pass 1），调用方应作废该 frame
+    pub grid_frame_rejected: bool,
 }
 
 /// 计算 CGImage 的 SHA1 哈希（采样像素，避免全量读取大图）
@@ -823,11 +1282,19 @@ fn compute_image_hash(image: &CGImage) -> String {
 /// 如果 `prev_hash` 不为空且与当前截图哈希相同，跳过 OCR 返回空 quotes + skipped=true。
 /// 调用方应在 skipped=true 时复用上一轮结果。
 ///
-/// 如果提供了 `grid_frame`（来自 AX API 检测），直接按该区域裁剪，跳过 Pass 1 快速 OCR。
+/// 如果提供了 `grid_frame`（来自 AX API 检测），直接按该区域裁剪，跳过 Pass 1 快速 OCR；
+/// 若该区域解析出 0 条行情，同一截图降级到 Pass 1，并置 `grid_frame_rejected`。
+///
+/// `upscale` > 1 时自选股裁剪图先放大再做 Pass 2 精确识别；`min_window_area` 见 [`find_futu_window`]；
+/// `languages` 为 Vision 识别语言（`ocr.languages`）。
 pub fn ocr_capture_and_parse(
     pid: i32,
     prev_hash: &str,
     grid_frame: Option<crate::futu::accessibility::GridFrame>,
+    backend: CaptureBackend,
+    upscale: f64,
+    min_window_area: f64,
+    languages: &[String],
 ) -> Result<OcrResult> {
     const MAX_RETRIES: u32 = 2;
     const RETRY_DELAY_MS: u64 = 200;
@@ -836,7 +1303,7 @@ pub fn ocr_capture_and_parse(
 
     for attempt in 0..=MAX_RETRIES {
         // 每次重试都重新查找窗口（含尺寸，供 resize 检测）
-        let win = match find_futu_window(pid) {
+        let win = match find_futu_window(pid, min_window_area) {
             Ok(w) => w,
             Err(e) => {
                 last_err = Some(e);
@@ -856,7 +1323,7 @@ pub fn ocr_capture_and_parse(
         );
 
         // 截图
-        let image = match capture_window(win.id) {
+        let image = match capture_window(win.id, backend) {
             Ok(img) => img,
             Err(e) => {
                 last_err = Some(e);
@@ -878,54 +1345,84 @@ pub fn ocr_capture_and_parse(
                 window_height: win.height,
                 image_hash: hash,
                 skipped: true,
+                grid_frame_rejected: false,
             });
         }
 
         // 有 AX GridFrame → 跳过 Pass 1，直接按 grid frame 裁剪
         // 无 GridFrame → 降级到 Pass 1 快速 OCR 检测布局
-        let watchlist_crop = if let Some(gf) = grid_frame {
+        let mut grid_frame_rejected = false;
+        if let Some(gf) = grid_frame {
             debug!(
                 "Using AX grid frame: ({:.3},{:.3},{:.3},{:.3}), skipping Pass 1",
                 gf.x, gf.y, gf.width, gf.height
             );
             let x_range = (gf.x, (gf.x + gf.width).min(1.0));
             let y_range = Some((gf.y, (gf.y + gf.height).min(1.0)));
-            crop_image_xy(&image, x_range, y_range)?
-        } else {
-            // Pass 1: 快速 OCR 全图 → 检测布局
-            let fast_blocks = recognize_text_fast(&image)?;
-            if fast_blocks.is_empty() {
+            let watchlist_crop = crop_image_xy(&image, x_range, y_range)?;
+            let (quotes, row_count) = ocr_parse_crop(&watchlist_crop, upscale, languages)?;
+            if !quotes.is_empty() {
+                info!("OCR parsed {} quotes from {} rows", quotes.len(), row_count);
                 return Ok(OcrResult {
-                    quotes: Vec::new(),
+                    quotes,
                     window_width: win.width,
                     window_height: win.height,
                     image_hash: hash,
                     skipped: false,
+                    grid_frame_rejected: false,
                 });
             }
-            let layout = detect_layout(&fast_blocks);
-            debug!("Fast OCR: {} blocks, layout: {:?}", fast_blocks.len(), layout);
-            crop_image(&image, layout.watchlist_x)?
-        };
-        let blocks = recognize_text(&watchlist_crop)?;
-        debug!("Watchlist crop OCR: {} blocks", blocks.len());
+            // grid frame 过期/错误（如富途切换到其他页面）→ 同一截图降级到 Pass 1
+            warn!(
+                "AX grid frame crop yielded 0 quotes ({} rows), falling back to Pass 1 layout detection",
+                row_count
+            );
+            grid_frame_rejected = true;
+        }
 
-        // 分行 + 两行配对解析
-        let rows = group_into_rows(&blocks);
-        let quotes = parse_watchlist_from_ocr(&rows);
-        info!("OCR parsed {} quotes from {} rows", quotes.len(), rows.len());
+        // Pass 1: 快速 OCR 全图 → 检测布局
+        let fast_blocks = recognize_text_fast(&image, languages)?;
+        if fast_blocks.is_empty() {
+            return Ok(OcrResult {
+                quotes: Vec::new(),
+                window_width: win.width,
+                window_height: win.height,
+                image_hash: hash,
+                skipped: false,
+                grid_frame_rejected,
+            });
+        }
+        let layout = detect_layout(&fast_blocks);
+        debug!("Fast OCR: {} blocks, layout: {:?}", fast_blocks.len(), layout);
+        let watchlist_crop = crop_image(&image, layout.watchlist_x)?;
+        let (quotes, row_count) = ocr_parse_crop(&watchlist_crop, upscale, languages)?;
+        info!("OCR parsed {} quotes from {} rows", quotes.len(), row_count);
         return Ok(OcrResult {
             quotes,
             window_width: win.width,
             window_height: win.height,
             image_hash: hash,
             skipped: false,
+            grid_frame_rejected,
         });
     }
 
     Err(last_err.unwrap_or_else(|| anyhow::anyhow!("OCR capture failed after {} retries", MAX_RETRIES + 1)))
 }
 
+/// 对自选股区域裁剪图做精确 OCR（可先放大）→ 分行 → 两行配对解析，返回 (quotes, 行数)
+fn ocr_parse_crop(crop: &CGImage, upscale: f64, languages: &[String]) -> Result<(Vec<QuoteSnapshot>, usize)> {
+    let blocks = if upscale > 1.0 {
+        recognize_text(&upscale_image(crop, upscale)?, languages)?
+    } else {
+        recognize_text(crop, languages)?
+    };
+    debug!("Watchlist crop OCR: {} blocks", blocks.len());
+    let rows = group_into_rows(&blocks);
+    let quotes = parse_watchlist_from_ocr(&rows);
+    Ok((quotes, rows.len()))
+}
+
 // ---- 内部辅助函数 ----
 
 /// 从 CFDictionary 中读取字符串值
@@ -980,12 +1477,12 @@ unsafe fn dict_get_i32(dict: *const std::ffi::c_void, key: &str) -> Option<i32>
     }
 }
 
-/// 从 CFDictionary 中读取窗口 bounds (width, height)
-unsafe fn dict_get_window_bounds(dict: *const std::ffi::c_void) -> Option<(f64, f64)> {
+/// 从 CFDictionary 中读取布尔值（CFBoolean）
+unsafe fn dict_get_bool(dict: *const std::ffi::c_void, key: &str) -> Option<bool> {
     use core_foundation::base::TCFType;
     use core_foundation::string::CFString;
 
-    let cf_key = CFString::new("kCGWindowBounds");
+    let cf_key = CFString::new(key);
     let mut value: *const std::ffi::c_void = std::ptr::null();
     let found = core_foundation::dictionary::CFDictionaryGetValueIfPresent(
         dict as core_foundation::dictionary::CFDictionaryRef,
@@ -996,43 +1493,63 @@ unsafe fn dict_get_window_bounds(dict: *const std::ffi::c_void) -> Option<(f64,
         return None;
     }
 
-    // kCGWindowBounds 是一个 CFDictionary，含 Width/Height
-    let w_key = CFString::new("Width");
-    let h_key = CFString::new("Height");
+    Some(core_foundation::number::CFBooleanGetValue(
+        value as core_foundation::number::CFBooleanRef,
+    ))
+}
 
-    let mut w_val: *const std::ffi::c_void = std::ptr::null();
-    let mut h_val: *const std::ffi::c_void = std::ptr::null();
+/// 从 CFDictionary 中读取 f64 值（CFNumber）
+unsafe fn dict_get_f64(dict: *const std::ffi::c_void, key: &str) -> Option<f64> {
+    use core_foundation::base::TCFType;
+    use core_foundation::string::CFString;
 
-    core_foundation::dictionary::CFDictionaryGetValueIfPresent(
-        value as core_foundation::dictionary::CFDictionaryRef,
-        w_key.as_concrete_TypeRef() as *const _,
-        &mut w_val,
+    let cf_key = CFString::new(key);
+    let mut value: *const std::ffi::c_void = std::ptr::null();
+    let found = core_foundation::dictionary::CFDictionaryGetValueIfPresent(
+        dict as core_foundation::dictionary::CFDictionaryRef,
+        cf_key.as_concrete_TypeRef() as *const _,
+        &mut value,
     );
-    core_foundation::dictionary::CFDictionaryGetValueIfPresent(
-        value as core_foundation::dictionary::CFDictionaryRef,
-        h_key.as_concrete_TypeRef() as *const _,
-        &mut h_val,
+    if found == 0 || value.is_null() {
+        return None;
+    }
+
+    let mut result: f64 = 0.0;
+    let ok = core_foundation::number::CFNumberGetValue(
+        value as core_foundation::number::CFNumberRef,
+        core_foundation::number::kCFNumberFloat64Type,
+        &mut result as *mut f64 as *mut std::ffi::c_void,
     );
+    if ok {
+        Some(result)
+    } else {
+        None
+    }
+}
 
-    let mut w: f64 = 0.0;
-    let mut h: f64 = 0.0;
+/// 从 CFDictionary 中读取窗口 bounds (x, y, width, height)，屏幕坐标（原点左上角，单位 pt）
+unsafe fn dict_get_window_bounds(dict: *const std::ffi::c_void) -> Option<(f64, f64, f64, f64)> {
+    use core_foundation::base::TCFType;
+    use core_foundation::string::CFString;
 
-    if !w_val.is_null() {
-        core_foundation::number::CFNumberGetValue(
-            w_val as core_foundation::number::CFNumberRef,
-            core_foundation::number::kCFNumberFloat64Type,
-            &mut w as *mut f64 as *mut std::ffi::c_void,
-        );
-    }
-    if !h_val.is_null() {
-        core_foundation::number::CFNumberGetValue(
-            h_val as core_foundation::number::CFNumberRef,
-            core_foundation::number::kCFNumberFloat64Type,
-            &mut h as *mut f64 as *mut std::ffi::c_void,
-        );
+    let cf_key = CFString::new("kCGWindowBounds");
+    let mut value: *const std::ffi::c_void = std::ptr::null();
+    let found = core_foundation::dictionary::CFDictionaryGetValueIfPresent(
+        dict as core_foundation::dictionary::CFDictionaryRef,
+        cf_key.as_concrete_TypeRef() as *const _,
+        &mut value,
+    );
+    if found == 0 || value.is_null() {
+        return None;
     }
 
-    Some((w, h))
+    // kCGWindowBounds 是一个 CFDictionary，含 X/Y/Width/Height（缺失的字段按 0 处理）
+    let x = dict_get_f64(value, "X").unwrap_or(0.0);
+    let y = dict_get_f64(value, "Y").unwrap_or(0.0);
+    let w = dict_get_f64(value, "Width").unwrap_or(0.0);
+    let h = dict_get_f64(value, "Height").unwrap_or(0.0);
+
+    Some((x, y, w, h))
 }
 
 #[cfg(test)]
@@ -1085,7 +1602,7 @@ mod tests {
 
     #[test]
     fn test_group_into_rows_within_tolerance() {
-        // 同一行的块有微小 y 差异（< 0.5%）
+        // 同一行的块有微小 y 差异（< 高度 0.02 × 0.4）
         let blocks = vec![
             OcrTextBlock {
                 text: "A".to_string(),
@@ -1095,7 +1612,7 @@ mod tests {
             OcrTextBlock {
                 text: "B".to_string(),
                 confidence: 0.9,
-                bbox: (0.3, 0.502, 0.1, 0.02), // y top = 0.522（差 0.002 < 0.005）
+                bbox: (0.3, 0.502, 0.1, 0.02), // y top = 0.522（差 0.002 < 0.008）
             },
         ];
 
@@ -1104,6 +1621,35 @@ mod tests {
         assert_eq!(rows[0].len(), 2);
     }
 
+    #[test]
+    fn test_group_into_rows_tolerance_scales_with_font() {
+        let block = |text: &str, x: f64, y: f64, h: f64| OcrTextBlock {
+            text: text.to_string(),
+            confidence: 0.9,
+            bbox: (x, y, 0.1, h),
+        };
+
+        // 大字号（高 0.05）：同行块顶边差 0.01 > 固定容差 0.005，应仍归为一行
+        let tall = vec![
+            block("HK 腾讯控股", 0.1, 0.80, 0.05),
+            block("388.00", 0.4, 0.81, 0.05),
+            block("00700", 0.1, 0.72, 0.05),
+        ];
+        let rows = group_into_rows(&tall);
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].len(), 2);
+
+        // 小字号（高 0.004，行距 0.0045）：相邻两行顶边差 < 固定容差 0.005，应拆为两行
+        let short = vec![
+            block("HK 腾讯控股", 0.1, 0.5045, 0.004),
+            block("388.00", 0.4, 0.5045, 0.004),
+            block("00700", 0.1, 0.5000, 0.004),
+        ];
+        let rows = group_into_rows(&short);
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[1][0].text, "00700");
+    }
+
     #[test]
     fn test_parse_watchlist_two_line_format() {
         // 两行配对格式：第一行 "HK 腾讯控股 | 388.00 | +0.67%", 第二行 "00700"
@@ -1143,6 +1689,112 @@ mod tests {
         assert_eq!(quotes[0].source, DataSource::Ocr);
     }
 
+    #[test]
+    fn test_parse_watchlist_three_line_format() {
+        // 大字体布局：价格换行到独立一行 → "市场 名称" / "价格 涨跌%" / "代码" 三行一组
+        let block = |text: &str, x: f64, y: f64| OcrTextBlock {
+            text: text.to_string(),
+            confidence: 0.95,
+            bbox: (x, y, 0.1, 0.02),
+        };
+        let blocks = vec![
+            block("HK 腾讯控股", 0.0, 0.90),
+            block("388.00", 0.4, 0.87),
+            block("+0.67%", 0.6, 0.87),
+            block("00700", 0.0, 0.84),
+            block("SH 贵州茅台", 0.0, 0.80),
+            block("1688.00", 0.4, 0.77),
+            block("-0.50%", 0.6, 0.77),
+            block("2.5万手", 0.8, 0.77),
+            block("600519", 0.0, 0.74),
+            block("US 苹果", 0.0, 0.70),
+            block("190.50", 0.4, 0.67),
+            block("+1.20%", 0.6, 0.67),
+            block("AAPL", 0.0, 0.64),
+            // 涨跌幅 badge 未识别：独立价格行仍计入当前条目
+            block("HK 小米集团-W", 0.0, 0.60),
+            block("45.30", 0.4, 0.57),
+            block("01810", 0.0, 0.54),
+            // 条目结束后的独立数字行（图表噪声）不会被当作下一条目的价格
+            block("52.10", 0.4, 0.50),
+            block("09988", 0.0, 0.47),
+        ];
+
+        let rows = group_into_rows(&blocks);
+        let quotes = parse_watchlist_from_ocr(&rows);
+        let got: Vec<(&str, Market, &str, f64, f64)> = quotes
+            .iter()
+            .map(|q| {
+                (
+                    q.code.code.as_str(),
+                    q.code.market,
+                    q.name.as_str(),
+                    q.last_price,
+                    q.change_pct,
+                )
+            })
+            .collect();
+        assert_eq!(
+            got,
+            [
+                ("00700", Market::HK, "腾讯控股", 388.00, 0.67),
+                ("600519", Market::SH, "贵州茅台", 1688.00, -0.50),
+                ("AAPL", Market::US, "苹果", 190.50, 1.20),
+                ("01810", Market::HK, "小米集团-W", 45.30, 0.0),
+            ]
+        );
+        assert_eq!(quotes[1].volume, 2_500_000);
+        assert_eq!(quotes[2].extended_price, None);
+    }
+
+    #[test]
+    fn test_parse_watchlist_a_share_volume_in_lots() {
+        // A 股成交量以手显示："SH 贵州茅台 | 1688.00 | +0.75% | 2.5万手"，换算为股
+        let blocks = vec![
+            OcrTextBlock {
+                text: "SH 贵州茅台".to_string(),
+                confidence: 0.95,
+                bbox: (0.0, 0.80, 0.2, 0.02),
+            },
+            OcrTextBlock {
+                text: "1688.00".to_string(),
+                confidence: 0.92,
+                bbox: (0.4, 0.80, 0.1, 0.02),
+            },
+            OcrTextBlock {
+                text: "+0.75%".to_string(),
+                confidence: 0.91,
+                bbox: (0.6, 0.80, 0.1, 0.02),
+            },
+            OcrTextBlock {
+                text: "2.5万手".to_string(),
+                confidence: 0.90,
+                bbox: (0.8, 0.80, 0.1, 0.02),
+            },
+            OcrTextBlock {
+                text: "600519".to_string(),
+                confidence: 0.98,
+                bbox: (0.0, 0.77, 0.1, 0.02),
+            },
+        ];
+
+        let rows = group_into_rows(&blocks);
+        let quotes = parse_watchlist_from_ocr(&rows);
+        assert_eq!(quotes.len(), 1);
+        assert_eq!(quotes[0].code.market, Market::SH);
+        assert_eq!(quotes[0].last_price, 1688.00);
+        assert_eq!(quotes[0].volume, 2_500_000);
+    }
+
+    #[test]
+    fn test_ocr_parse_volume_requires_unit() {
+        assert_eq!(ocr_parse_volume("856手"), Some((856, VolumeUnit::Lots)));
+        assert_eq!(ocr_parse_volume("1.2万股"), Some((12_000, VolumeUnit::Shares)));
+        // 无单位的数字可能是价格或成交额，不识别
+        assert_eq!(ocr_parse_volume("1234万"), None);
+        assert_eq!(ocr_parse_volume("388.00"), None);
+    }
+
     #[test]
     fn test_parse_watchlist_standalone_market_prefix() {
         // OCR 将市场前缀和名称拆为独立块："HK" | "融创中国" | "1.220" | "-0.81%"
@@ -1328,6 +1980,299 @@ mod tests {
         assert_eq!(quotes[0].change_pct, -0.16);
     }
 
+    /// 按行构造 OCR 文字块（每行下移 0.03，块按列从左到右排列）后解析
+    fn parse_fixture(lines: &[&[&str]]) -> Vec<QuoteSnapshot> {
+        let blocks: Vec<OcrTextBlock> = lines
+            .iter()
+            .enumerate()
+            .flat_map(|(row, texts)| {
+                texts.iter().enumerate().map(move |(col, text)| OcrTextBlock {
+                    text: text.to_string(),
+                    confidence: 0.9,
+                    bbox: (col as f64 * 0.2, 0.9 - row as f64 * 0.03, 0.15, 0.02),
+                })
+            })
+            .collect();
+        let rows = group_into_rows(&blocks);
+        parse_watchlist_from_ocr(&rows)
+    }
+
+    #[test]
+    fn test_parse_watchlist_pairing_fixtures() {
+        // 期望的 (市场, 代码, 名称, 价格, 涨跌幅)
+        type Expected = (Market, &'static str, &'static str, f64, f64);
+        // (场景, OCR 行, 期望结果)
+        type Case = (&'static str, &'static [&'static [&'static str]], &'static [Expected]);
+        let cases: &[Case] = &[
+            (
+                "美股盘前两行格式",
+                &[
+                    &["US 特斯拉", "250.00", "+1.20%"],
+                    &["TSLA", "盘前", "252.00", "+0.80%"],
+                ],
+                &[(Market::US, "TSLA", "特斯拉", 250.0, 1.2)],
+            ),
+            (
+                "条目之间的噪声行",
+                &[
+                    &["HK 腾讯控股", "388.00", "+0.67%"],
+                    &["00700"],
+                    &["6"],
+                    &["12.50"],
+                    &["HK 阿里巴巴-W", "100.50", "-1.20%"],
+                    &["14"],
+                    &["09988"],
+                ],
+                &[
+                    (Market::HK, "00700", "腾讯控股", 388.0, 0.67),
+                    (Market::HK, "09988", "阿里巴巴-W", 100.5, -1.2),
+                ],
+            ),
+            (
+                "名称缺失",
+                &[&["HK", "388.00", "+0.67%"], &["00700"]],
+                &[(Market::HK, "00700", "", 388.0, 0.67)],
+            ),
+            (
+                "市场前缀缺失时不沿用上一条目的名称",
+                &[
+                    &["HK 腾讯控股", "388.00", "+0.67%"],
+                    &["0O7OO"],
+                    &["阿里巴巴-W", "100.50", "-1.20%"],
+                    &["09988"],
+                ],
+                &[(Market::HK, "09988", "阿里巴巴-W", 100.5, -1.2)],
+            ),
+            (
+                "代码先于任何 pending 数据出现",
+                &[&["00700"], &["HK 阿里巴巴-W", "100.50", "-1.20%"], &["09988"]],
+                &[(Market::HK, "09988", "阿里巴巴-W", 100.5, -1.2)],
+            ),
+            (
+                "单行格式代码在前",
+                &[
+                    &["00700", "HK 腾讯控股", "388.00", "+0.67%"],
+                    &["09988", "100.50", "-1.20%"],
+                ],
+                &[
+                    (Market::HK, "00700", "腾讯控股", 388.0, 0.67),
+                    (Market::HK, "09988", "", 100.5, -1.2),
+                ],
+            ),
+        ];
+
+        for (desc, lines, expected) in cases {
+            let quotes = parse_fixture(lines);
+            let got: Vec<(Market, &str, &str, f64, f64)> = quotes
+                .iter()
+                .map(|q| {
+                    (
+                        q.code.market,
+                        q.code.code.as_str(),
+                        q.name.as_str(),
+                        q.last_price,
+                        q.change_pct,
+                    )
+                })
+                .collect();
+            assert_eq!(got, *expected, "{}", desc);
+        }
+    }
+
+    #[test]
+    fn test_parse_watchlist_dedupes_repeated_code() {
+        // 滚动残影：同一股票的两行配对被识别两次，只保留一条
+        let quotes = parse_fixture(&[
+            &["HK 腾讯控股", "388.00", "+0.67%"],
+            &["00700"],
+            &["HK 阿里巴巴-W", "100.50", "-1.20%"],
+            &["09988"],
+            &["HK 腾讯控股", "388.20", "+0.72%"],
+            &["00700"],
+        ]);
+        assert_eq!(quotes.len(), 2);
+        assert_eq!(quotes[0].code.code, "00700");
+        // 置信度相同 → 保留首次出现
+        assert_eq!(quotes[0].last_price, 388.0);
+        assert_eq!(quotes[1].code.code, "09988");
+
+        // 重复行的代码块置信度更高 → 替换为该行（位置不变）
+        let blocks = vec![
+            OcrTextBlock {
+                text: "HK 腾讯控股".to_string(),
+                confidence: 0.9,
+                bbox: (0.0, 0.80, 0.2, 0.02),
+            },
+            OcrTextBlock {
+                text: "388.00".to_string(),
+                confidence: 0.9,
+                bbox: (0.4, 0.80, 0.1, 0.02),
+            },
+            OcrTextBlock {
+                text: "00700".to_string(),
+                confidence: 0.5,
+                bbox: (0.0, 0.77, 0.1, 0.02),
+            },
+            OcrTextBlock {
+                text: "HK 腾讯控股".to_string(),
+                confidence: 0.9,
+                bbox: (0.0, 0.74, 0.2, 0.02),
+            },
+            OcrTextBlock {
+                text: "388.20".to_string(),
+                confidence: 0.9,
+                bbox: (0.4, 0.74, 0.1, 0.02),
+            },
+            OcrTextBlock {
+                text: "00700".to_string(),
+                confidence: 0.95,
+                bbox: (0.0, 0.71, 0.1, 0.02),
+            },
+        ];
+        let rows = group_into_rows(&blocks);
+        let quotes = parse_watchlist_from_ocr(&rows);
+        assert_eq!(quotes.len(), 1);
+        assert_eq!(quotes[0].last_price, 388.2);
+    }
+
+    #[test]
+    fn test_parse_watchlist_drops_selected_row_phantom() {
+        // 选中行价格重复到详情区：无市场前缀，000001 按代码格式被推断为深市 → 丢弃
+        let quotes = parse_fixture(&[
+            &["SH 上证指数", "3250.12", "+0.52%"],
+            &["000001"],
+            &["000001", "3250.12", "+0.52%"],
+        ]);
+        assert_eq!(quotes.len(), 1);
+        assert_eq!(quotes[0].code, StockCode::new(Market::SH, "000001"));
+
+        // 两条都有明确市场前缀 → 不同股票，均保留
+        let quotes = parse_fixture(&[
+            &["SH 上证指数", "3250.12", "+0.52%"],
+            &["000001"],
+            &["SZ 平安银行", "11.20", "-0.36%"],
+            &["000001"],
+        ]);
+        assert_eq!(quotes.len(), 2);
+
+        // 高亮行代码块置信度更高，但价格块置信度被拉低 → 按条目最小置信度比较，保留首条
+        let block = |text: &str, confidence: f32, x: f64, y: f64| OcrTextBlock {
+            text: text.to_string(),
+            confidence,
+            bbox: (x, y, 0.1, 0.02),
+        };
+        let blocks = vec![
+            block("HK 腾讯控股", 0.9, 0.0, 0.80),
+            block("388.00", 0.9, 0.4, 0.80),
+            block("+0.67%", 0.9, 0.6, 0.80),
+            block("00700", 0.9, 0.0, 0.77),
+            block("HK 腾讯控股", 0.9, 0.0, 0.74),
+            block("338.00", 0.4, 0.4, 0.74),
+            block("+0.67%", 0.9, 0.6, 0.74),
+            block("00700", 0.95, 0.0, 0.71),
+        ];
+        let rows = group_into_rows(&blocks);
+        let quotes = parse_watchlist_from_ocr(&rows);
+        assert_eq!(quotes.len(), 1);
+        assert_eq!(quotes[0].last_price, 388.0);
+    }
+
+    #[test]
+    fn test_parse_watchlist_us_extended_data() {
+        // 代码行的价格/涨跌幅为盘前数据，不覆盖名称行的主价格
+        let quotes = parse_fixture(&[
+            &["US 特斯拉", "250.00", "+1.20%"],
+            &["TSLA", "盘前", "252.00", "+0.80%"],
+        ]);
+        assert_eq!(quotes.len(), 1);
+        assert_eq!(quotes[0].extended_price, Some(252.0));
+        assert_eq!(quotes[0].extended_change_pct, Some(0.8));
+
+        // 代码行只有孤立数字（无涨跌幅）→ 不识别为扩展数据
+        let quotes = parse_fixture(&[&["US 特斯拉", "250.00", "+1.20%"], &["TSLA", "252.00"]]);
+        assert_eq!(quotes[0].last_price, 250.0);
+        assert_eq!(quotes[0].extended_price, None);
+    }
+
+    fn ocr_quote(market: Market, code: &str, name: &str, price: f64) -> QuoteSnapshot {
+        let mut q = QuoteSnapshot::empty(StockCode::new(market, code), name.to_string());
+        q.last_price = price;
+        q.source = DataSource::Ocr;
+        q
+    }
+
+    #[test]
+    fn test_stabilize_row_codes_restores_misread_code() {
+        let known = [StockCode::new(Market::HK, "00700"), StockCode::new(Market::HK, "09988")];
+        let is_known = |c: &StockCode| known.contains(c);
+        let prev = vec![
+            ocr_quote(Market::HK, "00700", "腾讯控股", 388.0),
+            ocr_quote(Market::HK, "09988", "阿里巴巴-W", 100.5),
+        ];
+        // 第二行代码被误读为 09938，名称/价格与上一帧一致 → 沿用 09988
+        let mut cur = vec![
+            ocr_quote(Market::HK, "00700", "腾讯控股", 388.2),
+            ocr_quote(Market::HK, "09938", "阿里巴巴-W", 100.6),
+        ];
+        assert_eq!(stabilize_row_codes(&prev, &mut cur, is_known), 1);
+        assert_eq!(cur[1].code.code, "09988");
+    }
+
+    #[test]
+    fn test_stabilize_row_codes_conservative() {
+        let known = [StockCode::new(Market::HK, "00700"), StockCode::new(Market::HK, "09988")];
+        let is_known = |c: &StockCode| known.contains(c);
+        let prev = vec![
+            ocr_quote(Market::HK, "00700", "腾讯控股", 388.0),
+            ocr_quote(Market::HK, "09988", "阿里巴巴-W", 100.5),
+        ];
+
+        // 代码已知（真实顺序变化）→ 不修改
+        let mut swapped = vec![
+            ocr_quote(Market::HK, "09988", "阿里巴巴-W", 100.5),
+            ocr_quote(Market::HK, "00700", "腾讯控股", 388.0),
+        ];
+        assert_eq!(stabilize_row_codes(&prev, &mut swapped, is_known), 0);
+        assert_eq!(swapped[0].code.code, "09988");
+
+        // 名称不同的新股票 → 不修改
+        let mut other = vec![
+            ocr_quote(Market::HK, "00700", "腾讯控股", 388.0),
+            ocr_quote(Market::HK, "01810", "小米集团-W", 100.4),
+        ];
+        assert_eq!(stabilize_row_codes(&prev, &mut other, is_known), 0);
+        assert_eq!(other[1].code.code, "01810");
+
+        // 价格偏差过大 → 不修改
+        let mut far = vec![
+            ocr_quote(Market::HK, "00700", "腾讯控股", 388.0),
+            ocr_quote(Market::HK, "09938", "阿里巴巴-W", 10.05),
+        ];
+        assert_eq!(stabilize_row_codes(&prev, &mut far, is_known), 0);
+
+        // 上一帧代码本帧已被其他行占用 → 不修改（避免重复行）
+        let mut dup = vec![
+            ocr_quote(Market::HK, "09938", "腾讯控股", 388.0),
+            ocr_quote(Market::HK, "00700", "腾讯控股", 388.0),
+        ];
+        assert_eq!(stabilize_row_codes(&prev, &mut dup, is_known), 0);
+    }
+
+    #[test]
+    fn test_covered_ratio() {
+        let target = (0.0, 0.0, 100.0, 100.0);
+        assert_eq!(covered_ratio(target, &[]), 0.0);
+        // 不相交
+        assert_eq!(covered_ratio(target, &[(200.0, 0.0, 50.0, 50.0)]), 0.0);
+        // 覆盖左半边
+        assert!((covered_ratio(target, &[(-10.0, -10.0, 60.0, 120.0)]) - 0.5).abs() < 1e-9);
+        // 两窗口重叠部分不重复计算：左 60% + 右 60% = 全覆盖
+        let both = [(0.0, 0.0, 60.0, 100.0), (40.0, 0.0, 60.0, 100.0)];
+        assert!((covered_ratio(target, &both) - 1.0).abs() < 1e-9);
+        // 更大的窗口完全盖住
+        assert_eq!(covered_ratio(target, &[(-50.0, -50.0, 500.0, 500.0)]), 1.0);
+    }
+
     #[test]
     fn test_ocr_parse_change_amt() {
         assert_eq!(ocr_parse_change_amt("+153"), Some(153.0));
@@ -4,17 +4,27 @@
 //! 协议格式：固定 44 字节头部 + protobuf body
 //! 默认连接地址：localhost:11111
 
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::fmt::{self, Write as _};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::{Context, Result};
 use bytes::{Buf, BufMut, BytesMut};
+use chrono::{DateTime, Local};
 use prost::Message;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
 use tokio::net::TcpStream;
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, Mutex};
+use tokio::task::JoinHandle;
 use tracing::{debug, error, info, warn};
 
-use crate::models::{DailyKline, DataSource, Market, QuoteSnapshot, StockCode};
+use crate::models::{
+    us_market_session, ConnectionState, DailyKline, DataSource, Kline, KlineType, Market, QuoteSnapshot, StockCode,
+    UsMarketSession,
+};
 
 /// Futu 协议头部大小
 const HEADER_SIZE: usize = 44;
@@ -25,6 +35,19 @@ const FUTU_MAGIC: [u8; 2] = [0x46, 0x54]; // "FT"
 /// 协议版本
 const PROTO_VERSION: u8 = 0;
 
+/// 断线自动重连（请求与推送循环共用）的初始退避与默认上限
+const RECONNECT_INITIAL: Duration = Duration::from_secs(1);
+const RECONNECT_MAX: Duration = Duration::from_secs(30);
+
+/// 历史K线翻页上限（每页最多 1000 根，防止异常游标无限循环）
+const MAX_KLINE_PAGES: usize = 20;
+
+/// 等待 InitConnect 响应的超时（对端不是 OpenD 时可能一直不回包）
+const INIT_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// InitConnect 未返回有效 keepAliveInterval 时的心跳间隔
+const DEFAULT_KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(10);
+
 /// 常用协议号
 mod proto_id {
     pub const INIT_CONNECT: u32 = 1001;
@@ -35,15 +58,6 @@ mod proto_id {
     pub const QOT_REQUEST_HISTORY_KL: u32 = 3103; // 历史K线
 }
 
-/// Futu 市场代码（QotMarket 枚举值）
-mod futu_market {
-    pub const HK: i32 = 1; // 港股 QotMarket_HK_Security
-    pub const US: i32 = 11; // 美股 QotMarket_US_Security
-    pub const CN_SH: i32 = 21; // A股-沪 QotMarket_CNSH_Security
-    pub const CN_SZ: i32 = 22; // A股-深 QotMarket_CNSZ_Security
-    pub const SG: i32 = 13; // 新加坡 QotMarket_SG_Security
-}
-
 // ---- Protobuf 消息定义 (prost derive) ----
 
 /// 通用 Security 类型
@@ -130,6 +144,8 @@ mod pb_sub {
         pub ret_type: i32,
         #[prost(string, optional, tag = "2")]
         pub ret_msg: Option<String>,
+        #[prost(int32, optional, tag = "3")]
+        pub err_code: Option<i32>,
     }
 }
 
@@ -180,7 +196,10 @@ mod pb_basic_qot {
         pub list_timestamp: Option<f64>,
         #[prost(double, optional, tag = "18")]
         pub update_timestamp: Option<f64>,
-        // field 19: preMarket, 20: afterMarket (skip)
+        #[prost(message, optional, tag = "19")]
+        pub pre_market: Option<PreAfterMarketData>,
+        #[prost(message, optional, tag = "20")]
+        pub after_market: Option<PreAfterMarketData>,
         #[prost(int32, optional, tag = "21")]
         pub sec_status: Option<i32>,
         // field 22: futureExData, 23: warrantExData (skip)
@@ -188,6 +207,27 @@ mod pb_basic_qot {
         pub name: Option<String>,
     }
 
+    /// Qot_Common.PreAfterMarketData（美股盘前/盘后）
+    #[derive(Clone, PartialEq, Message)]
+    pub struct PreAfterMarketData {
+        #[prost(double, optional, tag = "1")]
+        pub price: Option<f64>,
+        #[prost(double, optional, tag = "2")]
+        pub high_price: Option<f64>,
+        #[prost(double, optional, tag = "3")]
+        pub low_price: Option<f64>,
+        #[prost(int64, optional, tag = "4")]
+        pub volume: Option<i64>,
+        #[prost(double, optional, tag = "5")]
+        pub turnover: Option<f64>,
+        #[prost(double, optional, tag = "6")]
+        pub change_val: Option<f64>,
+        #[prost(double, optional, tag = "7")]
+        pub change_rate: Option<f64>, // 涨跌幅 (%)
+        #[prost(double, optional, tag = "8")]
+        pub amplitude: Option<f64>,
+    }
+
     #[derive(Clone, PartialEq, Message)]
     pub struct S2C {
         #[prost(message, repeated, tag = "1")]
@@ -213,19 +253,376 @@ mod pb_basic_qot {
     }
 }
 
+/// QotRequestHistoryKL (3103)
+mod pb_history_kl {
+    use prost::Message;
+
+    #[derive(Clone, PartialEq, Message)]
+    pub struct C2S {
+        #[prost(int32, tag = "1")]
+        pub rehab_type: i32,
+        #[prost(int32, tag = "2")]
+        pub kl_type: i32,
+        #[prost(message, optional, tag = "3")]
+        pub security: Option<super::Security>,
+        #[prost(string, tag = "4")]
+        pub begin_time: String,
+        #[prost(string, tag = "5")]
+        pub end_time: String,
+        #[prost(int32, optional, tag = "6")]
+        pub max_ack_kl_num: Option<i32>,
+        #[prost(int64, optional, tag = "7")]
+        pub need_kl_fields_flag: Option<i64>,
+        #[prost(bytes = "vec", optional, tag = "8")]
+        pub next_req_key: Option<Vec<u8>>,
+    }
+
+    /// Qot_Common.KLine
+    #[derive(Clone, PartialEq, Message)]
+    pub struct KLine {
+        #[prost(string, tag = "1")]
+        pub time: String,
+        #[prost(bool, optional, tag = "2")]
+        pub is_blank: Option<bool>,
+        #[prost(double, optional, tag = "3")]
+        pub high_price: Option<f64>,
+        #[prost(double, optional, tag = "4")]
+        pub open_price: Option<f64>,
+        #[prost(double, optional, tag = "5")]
+        pub low_price: Option<f64>,
+        #[prost(double, optional, tag = "6")]
+        pub close_price: Option<f64>,
+        #[prost(double, optional, tag = "7")]
+        pub last_close_price: Option<f64>,
+        #[prost(int64, optional, tag = "8")]
+        pub volume: Option<i64>,
+        #[prost(double, optional, tag = "9")]
+        pub turnover: Option<f64>,
+    }
+
+    #[derive(Clone, PartialEq, Message)]
+    pub struct S2C {
+        #[prost(message, optional, tag = "1")]
+        pub security: Option<super::Security>,
+        #[prost(message, repeated, tag = "2")]
+        pub kl_list: Vec<KLine>,
+        #[prost(bytes = "vec", optional, tag = "3")]
+        pub next_req_key: Option<Vec<u8>>,
+    }
+
+    #[derive(Clone, PartialEq, Message)]
+    pub struct Request {
+        #[prost(message, optional, tag = "1")]
+        pub c2s: Option<C2S>,
+    }
+
+    #[derive(Clone, PartialEq, Message)]
+    pub struct Response {
+        #[prost(int32, tag = "1")]
+        pub ret_type: i32,
+        #[prost(string, optional, tag = "2")]
+        pub ret_msg: Option<String>,
+        #[prost(int32, optional, tag = "3")]
+        pub err_code: Option<i32>,
+        #[prost(message, optional, tag = "4")]
+        pub s2c: Option<S2C>,
+    }
+}
+
+// ---- 返回码 ----
+
+/// Futu 协议返回类型（Common.RetType），错误信息中附带数值便于对照官方文档
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FutuRetCode {
+    /// 0：成功
+    Succeed,
+    /// -1：失败（具体原因见 retMsg / errCode）
+    Failed,
+    /// -100：超时
+    TimeOut,
+    /// -200：连接断开
+    DisConnect,
+    /// -400：未知错误
+    Unknown,
+    /// -500：包内容非法
+    Invalid,
+    /// 文档未列出的返回值
+    Other(i64),
+}
+
+impl FutuRetCode {
+    pub fn from_code(code: i64) -> Self {
+        match code {
+            0 => FutuRetCode::Succeed,
+            -1 => FutuRetCode::Failed,
+            -100 => FutuRetCode::TimeOut,
+            -200 => FutuRetCode::DisConnect,
+            -400 => FutuRetCode::Unknown,
+            -500 => FutuRetCode::Invalid,
+            other => FutuRetCode::Other(other),
+        }
+    }
+
+    pub fn code(&self) -> i64 {
+        match self {
+            FutuRetCode::Succeed => 0,
+            FutuRetCode::Failed => -1,
+            FutuRetCode::TimeOut => -100,
+            FutuRetCode::DisConnect => -200,
+            FutuRetCode::Unknown => -400,
+            FutuRetCode::Invalid => -500,
+            FutuRetCode::Other(code) => *code,
+        }
+    }
+
+    /// 官方文档中的含义
+    pub fn meaning(&self) -> &'static str {
+        match self {
+            FutuRetCode::Succeed => "成功",
+            FutuRetCode::Failed => "失败",
+            FutuRetCode::TimeOut => "超时",
+            FutuRetCode::DisConnect => "连接断开",
+            FutuRetCode::Unknown => "未知错误",
+            FutuRetCode::Invalid => "包内容非法",
+            FutuRetCode::Other(_) => "未定义返回码",
+        }
+    }
+
+    pub fn is_success(&self) -> bool {
+        *self == FutuRetCode::Succeed
+    }
+}
+
+impl fmt::Display for FutuRetCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "retType={} {}", self.code(), self.meaning())
+    }
+}
+
+/// 请求失败错误：`<api> error: <retMsg> [retType=-1 失败, errCode=N]`
+fn ret_error(api: &str, ret: FutuRetCode, err_code: Option<i64>, ret_msg: Option<&str>) -> anyhow::Error {
+    let detail = match err_code {
+        Some(code) => format!("{}, errCode={}", ret, code),
+        None => ret.to_string(),
+    };
+    anyhow::anyhow!("{} error: {} [{}]", api, ret_msg.unwrap_or("unknown"), detail)
+}
+
+/// JSON 响应的返回码检查（缺少 retType 视为失败），失败时返回带返回码的错误
+fn check_json_ret(api: &str, resp: &serde_json::Value) -> Result<()> {
+    let ret = FutuRetCode::from_code(resp.get("retType").and_then(|v| v.as_i64()).unwrap_or(-1));
+    if ret.is_success() {
+        return Ok(());
+    }
+    let err_code = resp.get("errCode").and_then(|v| v.as_i64());
+    let ret_msg = resp.get("retMsg").and_then(|v| v.as_str());
+    Err(ret_error(api, ret, err_code, ret_msg))
+}
+
+/// protobuf 响应的返回码检查
+fn check_pb_ret(api: &str, ret_type: i32, err_code: Option<i32>, ret_msg: Option<&str>) -> Result<()> {
+    let ret = FutuRetCode::from_code(i64::from(ret_type));
+    if ret.is_success() {
+        return Ok(());
+    }
+    Err(ret_error(api, ret, err_code.map(i64::from), ret_msg))
+}
+
+// ---- 非 FutuOpenD 对端 ----
+
+/// 包头 magic 不是 "FT"（`parse_header` 返回，携带原始包头便于识别对端）
+#[derive(Debug)]
+struct InvalidMagic(Vec<u8>);
+
+impl fmt::Display for InvalidMagic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid magic bytes: {:02x}{:02x}", self.0[0], self.0[1])
+    }
+}
+
+impl std::error::Error for InvalidMagic {}
+
+/// 配置的地址不是 FutuOpenD 网关（如端口指向 Web 服务）：InitConnect 的首个响应包头不合法或超时无响应
+#[derive(Debug)]
+pub struct NotFutuGateway {
+    /// 连接地址 host:port
+    pub addr: String,
+    /// 对端返回内容的开头（不可打印字符以 '.' 代替，如 "HTTP/1.1 400 Bad Request"），None 表示无响应
+    pub preview: Option<String>,
+}
+
+impl NotFutuGateway {
+    fn new(addr: String, head: Option<&[u8]>) -> Self {
+        let preview = head.map(|head| {
+            head.iter()
+                .take_while(|&&b| b != b'\r' && b != b'\n')
+                .map(|&b| {
+                    if b.is_ascii_graphic() || b == b' ' {
+                        b as char
+                    } else {
+                        '.'
+                    }
+                })
+                .collect()
+        });
+        Self { addr, preview }
+    }
+}
+
+impl fmt::Display for NotFutuGateway {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.preview {
+            Some(preview) => write!(f, "目标端口不是 FutuOpenD 网关: {} 返回 {:?}", self.addr, preview)?,
+            None => write!(
+                f,
+                "目标端口不是 FutuOpenD 网关: {} 在 {}s 内未响应 InitConnect",
+                self.addr,
+                INIT_CONNECT_TIMEOUT.as_secs()
+            )?,
+        }
+        write!(
+            f,
+            "，请检查 futu.opend_host / futu.opend_port（OpenD 默认 API 端口 11111）"
+        )
+    }
+}
+
+impl std::error::Error for NotFutuGateway {}
+
 // ---- 客户端实现 ----
 
+/// 一条原始响应：(接收时间, 响应体)
+type RawResponse = (DateTime<Local>, Vec<u8>);
+
+/// 原始响应缓存：按 proto_id 保留最近 N 个响应体（调试 JSON / protobuf 解码不一致用）
+#[derive(Debug)]
+pub struct RawResponseLog {
+    per_proto: usize,
+    entries: BTreeMap<u32, VecDeque<RawResponse>>,
+}
+
+impl RawResponseLog {
+    pub fn new(per_proto: usize) -> Self {
+        Self {
+            per_proto,
+            entries: BTreeMap::new(),
+        }
+    }
+
+    fn record(&mut self, proto_id: u32, body: &[u8]) {
+        let queue = self.entries.entry(proto_id).or_default();
+        if queue.len() >= self.per_proto {
+            queue.pop_front();
+        }
+        queue.push_back((Local::now(), body.to_vec()));
+    }
+
+    /// 导出文本：`only` 指定时只输出该 proto_id；JSON 响应体原样输出，其余按十六进制
+    pub fn dump(&self, only: Option<u32>) -> String {
+        let mut out = String::new();
+        for (proto_id, queue) in &self.entries {
+            if only.is_some_and(|p| p != *proto_id) {
+                continue;
+            }
+            for (at, body) in queue {
+                let _ = writeln!(
+                    out,
+                    "[{}] proto_id={} {} bytes",
+                    crate::models::to_app_time(at).format("%H:%M:%S%.3f"),
+                    proto_id,
+                    body.len()
+                );
+                match std::str::from_utf8(body)
+                    .ok()
+                    .filter(|text| serde_json::from_str::<serde_json::Value>(text).is_ok())
+                {
+                    Some(text) => out.push_str(text),
+                    None => out.push_str(&to_hex(body)),
+                }
+                out.push('\n');
+            }
+        }
+        out
+    }
+}
+
+/// 十六进制编码（每 32 字节换行）
+fn to_hex(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len() * 3);
+    for (i, chunk) in bytes.chunks(32).enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        for b in chunk {
+            let _ = write!(out, "{:02x}", b);
+        }
+    }
+    out
+}
+
+/// 指数退避：每次失败后延迟翻倍，封顶 `max`，成功后 `reset`
+#[derive(Debug, Clone)]
+pub struct Backoff {
+    initial: Duration,
+    max: Duration,
+    next: Duration,
+}
+
+impl Backoff {
+    pub fn new(initial: Duration, max: Duration) -> Self {
+        Self {
+            initial,
+            max,
+            next: initial.min(max),
+        }
+    }
+
+    /// 本次等待时长，并把下次延迟翻倍
+    pub fn next_delay(&mut self) -> Duration {
+        let delay = self.next;
+        self.next = (self.next * 2).min(self.max);
+        delay
+    }
+
+    pub fn reset(&mut self) {
+        self.next = self.initial.min(self.max);
+    }
+}
+
 /// OpenAPI 客户端
 pub struct OpenApiClient {
     host: String,
     port: u16,
-    stream: Option<TcpStream>,
-    serial_no: u32,
+    /// TCP 连接读半部（请求/响应与推送循环独占）
+    reader: Option<OwnedReadHalf>,
+    /// TCP 连接写半部（与心跳任务共享）
+    writer: Option<Arc<Mutex<OwnedWriteHalf>>>,
+    serial_no: Arc<AtomicU32>,
     conn_id: u64,
+    /// 心跳间隔（InitConnect 返回的 keepAliveInterval）
+    keep_alive_interval: Duration,
+    /// 后台心跳任务（连接建立后启动，断开/重连时终止）
+    keep_alive_task: Option<JoinHandle<()>>,
+    /// 心跳健康标志：心跳写入失败时置 false，调用方据此决定重连
+    keep_alive_ok: Arc<AtomicBool>,
+    /// 连接状态：读写出错后进入 Reconnecting，下次请求时按退避自动重连
+    connection: ConnectionState,
+    reconnect_backoff: Backoff,
+    /// 下次允许重连的时间（退避期内的请求直接返回错误，不阻塞调用方）
+    next_reconnect_at: Option<tokio::time::Instant>,
     /// 推送数据接收通道
     quote_tx: Option<mpsc::Sender<QuoteSnapshot>>,
     /// 订阅成功的市场（只对这些市场发起行情请求）
     subscribed_markets: HashSet<Market>,
+    /// 当前订阅的股票与订阅类型（推送断线重连后据此恢复订阅）
+    subscribed_codes: HashSet<StockCode>,
+    subscribed_types: Vec<i32>,
+    /// 推送循环出错时是否重连（为 false 时推送循环出错即返回）
+    push_reconnect: bool,
+    /// 原始响应缓存（`enable_raw_log` 开启，默认关闭）
+    raw_log: Option<RawResponseLog>,
+    /// JSON 请求被网关拒绝（旧版 OpenD）、改用 protobuf 请求的协议
+    pb_protocols: HashSet<u32>,
 }
 
 impl OpenApiClient {
@@ -233,11 +630,23 @@ impl OpenApiClient {
         Self {
             host: host.to_string(),
             port,
-            stream: None,
-            serial_no: 0,
+            reader: None,
+            writer: None,
+            serial_no: Arc::new(AtomicU32::new(0)),
             conn_id: 0,
+            keep_alive_interval: DEFAULT_KEEP_ALIVE_INTERVAL,
+            keep_alive_task: None,
+            keep_alive_ok: Arc::new(AtomicBool::new(false)),
+            connection: ConnectionState::Disconnected,
+            reconnect_backoff: Backoff::new(RECONNECT_INITIAL, RECONNECT_MAX),
+            next_reconnect_at: None,
             quote_tx: None,
             subscribed_markets: HashSet::new(),
+            subscribed_codes: HashSet::new(),
+            subscribed_types: Vec::new(),
+            push_reconnect: true,
+            raw_log: None,
+            pb_protocols: HashSet::new(),
         }
     }
 
@@ -251,16 +660,67 @@ impl OpenApiClient {
         self.quote_tx = Some(tx);
     }
 
+    /// 设置断线重连的最大退避（请求与推送循环共用），`Duration::ZERO` 表示推送循环不重连
+    pub fn set_push_reconnect_max(&mut self, max: Duration) {
+        self.push_reconnect = !max.is_zero();
+        if self.push_reconnect {
+            self.reconnect_backoff = Backoff::new(RECONNECT_INITIAL, max);
+        }
+    }
+
+    /// 开启原始响应缓存，每个 proto_id 保留最近 `per_proto` 个响应体，0 表示关闭
+    pub fn enable_raw_log(&mut self, per_proto: usize) {
+        self.raw_log = (per_proto > 0).then(|| RawResponseLog::new(per_proto));
+    }
+
+    /// 导出缓存的原始响应（未开启时返回 None）
+    pub fn dump_raw_responses(&self) -> Option<String> {
+        self.raw_log.as_ref().map(|log| log.dump(None))
+    }
+
+    /// 解码/业务错误时把该 proto_id 的原始响应写入日志
+    fn log_raw_on_error(&self, proto_id: u32) {
+        if let Some(log) = &self.raw_log {
+            warn!(
+                "Recent raw responses for proto_id={}:\n{}",
+                proto_id,
+                log.dump(Some(proto_id))
+            );
+        }
+    }
+
+    /// 心跳是否正常：已连接且最近一次心跳写入成功
+    ///
+    /// 为 false 时连接多半已被网关断开，调用方应重新 `connect()`
+    pub fn keep_alive_ok(&self) -> bool {
+        self.keep_alive_ok.load(Ordering::Relaxed)
+    }
+
+    /// 当前连接状态
+    pub fn connection_state(&self) -> ConnectionState {
+        self.connection
+    }
+
     /// 断开连接（优雅关闭 TCP）
     pub async fn disconnect(&mut self) {
-        if let Some(mut stream) = self.stream.take() {
+        self.connection = ConnectionState::Disconnected;
+        self.stop_keep_alive();
+        self.reader = None;
+        if let Some(writer) = self.writer.take() {
             // 等待服务器完成内部处理后再关闭
             tokio::time::sleep(std::time::Duration::from_millis(200)).await;
-            let _ = stream.shutdown().await;
+            let _ = writer.lock().await.shutdown().await;
             info!("Disconnected from FutuOpenD");
         }
     }
 
+    /// 丢弃当前连接（不做优雅关闭）
+    fn drop_connection(&mut self) {
+        self.stop_keep_alive();
+        self.reader = None;
+        self.writer = None;
+    }
+
     /// 连接到 FutuOpenD
     pub async fn connect(&mut self) -> Result<()> {
         let addr = format!("{}:{}", self.host, self.port);
@@ -270,15 +730,136 @@ impl OpenApiClient {
             .await
             .with_context(|| format!("Failed to connect to FutuOpenD at {}", addr))?;
 
-        self.stream = Some(stream);
+        // 重连时先终止旧连接的心跳
+        self.drop_connection();
+        let (reader, writer) = stream.into_split();
+        self.reader = Some(reader);
+        self.writer = Some(Arc::new(Mutex::new(writer)));
         info!("TCP connection established");
 
-        // 发送 InitConnect
-        self.init_connect().await?;
+        // 发送 InitConnect（失败时关闭这条连接，避免残留半开的读写端）
+        if let Err(e) = self.init_connect().await {
+            self.drop_connection();
+            return Err(e);
+        }
+        self.start_keep_alive();
+
+        self.connection = ConnectionState::Connected;
+        self.next_reconnect_at = None;
+        Ok(())
+    }
+
+    /// 重新建立连接：重连 TCP、重放 InitConnect，并按记录恢复之前订阅的股票
+    pub async fn reconnect(&mut self) -> Result<()> {
+        // 旧连接已失效，直接丢弃（不做优雅关闭）
+        self.drop_connection();
+        self.connect().await?;
+
+        // 新连接上的订阅为空，按记录恢复
+        self.subscribed_markets.clear();
+        let codes: Vec<StockCode> = self.subscribed_codes.iter().cloned().collect();
+        let sub_types = self.subscribed_types.clone();
+        if !codes.is_empty() {
+            self.subscribe(&codes, &sub_types)
+                .await
+                .context("Re-subscribe after reconnect failed")?;
+        }
 
+        info!("FutuOpenD connection restored ({} stocks re-subscribed)", codes.len());
         Ok(())
     }
 
+    /// 读写出错：标记连接断开，下次请求时按退避重连
+    fn mark_lost(&mut self, err: &anyhow::Error) {
+        if self.connection == ConnectionState::Connected {
+            warn!("FutuOpenD connection lost: {}", err);
+            self.connection = ConnectionState::Reconnecting { attempt: 0 };
+            self.next_reconnect_at = Some(tokio::time::Instant::now() + self.reconnect_backoff.next_delay());
+        }
+    }
+
+    /// 请求前检查连接：断线且已过退避期时自动重连，退避期内返回错误
+    ///
+    /// 未连接过（或主动 `disconnect`）的客户端不自动重连
+    async fn ensure_connected(&mut self) -> Result<()> {
+        if self.connection == ConnectionState::Connected && self.keep_alive_task.is_some() && !self.keep_alive_ok() {
+            self.mark_lost(&anyhow::anyhow!("keepalive failed"));
+        }
+
+        let ConnectionState::Reconnecting { attempt } = self.connection else {
+            return Ok(());
+        };
+        if let Some(at) = self.next_reconnect_at {
+            let now = tokio::time::Instant::now();
+            if now < at {
+                anyhow::bail!("FutuOpenD 连接已断开，{}s 后重连", (at - now).as_secs().max(1));
+            }
+        }
+
+        info!("Reconnecting to FutuOpenD (attempt {})", attempt + 1);
+        match self.reconnect().await {
+            Ok(()) => {
+                self.reconnect_backoff.reset();
+                Ok(())
+            }
+            Err(e) => {
+                self.drop_connection();
+                self.connection = ConnectionState::Reconnecting { attempt: attempt + 1 };
+                let delay = self.reconnect_backoff.next_delay();
+                self.next_reconnect_at = Some(tokio::time::Instant::now() + delay);
+                warn!("FutuOpenD reconnect failed, retry in {}s: {}", delay.as_secs(), e);
+                Err(e.context("FutuOpenD 重连失败"))
+            }
+        }
+    }
+
+    /// 启动后台心跳任务：每 `keep_alive_interval` 发送一次 KeepAlive (1004)
+    ///
+    /// 网关在超过心跳间隔未收到 KeepAlive 时会断开连接并清空订阅；
+    /// 写入失败时记录警告、清除 `keep_alive_ok` 并退出任务
+    fn start_keep_alive(&mut self) {
+        self.stop_keep_alive();
+        let Some(writer) = self.writer.clone() else {
+            return;
+        };
+        let serial_no = self.serial_no.clone();
+        let ok = self.keep_alive_ok.clone();
+        let interval = self.keep_alive_interval;
+        ok.store(true, Ordering::Relaxed);
+
+        self.keep_alive_task = Some(tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            // 第一次 tick 立即触发，跳过（刚完成 InitConnect）
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                let body = serde_json::json!({ "c2s": { "time": Local::now().timestamp() } });
+                let result = match serde_json::to_vec(&body) {
+                    Ok(bytes) => write_packet(&writer, &serial_no, proto_id::KEEP_ALIVE, &bytes, 1).await,
+                    Err(e) => Err(e.into()),
+                };
+                match result {
+                    Ok(serial) => debug!("KeepAlive sent (serial={})", serial),
+                    Err(e) => {
+                        warn!("KeepAlive write failed, connection needs reconnect: {}", e);
+                        ok.store(false, Ordering::Relaxed);
+                        return;
+                    }
+                }
+            }
+        }));
+        debug!("KeepAlive task started (every {}s)", interval.as_secs());
+    }
+
+    /// 终止心跳任务
+    fn stop_keep_alive(&mut self) {
+        if let Some(task) = self.keep_alive_task.take() {
+            task.abort();
+        }
+        self.keep_alive_ok.store(false, Ordering::Relaxed);
+    }
+
     /// 发送 InitConnect 请求（JSON 模式，FutuOpenD 对此接口支持 JSON）
     async fn init_connect(&mut self) -> Result<()> {
         let body = serde_json::json!({
@@ -293,17 +874,22 @@ impl OpenApiClient {
         self.send_packet_with_fmt(proto_id::INIT_CONNECT, &body_bytes, 1)
             .await?;
 
-        let (_proto_id, response) = self.recv_packet().await?;
+        // 首个响应包头不合法或超时无响应 → 对端不是 FutuOpenD，给出指向配置的明确错误
+        let addr = format!("{}:{}", self.host, self.port);
+        let (_proto_id, response) = match tokio::time::timeout(INIT_CONNECT_TIMEOUT, self.recv_packet()).await {
+            Ok(Ok(packet)) => packet,
+            Ok(Err(e)) => match e.downcast::<InvalidMagic>() {
+                Ok(InvalidMagic(head)) => return Err(NotFutuGateway::new(addr, Some(&head)).into()),
+                Err(e) => return Err(e),
+            },
+            Err(_) => return Err(NotFutuGateway::new(addr, None).into()),
+        };
 
         // InitConnect 响应也是 JSON
         let resp: serde_json::Value =
             serde_json::from_slice(&response).with_context(|| "Failed to parse InitConnect response")?;
 
-        let ret_type = resp.get("retType").and_then(|v| v.as_i64()).unwrap_or(-1);
-        if ret_type != 0 {
-            let msg = resp.get("retMsg").and_then(|v| v.as_str()).unwrap_or("unknown");
-            anyhow::bail!("InitConnect failed: retType={}, msg={}", ret_type, msg);
-        }
+        check_json_ret("InitConnect", &resp)?;
 
         if let Some(conn_id) = resp.pointer("/s2c/connID").and_then(|v| v.as_u64()) {
             self.conn_id = conn_id;
@@ -311,6 +897,7 @@ impl OpenApiClient {
                 .pointer("/s2c/keepAliveInterval")
                 .and_then(|v| v.as_i64())
                 .unwrap_or(0);
+            self.keep_alive_interval = keep_alive_interval(keep_alive);
             info!(
                 "InitConnect successful, connID: {}, keepAlive: {}s",
                 conn_id, keep_alive
@@ -323,38 +910,33 @@ impl OpenApiClient {
     /// 订阅行情（按市场分批，避免一个市场失败影响全部）
     /// 订阅成功的市场会记录下来，后续 get_basic_quotes 只查这些市场
     pub async fn subscribe(&mut self, stocks: &[StockCode], sub_types: &[i32]) -> Result<()> {
-        let markets = [Market::HK, Market::SH, Market::SZ, Market::US, Market::SG];
-        let market_names = ["HK", "SH", "SZ", "US", "SG"];
-
-        // 按市场分组（跳过 Unknown/FX）
-        let mut groups: Vec<Vec<&StockCode>> = vec![Vec::new(); 5];
-        for s in stocks {
-            match s.market {
-                Market::HK => groups[0].push(s),
-                Market::SH => groups[1].push(s),
-                Market::SZ => groups[2].push(s),
-                Market::US => groups[3].push(s),
-                Market::SG => groups[4].push(s),
-                Market::FX | Market::Unknown => {
-                    debug!("Skipping unsupported market stock: {}", s.display_code());
-                }
-            }
+        for s in stocks.iter().filter(|s| s.market.futu_code().is_none()) {
+            debug!("Skipping unsupported market stock: {}", s.display_code());
         }
 
         let mut success_count = 0;
+        self.subscribed_types = sub_types.to_vec();
 
-        for (i, group) in groups.iter().enumerate() {
-            if group.is_empty() {
-                continue;
-            }
-            match self.subscribe_batch(group, sub_types).await {
+        // 断线重连期间只记录，重连成功后统一恢复订阅
+        if matches!(self.connection, ConnectionState::Reconnecting { .. }) {
+            self.subscribed_codes.extend(stocks.iter().cloned());
+            info!(
+                "Connection down, {} stocks will be subscribed after reconnect",
+                stocks.len()
+            );
+            return Ok(());
+        }
+
+        for (market, group) in group_by_market(stocks) {
+            match self.subscribe_batch(&group, sub_types).await {
                 Ok(()) => {
-                    self.subscribed_markets.insert(markets[i]);
-                    info!("Subscribed {} {} stocks", group.len(), market_names[i]);
+                    self.subscribed_markets.insert(market);
+                    self.subscribed_codes.extend(group.iter().map(|c| (*c).clone()));
+                    info!("Subscribed {} {} stocks", group.len(), market);
                     success_count += group.len();
                 }
                 Err(e) => {
-                    warn!("{} market unavailable: {}", market_names[i], e);
+                    warn!("{} market unavailable: {}", market, e);
                 }
             }
         }
@@ -369,16 +951,23 @@ impl OpenApiClient {
         Ok(())
     }
 
-    /// 订阅单批次行情
+    /// 订阅单批次行情（JSON 格式被网关拒绝时改用 protobuf）
     async fn subscribe_batch(&mut self, stocks: &[&StockCode], sub_types: &[i32]) -> Result<()> {
+        if !self.pb_protocols.contains(&proto_id::QOT_SUB) {
+            match self.subscribe_batch_json(stocks, sub_types).await {
+                Err(e) if is_format_rejection(&e) => self.fall_back_to_protobuf(proto_id::QOT_SUB, &e),
+                result => return result,
+            }
+        }
+        self.qot_sub_pb(stocks, sub_types, true).await
+    }
+
+    /// 订阅单批次行情（JSON 请求）
+    async fn subscribe_batch_json(&mut self, stocks: &[&StockCode], sub_types: &[i32]) -> Result<()> {
         let security_list: Vec<serde_json::Value> = stocks
             .iter()
-            .map(|s| {
-                serde_json::json!({
-                    "market": stock_code_to_futu_market(s),
-                    "code": &s.code
-                })
-            })
+            .filter_map(|s| stock_code_to_security(s))
+            .map(|sec| serde_json::json!({ "market": sec.market, "code": sec.code }))
             .collect();
 
         let body = serde_json::json!({
@@ -396,18 +985,12 @@ impl OpenApiClient {
         let response = self.recv_response(proto_id::QOT_SUB).await?;
         // 先尝试 protobuf
         if let Ok(resp) = pb_sub::Response::decode(response.as_slice()) {
-            if resp.ret_type != 0 {
-                anyhow::bail!("QotSub failed: {}", resp.ret_msg.as_deref().unwrap_or("unknown"));
-            }
+            check_pb_ret("QotSub", resp.ret_type, resp.err_code, resp.ret_msg.as_deref())?;
             return Ok(());
         }
         // 尝试 JSON 错误响应
         if let Ok(json_resp) = serde_json::from_slice::<serde_json::Value>(&response) {
-            let ret_type = json_resp.get("retType").and_then(|v| v.as_i64()).unwrap_or(-1);
-            let ret_msg = json_resp.get("retMsg").and_then(|v| v.as_str()).unwrap_or("unknown");
-            if ret_type != 0 {
-                anyhow::bail!("QotSub error: {}", ret_msg);
-            }
+            check_json_ret("QotSub", &json_resp)?;
         }
 
         Ok(())
@@ -415,41 +998,39 @@ impl OpenApiClient {
 
     /// 退订行情
     pub async fn unsubscribe(&mut self, stocks: &[StockCode], sub_types: &[i32]) -> Result<()> {
-        // 按市场分组
-        let mut groups: Vec<Vec<&StockCode>> = vec![Vec::new(); 5];
-        for s in stocks {
-            match s.market {
-                Market::HK => groups[0].push(s),
-                Market::SH => groups[1].push(s),
-                Market::SZ => groups[2].push(s),
-                Market::US => groups[3].push(s),
-                Market::SG => groups[4].push(s),
-                Market::FX | Market::Unknown => {}
-            }
+        for code in stocks {
+            self.subscribed_codes.remove(code);
         }
-
-        for group in &groups {
-            if group.is_empty() {
-                continue;
-            }
-            if let Err(e) = self.unsubscribe_batch(group, sub_types).await {
-                warn!("Unsubscribe batch failed: {}", e);
+        // 断线期间无需退订（重连后的新连接不含这些订阅）
+        if self.connection != ConnectionState::Connected {
+            return Ok(());
+        }
+        for (market, group) in group_by_market(stocks) {
+            if let Err(e) = self.unsubscribe_batch(&group, sub_types).await {
+                warn!("Unsubscribe {} batch failed: {}", market, e);
             }
         }
 
         Ok(())
     }
 
-    /// 退订单批次行情
+    /// 退订单批次行情（JSON 格式被网关拒绝时改用 protobuf）
     async fn unsubscribe_batch(&mut self, stocks: &[&StockCode], sub_types: &[i32]) -> Result<()> {
+        if !self.pb_protocols.contains(&proto_id::QOT_SUB) {
+            match self.unsubscribe_batch_json(stocks, sub_types).await {
+                Err(e) if is_format_rejection(&e) => self.fall_back_to_protobuf(proto_id::QOT_SUB, &e),
+                result => return result,
+            }
+        }
+        self.qot_sub_pb(stocks, sub_types, false).await
+    }
+
+    /// 退订单批次行情（JSON 请求）
+    async fn unsubscribe_batch_json(&mut self, stocks: &[&StockCode], sub_types: &[i32]) -> Result<()> {
         let security_list: Vec<serde_json::Value> = stocks
             .iter()
-            .map(|s| {
-                serde_json::json!({
-                    "market": stock_code_to_futu_market(s),
-                    "code": &s.code
-                })
-            })
+            .filter_map(|s| stock_code_to_security(s))
+            .map(|sec| serde_json::json!({ "market": sec.market, "code": sec.code }))
             .collect();
 
         let body = serde_json::json!({
@@ -466,17 +1047,11 @@ impl OpenApiClient {
 
         let response = self.recv_response(proto_id::QOT_SUB).await?;
         if let Ok(resp) = pb_sub::Response::decode(response.as_slice()) {
-            if resp.ret_type != 0 {
-                anyhow::bail!("QotUnsub failed: {}", resp.ret_msg.as_deref().unwrap_or("unknown"));
-            }
+            check_pb_ret("QotUnsub", resp.ret_type, resp.err_code, resp.ret_msg.as_deref())?;
             return Ok(());
         }
         if let Ok(json_resp) = serde_json::from_slice::<serde_json::Value>(&response) {
-            let ret_type = json_resp.get("retType").and_then(|v| v.as_i64()).unwrap_or(-1);
-            let ret_msg = json_resp.get("retMsg").and_then(|v| v.as_str()).unwrap_or("unknown");
-            if ret_type != 0 {
-                anyhow::bail!("QotUnsub error: {}", ret_msg);
-            }
+            check_json_ret("QotUnsub", &json_resp)?;
         }
 
         Ok(())
@@ -484,57 +1059,69 @@ impl OpenApiClient {
 
     /// 获取基本行情（只查询已订阅成功的市场）
     pub async fn get_basic_quotes(&mut self, stocks: &[StockCode]) -> Result<Vec<QuoteSnapshot>> {
-        let markets = [Market::HK, Market::SH, Market::SZ, Market::US, Market::SG];
-        let market_names = ["HK", "SH", "SZ", "US", "SG"];
-
-        // 按市场分组，只保留已订阅的市场
-        let mut groups: Vec<Vec<&StockCode>> = vec![Vec::new(); 5];
-        for s in stocks {
-            let idx = match s.market {
-                Market::HK => Some(0),
-                Market::SH => Some(1),
-                Market::SZ => Some(2),
-                Market::US => Some(3),
-                Market::SG => Some(4),
-                Market::FX | Market::Unknown => None,
-            };
-            if let Some(i) = idx {
-                if self.subscribed_markets.contains(&markets[i]) {
-                    groups[i].push(s);
-                }
-            }
-        }
-
+        self.ensure_connected().await?;
         let mut all_quotes = Vec::new();
 
-        for (i, group) in groups.iter().enumerate() {
-            if group.is_empty() {
+        // 按市场分组，只查询已订阅的市场
+        for (market, group) in group_by_market(stocks) {
+            if !self.subscribed_markets.contains(&market) {
                 continue;
             }
-            match self.get_basic_quotes_batch(group).await {
+            if self.connection != ConnectionState::Connected {
+                break;
+            }
+            match self.get_basic_quotes_batch(&group).await {
                 Ok(quotes) => {
-                    debug!("Got {} quotes for {} market", quotes.len(), market_names[i]);
+                    debug!("Got {} quotes for {} market", quotes.len(), market);
                     all_quotes.extend(quotes);
                 }
                 Err(e) => {
-                    warn!("Failed to fetch {} quotes: {}", market_names[i], e);
+                    warn!("Failed to fetch {} quotes: {}", market, e);
                 }
             }
         }
 
+        if let ConnectionState::Reconnecting { .. } = self.connection {
+            anyhow::bail!("FutuOpenD 连接已断开，等待重连");
+        }
         Ok(all_quotes)
     }
 
-    /// 获取单批次基本行情
+    /// 获取单批次基本行情（JSON 格式被网关拒绝时改用 protobuf）
     async fn get_basic_quotes_batch(&mut self, stocks: &[&StockCode]) -> Result<Vec<QuoteSnapshot>> {
+        if !self.pb_protocols.contains(&proto_id::QOT_GET_BASIC_QOT) {
+            match self.get_basic_quotes_batch_json(stocks).await {
+                Err(e) if is_format_rejection(&e) => self.fall_back_to_protobuf(proto_id::QOT_GET_BASIC_QOT, &e),
+                result => return result,
+            }
+        }
+
+        let req = pb_basic_qot::Request {
+            c2s: Some(pb_basic_qot::C2S {
+                security_list: stocks.iter().filter_map(|s| stock_code_to_security(s)).collect(),
+            }),
+        };
+        self.send_proto_packet(proto_id::QOT_GET_BASIC_QOT, &req).await?;
+        let response = self.recv_response(proto_id::QOT_GET_BASIC_QOT).await?;
+
+        let decoded = pb_basic_qot::Response::decode(response.as_slice())
+            .context("Failed to decode QotGetBasicQot protobuf response")
+            .and_then(|resp| {
+                check_pb_ret("QotGetBasicQot", resp.ret_type, resp.err_code, resp.ret_msg.as_deref())?;
+                Ok(parse_basic_qot_list(resp.s2c.as_ref()))
+            });
+        if decoded.is_err() {
+            self.log_raw_on_error(proto_id::QOT_GET_BASIC_QOT);
+        }
+        decoded
+    }
+
+    /// 获取单批次基本行情（JSON 请求）
+    async fn get_basic_quotes_batch_json(&mut self, stocks: &[&StockCode]) -> Result<Vec<QuoteSnapshot>> {
         let security_list: Vec<serde_json::Value> = stocks
             .iter()
-            .map(|s| {
-                serde_json::json!({
-                    "market": stock_code_to_futu_market(s),
-                    "code": &s.code
-                })
-            })
+            .filter_map(|s| stock_code_to_security(s))
+            .map(|sec| serde_json::json!({ "market": sec.market, "code": sec.code }))
             .collect();
 
         let body = serde_json::json!({
@@ -551,26 +1138,24 @@ impl OpenApiClient {
 
         // 尝试 JSON 解码（FutuOpenD 对 JSON 请求通常返回 JSON）
         if let Ok(json_resp) = serde_json::from_slice::<serde_json::Value>(&response) {
-            let ret_type = json_resp.get("retType").and_then(|v| v.as_i64()).unwrap_or(-1);
-            if ret_type != 0 {
-                let ret_msg = json_resp.get("retMsg").and_then(|v| v.as_str()).unwrap_or("unknown");
-                anyhow::bail!("QotGetBasicQot error: {}", ret_msg);
+            if let Err(e) = check_json_ret("QotGetBasicQot", &json_resp) {
+                self.log_raw_on_error(proto_id::QOT_GET_BASIC_QOT);
+                return Err(e);
             }
             return Ok(parse_basic_qot_json(&json_resp));
         }
 
         // JSON 失败，尝试 protobuf
         if let Ok(resp) = pb_basic_qot::Response::decode(response.as_slice()) {
-            if resp.ret_type != 0 {
-                anyhow::bail!(
-                    "QotGetBasicQot failed: {}",
-                    resp.ret_msg.as_deref().unwrap_or("unknown")
-                );
+            if let Err(e) = check_pb_ret("QotGetBasicQot", resp.ret_type, resp.err_code, resp.ret_msg.as_deref()) {
+                self.log_raw_on_error(proto_id::QOT_GET_BASIC_QOT);
+                return Err(e);
             }
             return Ok(parse_basic_qot_list(resp.s2c.as_ref()));
         }
 
         // 两种格式都失败
+        self.log_raw_on_error(proto_id::QOT_GET_BASIC_QOT);
         anyhow::bail!("Failed to decode QotGetBasicQot response ({} bytes)", response.len())
     }
 
@@ -582,20 +1167,136 @@ impl OpenApiClient {
         end: &str,
         max_count: u32,
     ) -> Result<Vec<DailyKline>> {
-        let body = serde_json::json!({
+        let klines = self.request_kline(stock, KlineType::Day, begin, end, max_count).await?;
+        Ok(klines.into_iter().map(DailyKline::from).collect())
+    }
+
+    /// 请求单只股票指定周期的历史K线（分钟K线的 begin/end 可带时分，如 "2025-01-10 09:30:00"）
+    ///
+    /// 范围超出单页时按 nextReqKey 继续翻页（页间隔 200ms 防限流，最多 `MAX_KLINE_PAGES` 页），
+    /// 合计最多 `max_count` 根；JSON 格式被网关拒绝时改用 protobuf
+    pub async fn request_kline(
+        &mut self,
+        stock: &StockCode,
+        kl_type: KlineType,
+        begin: &str,
+        end: &str,
+        max_count: u32,
+    ) -> Result<Vec<Kline>> {
+        let mut pages = Vec::new();
+        let mut next_key = None;
+        let mut remaining = max_count;
+        for page in 0..MAX_KLINE_PAGES {
+            if page > 0 {
+                tokio::time::sleep(Duration::from_millis(200)).await;
+            }
+            let (klines, key) = self
+                .request_kline_page(stock, kl_type, begin, end, remaining, next_key.as_ref())
+                .await?;
+            remaining = remaining.saturating_sub(klines.len() as u32);
+            pages.push(klines);
+            match key {
+                Some(key) if remaining > 0 => next_key = Some(key),
+                _ => return Ok(assemble_kline_pages(pages)),
+            }
+        }
+        warn!(
+            "{} {}K线超过 {} 页，已截断（剩余部分未获取）",
+            stock.display_code(),
+            kl_type,
+            MAX_KLINE_PAGES
+        );
+        Ok(assemble_kline_pages(pages))
+    }
+
+    /// 请求一页历史K线，返回 (本页K线, 下一页游标)
+    async fn request_kline_page(
+        &mut self,
+        stock: &StockCode,
+        kl_type: KlineType,
+        begin: &str,
+        end: &str,
+        max_count: u32,
+        next_key: Option<&NextReqKey>,
+    ) -> Result<(Vec<Kline>, Option<NextReqKey>)> {
+        let security = stock_code_to_security(stock)
+            .with_context(|| format!("{} 所属市场没有富途行情，无法请求K线", stock.display_code()))?;
+        if !self.pb_protocols.contains(&proto_id::QOT_REQUEST_HISTORY_KL) {
+            let json_key = match next_key {
+                Some(NextReqKey::Json(key)) => Some(key.as_str()),
+                _ => None,
+            };
+            match self
+                .request_kline_json(&security, kl_type, begin, end, max_count, json_key)
+                .await
+            {
+                Err(e) if is_format_rejection(&e) => self.fall_back_to_protobuf(proto_id::QOT_REQUEST_HISTORY_KL, &e),
+                result => return result,
+            }
+        }
+
+        let req = pb_history_kl::Request {
+            c2s: Some(pb_history_kl::C2S {
+                rehab_type: 1,
+                kl_type: kl_type.futu_kl_type(),
+                security: Some(security),
+                begin_time: begin.to_string(),
+                end_time: end.to_string(),
+                max_ack_kl_num: Some(i32::try_from(max_count).unwrap_or(i32::MAX)),
+                need_kl_fields_flag: Some(127),
+                next_req_key: match next_key {
+                    Some(NextReqKey::Pb(key)) => Some(key.clone()),
+                    _ => None,
+                },
+            }),
+        };
+        self.send_proto_packet(proto_id::QOT_REQUEST_HISTORY_KL, &req).await?;
+        let response = self.recv_response(proto_id::QOT_REQUEST_HISTORY_KL).await?;
+
+        let resp = pb_history_kl::Response::decode(response.as_slice())
+            .context("Failed to decode QotRequestHistoryKL protobuf response")?;
+        check_pb_ret(
+            "QotRequestHistoryKL",
+            resp.ret_type,
+            resp.err_code,
+            resp.ret_msg.as_deref(),
+        )?;
+        let next = resp
+            .s2c
+            .as_ref()
+            .and_then(|s2c| s2c.next_req_key.clone())
+            .filter(|key| !key.is_empty())
+            .map(NextReqKey::Pb);
+        Ok((parse_kline_list(resp.s2c.as_ref(), kl_type), next))
+    }
+
+    /// 请求一页历史K线（JSON 请求）
+    async fn request_kline_json(
+        &mut self,
+        security: &Security,
+        kl_type: KlineType,
+        begin: &str,
+        end: &str,
+        max_count: u32,
+        next_key: Option<&str>,
+    ) -> Result<(Vec<Kline>, Option<NextReqKey>)> {
+        let mut body = serde_json::json!({
             "c2s": {
                 "security": {
-                    "market": stock_code_to_futu_market(stock),
-                    "code": &stock.code
+                    "market": security.market,
+                    "code": &security.code
                 },
-                "klType": 2,
+                "klType": kl_type.futu_kl_type(),
                 "rehabType": 1,
                 "beginTime": begin,
                 "endTime": end,
-                "maxCount": max_count,
+                "maxAckKLNum": max_count,
                 "needKLFieldsFlag": 127
             }
         });
+        if let Some(key) = next_key {
+            body["c2s"]["nextReqKey"] = serde_json::Value::from(key);
+        }
 
         let body_bytes = serde_json::to_vec(&body)?;
         self.send_packet_with_fmt(proto_id::QOT_REQUEST_HISTORY_KL, &body_bytes, 1)
@@ -605,12 +1306,13 @@ impl OpenApiClient {
 
         // 尝试 JSON 解码
         if let Ok(json_resp) = serde_json::from_slice::<serde_json::Value>(&response) {
-            let ret_type = json_resp.get("retType").and_then(|v| v.as_i64()).unwrap_or(-1);
-            if ret_type != 0 {
-                let ret_msg = json_resp.get("retMsg").and_then(|v| v.as_str()).unwrap_or("unknown");
-                anyhow::bail!("QotRequestHistoryKL error: {}", ret_msg);
-            }
-            return Ok(parse_kline_json(&json_resp));
+            check_json_ret("QotRequestHistoryKL", &json_resp)?;
+            let next = json_resp
+                .pointer("/s2c/nextReqKey")
+                .and_then(|v| v.as_str())
+                .filter(|key| !key.is_empty())
+                .map(|key| NextReqKey::Json(key.to_string()));
+            return Ok((parse_kline_json(&json_resp, kl_type), next));
         }
 
         anyhow::bail!(
@@ -688,6 +1390,9 @@ impl OpenApiClient {
     }
 
     /// 启动推送接收循环
+    ///
+    /// 连接出错时与请求路径共用退避重连并恢复订阅（`set_push_reconnect_max` 为 0 时直接返回错误），
+    /// 行情通道关闭（接收方已退出）时正常返回
     pub async fn start_push_loop(mut self) -> Result<()> {
         info!("Starting push receive loop");
 
@@ -711,13 +1416,96 @@ impl OpenApiClient {
                     }
                 }
                 Err(e) => {
-                    error!("Push loop error: {}", e);
-                    return Err(e);
+                    self.mark_lost(&e);
+                    if !self.push_reconnect || self.connection == ConnectionState::Disconnected {
+                        error!("Push loop error: {}", e);
+                        return Err(e);
+                    }
+                    warn!("Push connection lost: {}, reconnecting", e);
+                    if !self.reconnect_push().await {
+                        warn!("Quote channel closed during push reconnect");
+                        return Ok(());
+                    }
                 }
             }
         }
     }
 
+    /// 推送断线后等到退避期满再重连（`ensure_connected`），成功返回 true；行情通道已关闭时放弃并返回 false
+    async fn reconnect_push(&mut self) -> bool {
+        loop {
+            if self.quote_tx.as_ref().is_some_and(|tx| tx.is_closed()) {
+                return false;
+            }
+            if let Some(at) = self.next_reconnect_at {
+                debug!(
+                    "Push reconnect in {}s",
+                    at.saturating_duration_since(tokio::time::Instant::now()).as_secs()
+                );
+                tokio::time::sleep_until(at).await;
+            }
+            if self.ensure_connected().await.is_ok() {
+                return true;
+            }
+        }
+    }
+
+    /// QotSub 订阅/退订（protobuf 请求）
+    async fn qot_sub_pb(&mut self, stocks: &[&StockCode], sub_types: &[i32], subscribe: bool) -> Result<()> {
+        let api = if subscribe { "QotSub" } else { "QotUnsub" };
+        let req = pb_sub::Request {
+            c2s: Some(pb_sub::C2S {
+                security_list: stocks.iter().filter_map(|s| stock_code_to_security(s)).collect(),
+                sub_type_list: sub_types.to_vec(),
+                is_sub_or_un_sub: subscribe,
+                is_reg_or_un_reg_push: subscribe,
+            }),
+        };
+        self.send_proto_packet(proto_id::QOT_SUB, &req).await?;
+
+        let response = self.recv_response(proto_id::QOT_SUB).await?;
+        let resp = pb_sub::Response::decode(response.as_slice())
+            .with_context(|| format!("Failed to decode {} protobuf response", api))?;
+        check_pb_ret(api, resp.ret_type, resp.err_code, resp.ret_msg.as_deref())
+    }
+
+    /// 网关拒绝该协议的 JSON 请求：记住后本连接上的该协议都改用 protobuf
+    fn fall_back_to_protobuf(&mut self, proto_id: u32, err: &anyhow::Error) {
+        warn!(
+            "FutuOpenD rejected JSON format for proto_id={}, retrying with protobuf: {}",
+            proto_id, err
+        );
+        self.pb_protocols.insert(proto_id);
+    }
+
+    /// 发送 JSON 请求并等待同协议的响应，返回码非成功时报错（`futu::trade` 交易协议复用）
+    pub async fn request_json(
+        &mut self,
+        proto_id: u32,
+        api: &str,
+        body: &serde_json::Value,
+    ) -> Result<serde_json::Value> {
+        self.ensure_connected().await?;
+        let body_bytes = serde_json::to_vec(body)?;
+        self.send_packet_with_fmt(proto_id, &body_bytes, 1).await?;
+
+        let response = self.recv_response(proto_id).await?;
+        let json_resp: serde_json::Value = serde_json::from_slice(&response)
+            .with_context(|| format!("Failed to decode {} response ({} bytes)", api, response.len()))?;
+        check_json_ret(api, &json_resp)?;
+        Ok(json_resp)
+    }
+
+    /// InitConnect 返回的连接 ID（交易协议 PacketID 使用）
+    pub fn conn_id(&self) -> u64 {
+        self.conn_id
+    }
+
+    /// 取下一个序列号（与包头共用计数器，交易协议 PacketID 防重放）
+    pub fn next_serial_no(&self) -> u32 {
+        self.serial_no.fetch_add(1, Ordering::Relaxed).wrapping_add(1)
+    }
+
     /// 发送 protobuf 数据包
     async fn send_proto_packet<M: Message>(&mut self, proto_id: u32, msg: &M) -> Result<()> {
         let body = msg.encode_to_vec();
@@ -731,19 +1519,19 @@ impl OpenApiClient {
 
     /// 发送数据包，指定格式类型（0=protobuf, 1=json）
     async fn send_packet_with_fmt(&mut self, proto_id: u32, body: &[u8], fmt: u8) -> Result<()> {
-        let stream = self.stream.as_mut().context("Not connected")?;
-
-        self.serial_no += 1;
-        let header = build_header(proto_id, self.serial_no, body, fmt);
-
-        stream.write_all(&header).await?;
-        stream.write_all(body).await?;
-        stream.flush().await?;
+        let writer = self.writer.as_ref().context("Not connected")?;
+        let serial = match write_packet(writer, &self.serial_no, proto_id, body, fmt).await {
+            Ok(serial) => serial,
+            Err(e) => {
+                self.mark_lost(&e);
+                return Err(e);
+            }
+        };
 
         debug!(
             "Sent packet: proto_id={}, serial={}, body_len={}, fmt={}",
             proto_id,
-            self.serial_no,
+            serial,
             body.len(),
             fmt
         );
@@ -751,9 +1539,18 @@ impl OpenApiClient {
         Ok(())
     }
 
-    /// 接收数据包
+    /// 接收数据包（读取出错视为连接断开）
     async fn recv_packet(&mut self) -> Result<(u32, Vec<u8>)> {
-        let stream = self.stream.as_mut().context("Not connected")?;
+        let result = self.read_packet().await;
+        if let Err(e) = &result {
+            self.mark_lost(e);
+        }
+        result
+    }
+
+    /// 从读半部读取一个完整数据包
+    async fn read_packet(&mut self) -> Result<(u32, Vec<u8>)> {
+        let stream = self.reader.as_mut().context("Not connected")?;
 
         // 读取头部
         let mut header_buf = [0u8; HEADER_SIZE];
@@ -768,6 +1565,9 @@ impl OpenApiClient {
         }
 
         debug!("Received packet: proto_id={}, body_len={}", proto_id, body_len);
+        if let Some(log) = &mut self.raw_log {
+            log.record(proto_id, &body);
+        }
 
         Ok((proto_id, body))
     }
@@ -793,6 +1593,41 @@ impl OpenApiClient {
     }
 }
 
+impl Drop for OpenApiClient {
+    fn drop(&mut self) {
+        // 心跳任务持有写半部，不终止会让连接一直保持
+        self.stop_keep_alive();
+    }
+}
+
+/// 写入一个完整数据包（头部 + body），返回使用的序列号
+///
+/// 写半部加锁保证请求与心跳包不会交错
+async fn write_packet(
+    writer: &Mutex<OwnedWriteHalf>,
+    serial_no: &AtomicU32,
+    proto_id: u32,
+    body: &[u8],
+    fmt: u8,
+) -> Result<u32> {
+    let serial = serial_no.fetch_add(1, Ordering::Relaxed).wrapping_add(1);
+    let header = build_header(proto_id, serial, body, fmt);
+
+    let mut writer = writer.lock().await;
+    writer.write_all(&header).await?;
+    writer.write_all(body).await?;
+    writer.flush().await?;
+    Ok(serial)
+}
+
+/// InitConnect 返回的 keepAliveInterval（秒）→ 心跳间隔，非正数时使用默认值
+fn keep_alive_interval(secs: i64) -> Duration {
+    match u64::try_from(secs) {
+        Ok(secs) if secs > 0 => Duration::from_secs(secs),
+        _ => DEFAULT_KEEP_ALIVE_INTERVAL,
+    }
+}
+
 /// 构建 Futu 协议头部（44 字节）
 fn build_header(proto_id: u32, serial_no: u32, body: &[u8], fmt: u8) -> Vec<u8> {
     use sha1::{Digest, Sha1};
@@ -824,7 +1659,7 @@ fn parse_header(buf: &[u8]) -> Result<(u32, u32)> {
 
     // 验证 magic
     if buf[0] != FUTU_MAGIC[0] || buf[1] != FUTU_MAGIC[1] {
-        anyhow::bail!("Invalid magic bytes: {:02x}{:02x}", buf[0], buf[1]);
+        return Err(InvalidMagic(buf[..HEADER_SIZE].to_vec()).into());
     }
 
     let mut cursor = &buf[2..];
@@ -843,6 +1678,7 @@ fn parse_basic_qot_list(s2c: Option<&pb_basic_qot::S2C>) -> Vec<QuoteSnapshot> {
         return Vec::new();
     };
 
+    let session = us_market_session();
     s2c.basic_qot_list
         .iter()
         .map(|qot| {
@@ -853,6 +1689,15 @@ fn parse_basic_qot_list(s2c: Option<&pb_basic_qot::S2C>) -> Vec<QuoteSnapshot> {
                 .unwrap_or((0, ""));
             let stock_code = futu_market_to_stock_code(market, code);
 
+            let pre_after = |d: &Option<pb_basic_qot::PreAfterMarketData>| {
+                d.as_ref().and_then(|d| Some((d.price?, d.change_rate.unwrap_or(0.0))))
+            };
+            let (extended_price, extended_change_pct) = if stock_code.market == Market::US {
+                pick_extended(session, pre_after(&qot.pre_market), pre_after(&qot.after_market))
+            } else {
+                (None, None)
+            };
+
             let cur_price = qot.cur_price.unwrap_or(0.0);
             let last_close = qot.last_close_price.unwrap_or(0.0);
             let change = cur_price - last_close;
@@ -876,8 +1721,9 @@ fn parse_basic_qot_list(s2c: Option<&pb_basic_qot::S2C>) -> Vec<QuoteSnapshot> {
                 change_pct,
                 turnover_rate: qot.turnover_rate.unwrap_or(0.0),
                 amplitude: qot.amplitude.unwrap_or(0.0),
-                extended_price: None,
-                extended_change_pct: None,
+                extended_price,
+                extended_change_pct,
+                is_suspended: is_halted(qot.is_suspended, qot.sec_status),
                 timestamp: chrono::Local::now(),
                 source: DataSource::OpenApi,
             }
@@ -885,6 +1731,32 @@ fn parse_basic_qot_list(s2c: Option<&pb_basic_qot::S2C>) -> Vec<QuoteSnapshot> {
         .collect()
 }
 
+/// Qot_Common.SecurityStatus：停牌 / 可恢复熔断 / 不可恢复熔断
+const SEC_STATUS_HALTED: [i32; 3] = [9, 18, 19];
+
+/// 是否暂停交易：`isSuspended` 为真，或证券状态为停牌/熔断（部分市场只给其中一个字段）
+fn is_halted(is_suspended: Option<bool>, sec_status: Option<i32>) -> bool {
+    is_suspended == Some(true) || sec_status.is_some_and(|s| SEC_STATUS_HALTED.contains(&s))
+}
+
+/// 按美股时段选取扩展价格 (extended_price, extended_change_pct)，输入为 (价格, 涨跌幅%)
+///
+/// 盘前/盘中取 preMarket（与仪表盘 "盘前" 标注一致），盘后/夜盘/休市取 afterMarket；价格为 0 视为无数据
+fn pick_extended(
+    session: UsMarketSession,
+    pre: Option<(f64, f64)>,
+    after: Option<(f64, f64)>,
+) -> (Option<f64>, Option<f64>) {
+    let data = match session {
+        UsMarketSession::PreMarket | UsMarketSession::Regular => pre,
+        _ => after,
+    };
+    match data {
+        Some((price, pct)) if price > 0.0 => (Some(price), Some(pct)),
+        _ => (None, None),
+    }
+}
+
 /// 从 JSON 值中提取整数（兼容数字和字符串格式）
 /// FutuOpenD 对大数值（如 volume）可能返回字符串而非数字
 fn json_as_i64(v: &serde_json::Value) -> Option<i64> {
@@ -904,6 +1776,7 @@ fn parse_basic_qot_json(resp: &serde_json::Value) -> Vec<QuoteSnapshot> {
         return Vec::new();
     };
 
+    let session = us_market_session();
     list.iter()
         .filter_map(|qot| {
             let security = qot.get("security")?;
@@ -911,6 +1784,19 @@ fn parse_basic_qot_json(resp: &serde_json::Value) -> Vec<QuoteSnapshot> {
             let code = security.get("code").and_then(|v| v.as_str()).unwrap_or("");
             let stock_code = futu_market_to_stock_code(market, code);
 
+            let pre_after = |key: &str| {
+                let d = qot.get(key)?;
+                Some((
+                    d.get("price").and_then(json_as_f64)?,
+                    d.get("changeRate").and_then(json_as_f64).unwrap_or(0.0),
+                ))
+            };
+            let (extended_price, extended_change_pct) = if stock_code.market == Market::US {
+                pick_extended(session, pre_after("preMarket"), pre_after("afterMarket"))
+            } else {
+                (None, None)
+            };
+
             let cur_price = qot.get("curPrice").and_then(json_as_f64).unwrap_or(0.0);
             let last_close = qot.get("lastClosePrice").and_then(json_as_f64).unwrap_or(0.0);
             let change = cur_price - last_close;
@@ -934,8 +1820,12 @@ fn parse_basic_qot_json(resp: &serde_json::Value) -> Vec<QuoteSnapshot> {
                 change_pct,
                 turnover_rate: qot.get("turnoverRate").and_then(json_as_f64).unwrap_or(0.0),
                 amplitude: qot.get("amplitude").and_then(json_as_f64).unwrap_or(0.0),
-                extended_price: None,
-                extended_change_pct: None,
+                extended_price,
+                extended_change_pct,
+                is_suspended: is_halted(
+                    qot.get("isSuspended").and_then(|v| v.as_bool()),
+                    qot.get("secStatus").and_then(json_as_i64).map(|v| v as i32),
+                ),
                 timestamp: chrono::Local::now(),
                 source: DataSource::OpenApi,
             })
@@ -943,43 +1833,82 @@ fn parse_basic_qot_json(resp: &serde_json::Value) -> Vec<QuoteSnapshot> {
         .collect()
 }
 
-/// StockCode → protobuf Security
-fn stock_code_to_security(code: &StockCode) -> Security {
-    Security {
-        market: stock_code_to_futu_market(code),
+/// StockCode → protobuf Security，无富途行情市场码（FX / Unknown）时返回 None
+fn stock_code_to_security(code: &StockCode) -> Option<Security> {
+    Some(Security {
+        market: code.market.futu_code()?,
         code: code.code.clone(),
-    }
+    })
 }
 
-/// StockCode → Futu 市场代码
-fn stock_code_to_futu_market(code: &StockCode) -> i32 {
-    match code.market {
-        Market::HK => futu_market::HK,
-        Market::US => futu_market::US,
-        Market::SH => futu_market::CN_SH,
-        Market::SZ => futu_market::CN_SZ,
-        Market::SG => futu_market::SG,
-        Market::FX => futu_market::HK, // FX 暂无独立市场码，降级到 HK
-        Market::Unknown => futu_market::HK,
-    }
+/// 按市场分组（顺序同 `Market::all()`），跳过无富途行情市场码的股票（FX / Unknown）和空组
+fn group_by_market(stocks: &[StockCode]) -> Vec<(Market, Vec<&StockCode>)> {
+    Market::all()
+        .into_iter()
+        .filter(|m| m.futu_code().is_some())
+        .map(|m| (m, stocks.iter().filter(|s| s.market == m).collect::<Vec<_>>()))
+        .filter(|(_, group)| !group.is_empty())
+        .collect()
+}
+
+/// 错误是否为网关不支持 JSON 请求格式（旧版 FutuOpenD 返回 "不支持的协议格式"）
+fn is_format_rejection(err: &anyhow::Error) -> bool {
+    let msg = err.to_string();
+    msg.contains("协议格式") || msg.to_ascii_lowercase().contains("unsupported format")
+}
+
+/// 历史K线分页游标（S2C.nextReqKey，下一页原样回传；JSON 模式为 base64 字符串）
+#[derive(Debug, Clone)]
+enum NextReqKey {
+    Json(String),
+    Pb(Vec<u8>),
+}
+
+/// 合并各页K线：按时间排序，跨页重叠的同一根K线只保留首次出现的
+fn assemble_kline_pages(pages: Vec<Vec<Kline>>) -> Vec<Kline> {
+    let mut klines: Vec<Kline> = pages.into_iter().flatten().collect();
+    klines.sort_by(|a, b| a.time.cmp(&b.time));
+    klines.dedup_by(|later, earlier| later.time == earlier.time);
+    klines
+}
+
+/// 从 protobuf 响应解析历史K线
+fn parse_kline_list(s2c: Option<&pb_history_kl::S2C>, period: KlineType) -> Vec<Kline> {
+    let Some(s2c) = s2c else {
+        return Vec::new();
+    };
+    s2c.kl_list
+        .iter()
+        .map(|kl| Kline {
+            period,
+            open: kl.open_price.unwrap_or(0.0),
+            close: kl.close_price.unwrap_or(0.0),
+            high: kl.high_price.unwrap_or(0.0),
+            low: kl.low_price.unwrap_or(0.0),
+            volume: kl.volume.unwrap_or(0).max(0) as u64,
+            turnover: kl.turnover.unwrap_or(0.0),
+            time: kl.time.clone(),
+        })
+        .collect()
 }
 
 /// 从 JSON 响应解析历史K线
-fn parse_kline_json(resp: &serde_json::Value) -> Vec<DailyKline> {
+fn parse_kline_json(resp: &serde_json::Value, period: KlineType) -> Vec<Kline> {
     let Some(list) = resp.pointer("/s2c/klList").and_then(|v| v.as_array()) else {
         return Vec::new();
     };
 
     list.iter()
         .filter_map(|kl| {
-            Some(DailyKline {
+            Some(Kline {
+                period,
                 open: kl.get("openPrice").and_then(|v| v.as_f64()).unwrap_or(0.0),
                 close: kl.get("closePrice").and_then(|v| v.as_f64()).unwrap_or(0.0),
                 high: kl.get("highPrice").and_then(|v| v.as_f64()).unwrap_or(0.0),
                 low: kl.get("lowPrice").and_then(|v| v.as_f64()).unwrap_or(0.0),
                 volume: kl.get("volume").and_then(|v| v.as_i64()).unwrap_or(0) as u64,
                 turnover: kl.get("turnover").and_then(|v| v.as_f64()).unwrap_or(0.0),
-                date: kl.get("time").and_then(|v| v.as_str()).unwrap_or("").to_string(),
+                time: kl.get("time").and_then(|v| v.as_str()).unwrap_or("").to_string(),
             })
         })
         .collect()
@@ -987,13 +1916,320 @@ fn parse_kline_json(resp: &serde_json::Value) -> Vec<DailyKline> {
 
 /// Futu 市场代码 → StockCode
 fn futu_market_to_stock_code(market: i32, code: &str) -> StockCode {
-    let m = match market {
-        1 => Market::HK,
-        11 => Market::US,
-        21 => Market::SH,
-        22 => Market::SZ,
-        13 => Market::SG,
-        _ => Market::Unknown,
-    };
-    StockCode::new(m, code)
+    StockCode::new(Market::from_futu_code(market).unwrap_or(Market::Unknown), code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kline(time: &str, close: f64) -> Kline {
+        Kline {
+            period: KlineType::Day,
+            time: time.to_string(),
+            open: close,
+            close,
+            high: close,
+            low: close,
+            volume: 100,
+            turnover: close * 100.0,
+        }
+    }
+
+    #[test]
+    fn test_assemble_kline_pages_in_date_order() {
+        // 三页，第二页与第一页边界重叠一根，第三页乱序
+        let pages = vec![
+            vec![kline("2025-01-02 00:00:00", 1.0), kline("2025-01-03 00:00:00", 2.0)],
+            vec![kline("2025-01-03 00:00:00", 2.5), kline("2025-01-06 00:00:00", 3.0)],
+            vec![kline("2025-01-08 00:00:00", 5.0), kline("2025-01-07 00:00:00", 4.0)],
+        ];
+        let klines = assemble_kline_pages(pages);
+        let times: Vec<&str> = klines.iter().map(|k| &k.time[..10]).collect();
+        assert_eq!(
+            times,
+            ["2025-01-02", "2025-01-03", "2025-01-06", "2025-01-07", "2025-01-08"]
+        );
+        // 重叠的K线保留先取到的那一页
+        assert_eq!(klines[1].close, 2.0);
+    }
+
+    #[test]
+    fn test_parse_basic_qot_json_suspended() {
+        let resp = serde_json::json!({
+            "s2c": { "basicQotList": [
+                { "security": { "market": 1, "code": "00700" }, "curPrice": 388.2, "lastClosePrice": 380.0 },
+                { "security": { "market": 1, "code": "01234" }, "curPrice": 5.0, "lastClosePrice": 5.0, "isSuspended": true },
+                { "security": { "market": 11, "code": "AAPL" }, "curPrice": 190.0, "lastClosePrice": 200.0, "secStatus": 18 },
+            ]}
+        });
+        let suspended: Vec<bool> = parse_basic_qot_json(&resp).iter().map(|q| q.is_suspended).collect();
+        assert_eq!(suspended, [false, true, true]);
+    }
+
+    #[test]
+    fn test_backoff_doubles_up_to_cap_and_resets() {
+        let mut backoff = Backoff::new(Duration::from_secs(1), Duration::from_secs(10));
+        let delays: Vec<u64> = (0..6).map(|_| backoff.next_delay().as_secs()).collect();
+        assert_eq!(delays, [1, 2, 4, 8, 10, 10]);
+
+        backoff.reset();
+        assert_eq!(backoff.next_delay(), Duration::from_secs(1));
+
+        // 初始值大于上限时从上限起步
+        let mut backoff = Backoff::new(Duration::from_secs(5), Duration::from_secs(3));
+        assert_eq!(backoff.next_delay(), Duration::from_secs(3));
+        assert_eq!(backoff.next_delay(), Duration::from_secs(3));
+    }
+
+    #[test]
+    fn test_is_format_rejection() {
+        // 旧版网关的中文/英文拒绝消息（经 check_json_ret 包装后仍可识别）
+        let rejected = serde_json::json!({ "retType": -1, "retMsg": "不支持的协议格式" });
+        assert!(is_format_rejection(&check_json_ret("QotSub", &rejected).unwrap_err()));
+        assert!(is_format_rejection(&anyhow::anyhow!(
+            "QotSub error: Unsupported format"
+        )));
+
+        // 其他业务错误不触发降级
+        let failed = serde_json::json!({ "retType": -1, "retMsg": "订阅额度不足" });
+        assert!(!is_format_rejection(&check_json_ret("QotSub", &failed).unwrap_err()));
+        assert!(!is_format_rejection(&anyhow::anyhow!("connection reset")));
+    }
+
+    #[test]
+    fn test_parse_kline_list() {
+        let s2c = pb_history_kl::S2C {
+            security: None,
+            kl_list: vec![
+                pb_history_kl::KLine {
+                    time: "2025-01-02 00:00:00".to_string(),
+                    open_price: Some(380.0),
+                    close_price: Some(388.2),
+                    high_price: Some(390.0),
+                    low_price: Some(379.0),
+                    volume: Some(1_000),
+                    turnover: Some(388_200.0),
+                    ..Default::default()
+                },
+                // 缺失字段按 0 处理，负成交量截断为 0
+                pb_history_kl::KLine {
+                    time: "2025-01-03 00:00:00".to_string(),
+                    volume: Some(-5),
+                    ..Default::default()
+                },
+            ],
+            next_req_key: None,
+        };
+
+        let klines = parse_kline_list(Some(&s2c), KlineType::Day);
+        assert_eq!(klines.len(), 2);
+        assert_eq!(
+            (klines[0].open, klines[0].close, klines[0].high, klines[0].low),
+            (380.0, 388.2, 390.0, 379.0)
+        );
+        assert_eq!((klines[0].volume, klines[0].turnover), (1_000, 388_200.0));
+        assert_eq!(klines[0].period, KlineType::Day);
+        assert_eq!(klines[1].time, "2025-01-03 00:00:00");
+        assert_eq!((klines[1].close, klines[1].volume), (0.0, 0));
+
+        assert!(parse_kline_list(None, KlineType::Day).is_empty());
+    }
+
+    /// 测试网关：读取一个请求包，返回 (proto_id, 格式, body)
+    async fn read_request(stream: &mut TcpStream) -> (u32, u8, Vec<u8>) {
+        let mut header = [0u8; HEADER_SIZE];
+        stream.read_exact(&mut header).await.unwrap();
+        let (proto_id, body_len) = parse_header(&header).unwrap();
+        let mut body = vec![0u8; body_len as usize];
+        stream.read_exact(&mut body).await.unwrap();
+        (proto_id, header[6], body)
+    }
+
+    async fn write_response(stream: &mut TcpStream, proto_id: u32, body: &[u8], fmt: u8) {
+        stream.write_all(&build_header(proto_id, 0, body, fmt)).await.unwrap();
+        stream.write_all(body).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_kline_falls_back_to_protobuf_when_json_rejected() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let gateway = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let (pid, fmt, _) = read_request(&mut stream).await;
+            assert_eq!((pid, fmt), (proto_id::INIT_CONNECT, 1));
+            let init = serde_json::json!({ "retType": 0, "s2c": { "connID": 7, "keepAliveInterval": 10 } });
+            write_response(&mut stream, pid, &serde_json::to_vec(&init).unwrap(), 1).await;
+
+            // 旧版网关拒绝 JSON 请求
+            let (pid, fmt, _) = read_request(&mut stream).await;
+            assert_eq!((pid, fmt), (proto_id::QOT_REQUEST_HISTORY_KL, 1));
+            let rejected = serde_json::json!({ "retType": -1, "retMsg": "不支持的协议格式" });
+            write_response(&mut stream, pid, &serde_json::to_vec(&rejected).unwrap(), 1).await;
+
+            // 同一请求改用 protobuf 重发，之后该协议不再尝试 JSON
+            for _ in 0..2 {
+                let (pid, fmt, body) = read_request(&mut stream).await;
+                assert_eq!((pid, fmt), (proto_id::QOT_REQUEST_HISTORY_KL, 0));
+                let req = pb_history_kl::Request::decode(body.as_slice()).unwrap();
+                assert_eq!(req.c2s.unwrap().security.unwrap().code, "00700");
+                let resp = pb_history_kl::Response {
+                    ret_type: 0,
+                    ret_msg: None,
+                    err_code: None,
+                    s2c: Some(pb_history_kl::S2C {
+                        security: None,
+                        kl_list: vec![pb_history_kl::KLine {
+                            time: "2025-01-02 00:00:00".to_string(),
+                            close_price: Some(388.2),
+                            ..Default::default()
+                        }],
+                        next_req_key: None,
+                    }),
+                };
+                write_response(&mut stream, pid, &resp.encode_to_vec(), 0).await;
+            }
+        });
+
+        let mut client = OpenApiClient::new("127.0.0.1", port);
+        client.connect().await.unwrap();
+        let stock = StockCode::new(Market::HK, "00700");
+        for _ in 0..2 {
+            let klines = client
+                .request_kline(&stock, KlineType::Day, "2025-01-01", "2025-01-31", 10)
+                .await
+                .unwrap();
+            assert_eq!(klines.len(), 1);
+            assert_eq!(klines[0].close, 388.2);
+            assert!(client.pb_protocols.contains(&proto_id::QOT_REQUEST_HISTORY_KL));
+        }
+        gateway.await.unwrap();
+    }
+
+    #[test]
+    fn test_futu_ret_code_mapping() {
+        for (code, expected, meaning) in [
+            (0, FutuRetCode::Succeed, "成功"),
+            (-1, FutuRetCode::Failed, "失败"),
+            (-100, FutuRetCode::TimeOut, "超时"),
+            (-200, FutuRetCode::DisConnect, "连接断开"),
+            (-400, FutuRetCode::Unknown, "未知错误"),
+            (-500, FutuRetCode::Invalid, "包内容非法"),
+            (-300, FutuRetCode::Other(-300), "未定义返回码"),
+        ] {
+            let ret = FutuRetCode::from_code(code);
+            assert_eq!(ret, expected);
+            assert_eq!(ret.code(), code);
+            assert_eq!(ret.meaning(), meaning);
+            assert_eq!(ret.is_success(), code == 0);
+        }
+
+        let err = check_pb_ret("QotSub", -200, Some(3), Some("网络断开")).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "QotSub error: 网络断开 [retType=-200 连接断开, errCode=3]"
+        );
+    }
+
+    #[test]
+    fn test_keep_alive_interval_falls_back_to_default() {
+        assert_eq!(keep_alive_interval(30), Duration::from_secs(30));
+        assert_eq!(keep_alive_interval(0), DEFAULT_KEEP_ALIVE_INTERVAL);
+        assert_eq!(keep_alive_interval(-5), DEFAULT_KEEP_ALIVE_INTERVAL);
+    }
+
+    #[test]
+    fn test_pick_extended_by_us_session() {
+        let pre = Some((101.0, 1.0));
+        let after = Some((99.0, -1.0));
+        let pre_pick = (Some(101.0), Some(1.0));
+        let after_pick = (Some(99.0), Some(-1.0));
+        for (session, expected) in [
+            (UsMarketSession::PreMarket, pre_pick),
+            (UsMarketSession::Regular, pre_pick),
+            (UsMarketSession::AfterHours, after_pick),
+            (UsMarketSession::Overnight, after_pick),
+            (UsMarketSession::Closed, after_pick),
+        ] {
+            assert_eq!(pick_extended(session, pre, after), expected, "{:?}", session);
+        }
+
+        // 价格为 0 或缺失视为无数据，不回退到另一时段的价格
+        let zero = Some((0.0, 0.0));
+        assert_eq!(pick_extended(UsMarketSession::PreMarket, zero, after), (None, None));
+        assert_eq!(pick_extended(UsMarketSession::AfterHours, pre, zero), (None, None));
+        assert_eq!(pick_extended(UsMarketSession::Overnight, pre, None), (None, None));
+    }
+
+    #[tokio::test]
+    async fn test_connect_to_non_futu_port_reports_not_futu_gateway() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        // 端口上是 HTTP 服务：对 InitConnect 回一个 HTTP 错误页
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let _ = read_request(&mut stream).await;
+            let reply = "HTTP/1.1 400 Bad Request\r\nContent-Type: text/html\r\nContent-Length: 0\r\n\r\n";
+            stream.write_all(reply.as_bytes()).await.unwrap();
+        });
+
+        let mut client = OpenApiClient::new("127.0.0.1", port);
+        let err = client.connect().await.unwrap_err();
+        let not_futu = err.downcast_ref::<NotFutuGateway>().expect("NotFutuGateway");
+        assert_eq!(not_futu.addr, format!("127.0.0.1:{}", port));
+        assert_eq!(not_futu.preview.as_deref(), Some("HTTP/1.1 400 Bad Request"));
+        assert!(err.to_string().contains("futu.opend_port"));
+        assert_eq!(client.connection_state(), ConnectionState::Disconnected);
+        server.await.unwrap();
+    }
+
+    #[test]
+    fn test_same_code_different_markets_stay_distinct() {
+        let us = StockCode::new(Market::US, "C6L");
+        let sg = StockCode::new(Market::SG, "C6L");
+        let stocks = [us.clone(), sg.clone()];
+
+        // 订阅/拉取按市场分批：两只各在自己的批次，请求里的市场码不同
+        let groups = group_by_market(&stocks);
+        assert_eq!(groups, vec![(Market::US, vec![&us]), (Market::SG, vec![&sg])]);
+        assert_ne!(stock_code_to_security(&us), stock_code_to_security(&sg));
+
+        // 订阅记录按 (市场, 代码) 去重，两只都保留
+        let subscribed: HashSet<StockCode> = groups
+            .iter()
+            .flat_map(|(_, g)| g.iter().map(|c| (*c).clone()))
+            .collect();
+        assert_eq!(subscribed.len(), 2);
+
+        // 返回的行情按各自市场码还原，不会合并成一只
+        let resp = serde_json::json!({
+            "s2c": { "basicQotList": [
+                { "security": { "market": 11, "code": "C6L" }, "curPrice": 1.0, "lastClosePrice": 1.0 },
+                { "security": { "market": 13, "code": "C6L" }, "curPrice": 5.0, "lastClosePrice": 5.0 },
+            ]}
+        });
+        let codes: Vec<StockCode> = parse_basic_qot_json(&resp).into_iter().map(|q| q.code).collect();
+        assert_eq!(codes, [us, sg]);
+    }
+
+    #[test]
+    fn test_market_without_futu_code_is_skipped() {
+        let fx = StockCode::new(Market::FX, "USDCNH");
+        let unknown = StockCode::new(Market::Unknown, "00700");
+        let hk = StockCode::new(Market::HK, "00700");
+        assert_eq!(stock_code_to_security(&fx), None);
+        assert_eq!(stock_code_to_security(&unknown), None);
+        assert_eq!(
+            stock_code_to_security(&hk),
+            Some(Security {
+                market: 1,
+                code: "00700".to_string()
+            })
+        );
+        let stocks = [fx, unknown, hk.clone()];
+        assert_eq!(group_by_market(&stocks), vec![(Market::HK, vec![&hk])]);
+    }
 }
@@ -0,0 +1,331 @@
+//! FutuOpenD 交易协议 — 通过 OpenAPI 直接下单（不依赖财富通界面）
+//!
+//! 复用 `OpenApiClient` 的连接与 JSON 收发：
+//! - TrdGetAccList (2001)：列出交易账户
+//! - TrdUnlockTrade (2005)：解锁交易（真实账户下单前必须解锁）
+//! - TrdPlaceOrder (2202)：普通限价单
+//!
+//! OpenD 需已登录交易账户；模拟账户无需解锁。
+
+use anyhow::{Context, Result};
+use tracing::{info, warn};
+
+use super::openapi::OpenApiClient;
+use crate::models::ConnectionState;
+use crate::trading::executor::{OrderRequest, OrderResult, OrderSide, TradingMarket};
+
+/// 交易协议 ID
+mod proto_id {
+    pub const TRD_GET_ACC_LIST: u32 = 2001;
+    pub const TRD_UNLOCK_TRADE: u32 = 2005;
+    pub const TRD_PLACE_ORDER: u32 = 2202;
+}
+
+/// 交易环境（Trd_Common.TrdEnv）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrdEnv {
+    /// 模拟盘
+    Simulate,
+    /// 真实账户
+    Real,
+}
+
+impl TrdEnv {
+    fn from_code(code: i64) -> Option<Self> {
+        match code {
+            0 => Some(TrdEnv::Simulate),
+            1 => Some(TrdEnv::Real),
+            _ => None,
+        }
+    }
+
+    fn code(self) -> i32 {
+        match self {
+            TrdEnv::Simulate => 0,
+            TrdEnv::Real => 1,
+        }
+    }
+}
+
+impl std::fmt::Display for TrdEnv {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TrdEnv::Simulate => write!(f, "模拟"),
+            TrdEnv::Real => write!(f, "真实"),
+        }
+    }
+}
+
+/// Trd_Common.TrdMarket 取值
+mod trd_market {
+    pub const HK: i32 = 1;
+    /// A 股（内地账户）
+    pub const CN: i32 = 3;
+    /// A 股通（港股通账户经沪深港通交易 A 股）
+    pub const HKCC: i32 = 4;
+}
+
+/// 交易账户（TrdGetAccList 返回）
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrdAccount {
+    pub acc_id: u64,
+    pub env: TrdEnv,
+    /// 有交易权限的市场（Trd_Common.TrdMarket 取值）
+    pub markets: Vec<i32>,
+}
+
+impl TrdAccount {
+    /// 该账户交易此市场时使用的 TrdMarket：A 股优先内地市场，其次 A 股通
+    fn trd_market(&self, market: TradingMarket) -> Option<i32> {
+        let candidates: &[i32] = match market {
+            TradingMarket::HK => &[trd_market::HK],
+            TradingMarket::CN => &[trd_market::CN, trd_market::HKCC],
+        };
+        candidates.iter().copied().find(|m| self.markets.contains(m))
+    }
+}
+
+impl OpenApiClient {
+    /// 获取交易账户列表（证券类账户）
+    pub async fn get_acc_list(&mut self) -> Result<Vec<TrdAccount>> {
+        let body = serde_json::json!({
+            "c2s": {
+                "userID": 0,
+                "trdCategory": 1
+            }
+        });
+        let resp = self
+            .request_json(proto_id::TRD_GET_ACC_LIST, "TrdGetAccList", &body)
+            .await?;
+
+        Ok(parse_acc_list(&resp))
+    }
+
+    /// 解锁交易（`pwd_md5` 为交易密码的 32 位小写 MD5）
+    pub async fn unlock_trade(&mut self, pwd_md5: &str) -> Result<()> {
+        let body = serde_json::json!({
+            "c2s": {
+                "unlock": true,
+                "pwdMD5": pwd_md5
+            }
+        });
+        self.request_json(proto_id::TRD_UNLOCK_TRADE, "TrdUnlockTrade", &body)
+            .await?;
+        info!("FutuOpenD trade unlocked");
+        Ok(())
+    }
+
+    /// 普通限价单下单（`acc_id` 为 `get_acc_list` 返回的账户 ID）
+    ///
+    /// 与 `TradingExecutor::execute_order` 一致：OpenD 拒单返回 `success = false`，连接错误返回 Err。
+    /// 未经界面验价，`verified_price` / `verified_code` 恒为 None。
+    pub async fn place_order(&mut self, acc_id: u64, req: &OrderRequest) -> Result<OrderResult> {
+        let timestamp = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        let account = self
+            .get_acc_list()
+            .await?
+            .into_iter()
+            .find(|acc| acc.acc_id == acc_id)
+            .with_context(|| format!("未找到交易账户 {}", acc_id))?;
+        let trd_market = account
+            .trd_market(req.market)
+            .with_context(|| format!("账户 {} 无{}交易权限", acc_id, req.market))?;
+
+        let prec = req.market.price_decimals();
+        info!(
+            "OpenAPI 委托: {}账户 {} {} {} {} {} 股 @ {:.prec$} {}",
+            account.env,
+            account.acc_id,
+            req.market,
+            req.side,
+            req.stock_code,
+            req.quantity,
+            req.price,
+            req.market.currency(),
+            prec = prec
+        );
+
+        let body = serde_json::json!({
+            "c2s": {
+                "packetID": {
+                    "connID": self.conn_id(),
+                    "serialNo": self.next_serial_no()
+                },
+                "header": {
+                    "trdEnv": account.env.code(),
+                    "accID": account.acc_id,
+                    "trdMarket": trd_market
+                },
+                "trdSide": trd_side(req.side),
+                "orderType": 1, // OrderType_Normal 普通限价单
+                "code": &req.stock_code,
+                "qty": f64::from(req.quantity),
+                "price": req.price,
+                "secMarket": sec_market(req.market, &req.stock_code)
+            }
+        });
+
+        match self
+            .request_json(proto_id::TRD_PLACE_ORDER, "TrdPlaceOrder", &body)
+            .await
+        {
+            Ok(resp) => {
+                let order_id = resp
+                    .pointer("/s2c/orderID")
+                    .and_then(json_as_u64)
+                    .context("TrdPlaceOrder 响应缺少 orderID")?;
+                info!("委托成功: 订单号 {}", order_id);
+                Ok(OrderResult {
+                    success: true,
+                    message: format!("委托已提交，订单号 {}", order_id),
+                    verified_price: None,
+                    verified_code: None,
+                    timestamp,
+                })
+            }
+            // 连接断开（已由客户端标记重连）→ 交给调用方；其余为 OpenD 拒单
+            Err(e) if self.connection_state() != ConnectionState::Connected => Err(e),
+            Err(e) => {
+                warn!("委托失败: {}", e);
+                Ok(OrderResult {
+                    success: false,
+                    message: format!("{}", e),
+                    verified_price: None,
+                    verified_code: None,
+                    timestamp,
+                })
+            }
+        }
+    }
+}
+
+/// 解析 TrdGetAccList 响应，跳过缺少账户 ID 或交易环境未知的条目
+fn parse_acc_list(resp: &serde_json::Value) -> Vec<TrdAccount> {
+    let list = resp
+        .pointer("/s2c/accList")
+        .and_then(|v| v.as_array())
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    list.iter()
+        .filter_map(|acc| {
+            Some(TrdAccount {
+                acc_id: acc.get("accID").and_then(json_as_u64)?,
+                env: TrdEnv::from_code(acc.get("trdEnv")?.as_i64()?)?,
+                markets: acc
+                    .get("trdMarketAuthList")
+                    .and_then(|v| v.as_array())
+                    .map(|l| l.iter().filter_map(|m| m.as_i64()).map(|m| m as i32).collect())
+                    .unwrap_or_default(),
+            })
+        })
+        .collect()
+}
+
+/// 股票代码 → Trd_Common.TrdSecMarket（A 股按代码首位区分沪深）
+fn sec_market(market: TradingMarket, stock_code: &str) -> i32 {
+    match market {
+        TradingMarket::HK => 1,
+        TradingMarket::CN if stock_code.starts_with('6') => 31,
+        TradingMarket::CN => 32,
+    }
+}
+
+/// OrderSide → Trd_Common.TrdSide
+fn trd_side(side: OrderSide) -> i32 {
+    match side {
+        OrderSide::Buy => 1,
+        OrderSide::Sell => 2,
+    }
+}
+
+/// 账户 / 订单 ID（兼容数字和字符串格式，大整数可能以字符串返回）
+fn json_as_u64(v: &serde_json::Value) -> Option<u64> {
+    v.as_u64().or_else(|| v.as_str().and_then(|s| s.parse().ok()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn account(markets: &[i32]) -> TrdAccount {
+        TrdAccount {
+            acc_id: 1,
+            env: TrdEnv::Simulate,
+            markets: markets.to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_account_trd_market() {
+        let hk = account(&[trd_market::HK]);
+        assert_eq!(hk.trd_market(TradingMarket::HK), Some(1));
+        assert_eq!(hk.trd_market(TradingMarket::CN), None);
+
+        // 内地账户走 CN，港股通账户的 A 股走 HKCC
+        assert_eq!(account(&[trd_market::CN]).trd_market(TradingMarket::CN), Some(3));
+        let connect = account(&[trd_market::HK, trd_market::HKCC]);
+        assert_eq!(connect.trd_market(TradingMarket::CN), Some(4));
+        assert_eq!(connect.trd_market(TradingMarket::HK), Some(1));
+        assert_eq!(
+            account(&[trd_market::HKCC, trd_market::CN]).trd_market(TradingMarket::CN),
+            Some(3)
+        );
+        assert_eq!(account(&[]).trd_market(TradingMarket::HK), None);
+    }
+
+    #[test]
+    fn test_sec_market_and_side() {
+        assert_eq!(sec_market(TradingMarket::HK, "00700"), 1);
+        assert_eq!(sec_market(TradingMarket::CN, "600519"), 31);
+        assert_eq!(sec_market(TradingMarket::CN, "000001"), 32);
+        assert_eq!(sec_market(TradingMarket::CN, "300750"), 32);
+        assert_eq!(trd_side(OrderSide::Buy), 1);
+        assert_eq!(trd_side(OrderSide::Sell), 2);
+    }
+
+    #[test]
+    fn test_json_as_u64() {
+        assert_eq!(
+            json_as_u64(&serde_json::json!(281756455983291234u64)),
+            Some(281756455983291234)
+        );
+        assert_eq!(
+            json_as_u64(&serde_json::json!("281756455983291234")),
+            Some(281756455983291234)
+        );
+        assert_eq!(json_as_u64(&serde_json::json!("abc")), None);
+        assert_eq!(json_as_u64(&serde_json::json!(-1)), None);
+        assert_eq!(json_as_u64(&serde_json::Value::Null), None);
+    }
+
+    #[test]
+    fn test_parse_acc_list() {
+        let resp = serde_json::json!({
+            "retType": 0,
+            "s2c": {
+                "accList": [
+                    { "trdEnv": 1, "accID": "281756455983291234", "trdMarketAuthList": [1, 4] },
+                    { "trdEnv": 0, "accID": 6543210, "trdMarketAuthList": [3] },
+                    { "trdEnv": 9, "accID": 1 },
+                    { "trdEnv": 0 }
+                ]
+            }
+        });
+        assert_eq!(
+            parse_acc_list(&resp),
+            [
+                TrdAccount {
+                    acc_id: 281756455983291234,
+                    env: TrdEnv::Real,
+                    markets: vec![1, 4],
+                },
+                TrdAccount {
+                    acc_id: 6543210,
+                    env: TrdEnv::Simulate,
+                    markets: vec![3],
+                },
+            ]
+        );
+        assert!(parse_acc_list(&serde_json::json!({ "s2c": {} })).is_empty());
+    }
+}
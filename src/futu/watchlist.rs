@@ -1,6 +1,8 @@
 use anyhow::{Context, Result};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::SystemTime;
 use tracing::{debug, info, warn};
 
 use crate::models::{Market, StockCode, WatchlistEntry};
@@ -44,7 +46,38 @@ pub fn find_user_dir(base_path: &Path, user_id: Option<&str>) -> Result<PathBuf>
         anyhow::bail!("指定的用户目录 {} 中未找到 {}", uid, WATCHLIST_FILENAME);
     }
 
-    // 自动扫描：找所有数字命名的目录
+    let candidates = scan_user_dirs(base_path)?;
+    let chosen = &candidates[0];
+    info!(
+        "Auto-selected user directory: {} (most recently modified)",
+        chosen.file_name().unwrap_or_default().to_string_lossy()
+    );
+
+    Ok(chosen.clone())
+}
+
+/// 按配置解析要读取的用户目录
+///
+/// - 空列表：自动选择最近活跃的单个用户目录（同 `find_user_dir`）
+/// - 含 "all"：所有含自选股文件的数字目录，最近修改的在前
+/// - 否则：逐个使用指定的用户 ID，顺序与配置一致
+pub fn find_user_dirs(base_path: &Path, user_ids: &[String]) -> Result<Vec<PathBuf>> {
+    if user_ids.is_empty() {
+        return Ok(vec![find_user_dir(base_path, None)?]);
+    }
+    if user_ids.iter().any(|u| u.trim().eq_ignore_ascii_case("all")) {
+        let dirs = scan_user_dirs(base_path)?;
+        info!("Using all {} user directories", dirs.len());
+        return Ok(dirs);
+    }
+    user_ids
+        .iter()
+        .map(|u| find_user_dir(base_path, Some(u.trim())))
+        .collect()
+}
+
+/// 扫描所有包含自选股文件的数字命名用户目录，按自选股文件修改时间从新到旧排序
+fn scan_user_dirs(base_path: &Path) -> Result<Vec<PathBuf>> {
     let mut candidates: Vec<(PathBuf, std::time::SystemTime)> = Vec::new();
 
     let entries =
@@ -85,15 +118,9 @@ pub fn find_user_dir(base_path: &Path, user_id: Option<&str>) -> Result<PathBuf>
         );
     }
 
-    // 选择修改时间最新的
+    // 修改时间最新的在前
     candidates.sort_by(|a, b| b.1.cmp(&a.1));
-    let chosen = &candidates[0].0;
-    info!(
-        "Auto-selected user directory: {} (most recently modified)",
-        chosen.file_name().unwrap_or_default().to_string_lossy()
-    );
-
-    Ok(chosen.clone())
+    Ok(candidates.into_iter().map(|(path, _)| path).collect())
 }
 
 /// 从 plist 文件读取自选股列表
@@ -108,43 +135,66 @@ pub fn read_watchlist(plist_path: &Path) -> Result<Vec<WatchlistEntry>> {
     parse_watchlist_plist(&value)
 }
 
-/// 检测 plist 文件完整路径（供 mtime 监测用）
-pub fn detect_plist_path(data_path: Option<&str>, user_id: Option<&str>) -> Result<PathBuf> {
+/// 检测各用户的 plist 文件完整路径（供 mtime 监测用），用户选择规则见 `find_user_dirs`
+pub fn detect_plist_paths(data_path: Option<&str>, user_ids: &[String]) -> Result<Vec<PathBuf>> {
     let base_path = match data_path {
         Some(p) => PathBuf::from(p),
         None => detect_futu_data_path()?,
     };
-    let user_dir = find_user_dir(&base_path, user_id)?;
-    Ok(user_dir.join(WATCHLIST_FILENAME))
+    let user_dirs = find_user_dirs(&base_path, user_ids)?;
+    Ok(user_dirs.into_iter().map(|d| d.join(WATCHLIST_FILENAME)).collect())
+}
+
+/// 读取多个 plist 并合并，按 StockCode 去重（保留先出现的条目）
+///
+/// 单个文件时原样返回；多个文件时按合并后的顺序重新编号 `sort_index`。
+fn read_merged_watchlist(plist_paths: &[PathBuf]) -> Result<Vec<WatchlistEntry>> {
+    if let [single] = plist_paths {
+        return read_watchlist(single);
+    }
+    let mut seen = HashSet::new();
+    let mut entries = Vec::new();
+    for path in plist_paths {
+        entries.extend(
+            read_watchlist(path)?
+                .into_iter()
+                .filter(|e| seen.insert(e.code.clone())),
+        );
+    }
+    for (i, entry) in entries.iter_mut().enumerate() {
+        entry.sort_index = i;
+    }
+    info!("Merged {} stocks from {} watchlists", entries.len(), plist_paths.len());
+    Ok(entries)
 }
 
 /// 轻量读取：只返回 plist 路径和股票代码集合（不读 StockDB），供白名单过滤用
-pub fn load_watchlist_codes(user_id: Option<&str>) -> Result<(PathBuf, Vec<StockCode>)> {
-    let base_path = detect_futu_data_path()?;
-    let user_dir = find_user_dir(&base_path, user_id)?;
-    let plist_path = user_dir.join(WATCHLIST_FILENAME);
-    let entries = read_watchlist(&plist_path)?;
+pub fn load_watchlist_codes(user_ids: &[String]) -> Result<(Vec<PathBuf>, Vec<StockCode>)> {
+    let plist_paths = detect_plist_paths(None, user_ids)?;
+    let entries = read_merged_watchlist(&plist_paths)?;
     let codes = entries.into_iter().map(|e| e.code).collect();
-    Ok((plist_path, codes))
+    Ok((plist_paths, codes))
 }
 
-/// 一站式读取：自动检测路径 + 读取自选股 + 填充名称
-pub fn load_watchlist(data_path: Option<&str>, user_id: Option<&str>) -> Result<Vec<WatchlistEntry>> {
+/// 一站式读取：自动检测路径 + 读取自选股（多用户时合并去重）+ 填充名称
+pub fn load_watchlist(data_path: Option<&str>, user_ids: &[String]) -> Result<Vec<WatchlistEntry>> {
     let base_path = match data_path {
         Some(p) => PathBuf::from(p),
         None => detect_futu_data_path()?,
     };
 
-    let user_dir = find_user_dir(&base_path, user_id)?;
-    let plist_path = user_dir.join(WATCHLIST_FILENAME);
+    let plist_paths: Vec<PathBuf> = find_user_dirs(&base_path, user_ids)?
+        .into_iter()
+        .map(|d| d.join(WATCHLIST_FILENAME))
+        .collect();
 
-    let mut entries = read_watchlist(&plist_path)?;
+    let mut entries = read_merged_watchlist(&plist_paths)?;
 
     // 从 StockDB 填充股票名称
     let db_path = base_path.join(STOCK_DB_PATH);
     if let Some(names) = db_path
         .exists()
-        .then(|| load_stock_names(&db_path))
+        .then(|| load_stock_names_cached(&db_path))
         .and_then(|r| r.map_err(|e| warn!("Failed to read StockDB: {}", e)).ok())
     {
         let matched = entries
@@ -157,13 +207,96 @@ pub fn load_watchlist(data_path: Option<&str>, user_id: Option<&str>) -> Result<
     Ok(entries)
 }
 
+/// 名称缓存条目：DB 文件（含 -wal）修改时间不变则复用
+struct CachedNames {
+    stamp: (Option<SystemTime>, Option<SystemTime>),
+    names: Arc<HashMap<u64, String>>,
+}
+
+/// 进程内 stock_id → 名称缓存，按 DB 路径区分
+static STOCK_NAME_CACHE: OnceLock<Mutex<HashMap<PathBuf, CachedNames>>> = OnceLock::new();
+
+/// 带缓存读取 StockDB 名称
+///
+/// 名称极少变化，而 plist 重载（富途更新缓存价格时）很频繁：
+/// 仅当 DB 或其 -wal 的修改时间变化时才重新读取 SQLite。
+fn load_stock_names_cached(db_path: &Path) -> Result<Arc<HashMap<u64, String>>> {
+    let stamp = stock_db_stamp(db_path);
+    let cache = STOCK_NAME_CACHE.get_or_init(|| Mutex::new(HashMap::new()));
+
+    if let Some(cached) = cache.lock().unwrap_or_else(|e| e.into_inner()).get(db_path) {
+        if cached.stamp == stamp {
+            debug!("Using cached stock names ({})", cached.names.len());
+            return Ok(Arc::clone(&cached.names));
+        }
+    }
+
+    let names = Arc::new(load_stock_names(db_path)?);
+    cache.lock().unwrap_or_else(|e| e.into_inner()).insert(
+        db_path.to_path_buf(),
+        CachedNames {
+            stamp,
+            names: Arc::clone(&names),
+        },
+    );
+    Ok(names)
+}
+
+/// DB 与 -wal 文件的修改时间（WAL 模式下写入只改 -wal）
+fn stock_db_stamp(db_path: &Path) -> (Option<SystemTime>, Option<SystemTime>) {
+    let mtime = |p: &Path| std::fs::metadata(p).and_then(|m| m.modified()).ok();
+    let mut wal = db_path.as_os_str().to_owned();
+    wal.push("-wal");
+    (mtime(db_path), mtime(Path::new(&wal)))
+}
+
+/// StockDB 被锁时的重试次数（之后回退到读取副本）
+const STOCK_DB_RETRIES: u32 = 3;
+
+/// StockDB 重试的初始退避时间（毫秒），每次翻倍
+const STOCK_DB_RETRY_BASE_MS: u64 = 50;
+
+/// 单次查询等待锁释放的时间（毫秒）
+const STOCK_DB_BUSY_TIMEOUT_MS: u64 = 100;
+
 /// 从 StockDB SQLite 读取 stock_id → 中文名 映射
+///
+/// 运行中的富途可能持有写锁（WAL），导致偶发 "database is locked"：
+/// 先带退避重试，仍被锁则将 DB（连同 -wal/-shm）复制到临时目录读取副本。
 fn load_stock_names(db_path: &Path) -> Result<HashMap<u64, String>> {
-    let conn = rusqlite::Connection::open_with_flags(
-        db_path,
-        rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY | rusqlite::OpenFlags::SQLITE_OPEN_NO_MUTEX,
-    )
-    .with_context(|| format!("Failed to open StockDB: {}", db_path.display()))?;
+    let mut delay_ms = STOCK_DB_RETRY_BASE_MS;
+    for attempt in 1..=STOCK_DB_RETRIES {
+        match query_stock_names(db_path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY) {
+            Ok(names) => {
+                info!("Loaded {} stock names from StockDB", names.len());
+                return Ok(names);
+            }
+            Err(e) if is_lock_error(&e) => {
+                debug!("StockDB locked (attempt {}/{}): {}", attempt, STOCK_DB_RETRIES, e);
+                if attempt < STOCK_DB_RETRIES {
+                    std::thread::sleep(std::time::Duration::from_millis(delay_ms));
+                    delay_ms = delay_ms.saturating_mul(2);
+                }
+            }
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to read StockDB: {}", db_path.display()));
+            }
+        }
+    }
+
+    warn!(
+        "StockDB still locked after {} attempts, reading from a copy",
+        STOCK_DB_RETRIES
+    );
+    let names = load_stock_names_from_copy(db_path)?;
+    info!("Loaded {} stock names from StockDB copy", names.len());
+    Ok(names)
+}
+
+/// 打开 StockDB 并查询所有名称
+fn query_stock_names(db_path: &Path, flags: rusqlite::OpenFlags) -> rusqlite::Result<HashMap<u64, String>> {
+    let conn = rusqlite::Connection::open_with_flags(db_path, flags | rusqlite::OpenFlags::SQLITE_OPEN_NO_MUTEX)?;
+    conn.busy_timeout(std::time::Duration::from_millis(STOCK_DB_BUSY_TIMEOUT_MS))?;
 
     let mut stmt = conn.prepare("SELECT ID, zh FROM Stock WHERE zh IS NOT NULL AND zh != ''")?;
     let mut names = HashMap::new();
@@ -178,10 +311,58 @@ fn load_stock_names(db_path: &Path) -> Result<HashMap<u64, String>> {
         names.insert(row.0, row.1);
     }
 
-    info!("Loaded {} stock names from StockDB", names.len());
     Ok(names)
 }
 
+/// 是否为锁冲突错误（SQLITE_BUSY / SQLITE_LOCKED）
+fn is_lock_error(e: &rusqlite::Error) -> bool {
+    matches!(
+        e,
+        rusqlite::Error::SqliteFailure(err, _)
+            if matches!(err.code, rusqlite::ErrorCode::DatabaseBusy | rusqlite::ErrorCode::DatabaseLocked)
+    )
+}
+
+/// 将 StockDB 及其 -wal/-shm 复制到临时目录后读取，读完删除副本
+fn load_stock_names_from_copy(db_path: &Path) -> Result<HashMap<u64, String>> {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    static COPY_SEQ: AtomicUsize = AtomicUsize::new(0);
+
+    let file_name = db_path
+        .file_name()
+        .with_context(|| format!("Invalid StockDB path: {}", db_path.display()))?;
+    let tmp_dir = std::env::temp_dir().join(format!(
+        "qtrade-stockdb-{}-{}",
+        std::process::id(),
+        COPY_SEQ.fetch_add(1, Ordering::Relaxed)
+    ));
+    std::fs::create_dir_all(&tmp_dir).with_context(|| format!("Failed to create {}", tmp_dir.display()))?;
+
+    let result = (|| {
+        let copy_path = tmp_dir.join(file_name);
+        std::fs::copy(db_path, &copy_path).with_context(|| format!("Failed to copy StockDB: {}", db_path.display()))?;
+        // WAL 模式下未 checkpoint 的数据在 -wal 中，需一并复制（文件名须与主库对应）
+        for suffix in ["-wal", "-shm"] {
+            let mut src = db_path.as_os_str().to_owned();
+            src.push(suffix);
+            let src = PathBuf::from(src);
+            if src.exists() {
+                let mut dst = copy_path.as_os_str().to_owned();
+                dst.push(suffix);
+                std::fs::copy(&src, PathBuf::from(dst)).with_context(|| format!("Failed to copy {}", src.display()))?;
+            }
+        }
+        // 副本归本进程所有，以读写方式打开以便 SQLite 回放 WAL
+        query_stock_names(&copy_path, rusqlite::OpenFlags::SQLITE_OPEN_READ_WRITE)
+            .with_context(|| format!("Failed to read StockDB copy: {}", copy_path.display()))
+    })();
+
+    if let Err(e) = std::fs::remove_dir_all(&tmp_dir) {
+        debug!("Failed to remove StockDB copy {}: {}", tmp_dir.display(), e);
+    }
+    result
+}
+
 /// 解析 plist 数据结构，提取自选股信息
 ///
 /// 富途 watchstockContainer.dat 实际结构:
@@ -314,11 +495,11 @@ fn parse_futu_stock_entry(dict: &plist::Dictionary, sort_index: usize) -> Option
     let prev_close_raw = extract_integer(dict, &["FLStockKeyLastClosePriceHighPrecision"]);
 
     let cached_price = price_raw.filter(|&p| p > 0).map(|p| p as f64 / PRICE_DIVISOR);
-    let _prev_close = prev_close_raw.filter(|&p| p > 0).map(|p| p as f64 / PRICE_DIVISOR);
+    let prev_close = prev_close_raw.filter(|&p| p > 0).map(|p| p as f64 / PRICE_DIVISOR);
 
     debug!(
-        "Stock: {} (ID={:?}, market={:?}, price={:?})",
-        code_str, stock_id, market, cached_price
+        "Stock: {} (ID={:?}, market={:?}, price={:?}, prev_close={:?})",
+        code_str, stock_id, market, cached_price, prev_close
     );
 
     Some(WatchlistEntry {
@@ -326,6 +507,7 @@ fn parse_futu_stock_entry(dict: &plist::Dictionary, sort_index: usize) -> Option
         stock_id: stock_id.unwrap_or(0),
         name: String::new(), // 名称后续从 StockDB 填充
         cached_price,
+        prev_close,
         sort_index,
     })
 }
@@ -480,6 +662,31 @@ mod tests {
         assert!((actual - 6.9581).abs() < 0.001);
     }
 
+    #[test]
+    fn test_parse_entry_with_price_and_prev_close() {
+        let mut dict = plist::Dictionary::new();
+        dict.insert("FLStockKeyCode".into(), plist::Value::String("00700".into()));
+        dict.insert("FLStockKeyID".into(), plist::Value::Integer(54047868453564_i64.into()));
+        dict.insert(
+            "FLStockKeyPriceHighPrecision".into(),
+            plist::Value::Integer(606000000000_i64.into()),
+        );
+        dict.insert(
+            "FLStockKeyLastClosePriceHighPrecision".into(),
+            plist::Value::Integer(600000000000_i64.into()),
+        );
+
+        let entry = parse_futu_stock_entry(&dict, 3).unwrap();
+        assert_eq!(entry.code.market, Market::HK);
+        assert_eq!(entry.sort_index, 3);
+        assert!((entry.cached_price.unwrap() - 606.0).abs() < 1e-9);
+        assert!((entry.prev_close.unwrap() - 600.0).abs() < 1e-9);
+
+        // 种子快照带昨收 → 可显示涨跌幅
+        let q = entry.cached_quote();
+        assert!((q.change_pct - 1.0).abs() < 1e-9);
+    }
+
     #[test]
     fn test_infer_market_from_id_and_code() {
         // A 股沪市指数 — ID 以 1 开头
@@ -557,4 +764,164 @@ mod tests {
         assert_eq!(entries[1].code.market, Market::SH);
         assert!(entries[1].cached_price.is_some());
     }
+
+    /// 构造只含一个分组的自选股 plist：(code, stock_id)
+    fn make_watchlist_plist(stocks: &[(&str, i64)]) -> plist::Value {
+        let stocks = stocks
+            .iter()
+            .map(|&(code, id)| {
+                let mut stock = plist::Dictionary::new();
+                stock.insert("FLStockKeyCode".into(), plist::Value::String(code.into()));
+                stock.insert("FLStockKeyID".into(), plist::Value::Integer(id.into()));
+                plist::Value::Dictionary(stock)
+            })
+            .collect();
+        let mut group = plist::Dictionary::new();
+        group.insert("FLWatchGroupKeyName".into(), plist::Value::String("全部".into()));
+        group.insert("FLWatchGroupKeyStocks".into(), plist::Value::Array(stocks));
+        let mut root = plist::Dictionary::new();
+        root.insert(
+            "ReservedGroups".into(),
+            plist::Value::Array(vec![plist::Value::Dictionary(group)]),
+        );
+        plist::Value::Dictionary(root)
+    }
+
+    #[test]
+    fn test_load_watchlist_merges_multiple_users() {
+        let base = std::env::temp_dir().join(format!("qtrade-test-users-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&base);
+        for (uid, stocks) in [
+            ("1001", &[("00700", 54047868453564_i64), ("09988", 9988)][..]),
+            ("1002", &[("600519", 1600519), ("00700", 54047868453564)][..]),
+        ] {
+            std::fs::create_dir_all(base.join(uid)).unwrap();
+            make_watchlist_plist(stocks)
+                .to_file_binary(base.join(uid).join(WATCHLIST_FILENAME))
+                .unwrap();
+        }
+        // 非数字目录不是用户目录
+        std::fs::create_dir_all(base.join("StockDB")).unwrap();
+        let data_path = base.to_str();
+        let ids = |v: &[&str]| v.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+        // 指定多个用户：按配置顺序合并，00700 去重
+        let entries = load_watchlist(data_path, &ids(&["1002", "1001"])).unwrap();
+        let codes: Vec<&str> = entries.iter().map(|e| e.code.code.as_str()).collect();
+        assert_eq!(codes, ["600519", "00700", "09988"]);
+        assert_eq!(entries.iter().map(|e| e.sort_index).collect::<Vec<_>>(), [0, 1, 2]);
+
+        // "all" 读取所有用户目录
+        assert_eq!(find_user_dirs(&base, &ids(&["all"])).unwrap().len(), 2);
+        assert_eq!(load_watchlist(data_path, &ids(&["all"])).unwrap().len(), 3);
+
+        // 默认只读单个用户
+        assert_eq!(load_watchlist(data_path, &[]).unwrap().len(), 2);
+        assert!(load_watchlist(data_path, &ids(&["1003"])).is_err());
+
+        std::fs::remove_dir_all(base).unwrap();
+    }
+
+    /// 仓库内的富途数据目录样例（`10001/watchstockContainer.dat` + `StockDB/appdatav82.db`）
+    const FIXTURE_DATA_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/futu_data");
+
+    #[test]
+    fn test_load_watchlist_from_fixture() {
+        let entries = load_watchlist(Some(FIXTURE_DATA_PATH), &[]).unwrap();
+
+        // 取 "全部" 分组（ID=1000）而非排在前面的 "港股" 分组
+        let codes: Vec<String> = entries.iter().map(|e| e.code.display_code()).collect();
+        assert_eq!(
+            codes,
+            ["HK.00700", "SH.600519", "SZ.300750", "US.AAPL", "HK.800000", "HK.09988"]
+        );
+        assert_eq!(
+            entries.iter().map(|e| e.sort_index).collect::<Vec<_>>(),
+            [0, 1, 2, 3, 4, 5]
+        );
+
+        // 名称按 FLStockKeyID 关联 StockDB；空名称不覆盖
+        let names: Vec<&str> = entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["腾讯控股", "贵州茅台", "宁德时代", "苹果", "恒生指数", ""]);
+
+        let tencent = &entries[0];
+        assert_eq!(tencent.stock_id, 54047868453564);
+        assert_eq!(tencent.cached_price, Some(380.2));
+        assert_eq!(tencent.prev_close, Some(378.0));
+        assert_eq!(entries[1].cached_price, Some(1680.5));
+        assert_eq!(entries[2].cached_price, Some(190.12));
+        assert_eq!(entries[3].prev_close, Some(189.25));
+        assert!(entries[4].code.is_index());
+        // 无价格字段 → None
+        assert_eq!(entries[5].cached_price, None);
+        assert_eq!(entries[5].prev_close, None);
+
+        // 指定用户目录与自动选择结果一致
+        let by_id = load_watchlist(Some(FIXTURE_DATA_PATH), &["10001".to_string()]).unwrap();
+        assert_eq!(by_id.len(), entries.len());
+    }
+
+    /// 在临时目录创建含 Stock 表的测试库
+    fn make_stock_db(tag: &str) -> (PathBuf, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("qtrade-test-stockdb-{}-{}", tag, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let db_path = dir.join("appdatav82.db");
+        let _ = std::fs::remove_file(&db_path);
+        let conn = rusqlite::Connection::open(&db_path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE Stock (ID INTEGER, zh TEXT);
+             INSERT INTO Stock VALUES (54047868453564, '腾讯控股');
+             INSERT INTO Stock VALUES (1, '');",
+        )
+        .unwrap();
+        (dir, db_path)
+    }
+
+    #[test]
+    fn test_load_stock_names() {
+        let (dir, db_path) = make_stock_db("plain");
+        let names = load_stock_names(&db_path).unwrap();
+        assert_eq!(names.len(), 1);
+        assert_eq!(names.get(&54047868453564).map(String::as_str), Some("腾讯控股"));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_load_stock_names_falls_back_to_copy_when_locked() {
+        let (dir, db_path) = make_stock_db("locked");
+
+        // 另一连接持有排他锁，直接读取会报 database is locked
+        let locker = rusqlite::Connection::open(&db_path).unwrap();
+        locker.execute_batch("BEGIN EXCLUSIVE").unwrap();
+        let err = query_stock_names(&db_path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY).unwrap_err();
+        assert!(is_lock_error(&err), "{:?}", err);
+
+        let names = load_stock_names(&db_path).unwrap();
+        assert_eq!(names.get(&54047868453564).map(String::as_str), Some("腾讯控股"));
+
+        locker.execute_batch("ROLLBACK").unwrap();
+        drop(locker);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_stock_names_cache_reloads_on_change() {
+        let (dir, db_path) = make_stock_db("cache");
+
+        let first = load_stock_names_cached(&db_path).unwrap();
+        let second = load_stock_names_cached(&db_path).unwrap();
+        // DB 未变化 → 复用同一份缓存
+        assert!(Arc::ptr_eq(&first, &second));
+
+        // 修改 DB 后重新读取（保证修改时间确实变化）
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        let conn = rusqlite::Connection::open(&db_path).unwrap();
+        conn.execute("INSERT INTO Stock VALUES (2, '贵州茅台')", []).unwrap();
+        drop(conn);
+        let third = load_stock_names_cached(&db_path).unwrap();
+        assert!(!Arc::ptr_eq(&first, &third));
+        assert_eq!(third.get(&2).map(String::as_str), Some("贵州茅台"));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
 }
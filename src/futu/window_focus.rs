@@ -0,0 +1,52 @@
+//! 应用前台切换（SetFrontProcess / NSWorkspace）
+//!
+//! 不依赖 AX 写操作，默认构建可用：OCR 截图前临时激活被遮挡的富途窗口，
+//! 交易执行器（`trading` feature）激活财富通窗口。
+
+use tracing::debug;
+
+/// 激活应用到前台（处理跨桌面/Space 场景）
+pub fn activate_app(pid: i32) {
+    #[repr(C)]
+    #[derive(Copy, Clone)]
+    struct ProcessSerialNumber {
+        high: u32,
+        low: u32,
+    }
+
+    extern "C" {
+        fn GetProcessForPID(pid: i32, psn: *mut ProcessSerialNumber) -> i32;
+        fn SetFrontProcess(psn: *const ProcessSerialNumber) -> i32;
+    }
+
+    unsafe {
+        let mut psn = ProcessSerialNumber { high: 0, low: 0 };
+        if GetProcessForPID(pid, &mut psn) == 0 {
+            let status = SetFrontProcess(&psn);
+            if status != 0 {
+                debug!("SetFrontProcess failed: {}", status);
+            }
+        } else {
+            debug!("GetProcessForPID({}) failed", pid);
+        }
+    }
+    std::thread::sleep(std::time::Duration::from_millis(200));
+}
+
+/// 当前前台 App 的 PID
+pub fn frontmost_app_pid() -> Option<i32> {
+    let workspace = objc2_app_kit::NSWorkspace::sharedWorkspace();
+    workspace.frontmostApplication().map(|app| app.processIdentifier())
+}
+
+/// 将应用激活到前台，返回此前的前台 App PID（供之后恢复焦点）
+///
+/// 应用已在前台时不做任何操作，返回 None。
+pub fn bring_to_front(pid: i32) -> Option<i32> {
+    let previous = frontmost_app_pid();
+    if previous == Some(pid) {
+        return None;
+    }
+    activate_app(pid);
+    previous
+}
@@ -1,14 +1,25 @@
+//! qtrade — 量化交易盯盘系统
+//!
+//! ## Cargo features
+//!
+//! - `trading`（默认关闭）：编译交易执行器（`trading` 模块，AX 驱动财富通下单）、
+//!   MCP 交易服务器（`mcp-server` 子命令）、`test-trade` 与 `orders` 子命令。
+//!   仅盯盘时无需开启，构建更小，也不会包含任何下单代码路径。
+//!   启用：`cargo build --features trading`
+
 mod alerts;
 mod analysis;
 mod config;
 mod data;
 mod futu;
+#[cfg(feature = "trading")]
 mod mcp;
 mod models;
+#[cfg(feature = "trading")]
 mod trading;
 mod ui;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use clap::{Parser, Subcommand};
 use crossterm::event::EventStream;
 use futures::StreamExt;
@@ -16,17 +27,17 @@ use std::collections::HashSet;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::{mpsc, watch, Mutex};
-use tracing::{info, warn};
+use tracing::{debug, error, info, warn};
 
 use crate::alerts::manager::AlertManager;
-use crate::alerts::notify::Notifier;
-use crate::alerts::rules::ChangeThresholdRule;
+use crate::alerts::notify;
+use crate::alerts::rules::RuleRegistry;
 use crate::analysis::daily::DailyAnalysisEngine;
 use crate::analysis::engine::AnalysisEngine;
 use crate::config::AppConfig;
 use crate::data::provider::DataProviderKind;
 use crate::models::{QuoteSnapshot, StockCode};
-use crate::ui::dashboard::{DashboardState, MAX_RECENT_ALERTS};
+use crate::ui::dashboard::{DashboardState, InputAction, MAX_RECENT_ALERTS};
 
 #[derive(Parser)]
 #[command(name = "qtrade", about = "量化交易盯盘系统")]
@@ -43,17 +54,54 @@ struct Cli {
 enum Commands {
     /// 启动盯盘系统
     Start,
+    /// 演示模式：合成行情驱动完整盯盘链路（无需富途）
+    Demo,
     /// 显示自选股列表
     Watchlist,
     /// 检查 Accessibility 权限并打印 App 元素树（调试用）
     Debug,
     /// 测试 FutuOpenD 连接并获取行情
-    TestApi,
+    TestApi {
+        /// 结束时打印缓存的原始响应（JSON / 十六进制），排查解码问题
+        #[arg(long)]
+        dump_raw: bool,
+    },
     /// 测试窗口截图 + Vision OCR 识别效果
     TestOcr,
+    /// 查看最近触发的提醒（读取 alerts.log_path）
+    AlertsHistory {
+        /// 显示条数
+        #[arg(short = 'n', long, default_value_t = 20)]
+        count: usize,
+    },
+    /// 离线导出日K缓存的日线信号为 CSV（无需 FutuOpenD）
+    ExportSignals {
+        /// 输出文件
+        #[arg(long, default_value = "signals.csv")]
+        out: std::path::PathBuf,
+    },
+    /// 打印生效配置（已合并默认值，敏感字段脱敏）
+    ShowConfig {
+        /// 以 JSON 输出（默认 TOML）
+        #[arg(long)]
+        json: bool,
+    },
     /// 启动 MCP 交易服务器
-    McpServer,
+    #[cfg(feature = "trading")]
+    McpServer {
+        /// 模拟下单：验价后取消确认弹窗，不真正提交（覆盖 mcp.dry_run）
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// 查看最近的委托记录（读取 mcp.order_log_path）
+    #[cfg(feature = "trading")]
+    Orders {
+        /// 显示条数
+        #[arg(short = 'n', long, default_value_t = 20)]
+        count: usize,
+    },
     /// 测试港股交易流程（绕过 MCP，直接调用执行器）
+    #[cfg(feature = "trading")]
     TestTrade {
         /// 股票代码，如 00700
         #[arg(long)]
@@ -67,6 +115,12 @@ enum Commands {
         /// 交易方向：buy 或 sell
         #[arg(long)]
         side: String,
+        /// 模拟下单：验价后取消确认弹窗，不真正提交
+        #[arg(long)]
+        dry_run: bool,
+        /// 改走 FutuOpenD 交易协议，向该账户 ID 下单（不操作财富通界面；真实账户用 futu.trade_pwd_md5 解锁）
+        #[arg(long, conflicts_with = "dry_run")]
+        opend_acc: Option<u64>,
     },
 }
 
@@ -79,41 +133,82 @@ async fn main() -> Result<()> {
         Some(path) => AppConfig::load(std::path::Path::new(path))?,
         None => AppConfig::load_or_default(),
     };
+    config.validate()?;
 
-    // 初始化日志
-    let env_filter = tracing_subscriber::EnvFilter::try_from_default_env()
-        .unwrap_or_else(|_| config.general.log_level.parse().unwrap_or_default());
+    // 应用时区（未配置则使用系统本地时区）
+    if let Some(tz) = config.general.parse_timezone()? {
+        models::set_app_timezone(tz);
+    }
+    // 半日市日历（交易时段判断按配置提前收盘）
+    models::set_half_days(config.general.parse_half_days()?);
+
+    // 初始化日志：RUST_LOG 优先，否则使用 general.log_level（支持 EnvFilter 指令，可按模块设置级别）
+    let env_filter = match tracing_subscriber::EnvFilter::try_from_default_env() {
+        Ok(filter) => filter,
+        Err(_) => tracing_subscriber::EnvFilter::try_new(&config.general.log_level).map_err(|e| {
+            anyhow::anyhow!(
+                "无效的日志级别 general.log_level = {:?}: {}",
+                config.general.log_level,
+                e
+            )
+        })?,
+    };
 
     let command = cli.command.unwrap_or(Commands::Start);
 
-    let is_tui = matches!(command, Commands::Start);
+    let json_logs = config.general.json_logs()?;
+    let is_tui = matches!(command, Commands::Start | Commands::Demo);
     if is_tui {
         // TUI 模式：日志写文件，避免干扰终端界面
         let log_file = std::fs::File::create("qtrade.log").expect("Failed to create qtrade.log");
-        tracing_subscriber::fmt()
+        let builder = tracing_subscriber::fmt()
             .with_env_filter(env_filter)
             .with_writer(log_file)
-            .with_ansi(false)
-            .init();
+            .with_ansi(false);
+        if json_logs {
+            builder.json().init();
+        } else {
+            builder.init();
+        }
     } else {
         // CLI 模式：日志输出到终端
-        tracing_subscriber::fmt().with_env_filter(env_filter).init();
+        let builder = tracing_subscriber::fmt().with_env_filter(env_filter);
+        if json_logs {
+            builder.json().init();
+        } else {
+            builder.init();
+        }
     }
 
     match command {
         Commands::Start => cmd_start(config).await,
+        Commands::Demo => cmd_demo(config).await,
         Commands::Watchlist => cmd_watchlist(config),
         Commands::Debug => cmd_debug(config),
-        Commands::TestApi => cmd_test_api(config).await,
+        Commands::TestApi { dump_raw } => cmd_test_api(config, dump_raw).await,
         Commands::TestOcr => cmd_test_ocr(config).await,
-        Commands::McpServer => cmd_mcp_server(config).await,
-        Commands::TestTrade { code, price, qty, side } => cmd_test_trade(code, price, qty, side).await,
+        Commands::AlertsHistory { count } => cmd_alerts_history(config, count),
+        Commands::ExportSignals { out } => cmd_export_signals(config, &out),
+        Commands::ShowConfig { json } => cmd_show_config(config, json),
+        #[cfg(feature = "trading")]
+        Commands::McpServer { dry_run } => cmd_mcp_server(config, dry_run).await,
+        #[cfg(feature = "trading")]
+        Commands::Orders { count } => cmd_orders(config, count),
+        #[cfg(feature = "trading")]
+        Commands::TestTrade {
+            code,
+            price,
+            qty,
+            side,
+            dry_run,
+            opend_acc,
+        } => cmd_test_trade(config, code, price, qty, side, dry_run, opend_acc).await,
     }
 }
 
 /// 显示自选股列表
 fn cmd_watchlist(config: AppConfig) -> Result<()> {
-    let entries = futu::watchlist::load_watchlist(config.futu.data_path.as_deref(), config.futu.user_id.as_deref())?;
+    let entries = futu::watchlist::load_watchlist(config.futu.data_path.as_deref(), &config.futu.selected_user_ids())?;
 
     println!("自选股列表 ({} 只):", entries.len());
     println!("{:-<70}", "");
@@ -137,6 +232,79 @@ fn cmd_watchlist(config: AppConfig) -> Result<()> {
     Ok(())
 }
 
+/// 打印最近 `count` 条提醒日志
+fn cmd_alerts_history(config: AppConfig, count: usize) -> Result<()> {
+    let Some(path) = config.alerts.log_file() else {
+        println!("未启用提醒日志（alerts.log_path 为空）");
+        return Ok(());
+    };
+    let Some(events) = alerts::log::read_last(&path, count)? else {
+        println!("暂无提醒记录（{} 不存在）", path.display());
+        return Ok(());
+    };
+
+    println!("最近 {} 条提醒 ({}):", events.len(), path.display());
+    println!("{:-<90}", "");
+    for event in &events {
+        println!(
+            "{} [{}] {:<12} {:<10} {} | {}",
+            models::to_app_time(&event.triggered_at).format("%Y-%m-%d %H:%M:%S"),
+            notify::severity_text(event.severity),
+            event.code.display_code(),
+            event.name,
+            event.rule_name,
+            event.message
+        );
+    }
+
+    Ok(())
+}
+
+/// 离线导出日线信号：加载日K缓存，按当前分析参数重算信号后写 CSV
+fn cmd_export_signals(config: AppConfig, out: &std::path::Path) -> Result<()> {
+    let mut daily_engine = DailyAnalysisEngine::new(&config.analysis);
+    daily_engine.load_cache();
+    if daily_engine.stock_count() == 0 {
+        println!(
+            "日K缓存为空（{}），请先运行 qtrade start 拉取日K",
+            DailyAnalysisEngine::cache_path().display()
+        );
+        return Ok(());
+    }
+
+    // 缓存只有代码，名称取自自选股；读取失败时名称留空
+    let names =
+        match futu::watchlist::load_watchlist(config.futu.data_path.as_deref(), &config.futu.selected_user_ids()) {
+            Ok(entries) => entries.into_iter().map(|e| (e.code, e.name)).collect(),
+            Err(e) => {
+                warn!("读取自选股失败，名称列留空: {}", e);
+                std::collections::HashMap::new()
+            }
+        };
+
+    let csv = analysis::journal::daily_signals_csv(&daily_engine, &names);
+    std::fs::write(out, &csv).with_context(|| format!("写入 {} 失败", out.display()))?;
+    println!(
+        "已导出 {} 只股票的 {} 条日线信号 → {}",
+        daily_engine.stock_count(),
+        csv.lines().count() - 1,
+        out.display()
+    );
+    Ok(())
+}
+
+/// 打印生效配置（已合并默认值，敏感字段脱敏）
+fn cmd_show_config(config: AppConfig, json: bool) -> Result<()> {
+    let config = config.redacted();
+    let output = if json {
+        serde_json::to_string_pretty(&config)?
+    } else {
+        toml::to_string_pretty(&config)?
+    };
+    println!("{}", output);
+    Ok(())
+}
+
 /// 调试：检查 AX 权限并打印元素树
 fn cmd_debug(_config: AppConfig) -> Result<()> {
     use crate::futu::accessibility::AccessibilityReader;
@@ -162,11 +330,11 @@ fn cmd_debug(_config: AppConfig) -> Result<()> {
     Ok(())
 }
 
-/// 过滤 watchlist entries：去掉 800xxx 内部索引和 Unknown 市场
-fn filter_stock_codes(watchlist: &[models::WatchlistEntry]) -> Vec<StockCode> {
+/// 过滤 watchlist entries：去掉 Unknown 市场及按 `futu.exclude_*` 配置排除的代码
+fn filter_stock_codes(watchlist: &[models::WatchlistEntry], futu: &config::FutuConfig) -> Vec<StockCode> {
     watchlist
         .iter()
-        .filter(|e| !e.code.code.starts_with("800") && e.code.market != crate::models::Market::Unknown)
+        .filter(|e| !futu.is_excluded(&e.code))
         .map(|e| e.code.clone())
         .collect()
 }
@@ -176,7 +344,8 @@ async fn cmd_start(config: AppConfig) -> Result<()> {
     info!("qtrade 量化盯盘系统启动");
 
     // 读取自选股
-    let watchlist = futu::watchlist::load_watchlist(config.futu.data_path.as_deref(), config.futu.user_id.as_deref())?;
+    let watchlist =
+        futu::watchlist::load_watchlist(config.futu.data_path.as_deref(), &config.futu.selected_user_ids())?;
 
     if watchlist.is_empty() {
         anyhow::bail!("自选股列表为空");
@@ -184,20 +353,58 @@ async fn cmd_start(config: AppConfig) -> Result<()> {
 
     info!("已加载 {} 只自选股", watchlist.len());
 
-    let stock_codes = filter_stock_codes(&watchlist);
-    info!("可订阅股票: {} 只（已过滤内部索引代码）", stock_codes.len());
+    // 检测 plist 路径（用于 mtime 监测，多用户时每个用户一个）
+    let plist_paths =
+        futu::watchlist::detect_plist_paths(config.futu.data_path.as_deref(), &config.futu.selected_user_ids())?;
+    for path in &plist_paths {
+        info!("Plist path for monitoring: {}", path.display());
+    }
+
+    // 创建数据提供者
+    let provider = DataProviderKind::from_config(&config);
+
+    run_monitor(config, watchlist, provider, plist_paths).await
+}
+
+/// 演示模式：合成行情驱动完整盯盘链路（无需富途 App / FutuOpenD）
+async fn cmd_demo(mut config: AppConfig) -> Result<()> {
+    info!("qtrade 演示模式启动（合成行情）");
+
+    // 演示数据不拉取日K、不推送 webhook / telegram / 邮件、不写提醒日志、不导出信号
+    config.analysis.daily_kline_enabled = false;
+    config.alerts.webhook_url = None;
+    config.alerts.channels.clear();
+    config.alerts.log_path.clear();
+    config.export.signals_on_exit = false;
+    config.export.tick_log_dir.clear();
+
+    let watchlist = data::demo::demo_watchlist();
+    info!("已加载 {} 只演示股票", watchlist.len());
+
+    let provider = DataProviderKind::Demo(data::demo::DemoProvider::new(&watchlist));
+
+    run_monitor(config, watchlist, provider, Vec::new()).await
+}
 
-    // 检测 plist 路径（用于 mtime 监测）
-    let plist_path =
-        futu::watchlist::detect_plist_path(config.futu.data_path.as_deref(), config.futu.user_id.as_deref())?;
-    info!("Plist path for monitoring: {}", plist_path.display());
+/// 盯盘主流程：数据采集 → 分析 → 提醒 → 仪表盘
+///
+/// `plist_paths` 为空时不监测自选股变更（演示模式）
+async fn run_monitor(
+    config: AppConfig,
+    watchlist: Vec<models::WatchlistEntry>,
+    mut provider: DataProviderKind,
+    plist_paths: Vec<std::path::PathBuf>,
+) -> Result<()> {
+    let stock_codes = filter_stock_codes(&watchlist, &config.futu);
+    info!(
+        "可订阅股票: {} 只（已过滤 {} 只排除代码）",
+        stock_codes.len(),
+        watchlist.len().saturating_sub(stock_codes.len())
+    );
 
     // 创建 watch channel 广播 watchlist 变化
     let (watch_tx, watch_rx) = watch::channel(stock_codes.clone());
 
-    // 创建数据提供者
-    let mut provider = DataProviderKind::from_config(&config);
-
     // 尝试连接
     match provider.connect().await {
         Ok(()) => {
@@ -216,7 +423,7 @@ async fn cmd_start(config: AppConfig) -> Result<()> {
     }
 
     // 创建日线分析引擎，加载缓存
-    let daily_engine = Arc::new(Mutex::new(DailyAnalysisEngine::new()));
+    let daily_engine = Arc::new(Mutex::new(DailyAnalysisEngine::new(&config.analysis)));
     {
         let mut de = daily_engine.lock().await;
         de.load_cache();
@@ -229,47 +436,80 @@ async fn cmd_start(config: AppConfig) -> Result<()> {
     let engine = Arc::new(Mutex::new(AnalysisEngine::new(&config.analysis)));
 
     // 创建提醒管理器
-    let notifier = Notifier::new(config.alerts.webhook_url.clone());
-    let mut alert_manager = AlertManager::new(notifier);
-    if config.alerts.enabled {
-        for threshold in config.alerts.effective_thresholds() {
-            alert_manager.add_rule(Box::new(ChangeThresholdRule::new(threshold)));
-        }
-    }
+    let mut alert_manager = AlertManager::new(notify::build_channels(&config.alerts)?);
+    alert_manager.set_cooldown(Duration::from_secs(config.alerts.cooldown_secs));
+    alert_manager.set_log_path(config.alerts.log_file());
+    alert_manager.add_rules(RuleRegistry::with_builtin().build(&config.alerts));
     let alert_manager = Arc::new(Mutex::new(alert_manager));
 
     // 数据通道
-    let (quote_tx, mut quote_rx) = mpsc::channel::<Vec<QuoteSnapshot>>(32);
+    let (quote_tx, quote_rx) = mpsc::channel::<Vec<QuoteSnapshot>>(32);
 
-    // 仪表盘状态（每只股票最大日线信号数量与 daily_kline_days 一致）
-    let max_daily_signals = config.analysis.daily_kline_days as usize;
+    // 仪表盘状态（每只股票最大日线信号数量，未配置时与 daily_kline_days 一致）
+    let max_daily_signals = match config.ui.max_daily_signals_per_stock {
+        0 => config.analysis.daily_kline_days as usize,
+        n => n,
+    };
     let dash_state = Arc::new(Mutex::new(DashboardState::new(max_daily_signals)));
     {
         let mut state = dash_state.lock().await;
         state.source_name = provider.name().to_string();
-        state.source_connected = provider.is_connected();
-
-        // 初始数据：用缓存价格填充
-        let initial_quotes: Vec<QuoteSnapshot> = watchlist
+        state.connection = provider.connection_state();
+        state.subscribed_markets = provider.subscribed_markets();
+        state.source_latency = config.data_source.source_latency();
+        state.stale_after = match config.data_source.stale_after_secs {
+            0 => None,
+            secs => Some(Duration::from_secs(secs)),
+        };
+        state.show_extended_hours = config.ui.show_extended_hours;
+        state.change_pct_decimals = config.ui.change_pct_decimals;
+        state.columns = config
+            .ui
+            .columns
             .iter()
-            .map(|e| {
-                let mut q = QuoteSnapshot::empty(e.code.clone(), e.name.clone());
-                if let Some(price) = e.cached_price {
-                    q.last_price = price;
-                }
-                q
-            })
+            .filter_map(|c| ui::dashboard::Column::from_config(c))
             .collect();
+        state.follow_mode = ui::dashboard::FollowMode::from_config(&config.ui.follow_action);
+        state.follow_pause = Duration::from_secs(config.ui.follow_pause_secs);
+
+        // 初始数据：用缓存价格 + 昨收填充（实时数据到达前即可显示涨跌幅）
+        let initial_quotes: Vec<QuoteSnapshot> = watchlist.iter().map(|e| e.cached_quote()).collect();
         state.update_quotes(initial_quotes);
 
+        // 读回显示窗口内的 tick 信号日志（跨午夜时含前一天的文件），恢复信号
+        if let Some(dir) = config
+            .export
+            .tick_log_dir()
+            .filter(|_| config.export.reload_tick_signals)
+        {
+            let since =
+                chrono::Local::now() - chrono::Duration::minutes(config.analysis.tick_signal_display_minutes as i64);
+            match analysis::tick_log::read_since(&dir, since) {
+                Ok(entries) => {
+                    if !entries.is_empty() {
+                        info!("Reloaded {} tick signals from {}", entries.len(), dir.display());
+                    }
+                    for e in entries {
+                        let sigs = state.tick_signals.entry(e.code).or_default();
+                        let disc = std::mem::discriminant(&e.signal);
+                        sigs.retain(|(existing, _)| std::mem::discriminant(existing) != disc);
+                        sigs.push((e.signal, e.timestamp));
+                    }
+                }
+                Err(e) => warn!("读取 tick 信号日志失败: {}", e),
+            }
+        }
+
         // 如果有缓存，立即填充日线数据 + 注入 ADV
         {
             let de = daily_engine.lock().await;
             if de.stock_count() > 0 {
                 state.daily_indicators = de.get_indicators().clone();
-                state.daily_signals = de.get_signals().clone();
+                state.set_daily_signals(de.get_signals().clone());
+                state.update_daily_closes(&de);
                 let sig_count: usize = state.daily_signals.values().map(|v| v.len()).sum();
                 state.daily_kline_status = format!("日K:{}只 信号:{} (缓存)", de.stock_count(), sig_count);
+                state.record_daily_signals();
 
                 // 从缓存计算 ADV 并注入 tick 引擎
                 let adv = de.compute_adv();
@@ -280,173 +520,152 @@ async fn cmd_start(config: AppConfig) -> Result<()> {
     }
 
     // Plist 监测任务（3s 轮询 mtime）
-    let monitor_plist_path = plist_path.clone();
-    let monitor_config_data_path = config.futu.data_path.clone();
-    let monitor_config_user_id = config.futu.user_id.clone();
-    let monitor_engine = engine.clone();
-    let monitor_daily_engine = daily_engine.clone();
-    let monitor_alert_mgr = alert_manager.clone();
-    let monitor_dash = dash_state.clone();
-    let monitor_watch_tx = watch_tx.clone();
-    let monitor_handle = tokio::spawn(async move {
-        let mut last_mtime = monitor_plist_path
-            .metadata()
-            .and_then(|m| m.modified())
-            .unwrap_or(std::time::UNIX_EPOCH);
-
-        loop {
-            tokio::time::sleep(Duration::from_secs(3)).await;
-
-            let current_mtime = match monitor_plist_path.metadata().and_then(|m| m.modified()) {
-                Ok(mt) => mt,
-                Err(_) => continue,
-            };
-
-            if current_mtime == last_mtime {
-                continue;
-            }
-            last_mtime = current_mtime;
-            info!("Plist mtime changed, reloading watchlist...");
-
-            // 重新加载 watchlist
-            let new_watchlist = match futu::watchlist::load_watchlist(
-                monitor_config_data_path.as_deref(),
-                monitor_config_user_id.as_deref(),
-            ) {
-                Ok(w) => w,
-                Err(e) => {
-                    warn!("Failed to reload watchlist: {}", e);
-                    continue;
-                }
+    let monitor_handle = if !plist_paths.is_empty() {
+        let monitor_config_data_path = config.futu.data_path.clone();
+        let monitor_config_user_ids = config.futu.selected_user_ids();
+        let monitor_cached_fallback = config.futu.cached_price_fallback;
+        let monitor_futu_config = config.futu.clone();
+        let monitor_engine = engine.clone();
+        let monitor_daily_engine = daily_engine.clone();
+        let monitor_alert_mgr = alert_manager.clone();
+        let monitor_dash = dash_state.clone();
+        let monitor_watch_tx = watch_tx.clone();
+        Some(tokio::spawn(async move {
+            // 任一用户的 plist 变化都重新加载合并后的 watchlist
+            let plist_mtimes = || -> Option<Vec<std::time::SystemTime>> {
+                plist_paths
+                    .iter()
+                    .map(|p| p.metadata().and_then(|m| m.modified()).ok())
+                    .collect()
             };
+            let mut last_mtimes = plist_mtimes().unwrap_or_default();
 
-            let new_codes = filter_stock_codes(&new_watchlist);
-            let old_codes: Vec<StockCode> = monitor_watch_tx.borrow().clone();
-
-            let old_set: HashSet<&StockCode> = old_codes.iter().collect();
-            let new_set: HashSet<&StockCode> = new_codes.iter().collect();
-
-            let added: Vec<&StockCode> = new_set.difference(&old_set).copied().collect();
-            let removed: Vec<&StockCode> = old_set.difference(&new_set).copied().collect();
-
-            if added.is_empty() && removed.is_empty() {
-                info!("Watchlist codes unchanged after reload");
-                continue;
-            }
+            loop {
+                tokio::time::sleep(Duration::from_secs(3)).await;
 
-            info!("Watchlist changed: +{} added, -{} removed", added.len(), removed.len());
+                let current_mtimes = match plist_mtimes() {
+                    Some(mt) => mt,
+                    None => continue,
+                };
 
-            // 处理删除
-            if !removed.is_empty() {
-                let removed_codes: Vec<StockCode> = removed.iter().map(|c| (*c).clone()).collect();
-                // 清理 tick 分析引擎
-                {
-                    let mut eng = monitor_engine.lock().await;
-                    for code in &removed_codes {
-                        eng.remove_stock(code);
-                    }
-                }
-                // 清理日线分析引擎
-                {
-                    let mut de = monitor_daily_engine.lock().await;
-                    de.remove_stocks(&removed_codes);
+                if current_mtimes == last_mtimes {
+                    continue;
                 }
-                // 清理提醒管理器
-                {
-                    let mut amgr = monitor_alert_mgr.lock().await;
-                    for code in &removed_codes {
-                        amgr.remove_stock(code);
+                last_mtimes = current_mtimes;
+                info!("Plist mtime changed, reloading watchlist...");
+
+                // 重新加载 watchlist
+                let new_watchlist = match futu::watchlist::load_watchlist(
+                    monitor_config_data_path.as_deref(),
+                    &monitor_config_user_ids,
+                ) {
+                    Ok(w) => w,
+                    Err(e) => {
+                        warn!("Failed to reload watchlist: {}", e);
+                        continue;
+                    }
+                };
+
+                // 缓存价格兜底：无实时数据的股票随 plist 刷新
+                if monitor_cached_fallback {
+                    let updated = monitor_dash.lock().await.apply_cached_prices(&new_watchlist);
+                    if updated > 0 {
+                        info!("Refreshed {} stocks from plist cached prices", updated);
                     }
                 }
-                for code in &removed_codes {
-                    info!("Removed stock: {}", code.display_code());
-                }
-            }
 
-            // 同步 dashboard
-            {
-                let filtered_entries: Vec<_> = new_watchlist
-                    .iter()
-                    .filter(|e| !e.code.code.starts_with("800") && e.code.market != crate::models::Market::Unknown)
-                    .cloned()
-                    .collect();
-                let mut state = monitor_dash.lock().await;
-                state.sync_watchlist(&new_codes, &filtered_entries);
-            }
+                let new_codes = filter_stock_codes(&new_watchlist, &monitor_futu_config);
+                let old_codes: Vec<StockCode> = monitor_watch_tx.borrow().clone();
 
-            if !added.is_empty() {
-                for code in &added {
-                    info!("Added stock: {}", code.display_code());
-                }
-            }
+                let old_set: HashSet<&StockCode> = old_codes.iter().collect();
+                let new_set: HashSet<&StockCode> = new_codes.iter().collect();
 
-            // 广播新的 stock_codes
-            let _ = monitor_watch_tx.send(new_codes);
-        }
-    });
+                let added: Vec<&StockCode> = new_set.difference(&old_set).copied().collect();
+                let removed: Vec<&StockCode> = old_set.difference(&new_set).copied().collect();
 
-    // 数据采集任务（使用 watch channel 感知 watchlist 变化）
-    let refresh_interval = Duration::from_secs(config.data_source.refresh_interval_secs);
-    let dash_for_fetch = dash_state.clone();
-    let mut watch_rx_fetch = watch_rx.clone();
-    let fetch_handle = tokio::spawn(async move {
-        let mut interval = tokio::time::interval(refresh_interval);
-        let mut current_codes = watch_rx_fetch.borrow_and_update().clone();
-
-        loop {
-            tokio::select! {
-                _ = interval.tick() => {
-                    match provider.get_quotes(&current_codes).await {
-                        Ok(quotes) => {
-                            {
-                                let mut state = dash_for_fetch.lock().await;
-                                state.last_error = None;
-                            }
-                            if !quotes.is_empty() {
-                                if quote_tx.send(quotes).await.is_err() {
-                                    break;
-                                }
-                            }
-                        }
-                        Err(e) => {
-                            let msg = format!("{}", e);
-                            warn!("数据获取失败: {}", msg);
-                            let mut state = dash_for_fetch.lock().await;
-                            state.last_error = Some(msg);
-                        }
-                    }
+                if added.is_empty() && removed.is_empty() {
+                    info!("Watchlist codes unchanged after reload");
+                    continue;
                 }
-                result = watch_rx_fetch.changed() => {
-                    if result.is_err() {
-                        break; // sender dropped
-                    }
-                    let new_codes = watch_rx_fetch.borrow_and_update().clone();
-                    let old_set: HashSet<StockCode> = current_codes.iter().cloned().collect();
-                    let new_set: HashSet<StockCode> = new_codes.iter().cloned().collect();
 
-                    let removed: Vec<StockCode> = old_set.difference(&new_set).cloned().collect();
-                    let added: Vec<StockCode> = new_set.difference(&old_set).cloned().collect();
+                info!("Watchlist changed: +{} added, -{} removed", added.len(), removed.len());
 
-                    // 退订已删股票
-                    if !removed.is_empty() {
-                        if let Err(e) = provider.unsubscribe(&removed).await {
-                            warn!("Unsubscribe failed: {}", e);
+                // 处理删除
+                if !removed.is_empty() {
+                    let removed_codes: Vec<StockCode> = removed.iter().map(|c| (*c).clone()).collect();
+                    // 清理 tick 分析引擎
+                    {
+                        let mut eng = monitor_engine.lock().await;
+                        for code in &removed_codes {
+                            eng.remove_stock(code);
                         }
                     }
-
-                    // 订阅新增股票
-                    if !added.is_empty() {
-                        if let Err(e) = provider.subscribe(&added).await {
-                            warn!("Subscribe new stocks failed: {}", e);
+                    // 清理日线分析引擎
+                    {
+                        let mut de = monitor_daily_engine.lock().await;
+                        de.remove_stocks(&removed_codes);
+                    }
+                    // 清理提醒管理器
+                    {
+                        let mut amgr = monitor_alert_mgr.lock().await;
+                        for code in &removed_codes {
+                            amgr.remove_stock(code);
                         }
                     }
+                    for code in &removed_codes {
+                        info!("Removed stock: {}", code.display_code());
+                    }
+                }
+
+                // 同步 dashboard
+                {
+                    let filtered_entries: Vec<_> = new_watchlist
+                        .iter()
+                        .filter(|e| !monitor_futu_config.is_excluded(&e.code))
+                        .cloned()
+                        .collect();
+                    let mut state = monitor_dash.lock().await;
+                    state.sync_watchlist(&new_codes, &filtered_entries);
+                }
 
-                    current_codes = new_codes;
-                    info!("Fetch loop updated: {} stocks", current_codes.len());
+                if !added.is_empty() {
+                    for code in &added {
+                        info!("Added stock: {}", code.display_code());
+                    }
                 }
+
+                // 广播新的 stock_codes
+                let _ = monitor_watch_tx.send(new_codes);
             }
+        }))
+    } else {
+        None
+    };
+
+    // 数据采集任务（使用 watch channel 感知 watchlist 变化）
+    let refresh_interval = provider.poll_interval(&config);
+    info!("数据轮询间隔: {}s", refresh_interval.as_secs());
+    // UI 手动刷新通知（r 键）→ 跳过等待立即拉取
+    let refresh_notify = Arc::new(tokio::sync::Notify::new());
+    // 强制重新探测市场权限（p 键）
+    let reprobe_notify = Arc::new(tokio::sync::Notify::new());
+    let provider = Arc::new(Mutex::new(provider));
+    let spawn_fetch = {
+        let dash = dash_state.clone();
+        let watch_rx = watch_rx.clone();
+        let refresh = refresh_notify.clone();
+        move || {
+            tokio::spawn(fetch_loop(
+                provider.clone(),
+                dash.clone(),
+                watch_rx.clone(),
+                refresh.clone(),
+                quote_tx.clone(),
+                refresh_interval,
+            ))
         }
-    });
+    };
+    let mut fetch_handle = spawn_fetch();
 
     // 日K线异步获取任务（使用 watch channel 感知新增股票）
     let daily_refresh_handle = if config.analysis.daily_kline_enabled {
@@ -458,6 +677,8 @@ async fn cmd_start(config: AppConfig) -> Result<()> {
         let daily_days = config.analysis.daily_kline_days;
         let refresh_mins = config.analysis.daily_kline_refresh_minutes;
         let mut watch_rx_daily = watch_rx.clone();
+        let mut permissions = MarketPermissionCache::from_config(&config.analysis);
+        let reprobe_for_daily = reprobe_notify.clone();
 
         info!("Daily K-line target: {} stocks", stock_codes.len());
 
@@ -473,6 +694,7 @@ async fn cmd_start(config: AppConfig) -> Result<()> {
                 &tick_engine_for_daily,
                 &dash_for_daily,
                 daily_days,
+                &mut permissions,
             )
             .await;
 
@@ -497,6 +719,24 @@ async fn cmd_start(config: AppConfig) -> Result<()> {
                             &tick_engine_for_daily,
                             &dash_for_daily,
                             daily_days,
+                            &mut permissions,
+                        )
+                        .await;
+                    }
+                    _ = reprobe_for_daily.notified() => {
+                        // 强制重新探测市场权限（今日已拉取的股票会被跳过）
+                        info!("Daily kline: forced market permission re-probe");
+                        permissions.clear();
+                        current_codes = watch_rx_daily.borrow_and_update().clone();
+                        run_daily_kline_cycle(
+                            &futu_host,
+                            futu_port,
+                            &current_codes,
+                            &daily_engine_clone,
+                            &tick_engine_for_daily,
+                            &dash_for_daily,
+                            daily_days,
+                            &mut permissions,
                         )
                         .await;
                     }
@@ -522,6 +762,7 @@ async fn cmd_start(config: AppConfig) -> Result<()> {
                                 &tick_engine_for_daily,
                                 &dash_for_daily,
                                 daily_days,
+                                &mut permissions,
                             )
                             .await;
                         }
@@ -533,95 +774,94 @@ async fn cmd_start(config: AppConfig) -> Result<()> {
         None
     };
 
-    // 渲染信号 channel（数据更新时通知 UI 渲染，使用 watch 确保最新信号不丢失）
-    let (render_tx, mut render_rx) = tokio::sync::watch::channel(());
+    // 配置文件监测任务（3s 轮询 mtime）：tick 信号阈值、日线信号参数与提醒规则热加载，解析/校验失败时沿用原配置
+    let config_watch_handle = config.path.clone().map(|path| {
+        let engine = engine.clone();
+        let daily_engine = daily_engine.clone();
+        let alert_mgr = alert_manager.clone();
+        let dash = dash_state.clone();
+        tokio::spawn(async move {
+            let mtime = || path.metadata().and_then(|m| m.modified()).ok();
+            let mut last_mtime = mtime();
 
-    // 分析 + 提醒任务
-    let engine_clone = engine.clone();
-    let alert_clone = alert_manager.clone();
-    let dash_clone = dash_state.clone();
-    let tick_display_minutes = config.analysis.tick_signal_display_minutes;
-    let analysis_handle = tokio::spawn(async move {
-        while let Some(quotes) = quote_rx.recv().await {
-            // 分析：事件型 tick 信号
-            let mut eng = engine_clone.lock().await;
-            let now = chrono::Local::now();
-            let mut all_new_signals = std::collections::HashMap::new();
-            for quote in &quotes {
-                let new_sigs = eng.process(quote);
-                if !new_sigs.is_empty() {
-                    all_new_signals.insert(quote.code.clone(), new_sigs);
-                }
-            }
-            drop(eng);
-
-            // 提醒（仅 ChangeThresholdRule）
-            let mut amgr = alert_clone.lock().await;
-            for quote in &quotes {
-                let events = amgr.evaluate(quote).await;
-                if !events.is_empty() {
-                    let mut state = dash_clone.lock().await;
-                    state.recent_alerts.extend(events);
+            loop {
+                tokio::time::sleep(Duration::from_secs(3)).await;
+
+                let current_mtime = mtime();
+                if current_mtime.is_none() || current_mtime == last_mtime {
+                    continue;
                 }
-            }
-            drop(amgr);
-
-            // 更新仪表盘状态
-            let mut state = dash_clone.lock().await;
-            let name_map: std::collections::HashMap<StockCode, String> =
-                quotes.iter().map(|q| (q.code.clone(), q.name.clone())).collect();
-            state.update_quotes(quotes);
-            for (code, sigs) in &all_new_signals {
-                for sig in sigs {
-                    if let crate::models::Signal::VolumeSpike { ratio, price, delta } = sig {
-                        let name = name_map.get(code).map(|s| s.as_str()).unwrap_or("");
-                        if state.recent_alerts.len() >= MAX_RECENT_ALERTS {
-                            state.recent_alerts.pop_front();
+                last_mtime = current_mtime;
+
+                let notice = match AppConfig::load(&path).and_then(|c| c.validate().map(|()| c)) {
+                    Ok(new_config) => {
+                        engine.lock().await.reconfigure(&new_config.analysis);
+                        let mut de = daily_engine.lock().await;
+                        if de.reconfigure(&new_config.analysis) {
+                            dash.lock().await.set_daily_signals(de.get_signals().clone());
                         }
-                        state.recent_alerts.push_back(crate::models::AlertEvent {
-                            code: code.clone(),
-                            name: name.to_string(),
-                            rule_name: "放量".to_string(),
-                            message: format!(
-                                "{} 放量{:.0}x 价:{:.2} 量:{:.1}万",
-                                name,
-                                ratio,
-                                price,
-                                *delta as f64 / 10000.0
-                            ),
-                            triggered_at: now,
-                            severity: crate::models::AlertSeverity::Warning,
-                            sentiment: None,
-                        });
+                        drop(de);
+                        let mut amgr = alert_mgr.lock().await;
+                        amgr.set_cooldown(Duration::from_secs(new_config.alerts.cooldown_secs));
+                        amgr.replace_rules(RuleRegistry::with_builtin().build(&new_config.alerts));
+                        drop(amgr);
+                        info!("Reloaded config from {}", path.display());
+                        "配置已重新加载（信号阈值/日线信号/提醒规则）".to_string()
                     }
-                }
-            }
-
-            for (code, sigs) in all_new_signals {
-                let entry = state.tick_signals.entry(code).or_default();
-                for sig in sigs {
-                    let disc = std::mem::discriminant(&sig);
-                    entry.retain(|(existing, _)| std::mem::discriminant(existing) != disc);
-                    entry.push((sig, now));
-                }
+                    Err(e) => {
+                        warn!("配置重新加载失败，沿用原配置: {:#}", e);
+                        format!("配置重新加载失败，沿用原配置: {}", e)
+                    }
+                };
+                dash.lock().await.notice = Some(notice);
             }
+        })
+    });
 
-            let cutoff = now - chrono::Duration::minutes(tick_display_minutes as i64);
-            state.tick_signals.retain(|_, sigs| {
-                sigs.retain(|(_, at)| *at > cutoff);
-                !sigs.is_empty()
-            });
-            drop(state);
+    // 渲染信号 channel（数据更新时通知 UI 渲染，使用 watch 确保最新信号不丢失）
+    let (render_tx, mut render_rx) = tokio::sync::watch::channel(());
 
-            // 通知 UI 渲染（watch channel 自动去重）
-            let _ = render_tx.send(());
+    // 分析 + 提醒任务
+    let spawn_analysis = {
+        let quote_rx = Arc::new(Mutex::new(quote_rx));
+        let render_tx = Arc::new(render_tx);
+        let engine = engine.clone();
+        let alert_mgr = alert_manager.clone();
+        let dash = dash_state.clone();
+        let tick_display_minutes = config.analysis.tick_signal_display_minutes;
+        let tick_log_dir = config.export.tick_log_dir();
+        move || {
+            tokio::spawn(analysis_loop(
+                quote_rx.clone(),
+                engine.clone(),
+                alert_mgr.clone(),
+                dash.clone(),
+                render_tx.clone(),
+                tick_display_minutes,
+                tick_log_dir.clone(),
+            ))
         }
-    });
+    };
+    let mut analysis_handle = spawn_analysis();
+
+    // panic 钩子：写日志并在状态栏提示（默认钩子输出到 stderr 会破坏 TUI 画面），退出时恢复默认
+    let dash_for_panic = dash_state.clone();
+    std::panic::set_hook(Box::new(move |info| {
+        error!("后台任务 panic: {}", info);
+        if let Ok(mut state) = dash_for_panic.try_lock() {
+            state.last_error = Some(format!("后台任务异常: {}", info));
+        }
+    }));
 
     // UI 主循环（事件驱动）
+    let signals_dir = std::path::PathBuf::from(&config.export.signals_dir);
+    let daily_enabled = config.analysis.daily_kline_enabled;
     let mut terminal = ui::dashboard::init_terminal()?;
     let dash_for_ui = dash_state.clone();
     let mut event_stream = EventStream::new();
+    // 标题栏时钟/交易时段每秒重绘
+    let mut clock_tick = tokio::time::interval(Duration::from_secs(1));
+    clock_tick.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
 
     // 初始渲染
     {
@@ -637,14 +877,40 @@ async fn cmd_start(config: AppConfig) -> Result<()> {
                 terminal.draw(|frame| ui::dashboard::render(frame, &state))?;
             }
 
+            // 时钟走时；顺带充当看门狗，重启意外退出的采集/分析任务
+            _ = clock_tick.tick() => {
+                let mut restarted = Vec::new();
+                restarted.extend(restart_if_finished(&mut fetch_handle, &spawn_fetch, "采集").await);
+                restarted.extend(restart_if_finished(&mut analysis_handle, &spawn_analysis, "分析").await);
+                let mut state = dash_for_ui.lock().await;
+                if !restarted.is_empty() {
+                    state.notice = Some(restarted.join("，"));
+                }
+                terminal.draw(|frame| ui::dashboard::render(frame, &state))?;
+            }
+
             // 等待键盘事件（事件驱动，非轮询）
             maybe_event = event_stream.next() => {
                 match maybe_event {
                     Some(Ok(crossterm::event::Event::Key(key))) => {
                         if key.kind == crossterm::event::KeyEventKind::Press {
                             let mut state = dash_for_ui.lock().await;
-                            if ui::dashboard::handle_key_event(&mut *state, key) {
-                                break;
+                            match ui::dashboard::handle_key_event(&mut *state, key) {
+                                InputAction::Quit => break,
+                                InputAction::Refresh => refresh_notify.notify_one(),
+                                InputAction::ReprobeMarkets => {
+                                    state.notice = Some(if daily_enabled {
+                                        reprobe_notify.notify_one();
+                                        "已请求重新探测市场权限".to_string()
+                                    } else {
+                                        "日K线分析未启用，无需探测".to_string()
+                                    });
+                                }
+                                InputAction::ExportSignals => {
+                                    let msg = export_signals(&state.signal_journal, &signals_dir);
+                                    state.notice = Some(msg);
+                                }
+                                InputAction::None => {}
                             }
                             // 按键后立即重新渲染（反馈）
                             terminal.draw(|frame| ui::dashboard::render(frame, &state))?;
@@ -661,9 +927,28 @@ async fn cmd_start(config: AppConfig) -> Result<()> {
 
     // 清理
     ui::dashboard::restore_terminal()?;
+    let _ = std::panic::take_hook();
+    if config.export.signals_on_exit || config.export.digest_on_exit {
+        let state = dash_for_ui.lock().await;
+        if config.export.digest_on_exit {
+            if state.signal_journal.is_empty() {
+                println!("本次运行未触发信号");
+            } else {
+                println!("{}", state.signal_journal.digest());
+            }
+        }
+        if config.export.signals_on_exit && !state.signal_journal.is_empty() {
+            println!("{}", export_signals(&state.signal_journal, &signals_dir));
+        }
+    }
     fetch_handle.abort();
     analysis_handle.abort();
-    monitor_handle.abort();
+    if let Some(h) = monitor_handle {
+        h.abort();
+    }
+    if let Some(h) = config_watch_handle {
+        h.abort();
+    }
     if let Some(h) = daily_refresh_handle {
         h.abort();
     }
@@ -672,31 +957,264 @@ async fn cmd_start(config: AppConfig) -> Result<()> {
     Ok(())
 }
 
+/// 数据采集循环：按间隔（或 r 键手动刷新）拉取行情送入分析通道，感知 watchlist 变化增减订阅
+///
+/// 状态均在外部共享（provider 由 Mutex 持有），任务 panic 后看门狗可原样重新启动
+async fn fetch_loop(
+    provider: Arc<Mutex<DataProviderKind>>,
+    dash: Arc<Mutex<DashboardState>>,
+    mut watch_rx: watch::Receiver<Vec<StockCode>>,
+    refresh: Arc<tokio::sync::Notify>,
+    quote_tx: mpsc::Sender<Vec<QuoteSnapshot>>,
+    refresh_interval: Duration,
+) {
+    let mut provider = provider.lock().await;
+    let mut interval = tokio::time::interval(refresh_interval);
+    let mut current_codes = watch_rx.borrow_and_update().clone();
+
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {}
+            _ = refresh.notified() => {
+                // 手动刷新：立即拉取，并从此刻重新计时
+                info!("Manual refresh requested");
+                interval.reset();
+            }
+            result = watch_rx.changed() => {
+                if result.is_err() {
+                    break; // sender dropped
+                }
+                let new_codes = watch_rx.borrow_and_update().clone();
+                let old_set: HashSet<StockCode> = current_codes.iter().cloned().collect();
+                let new_set: HashSet<StockCode> = new_codes.iter().cloned().collect();
+
+                let removed: Vec<StockCode> = old_set.difference(&new_set).cloned().collect();
+                let added: Vec<StockCode> = new_set.difference(&old_set).cloned().collect();
+
+                // 退订已删股票
+                if !removed.is_empty() {
+                    if let Err(e) = provider.unsubscribe(&removed).await {
+                        warn!("Unsubscribe failed: {}", e);
+                    }
+                }
+
+                // 订阅新增股票
+                if !added.is_empty() {
+                    if let Err(e) = provider.subscribe(&added).await {
+                        warn!("Subscribe new stocks failed: {}", e);
+                    }
+                }
+
+                current_codes = new_codes;
+                info!("Fetch loop updated: {} stocks", current_codes.len());
+                continue;
+            }
+        }
+
+        let result = provider.get_quotes(&current_codes).await;
+        {
+            let mut state = dash.lock().await;
+            state.refresh_pending = false;
+            state.connection = provider.connection_state();
+            // 降级链的名称随实际提供行情的数据源变化
+            state.source_name = provider.name().to_string();
+            // 重连恢复订阅、新增股票订阅后市场集合可能变化
+            state.subscribed_markets = provider.subscribed_markets();
+            match &result {
+                Ok(_) => state.last_error = None,
+                Err(e) => {
+                    let msg = format!("{}", e);
+                    warn!("数据获取失败: {}", msg);
+                    state.last_error = Some(msg);
+                }
+            }
+        }
+        if let Ok(quotes) = result {
+            if !quotes.is_empty() && quote_tx.send(quotes).await.is_err() {
+                break;
+            }
+        }
+    }
+}
+
+/// 分析 + 提醒循环：消费行情批次，产出 tick 信号与提醒并更新仪表盘
+///
+/// 接收端由 Mutex 持有，任务 panic 后看门狗重新启动时继续消费同一通道
+async fn analysis_loop(
+    quote_rx: Arc<Mutex<mpsc::Receiver<Vec<QuoteSnapshot>>>>,
+    engine: Arc<Mutex<AnalysisEngine>>,
+    alert_mgr: Arc<Mutex<AlertManager>>,
+    dash: Arc<Mutex<DashboardState>>,
+    render_tx: Arc<watch::Sender<()>>,
+    tick_display_minutes: u64,
+    tick_log_dir: Option<std::path::PathBuf>,
+) {
+    let mut quote_rx = quote_rx.lock().await;
+    while let Some(quotes) = quote_rx.recv().await {
+        // 分析：事件型 tick 信号
+        let mut eng = engine.lock().await;
+        let now = chrono::Local::now();
+        let mut all_new_signals = std::collections::HashMap::new();
+        for quote in &quotes {
+            let new_sigs = eng.process(quote);
+            if !new_sigs.is_empty() {
+                all_new_signals.insert(quote.code.clone(), new_sigs);
+            }
+        }
+        drop(eng);
+
+        // 提醒（规则由 RuleRegistry 构造）
+        let mut amgr = alert_mgr.lock().await;
+        for quote in &quotes {
+            let events = amgr.evaluate(quote).await;
+            if !events.is_empty() {
+                let mut state = dash.lock().await;
+                state.recent_alerts.extend(events);
+            }
+        }
+        drop(amgr);
+
+        // 更新仪表盘状态
+        let mut state = dash.lock().await;
+        let name_map: std::collections::HashMap<StockCode, String> =
+            quotes.iter().map(|q| (q.code.clone(), q.name.clone())).collect();
+        let price_map: std::collections::HashMap<StockCode, f64> =
+            quotes.iter().map(|q| (q.code.clone(), q.last_price)).collect();
+        state.update_quotes(quotes);
+        let mut log_entries = Vec::new();
+        for (code, sigs) in &all_new_signals {
+            for sig in sigs {
+                debug!(code = %code, market = %code.market, signal = %sig, "Tick signal");
+                let name = name_map.get(code).map(|s| s.as_str()).unwrap_or("");
+                let price = price_map.get(code).copied().unwrap_or(0.0);
+                state.signal_journal.record_tick(code, name, sig, price, now);
+                if tick_log_dir.is_some() {
+                    log_entries.push(analysis::tick_log::TickSignalEntry {
+                        timestamp: now,
+                        code: code.clone(),
+                        name: name.to_string(),
+                        signal: sig.clone(),
+                        price,
+                    });
+                }
+                state.follow_signal(code);
+                if let crate::models::Signal::VolumeSpike { ratio, price, delta } = sig {
+                    let name = name_map.get(code).map(|s| s.as_str()).unwrap_or("");
+                    if state.recent_alerts.len() >= MAX_RECENT_ALERTS {
+                        state.recent_alerts.pop_front();
+                    }
+                    state.recent_alerts.push_back(crate::models::AlertEvent {
+                        code: code.clone(),
+                        name: name.to_string(),
+                        rule_name: "放量".to_string(),
+                        message: format!(
+                            "{} 放量{:.0}x 价:{:.2} 量:{:.1}万",
+                            name,
+                            ratio,
+                            price,
+                            *delta as f64 / 10000.0
+                        ),
+                        triggered_at: now,
+                        severity: crate::models::AlertSeverity::Warning,
+                        sentiment: None,
+                    });
+                }
+            }
+        }
+
+        for (code, sigs) in all_new_signals {
+            let entry = state.tick_signals.entry(code).or_default();
+            for sig in sigs {
+                let disc = std::mem::discriminant(&sig);
+                entry.retain(|(existing, _)| std::mem::discriminant(existing) != disc);
+                entry.push((sig, now));
+            }
+        }
+
+        let cutoff = now - chrono::Duration::minutes(tick_display_minutes as i64);
+        state.tick_signals.retain(|_, sigs| {
+            sigs.retain(|(_, at)| *at > cutoff);
+            !sigs.is_empty()
+        });
+        drop(state);
+
+        // tick 信号落盘（失败只记日志）
+        if let Some(dir) = &tick_log_dir {
+            if let Err(e) = analysis::tick_log::append(dir, &log_entries) {
+                warn!("tick 信号日志写入失败: {}", e);
+            }
+        }
+
+        // 通知 UI 渲染（watch channel 自动去重）
+        let _ = render_tx.send(());
+    }
+}
+
+/// 看门狗：任务已退出（panic 或意外结束）则重新启动，返回状态栏提示
+async fn restart_if_finished<F>(handle: &mut tokio::task::JoinHandle<()>, spawn: &F, name: &str) -> Option<String>
+where
+    F: Fn() -> tokio::task::JoinHandle<()>,
+{
+    if !handle.is_finished() {
+        return None;
+    }
+    let finished = std::mem::replace(handle, spawn());
+    match finished.await {
+        Err(e) if e.is_panic() => error!("{}任务 panic，已重启", name),
+        _ => warn!("{}任务意外退出，已重启", name),
+    }
+    Some(format!("{}任务已重启", name))
+}
+
+/// 导出信号日志 CSV，返回结果提示
+fn export_signals(journal: &analysis::journal::SignalJournal, dir: &std::path::Path) -> String {
+    if journal.is_empty() {
+        return "暂无信号可导出".to_string();
+    }
+    match journal.write_csv(dir) {
+        Ok(path) => {
+            info!("Exported {} signals to {}", journal.len(), path.display());
+            format!("已导出 {} 条信号: {}", journal.len(), path.display())
+        }
+        Err(e) => {
+            warn!("Failed to export signals: {}", e);
+            format!("导出失败: {}", e)
+        }
+    }
+}
+
 /// 测试 FutuOpenD OpenAPI 连接
-async fn cmd_test_api(config: AppConfig) -> Result<()> {
+async fn cmd_test_api(config: AppConfig, dump_raw: bool) -> Result<()> {
     use crate::futu::openapi::OpenApiClient;
 
     println!("测试 FutuOpenD 连接...");
     println!("目标: {}:{}", config.futu.opend_host, config.futu.opend_port);
 
     let mut client = OpenApiClient::new(&config.futu.opend_host, config.futu.opend_port);
+    // --dump-raw 未配置缓存条数时默认每个协议号保留 5 个
+    let raw_log = match config.futu.raw_response_log {
+        0 if dump_raw => 5,
+        n => n,
+    };
+    client.enable_raw_log(raw_log);
 
     // 连接 + InitConnect
     client.connect().await?;
     println!("✓ 连接成功");
 
     // 读取自选股列表
-    let watchlist = futu::watchlist::load_watchlist(config.futu.data_path.as_deref(), config.futu.user_id.as_deref())?;
+    let watchlist =
+        futu::watchlist::load_watchlist(config.futu.data_path.as_deref(), &config.futu.selected_user_ids())?;
 
     if watchlist.is_empty() {
         println!("自选股列表为空");
         return Ok(());
     }
 
-    // 只取前 5 只港股测试（排除 A 股指数和 800xxx 内部代码）
+    // 只取前 5 只港股测试（排除指数）
     let test_stocks: Vec<StockCode> = watchlist
         .iter()
-        .filter(|e| e.code.market == crate::models::Market::HK && !e.code.code.starts_with("800"))
+        .filter(|e| e.code.market == crate::models::Market::HK && !e.code.is_index())
         .take(5)
         .map(|e| e.code.clone())
         .collect();
@@ -742,6 +1260,12 @@ async fn cmd_test_api(config: AppConfig) -> Result<()> {
         }
     }
 
+    if dump_raw {
+        if let Some(dump) = client.dump_raw_responses() {
+            println!("\n原始响应:\n{}", dump);
+        }
+    }
+
     // 优雅断开连接
     client.disconnect().await;
     println!("\n✓ 连接已断开");
@@ -750,7 +1274,7 @@ async fn cmd_test_api(config: AppConfig) -> Result<()> {
 }
 
 /// 测试窗口截图 + Vision OCR
-async fn cmd_test_ocr(_config: AppConfig) -> Result<()> {
+async fn cmd_test_ocr(config: AppConfig) -> Result<()> {
     use crate::futu::accessibility::AccessibilityReader;
     use crate::futu::ocr;
 
@@ -775,10 +1299,30 @@ async fn cmd_test_ocr(_config: AppConfig) -> Result<()> {
 
     // 2. 查找窗口（获取实际 GUI 进程 PID）
     println!("\n查找主窗口...");
-    let win_info = ocr::find_futu_window(pid)?;
+    let win_info = ocr::find_futu_window(pid, config.ocr.min_window_area)?;
     let window_id = win_info.id;
     let gui_pid = win_info.owner_pid;
-    println!("  窗口 ID: {}  GUI PID: {}", window_id, gui_pid);
+    println!(
+        "  窗口 ID: {}  GUI PID: {}  可见: {}",
+        window_id,
+        gui_pid,
+        if win_info.is_onscreen {
+            "是"
+        } else {
+            "否（已最小化或隐藏）"
+        }
+    );
+    if win_info.occluded_ratio > 0.0 {
+        println!(
+            "  被其他窗口遮挡: {:.0}%{}",
+            win_info.occluded_ratio * 100.0,
+            if win_info.is_occluded() {
+                "（完全遮挡，运行时将暂停 OCR）"
+            } else {
+                ""
+            }
+        );
+    }
 
     // 2.5 尝试 AX API 检测自选股表格区域（使用 GUI PID）
     println!("\n[AX] 检测自选股表格区域...");
@@ -802,8 +1346,11 @@ async fn cmd_test_ocr(_config: AppConfig) -> Result<()> {
     // 3-7: 截图 + OCR + 解析（同步 macOS API，必须在 spawn_blocking 中运行，
     //       否则 CGWindowListCreateImage 可能在 tokio 主线程上死锁）
     println!("\n截取窗口截图 + OCR...");
+    let backend = ocr::CaptureBackend::from_config(&config.ocr.capture_backend);
+    let upscale = config.ocr.upscale;
+    let languages = config.ocr.languages.clone();
     let result = tokio::task::spawn_blocking(move || -> Result<()> {
-        let image = ocr::capture_window(window_id)?;
+        let image = ocr::capture_window(window_id, backend)?;
         println!(
             "  截图尺寸: {}x{} 像素",
             objc2_core_graphics::CGImage::width(Some(&image)),
@@ -821,7 +1368,7 @@ async fn cmd_test_ocr(_config: AppConfig) -> Result<()> {
         } else {
             println!("\n[Pass 1] 快速 OCR 检测布局...");
             let t0 = std::time::Instant::now();
-            let fast_blocks = ocr::recognize_text_fast(&image)?;
+            let fast_blocks = ocr::recognize_text_fast(&image, &languages)?;
             fast_ms = t0.elapsed().as_millis();
             println!("  Fast OCR: {} 个文字块 ({} ms)", fast_blocks.len(), fast_ms);
 
@@ -854,8 +1401,21 @@ async fn cmd_test_ocr(_config: AppConfig) -> Result<()> {
             objc2_core_graphics::CGImage::height(Some(&watchlist_crop)),
         );
 
+        let watchlist_crop = if upscale > 1.0 {
+            let scaled = ocr::upscale_image(&watchlist_crop, upscale)?;
+            println!(
+                "  放大 {:.1}x: {}x{}",
+                upscale,
+                objc2_core_graphics::CGImage::width(Some(&scaled)),
+                objc2_core_graphics::CGImage::height(Some(&scaled)),
+            );
+            scaled
+        } else {
+            watchlist_crop
+        };
+
         let t1 = std::time::Instant::now();
-        let blocks = ocr::recognize_text(&watchlist_crop)?;
+        let blocks = ocr::recognize_text(&watchlist_crop, &languages)?;
         let acc_ms = t1.elapsed().as_millis();
         println!("  Accurate OCR: {} 个文字块 ({} ms)", blocks.len(), acc_ms);
 
@@ -922,9 +1482,19 @@ async fn cmd_test_ocr(_config: AppConfig) -> Result<()> {
     Ok(())
 }
 
-/// 启动 MCP 交易服务器
-async fn cmd_test_trade(code: String, price: f64, qty: u32, side: String) -> Result<()> {
+/// 测试交易流程（直接调用执行器）
+#[cfg(feature = "trading")]
+async fn cmd_test_trade(
+    config: AppConfig,
+    code: String,
+    price: f64,
+    qty: u32,
+    side: String,
+    dry_run: bool,
+    opend_acc: Option<u64>,
+) -> Result<()> {
     use crate::trading::executor::{OrderRequest, OrderSide, TradingExecutor, TradingMarket};
+    use crate::trading::journal::{self, OrderRecord};
 
     let side = match side.to_lowercase().as_str() {
         "buy" => OrderSide::Buy,
@@ -935,7 +1505,7 @@ async fn cmd_test_trade(code: String, price: f64, qty: u32, side: String) -> Res
     let market = TradingMarket::infer(&code).ok_or_else(|| anyhow::anyhow!("无法识别股票代码 '{}' 的市场", code))?;
 
     let prec = market.price_decimals();
-    println!("=== 交易测试 ===");
+    println!("=== 交易测试{} ===", if dry_run { "（模拟）" } else { "" });
     println!(
         "市场: {}  股票: {}  价格: {:.prec$}  数量: {}  方向: {}",
         market,
@@ -947,9 +1517,6 @@ async fn cmd_test_trade(code: String, price: f64, qty: u32, side: String) -> Res
     );
     println!();
 
-    let executor = TradingExecutor::new()?;
-    println!("交易执行器就绪，PID={}", executor.pid());
-
     let req = OrderRequest {
         stock_code: code,
         price,
@@ -958,14 +1525,101 @@ async fn cmd_test_trade(code: String, price: f64, qty: u32, side: String) -> Res
         market,
     };
 
-    let result = executor.execute_order(&req).await?;
+    let result = if let Some(acc_id) = opend_acc {
+        let mut client = crate::futu::openapi::OpenApiClient::new(&config.futu.opend_host, config.futu.opend_port);
+        client.connect().await?;
+        let account = client
+            .get_acc_list()
+            .await?
+            .into_iter()
+            .find(|acc| acc.acc_id == acc_id)
+            .with_context(|| format!("未找到交易账户 {}", acc_id))?;
+        println!("已连接 FutuOpenD，{}账户 {}", account.env, acc_id);
+        // 真实账户下单前须解锁交易，模拟账户无需解锁
+        if account.env == crate::futu::trade::TrdEnv::Real {
+            let pwd_md5 = config.futu.trade_pwd_md5.as_deref().with_context(|| {
+                format!(
+                    "账户 {} 为真实账户，下单前需解锁交易：请在 [futu] 配置 trade_pwd_md5（交易密码的 MD5）",
+                    acc_id
+                )
+            })?;
+            client.unlock_trade(&pwd_md5.to_ascii_lowercase()).await?;
+        }
+        let result = client.place_order(acc_id, &req).await?;
+        if let Some(path) = config.mcp.order_log_file() {
+            let record = OrderRecord {
+                request: req.clone(),
+                result: result.clone(),
+                dry_run: false,
+            };
+            if let Err(e) = journal::append(&path, &record) {
+                warn!("{:#}", e);
+            }
+        }
+        result
+    } else {
+        let executor = TradingExecutor::new()?
+            .with_dry_run(dry_run)
+            .with_journal(config.mcp.order_log_file());
+        println!("交易执行器就绪，PID={}", executor.pid());
+        executor.execute_order(&req).await?
+    };
     println!();
     println!("=== 结果 ===");
     println!("{}", serde_json::to_string_pretty(&result)?);
     Ok(())
 }
 
-async fn cmd_mcp_server(config: AppConfig) -> Result<()> {
+/// 显示最近的委托记录（请求价 vs AX 验价结果）
+#[cfg(feature = "trading")]
+fn cmd_orders(config: AppConfig, count: usize) -> Result<()> {
+    let Some(path) = config.mcp.order_log_file() else {
+        println!("未启用委托日志（mcp.order_log_path 为空）");
+        return Ok(());
+    };
+    let Some(records) = trading::journal::read_last(&path, count)? else {
+        println!("暂无委托记录（{} 不存在）", path.display());
+        return Ok(());
+    };
+
+    println!("最近 {} 条委托 ({}):", records.len(), path.display());
+    println!("{:-<100}", "");
+    for record in &records {
+        let req = &record.request;
+        let res = &record.result;
+        let prec = req.market.price_decimals();
+        let status = match (res.success, record.dry_run) {
+            (true, true) => "模拟",
+            (true, false) => "成功",
+            (false, _) => "失败",
+        };
+        let verified = match (&res.verified_code, res.verified_price) {
+            (Some(code), Some(price)) => format!("{} @ {:.prec$}", code, price, prec = prec),
+            _ => "-".to_string(),
+        };
+        println!(
+            "{} [{}] {} {} {:<8} {} 股 @ {:.prec$} {} | 验证: {} | {}",
+            res.timestamp,
+            status,
+            req.market,
+            req.side,
+            req.stock_code,
+            req.quantity,
+            req.price,
+            req.market.currency(),
+            verified,
+            res.message,
+            prec = prec
+        );
+    }
+
+    Ok(())
+}
+
+/// 启动 MCP 交易服务器
+#[cfg(feature = "trading")]
+async fn cmd_mcp_server(mut config: AppConfig, dry_run: bool) -> Result<()> {
+    config.mcp.dry_run |= dry_run;
     crate::mcp::server::run_mcp_server(&config.mcp).await
 }
 
@@ -994,32 +1648,94 @@ fn date_gap_days(from: &str, to: &str) -> u32 {
     (to_date - from_date).num_days().max(0) as u32
 }
 
+/// 市场权限探测结果缓存（跨日K轮次复用，避免每轮每个市场都发一次探测请求）
+struct MarketPermissionCache {
+    /// 市场 → (是否有权限, 探测时间)
+    entries: std::collections::HashMap<crate::models::Market, (bool, std::time::Instant)>,
+    /// 结果有效期，None 表示本次运行内一直有效
+    ttl: Option<Duration>,
+    /// 每轮都重新探测（permission_probe = "per_cycle"）
+    per_cycle: bool,
+}
+
+impl MarketPermissionCache {
+    fn from_config(config: &crate::config::AnalysisConfig) -> Self {
+        Self {
+            entries: std::collections::HashMap::new(),
+            ttl: (config.permission_probe_ttl_minutes > 0)
+                .then(|| Duration::from_secs(config.permission_probe_ttl_minutes * 60)),
+            per_cycle: config.permission_probe == "per_cycle",
+        }
+    }
+
+    /// 该市场是否有可复用的探测结果
+    fn is_fresh(&self, market: crate::models::Market) -> bool {
+        if self.per_cycle {
+            return false;
+        }
+        self.entries
+            .get(&market)
+            .is_some_and(|(_, at)| self.ttl.is_none_or(|ttl| at.elapsed() < ttl))
+    }
+
+    fn record(&mut self, market: crate::models::Market, permitted: bool) {
+        self.entries.insert(market, (permitted, std::time::Instant::now()));
+    }
+
+    /// 使某市场的结果失效（下一轮重新探测）
+    fn invalidate(&mut self, market: crate::models::Market) {
+        self.entries.remove(&market);
+    }
+
+    /// 清空全部结果（强制重新探测）
+    fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// 已知无权限的市场
+    fn denied(&self) -> HashSet<crate::models::Market> {
+        self.entries
+            .iter()
+            .filter(|(_, (permitted, _))| !permitted)
+            .map(|(m, _)| *m)
+            .collect()
+    }
+}
+
 /// 按市场探测权限：每个市场试拉一只股票的K线，返回无权限的市场集合
+///
+/// 缓存中仍有效的市场不再探测；探测失败（非权限错误）的市场不写入缓存，下一轮重试
 async fn probe_market_permissions(
     client: &mut crate::futu::openapi::OpenApiClient,
     stocks: &[StockCode],
-) -> std::collections::HashSet<crate::models::Market> {
-    let mut no_permission = std::collections::HashSet::new();
-    let today_str = chrono::Local::now().format("%Y-%m-%d").to_string();
-    let probe_begin = (chrono::Local::now() - chrono::Duration::days(5))
+    cache: &mut MarketPermissionCache,
+) -> HashSet<crate::models::Market> {
+    let today_str = models::app_today().format("%Y-%m-%d").to_string();
+    let probe_begin = (models::app_today() - chrono::Duration::days(5))
         .format("%Y-%m-%d")
         .to_string();
-    let mut probed = std::collections::HashSet::new();
+    let mut probed = HashSet::new();
+    let mut reused = 0;
 
     for stock in stocks {
         if probed.contains(&stock.market) || stock.market == crate::models::Market::Unknown {
             continue;
         }
         probed.insert(stock.market);
+        if cache.is_fresh(stock.market) {
+            reused += 1;
+            continue;
+        }
         match client.request_history_kline(stock, &probe_begin, &today_str, 2).await {
             Ok(_) => {
                 info!("市场权限检测: {} ✓", stock.market);
+                cache.record(stock.market, true);
             }
             Err(e) => {
                 let msg = format!("{}", e);
                 if is_permission_error(&msg) {
                     info!("市场权限检测: {} ✗ 无权限", stock.market);
-                    no_permission.insert(stock.market);
+                    cache.record(stock.market, false);
                 } else {
                     warn!("市场权限检测: {} 探测失败 ({})", stock.market, msg);
                 }
@@ -1027,7 +1743,11 @@ async fn probe_market_permissions(
         }
         tokio::time::sleep(Duration::from_millis(200)).await;
     }
+    if reused > 0 {
+        debug!("市场权限检测: {} 个市场复用缓存结果", reused);
+    }
 
+    let no_permission = cache.denied();
     if !no_permission.is_empty() {
         let skipped: Vec<String> = no_permission.iter().map(|m| format!("{}", m)).collect();
         info!("以下市场无权限，将跳过日K拉取: {}", skipped.join(", "));
@@ -1068,7 +1788,7 @@ async fn fetch_and_merge_stock_kline(
     };
 
     let end = today.to_string();
-    let begin = (chrono::Local::now() - chrono::Duration::days(fetch_days as i64 * 2))
+    let begin = (models::app_today() - chrono::Duration::days(fetch_days as i64 * 2))
         .format("%Y-%m-%d")
         .to_string();
 
@@ -1107,6 +1827,7 @@ async fn fetch_and_merge_stock_kline(
 }
 
 /// 执行一轮日K线拉取：连接 FutuOpenD、探测权限、逐只拉取、保存缓存、更新 dashboard、注入 ADV
+#[allow(clippy::too_many_arguments)]
 async fn run_daily_kline_cycle(
     futu_host: &str,
     futu_port: u16,
@@ -1115,6 +1836,7 @@ async fn run_daily_kline_cycle(
     tick_engine: &Arc<Mutex<AnalysisEngine>>,
     dash_state: &Arc<Mutex<DashboardState>>,
     daily_days: u32,
+    permissions: &mut MarketPermissionCache,
 ) {
     let total = daily_codes.len();
     info!("Fetching daily K-line data ({}只)...", total);
@@ -1126,8 +1848,9 @@ async fn run_daily_kline_cycle(
     let mut client = crate::futu::openapi::OpenApiClient::new(futu_host, futu_port);
     match client.connect().await {
         Ok(()) => {
-            let mut no_permission_markets = probe_market_permissions(&mut client, daily_codes).await;
-            let today_str = chrono::Local::now().format("%Y-%m-%d").to_string();
+            let mut no_permission_markets = probe_market_permissions(&mut client, daily_codes, permissions).await;
+            dash_state.lock().await.no_permission_markets = no_permission_markets.clone();
+            let today_str = models::app_today().format("%Y-%m-%d").to_string();
 
             let mut fetched = 0u32;
             for (i, stock) in daily_codes.iter().enumerate() {
@@ -1135,6 +1858,15 @@ async fn run_daily_kline_cycle(
                     continue;
                 }
 
+                // 心跳写入失败 → 连接已被网关断开，重连后继续
+                if !client.keep_alive_ok() {
+                    warn!("Daily K-line connection lost (keepalive failed), reconnecting");
+                    if let Err(e) = client.connect().await {
+                        warn!("Daily K-line reconnect failed, stopping this cycle: {}", e);
+                        break;
+                    }
+                }
+
                 match fetch_and_merge_stock_kline(&mut client, stock, daily_engine, &today_str, daily_days).await {
                     Ok(true) => fetched += 1,
                     Ok(false) => {}
@@ -1143,8 +1875,11 @@ async fn run_daily_kline_cycle(
                         if is_permission_error(&msg) {
                             warn!("{} market no permission, skipping: {}", stock.market, msg);
                             no_permission_markets.insert(stock.market);
+                            permissions.record(stock.market, false);
                         } else {
                             warn!("Failed to get klines for {}: {}", stock.display_code(), msg);
+                            // 之前探测有权限的市场开始失败 → 下一轮重新探测
+                            permissions.invalidate(stock.market);
                         }
                     }
                 }
@@ -1161,7 +1896,8 @@ async fn run_daily_kline_cycle(
                     de.save_cache();
                     let mut state = dash_state.lock().await;
                     state.daily_indicators = de.get_indicators().clone();
-                    state.daily_signals = de.get_signals().clone();
+                    state.set_daily_signals(de.get_signals().clone());
+                    state.update_daily_closes(&de);
                 }
 
                 // 间隔 200ms 防限流
@@ -1174,10 +1910,13 @@ async fn run_daily_kline_cycle(
             {
                 let de = daily_engine.lock().await;
                 let mut state = dash_state.lock().await;
+                state.no_permission_markets = no_permission_markets;
                 state.daily_indicators = de.get_indicators().clone();
-                state.daily_signals = de.get_signals().clone();
+                state.set_daily_signals(de.get_signals().clone());
+                state.update_daily_closes(&de);
                 let sig_count: usize = state.daily_signals.values().map(|v| v.len()).sum();
                 state.daily_kline_status = format!("日K:{}只 信号:{}", de.stock_count(), sig_count);
+                state.record_daily_signals();
 
                 // 计算 ADV 并注入 tick 引擎（放量绝对量门槛）
                 let adv = de.compute_adv();
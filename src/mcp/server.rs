@@ -5,7 +5,7 @@
 
 use std::sync::Arc;
 use tokio::sync::Mutex;
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 
 use rmcp::{
     handler::server::{router::tool::ToolRouter, wrapper::Parameters},
@@ -18,7 +18,7 @@ use rmcp::{
 };
 
 use crate::config::McpConfig;
-use crate::trading::executor::{OrderRequest, OrderSide, TradingExecutor, TradingMarket};
+use crate::trading::executor::{OrderRequest, OrderResult, OrderSide, TradingExecutor, TradingMarket};
 
 // ===== Tool 参数定义 =====
 
@@ -75,7 +75,7 @@ impl QtradeMcpServer {
     }
 
     #[tool(
-        description = "买入委托。支持港股（5位代码如00700）和A股（6位代码如600519）。自动识别市场，通过财富通客户端提交限价买入订单。提交前会验证确认弹窗中的价格和代码。"
+        description = "买入委托。支持港股（5位代码如00700）和A股（6位代码如600519）。自动识别市场，通过财富通客户端提交限价买入订单。提交前会验证确认弹窗中的价格和代码。返回 JSON 中 dry_run 为 true 表示模拟委托（验价后取消，未提交）。"
     )]
     async fn buy(&self, Parameters(params): Parameters<BuyParams>) -> Result<CallToolResult, McpError> {
         let market = TradingMarket::infer(&params.stock_code).ok_or_else(|| {
@@ -99,7 +99,7 @@ impl QtradeMcpServer {
         let executor = self.executor.lock().await;
         match executor.execute_order(&req).await {
             Ok(result) => {
-                let json = serde_json::to_string_pretty(&result).unwrap_or_default();
+                let json = order_result_json(&result, executor.is_dry_run());
                 if result.success {
                     Ok(CallToolResult::success(vec![Content::text(json)]))
                 } else {
@@ -114,7 +114,7 @@ impl QtradeMcpServer {
     }
 
     #[tool(
-        description = "卖出委托。支持港股（5位代码如00700）和A股（6位代码如600519）。自动识别市场，通过财富通客户端提交限价卖出订单。提交前会验证确认弹窗中的价格和代码。"
+        description = "卖出委托。支持港股（5位代码如00700）和A股（6位代码如600519）。自动识别市场，通过财富通客户端提交限价卖出订单。提交前会验证确认弹窗中的价格和代码。返回 JSON 中 dry_run 为 true 表示模拟委托（验价后取消，未提交）。"
     )]
     async fn sell(&self, Parameters(params): Parameters<SellParams>) -> Result<CallToolResult, McpError> {
         let market = TradingMarket::infer(&params.stock_code).ok_or_else(|| {
@@ -138,7 +138,7 @@ impl QtradeMcpServer {
         let executor = self.executor.lock().await;
         match executor.execute_order(&req).await {
             Ok(result) => {
-                let json = serde_json::to_string_pretty(&result).unwrap_or_default();
+                let json = order_result_json(&result, executor.is_dry_run());
                 if result.success {
                     Ok(CallToolResult::success(vec![Content::text(json)]))
                 } else {
@@ -169,6 +169,13 @@ impl QtradeMcpServer {
     }
 }
 
+/// 委托结果 JSON，附带 `dry_run` 标明是否为模拟委托
+fn order_result_json(result: &OrderResult, dry_run: bool) -> String {
+    let mut value = serde_json::to_value(result).unwrap_or_default();
+    value["dry_run"] = dry_run.into();
+    serde_json::to_string_pretty(&value).unwrap_or_default()
+}
+
 // ===== ServerHandler =====
 
 #[tool_handler]
@@ -246,7 +253,14 @@ fn get_quote_sync(stock_code: &str) -> anyhow::Result<String> {
 
 pub async fn run_mcp_server(config: &McpConfig) -> anyhow::Result<()> {
     info!("初始化交易执行器...");
-    let executor = Arc::new(Mutex::new(TradingExecutor::new()?));
+    let executor = Arc::new(Mutex::new(
+        TradingExecutor::new()?
+            .with_dry_run(config.dry_run)
+            .with_journal(config.order_log_file()),
+    ));
+    if config.dry_run {
+        warn!("模拟模式：委托验价后取消，不会真正提交");
+    }
     info!("交易执行器就绪");
 
     let ct = tokio_util::sync::CancellationToken::new();
@@ -281,6 +295,9 @@ pub async fn run_mcp_server(config: &McpConfig) -> anyhow::Result<()> {
     let listener = tokio::net::TcpListener::bind(&bind_addr).await?;
     info!("MCP 服务器监听: http://{}/mcp", bind_addr);
     println!("MCP 服务器已启动: http://{}/mcp", bind_addr);
+    if config.dry_run {
+        println!("模拟模式（dry run）：委托不会真正提交");
+    }
     println!("按 Ctrl+C 停止");
 
     axum::serve(listener, router)
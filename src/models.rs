@@ -1,4 +1,4 @@
-use chrono::{DateTime, Local};
+use chrono::{DateTime, FixedOffset, Local, NaiveDate, TimeZone, Utc};
 use std::fmt;
 
 /// 市场类型
@@ -20,6 +20,53 @@ pub enum Market {
     Unknown,
 }
 
+impl Market {
+    /// 所有已知市场（不含 Unknown）
+    ///
+    /// 遍历市场的地方（解析市场前缀、分组统计、订阅分批等）都应基于此列表，
+    /// 新增市场时只需在此登记，避免各处硬编码的市场数组遗漏
+    pub const fn all() -> [Market; 6] {
+        [Market::HK, Market::SH, Market::SZ, Market::US, Market::SG, Market::FX]
+    }
+
+    /// 中文名称（分组/汇总显示）
+    pub fn label(self) -> &'static str {
+        match self {
+            Market::HK => "港股",
+            Market::SH => "沪市",
+            Market::SZ => "深市",
+            Market::US => "美股",
+            Market::SG => "新加坡",
+            Market::FX => "外汇",
+            Market::Unknown => "未知",
+        }
+    }
+
+    /// 富途 QotMarket 代码（FX / Unknown 无独立行情市场，返回 None）
+    pub fn futu_code(self) -> Option<i32> {
+        match self {
+            Market::HK => Some(1),  // QotMarket_HK_Security
+            Market::US => Some(11), // QotMarket_US_Security
+            Market::SH => Some(21), // QotMarket_CNSH_Security
+            Market::SZ => Some(22), // QotMarket_CNSZ_Security
+            Market::SG => Some(13), // QotMarket_SG_Security
+            Market::FX | Market::Unknown => None,
+        }
+    }
+
+    /// 由富途 QotMarket 代码反查市场
+    pub fn from_futu_code(code: i32) -> Option<Market> {
+        Market::all().into_iter().find(|m| m.futu_code() == Some(code))
+    }
+
+    /// 由市场标识解析（"HK" / "sg" 等，不区分大小写），与 Display 输出互逆
+    pub fn from_prefix(s: &str) -> Option<Market> {
+        Market::all()
+            .into_iter()
+            .find(|m| m.to_string().eq_ignore_ascii_case(s))
+    }
+}
+
 impl fmt::Display for Market {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -109,7 +156,10 @@ pub struct QuoteSnapshot {
     pub high_price: f64,
     /// 最低价
     pub low_price: f64,
-    /// 成交量
+    /// 成交量（单位：股）
+    ///
+    /// FutuOpenD 返回的即为股数；富途 UI 对 A 股以手显示时，
+    /// 解析阶段已按每手 100 股换算（见 `data::parser::volume_to_shares`）
     pub volume: u64,
     /// 成交额
     pub turnover: f64,
@@ -125,6 +175,8 @@ pub struct QuoteSnapshot {
     pub extended_price: Option<f64>,
     /// 盘前/盘后涨跌幅 (%)（美股）
     pub extended_change_pct: Option<f64>,
+    /// 停牌（含熔断暂停交易），价格为停牌前最后成交价
+    pub is_suspended: bool,
     /// 数据时间戳
     pub timestamp: DateTime<Local>,
     /// 数据源
@@ -150,10 +202,31 @@ impl QuoteSnapshot {
             amplitude: 0.0,
             extended_price: None,
             extended_change_pct: None,
+            is_suspended: false,
             timestamp: Local::now(),
             source: DataSource::Cache,
         }
     }
+
+    /// 美股非盘中时段的扩展价格及相对收盘价的涨跌：(价格, 涨跌额, 涨跌幅%)
+    ///
+    /// 仅美股、时段非 Regular、且扩展价与收盘价不同时返回（与仪表盘现价列显示逻辑一致）。
+    pub fn extended_change(&self, session: UsMarketSession) -> Option<(f64, f64, f64)> {
+        if self.code.market != Market::US || session == UsMarketSession::Regular {
+            return None;
+        }
+        let ext = self.extended_price?;
+        if (ext - self.last_price).abs() <= 0.001 {
+            return None;
+        }
+        let change = ext - self.last_price;
+        let change_pct = if self.last_price > 0.0 {
+            change / self.last_price * 100.0
+        } else {
+            0.0
+        };
+        Some((ext, change, change_pct))
+    }
 }
 
 /// 美股交易时段
@@ -175,7 +248,7 @@ pub enum UsMarketSession {
     AfterHours,
     /// 夜盘 (Overnight) 20:00–04:00 ET
     Overnight,
-    /// 休市（周末）
+    /// 休市（周末 / 美股全日休市节假日）
     Closed,
 }
 
@@ -194,70 +267,356 @@ impl fmt::Display for UsMarketSession {
 impl UsMarketSession {
     /// 当前时段灰色小字（扩展价格）的标签
     ///
-    /// - 盘前/盘中/休市 → "盘前"（盘中时灰色显示盘前价，休市时灰色可能是过时的盘前价）
+    /// - 盘前/盘中 → "盘前"（盘中时灰色显示盘前价）
     /// - 盘后 → "盘后"
-    /// - 夜盘 → "夜盘"
+    /// - 夜盘/休市 → "夜盘"（休市时灰色是冻结的最后一笔夜盘成交价）
     pub fn extended_label(&self) -> &'static str {
         match self {
-            UsMarketSession::PreMarket | UsMarketSession::Regular | UsMarketSession::Closed => "盘前",
+            UsMarketSession::PreMarket | UsMarketSession::Regular => "盘前",
             UsMarketSession::AfterHours => "盘后",
-            UsMarketSession::Overnight => "夜盘",
+            UsMarketSession::Overnight | UsMarketSession::Closed => "夜盘",
         }
     }
 }
 
+/// 应用时区（`general.timezone`），未设置时使用系统本地时区
+static APP_TIMEZONE: std::sync::OnceLock<chrono_tz::Tz> = std::sync::OnceLock::new();
+
+/// 设置应用时区（启动时调用一次，重复调用忽略）
+pub fn set_app_timezone(tz: chrono_tz::Tz) {
+    let _ = APP_TIMEZONE.set(tz);
+}
+
+/// 将任意时区的时间转换到应用时区（用于显示和日期计算）
+pub fn to_app_time<T: TimeZone>(t: &DateTime<T>) -> DateTime<FixedOffset> {
+    match APP_TIMEZONE.get() {
+        Some(tz) => t.with_timezone(tz).fixed_offset(),
+        None => t.with_timezone(&Local).fixed_offset(),
+    }
+}
+
+/// 半日市：某市场在指定日期提前收盘（如港股平安夜、除夕，美股感恩节次日）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HalfDay {
+    /// 市场（沪深 A 股任填 SH / SZ 均对整个 A 股生效）
+    pub market: Market,
+    /// 交易所本地日期
+    pub date: NaiveDate,
+    /// 提前收盘时间（交易所本地，HHMM，如 1200）
+    pub close_hhmm: u32,
+}
+
+/// 半日市日历（`general.half_days`），未设置时所有交易日按常规时间收盘
+static HALF_DAYS: std::sync::OnceLock<Vec<HalfDay>> = std::sync::OnceLock::new();
+
+/// 设置半日市日历（启动时调用一次，重复调用忽略）
+pub fn set_half_days(days: Vec<HalfDay>) {
+    let _ = HALF_DAYS.set(days);
+}
+
+/// 指定市场在某日的提前收盘时间（HHMM），非半日市返回 None
+pub fn early_close_hhmm(markets: &[Market], date: NaiveDate) -> Option<u32> {
+    HALF_DAYS
+        .get()?
+        .iter()
+        .find(|d| d.date == date && markets.contains(&d.market))
+        .map(|d| d.close_hhmm)
+}
+
+/// 应用时区下的当前时间
+pub fn app_now() -> DateTime<FixedOffset> {
+    to_app_time(&Utc::now())
+}
+
+/// 应用时区下的今天日期
+pub fn app_today() -> NaiveDate {
+    app_now().date_naive()
+}
+
 /// 获取当前美股交易时段
 ///
+/// 基于 UTC 当前时刻计算，与主机时区和 `general.timezone` 无关。
+pub fn us_market_session() -> UsMarketSession {
+    us_market_session_at(&Utc::now())
+}
+
+/// 获取指定时刻的美股交易时段
+///
 /// 使用 `chrono-tz` 转换为美东时间 (America/New_York)，自动处理夏/冬令时。
 /// 仅需比较 ET 本地时间与四个分界点：04:00 / 09:30 / 16:00 / 20:00。
 ///
-/// 周末休市：周六 04:00 ET – 周日 20:00 ET
-pub fn us_market_session() -> UsMarketSession {
-    use chrono::{Datelike, Timelike, Utc};
+/// 非交易日（周末 / 节假日）：
+/// - 00:00–04:00 ET：前一日为交易日则为其夜盘延续，否则休市
+/// - 04:00–20:00 ET：休市
+/// - 20:00 起：次日为交易日则为其夜盘，否则休市
+///
+/// 即周末休市区间为周六 04:00 ET – 周日 20:00 ET。
+/// 分界点左闭右开：09:30:00 起为盘中，16:00:00 起为盘后。
+/// 半日市（`general.half_days`）盘中提前到配置的收盘时间结束，随后照常为盘后（至 20:00）与夜盘。
+pub fn us_market_session_at<T: TimeZone>(t: &DateTime<T>) -> UsMarketSession {
+    use chrono::{Days, Timelike};
     use chrono_tz::America::New_York;
 
-    let now = Utc::now().with_timezone(&New_York);
-    let weekday = now.weekday();
+    let now = t.with_timezone(&New_York);
+    let date = now.date_naive();
     let hhmm = now.hour() * 100 + now.minute();
 
-    match weekday {
-        chrono::Weekday::Sat => {
-            // 周六 00:00–04:00 ET: 周五夜盘延续
-            // 周六 04:00 之后: 休市
-            if hhmm < 400 {
-                UsMarketSession::Overnight
-            } else {
-                UsMarketSession::Closed
-            }
+    if !is_us_trading_day(date) {
+        let adjacent_trading = if hhmm < 400 {
+            date.checked_sub_days(Days::new(1)).is_some_and(is_us_trading_day)
+        } else if hhmm >= 2000 {
+            date.checked_add_days(Days::new(1)).is_some_and(is_us_trading_day)
+        } else {
+            false
+        };
+        return if adjacent_trading {
+            UsMarketSession::Overnight
+        } else {
+            UsMarketSession::Closed
+        };
+    }
+
+    let close = early_close_hhmm(&[Market::US], date).unwrap_or(1600);
+    if hhmm < 400 {
+        UsMarketSession::Overnight
+    } else if hhmm < 930 {
+        UsMarketSession::PreMarket
+    } else if hhmm < close {
+        UsMarketSession::Regular
+    } else if hhmm < 2000 {
+        UsMarketSession::AfterHours
+    } else {
+        UsMarketSession::Overnight
+    }
+}
+
+/// 美股交易日：周一至周五且非全日休市节假日
+pub fn is_us_trading_day(date: NaiveDate) -> bool {
+    use chrono::{Datelike, Weekday};
+
+    !matches!(date.weekday(), Weekday::Sat | Weekday::Sun) && !is_us_market_holiday(date)
+}
+
+/// 美股 (NYSE) 全日休市节假日，按规则计算（不含半日市）
+///
+/// 固定日期假日落在周六提前到周五、周日顺延到周一；
+/// 元旦落在周六时不补休（NYSE 规则，前一年 12/31 照常交易）。
+pub fn is_us_market_holiday(date: NaiveDate) -> bool {
+    use chrono::{Datelike, Days, Weekday};
+
+    let year = date.year();
+    let observed = |month: u32, day: u32| -> Option<NaiveDate> {
+        let d = NaiveDate::from_ymd_opt(year, month, day)?;
+        match d.weekday() {
+            Weekday::Sat => d.checked_sub_days(Days::new(1)),
+            Weekday::Sun => d.checked_add_days(Days::new(1)),
+            _ => Some(d),
         }
-        chrono::Weekday::Sun => {
-            // 周日 20:00 起: 夜盘（属于下周一交易日）
-            // 周日 20:00 之前: 休市
-            if hhmm >= 2000 {
-                UsMarketSession::Overnight
-            } else {
-                UsMarketSession::Closed
-            }
+    };
+    let nth = |month: u32, weekday: Weekday, n: u8| NaiveDate::from_weekday_of_month_opt(year, month, weekday, n);
+
+    let new_year = NaiveDate::from_ymd_opt(year, 1, 1)
+        .filter(|d| d.weekday() != Weekday::Sat)
+        .and_then(|_| observed(1, 1));
+    let good_friday = easter_sunday(year).and_then(|d| d.checked_sub_days(Days::new(2)));
+    let memorial_day = nth(5, Weekday::Mon, 5).or_else(|| nth(5, Weekday::Mon, 4));
+    let juneteenth = if year >= 2022 { observed(6, 19) } else { None };
+
+    let holidays = [
+        new_year,
+        nth(1, Weekday::Mon, 3), // 马丁·路德·金纪念日
+        nth(2, Weekday::Mon, 3), // 总统日
+        good_friday,
+        memorial_day,
+        juneteenth,
+        observed(7, 4),           // 独立日
+        nth(9, Weekday::Mon, 1),  // 劳动节
+        nth(11, Weekday::Thu, 4), // 感恩节
+        observed(12, 25),         // 圣诞节
+    ];
+    holidays.contains(&Some(date))
+}
+
+/// 复活节日期（格里高利历，Anonymous Gregorian 算法）
+fn easter_sunday(year: i32) -> Option<NaiveDate> {
+    let a = year % 19;
+    let b = year / 100;
+    let c = year % 100;
+    let d = b / 4;
+    let e = b % 4;
+    let f = (b + 8) / 25;
+    let g = (b - f + 1) / 3;
+    let h = (19 * a + b - d - g + 15) % 30;
+    let i = c / 4;
+    let k = c % 4;
+    let l = (32 + 2 * e + 2 * i - h - k) % 7;
+    let m = (a + 11 * h + 22 * l) / 451;
+    let month = (h + l - 7 * m + 114) / 31;
+    let day = (h + l - 7 * m + 114) % 31 + 1;
+    NaiveDate::from_ymd_opt(year, month as u32, day as u32)
+}
+
+/// 港股 / A 股交易时段（连续竞价 + 午休）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AsiaMarketSession {
+    /// 连续交易中
+    Open,
+    /// 午间休市
+    LunchBreak,
+    /// 休市（开盘前 / 收盘后 / 周末）
+    Closed,
+}
+
+impl fmt::Display for AsiaMarketSession {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AsiaMarketSession::Open => write!(f, "开"),
+            AsiaMarketSession::LunchBreak => write!(f, "午休"),
+            AsiaMarketSession::Closed => write!(f, "休"),
         }
-        _ => {
-            // 周一至周五
-            if hhmm < 400 {
-                UsMarketSession::Overnight
-            } else if hhmm < 930 {
-                UsMarketSession::PreMarket
-            } else if hhmm < 1600 {
-                UsMarketSession::Regular
-            } else if hhmm < 2000 {
-                UsMarketSession::AfterHours
-            } else {
-                UsMarketSession::Overnight
-            }
+    }
+}
+
+/// 指定时刻的港股时段：09:30–12:00、13:00–16:00 HKT（不含竞价时段与港股假期，半日市见 `general.half_days`）
+pub fn hk_market_session_at<T: TimeZone>(t: &DateTime<T>) -> AsiaMarketSession {
+    asia_session_at(t, chrono_tz::Asia::Hong_Kong, &[Market::HK], (930, 1200), (1300, 1600))
+}
+
+/// 指定时刻的 A 股时段：09:30–11:30、13:00–15:00 北京时间（不含集合竞价与 A 股假期，半日市见 `general.half_days`）
+pub fn cn_market_session_at<T: TimeZone>(t: &DateTime<T>) -> AsiaMarketSession {
+    asia_session_at(
+        t,
+        chrono_tz::Asia::Shanghai,
+        &[Market::SH, Market::SZ],
+        (930, 1130),
+        (1300, 1500),
+    )
+}
+
+/// 指定时刻的新加坡 (SGX) 时段：09:00–12:00、13:00–17:00 SGT（不含开收市竞价与新加坡假期）
+pub fn sg_market_session_at<T: TimeZone>(t: &DateTime<T>) -> AsiaMarketSession {
+    asia_session_at(t, chrono_tz::Asia::Singapore, &[Market::SG], (900, 1200), (1300, 1700))
+}
+
+/// 各市场统一的交易时段（仪表盘按市场开闭状态显示行）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MarketSession {
+    /// 常规/连续交易中
+    Open,
+    /// 午间休市（港股 / A 股 / 新加坡）
+    LunchBreak,
+    /// 扩展时段（美股盘前 / 盘后 / 夜盘）
+    Extended,
+    /// 休市
+    Closed,
+}
+
+impl From<AsiaMarketSession> for MarketSession {
+    fn from(s: AsiaMarketSession) -> Self {
+        match s {
+            AsiaMarketSession::Open => MarketSession::Open,
+            AsiaMarketSession::LunchBreak => MarketSession::LunchBreak,
+            AsiaMarketSession::Closed => MarketSession::Closed,
         }
     }
 }
 
+/// 当前时刻该市场的交易时段
+pub fn market_session(market: Market) -> MarketSession {
+    market_session_at(market, &Utc::now())
+}
+
+/// 指定时刻该市场的交易时段
+///
+/// 外汇按周一至周五 24 小时交易（周五 17:00 ET 收市至周日 17:00 ET 开市）；
+/// 未知市场没有交易日历，视为交易中（不因此隐去行情）。
+pub fn market_session_at<T: TimeZone>(market: Market, t: &DateTime<T>) -> MarketSession {
+    match market {
+        Market::US => match us_market_session_at(t) {
+            UsMarketSession::Regular => MarketSession::Open,
+            UsMarketSession::Closed => MarketSession::Closed,
+            _ => MarketSession::Extended,
+        },
+        Market::HK => hk_market_session_at(t).into(),
+        Market::SH | Market::SZ => cn_market_session_at(t).into(),
+        Market::SG => sg_market_session_at(t).into(),
+        Market::FX => fx_market_session_at(t),
+        Market::Unknown => MarketSession::Open,
+    }
+}
+
+/// 外汇时段：以美东 17:00 为日界，周五 17:00 至周日 17:00 休市
+fn fx_market_session_at<T: TimeZone>(t: &DateTime<T>) -> MarketSession {
+    use chrono::{Datelike, Timelike, Weekday};
+
+    let et = t.with_timezone(&chrono_tz::America::New_York);
+    let after_close = et.hour() >= 17;
+    let closed = match et.weekday() {
+        Weekday::Fri => after_close,
+        Weekday::Sat => true,
+        Weekday::Sun => !after_close,
+        _ => false,
+    };
+    if closed {
+        MarketSession::Closed
+    } else {
+        MarketSession::Open
+    }
+}
+
+/// 指定时刻该市场是否在交易（常规时段或美股盘前/盘后/夜盘）
+///
+/// 午休、休市期间行情本就不动，仪表盘据此区分"休市"与真正的行情滞后。
+pub fn market_is_open<T: TimeZone>(market: Market, t: &DateTime<T>) -> bool {
+    matches!(
+        market_session_at(market, t),
+        MarketSession::Open | MarketSession::Extended
+    )
+}
+
+/// 指定时刻是否处于该市场的常规交易时段（美股盘中、港股/A 股/新加坡连续交易、外汇工作日）
+///
+/// 未知市场没有交易日历，始终返回 true。
+pub fn is_regular_session_at<T: TimeZone>(market: Market, t: &DateTime<T>) -> bool {
+    market_session_at(market, t) == MarketSession::Open
+}
+
+/// 按交易所本地时间判断上午/下午两段连续交易，分界点左闭右开
+///
+/// 半日市：收盘时间不晚于上午收市时只有上午一段（午间起即休市），否则下午一段提前结束
+fn asia_session_at<T: TimeZone>(
+    t: &DateTime<T>,
+    tz: chrono_tz::Tz,
+    markets: &[Market],
+    morning: (u32, u32),
+    afternoon: (u32, u32),
+) -> AsiaMarketSession {
+    use chrono::{Datelike, Timelike, Weekday};
+
+    let local = t.with_timezone(&tz);
+    if matches!(local.weekday(), Weekday::Sat | Weekday::Sun) {
+        return AsiaMarketSession::Closed;
+    }
+    let hhmm = local.hour() * 100 + local.minute();
+    let (morning, afternoon) = match early_close_hhmm(markets, local.date_naive()) {
+        Some(close) if close <= morning.1 => ((morning.0, close), (close, close)),
+        Some(close) => (morning, (afternoon.0, close.min(afternoon.1))),
+        None => (morning, afternoon),
+    };
+    if hhmm >= afternoon.1 {
+        return AsiaMarketSession::Closed;
+    }
+    if (morning.0..morning.1).contains(&hhmm) || (afternoon.0..afternoon.1).contains(&hhmm) {
+        AsiaMarketSession::Open
+    } else if (morning.1..afternoon.0).contains(&hhmm) {
+        AsiaMarketSession::LunchBreak
+    } else {
+        AsiaMarketSession::Closed
+    }
+}
+
 /// 数据源类型
-#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
 pub enum DataSource {
     /// macOS Accessibility API
     Accessibility,
@@ -267,6 +626,22 @@ pub enum DataSource {
     Ocr,
     /// 本地缓存（plist 等）
     Cache,
+    /// 演示模式合成行情
+    Demo,
+}
+
+impl DataSource {
+    /// 配置中的数据源名（与 `data_source.source` 取值一致，`data_source.latency_secs` 的键）
+    pub fn from_config_key(key: &str) -> Option<Self> {
+        match key {
+            "accessibility" => Some(DataSource::Accessibility),
+            "openapi" => Some(DataSource::OpenApi),
+            "ocr" => Some(DataSource::Ocr),
+            "cache" => Some(DataSource::Cache),
+            "demo" => Some(DataSource::Demo),
+            _ => None,
+        }
+    }
 }
 
 impl fmt::Display for DataSource {
@@ -276,6 +651,29 @@ impl fmt::Display for DataSource {
             DataSource::OpenApi => write!(f, "OpenAPI"),
             DataSource::Ocr => write!(f, "OCR"),
             DataSource::Cache => write!(f, "Cache"),
+            DataSource::Demo => write!(f, "Demo"),
+        }
+    }
+}
+
+/// 数据源连接状态（仪表盘状态栏显示）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionState {
+    Connected,
+    Disconnected,
+    /// 断线后按退避重连中，`attempt` 为已失败的重连次数
+    Reconnecting {
+        attempt: u32,
+    },
+}
+
+impl fmt::Display for ConnectionState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConnectionState::Connected => write!(f, "已连接"),
+            ConnectionState::Disconnected => write!(f, "未连接"),
+            ConnectionState::Reconnecting { attempt: 0 } => write!(f, "重连中"),
+            ConnectionState::Reconnecting { attempt } => write!(f, "重连中(第{}次)", attempt + 1),
         }
     }
 }
@@ -291,12 +689,34 @@ pub struct WatchlistEntry {
     pub name: String,
     /// 缓存价格（从 plist 读取的高精度整数转换）
     pub cached_price: Option<f64>,
+    /// 缓存昨收价（plist 中的 LastClosePrice）
+    pub prev_close: Option<f64>,
     /// 在 plist 中的排序位置
     pub sort_index: usize,
 }
 
+impl WatchlistEntry {
+    /// 用 plist 缓存价格构造快照（来源标记为 Cache）
+    ///
+    /// 同时有现价和昨收时计算涨跌额/涨跌幅。
+    pub fn cached_quote(&self) -> QuoteSnapshot {
+        let mut q = QuoteSnapshot::empty(self.code.clone(), self.name.clone());
+        if let Some(price) = self.cached_price {
+            q.last_price = price;
+        }
+        if let Some(prev_close) = self.prev_close {
+            q.prev_close = prev_close;
+            if q.last_price > 0.0 {
+                q.change = q.last_price - prev_close;
+                q.change_pct = q.change / prev_close * 100.0;
+            }
+        }
+        q
+    }
+}
+
 /// 信号情绪方向
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
 pub enum Sentiment {
     Bullish, // 利多
     Bearish, // 利空
@@ -331,10 +751,17 @@ pub struct TechnicalIndicators {
     pub rsi6: Option<f64>,
     pub rsi12: Option<f64>,
     pub rsi24: Option<f64>,
+    /// 可配置周期 RSI（analysis.rsi_period），用于超买/超卖穿越信号
+    pub rsi: Option<f64>,
+
+    /// 布林带（analysis.boll_period / boll_std_multiplier）
+    pub boll_upper: Option<f64>,
+    pub boll_mid: Option<f64>,
+    pub boll_lower: Option<f64>,
 }
 
 /// 交易信号
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 pub enum Signal {
     /// MA 金叉
     MaGoldenCross { short: usize, long: usize },
@@ -356,10 +783,20 @@ pub enum Signal {
     RapidMove { change_pct: f64 },
     /// 振幅突破
     AmplitudeBreakout { amplitude_pct: f64 },
+    /// 换手率异动（当日累计换手率 %，超过阈值）
+    TurnoverRateSpike { rate: f64 },
+    /// 收盘价突破布林带（上轨=过度延伸偏空，下轨=偏多）
+    BollingerBreakout { upper: bool },
+    /// 日线量能：最新成交量 / ADV（surge=放量，否则为缩量）
+    DailyVolume { ratio: f64, surge: bool },
+    /// 跳空缺口：当日开盘相对前一日收盘的幅度 (%，绝对值)，`date` 为缺口所在K线日期
+    Gap { pct: f64, up: bool, date: String },
     /// MS-MACD 买入（空头区域动能衰减）
     MsMacdBuy,
     /// MS-MACD 卖出（多头区域动能衰减）
     MsMacdSell,
+    /// 自定义检测器产生的信号（见 `analysis::detector::SignalDetector`）
+    Custom { name: String, sentiment: Sentiment },
 }
 
 impl Signal {
@@ -388,8 +825,69 @@ impl Signal {
                 }
             }
             Signal::AmplitudeBreakout { .. } => Sentiment::Neutral,
+            Signal::TurnoverRateSpike { .. } => Sentiment::Neutral,
+            Signal::BollingerBreakout { upper } => {
+                if *upper {
+                    Sentiment::Bearish
+                } else {
+                    Sentiment::Bullish
+                }
+            }
+            Signal::DailyVolume { surge, .. } => {
+                if *surge {
+                    Sentiment::Bullish
+                } else {
+                    Sentiment::Neutral
+                }
+            }
+            Signal::Gap { up, .. } => {
+                if *up {
+                    Sentiment::Bullish
+                } else {
+                    Sentiment::Bearish
+                }
+            }
             Signal::MsMacdBuy => Sentiment::Bullish,
             Signal::MsMacdSell => Sentiment::Bearish,
+            Signal::Custom { sentiment, .. } => *sentiment,
+        }
+    }
+
+    /// 信号类型的稳定标识（用于导出，不随显示文案变化）
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Signal::MaGoldenCross { .. } => "ma_golden_cross",
+            Signal::MaDeathCross { .. } => "ma_death_cross",
+            Signal::MacdGoldenCross => "macd_golden_cross",
+            Signal::MacdDeathCross => "macd_death_cross",
+            Signal::RsiOverbought { .. } => "rsi_overbought",
+            Signal::RsiOversold { .. } => "rsi_oversold",
+            Signal::VolumeSpike { .. } => "volume_spike",
+            Signal::VwapDeviation { .. } => "vwap_deviation",
+            Signal::RapidMove { .. } => "rapid_move",
+            Signal::AmplitudeBreakout { .. } => "amplitude_breakout",
+            Signal::TurnoverRateSpike { .. } => "turnover_rate_spike",
+            Signal::BollingerBreakout { .. } => "bollinger_breakout",
+            Signal::DailyVolume { .. } => "daily_volume",
+            Signal::Gap { .. } => "gap",
+            Signal::MsMacdBuy => "ms_macd_buy",
+            Signal::MsMacdSell => "ms_macd_sell",
+            Signal::Custom { .. } => "custom",
+        }
+    }
+
+    /// 信号强度数值（倍数 / 百分比 / RSI 值），无数值的信号返回 None
+    pub fn magnitude(&self) -> Option<f64> {
+        match self {
+            Signal::RsiOverbought { value, .. } | Signal::RsiOversold { value, .. } => Some(*value),
+            Signal::VolumeSpike { ratio, .. } => Some(*ratio),
+            Signal::VwapDeviation { deviation_pct } => Some(*deviation_pct),
+            Signal::RapidMove { change_pct } => Some(*change_pct),
+            Signal::AmplitudeBreakout { amplitude_pct } => Some(*amplitude_pct),
+            Signal::TurnoverRateSpike { rate } => Some(*rate),
+            Signal::DailyVolume { ratio, .. } => Some(*ratio),
+            Signal::Gap { pct, .. } => Some(*pct),
+            _ => None,
         }
     }
 }
@@ -427,8 +925,35 @@ impl fmt::Display for Signal {
             Signal::AmplitudeBreakout { amplitude_pct } => {
                 write!(f, "振幅突破{:.1}%", amplitude_pct)
             }
+            Signal::TurnoverRateSpike { rate } => {
+                write!(f, "换手率{:.1}%", rate)
+            }
+            Signal::BollingerBreakout { upper } => {
+                if *upper {
+                    write!(f, "突破布林上轨")
+                } else {
+                    write!(f, "跌破布林下轨")
+                }
+            }
+            Signal::DailyVolume { ratio, surge } => {
+                if *surge {
+                    write!(f, "日线放量({:.1}x)", ratio)
+                } else {
+                    write!(f, "日线缩量({:.1}x)", ratio)
+                }
+            }
+            Signal::Gap { pct, up, date } => {
+                // "2025-01-15" → "01-15"
+                let day = date.get(5..).unwrap_or(date);
+                if *up {
+                    write!(f, "跳空高开{:.1}%({})", pct, day)
+                } else {
+                    write!(f, "跳空低开{:.1}%({})", pct, day)
+                }
+            }
             Signal::MsMacdBuy => write!(f, "MS-MACD 买入"),
             Signal::MsMacdSell => write!(f, "MS-MACD 卖出"),
+            Signal::Custom { name, .. } => write!(f, "{}", name),
         }
     }
 }
@@ -452,6 +977,82 @@ pub struct DailyKline {
     pub date: String,
 }
 
+/// K线周期（对应富途 Qot_Common.KLType）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KlineType {
+    Min1,
+    Min5,
+    Min15,
+    Min30,
+    Min60,
+    Day,
+    Week,
+    Month,
+}
+
+impl KlineType {
+    /// 富途 klType 取值
+    pub fn futu_kl_type(self) -> i32 {
+        match self {
+            KlineType::Min1 => 1,
+            KlineType::Day => 2,
+            KlineType::Week => 3,
+            KlineType::Month => 4,
+            KlineType::Min5 => 6,
+            KlineType::Min15 => 7,
+            KlineType::Min30 => 8,
+            KlineType::Min60 => 9,
+        }
+    }
+
+    /// 是否为日内分钟K线
+    pub fn is_intraday(self) -> bool {
+        !matches!(self, KlineType::Day | KlineType::Week | KlineType::Month)
+    }
+}
+
+impl fmt::Display for KlineType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KlineType::Min1 => write!(f, "1分"),
+            KlineType::Min5 => write!(f, "5分"),
+            KlineType::Min15 => write!(f, "15分"),
+            KlineType::Min30 => write!(f, "30分"),
+            KlineType::Min60 => write!(f, "60分"),
+            KlineType::Day => write!(f, "日"),
+            KlineType::Week => write!(f, "周"),
+            KlineType::Month => write!(f, "月"),
+        }
+    }
+}
+
+/// 任意周期的K线（`time` 为富途返回的时间串，分钟K线含时分，如 "2025-01-10 09:35:00"）
+#[derive(Debug, Clone)]
+pub struct Kline {
+    pub period: KlineType,
+    pub time: String,
+    pub open: f64,
+    pub close: f64,
+    pub high: f64,
+    pub low: f64,
+    pub volume: u64,
+    pub turnover: f64,
+}
+
+impl From<Kline> for DailyKline {
+    fn from(k: Kline) -> Self {
+        Self {
+            open: k.open,
+            close: k.close,
+            high: k.high,
+            low: k.low,
+            volume: k.volume,
+            turnover: k.turnover,
+            date: k.time,
+        }
+    }
+}
+
 /// 带时间周期标签的信号
 #[derive(Debug, Clone, PartialEq)]
 pub struct TimedSignal {
@@ -467,8 +1068,8 @@ impl fmt::Display for TimedSignal {
     }
 }
 
-/// 提醒事件
-#[derive(Debug, Clone)]
+/// 提醒事件（按 JSON 行追加到 `alerts.log_path`）
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct AlertEvent {
     /// 股票代码
     pub code: StockCode,
@@ -487,7 +1088,7 @@ pub struct AlertEvent {
 }
 
 /// 提醒级别
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub enum AlertSeverity {
     Info,
     Warning,
@@ -498,6 +1099,36 @@ pub enum AlertSeverity {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_all_known_markets_handled() {
+        assert!(!Market::all().contains(&Market::Unknown));
+        assert!(Market::all().contains(&Market::SG));
+        for market in Market::all() {
+            // Display 与 from_prefix 互逆（大小写不敏感）
+            let prefix = market.to_string();
+            assert_ne!(prefix, "??");
+            assert_eq!(Market::from_prefix(&prefix), Some(market));
+            assert_eq!(Market::from_prefix(&prefix.to_lowercase()), Some(market));
+            assert_ne!(market.label(), Market::Unknown.label());
+            // 带市场前缀的代码可解析回原市场
+            let code = StockCode::new(market, "123");
+            assert_eq!(crate::data::parser::parse_stock_code(&code.display_code()), Some(code));
+        }
+        assert_eq!(Market::from_prefix("??"), None);
+    }
+
+    #[test]
+    fn test_futu_code_roundtrip() {
+        for market in Market::all() {
+            if let Some(code) = market.futu_code() {
+                assert_eq!(Market::from_futu_code(code), Some(market));
+            }
+        }
+        assert_eq!(Market::SG.futu_code(), Some(13));
+        assert_eq!(Market::FX.futu_code(), None);
+        assert_eq!(Market::from_futu_code(999), None);
+    }
+
     #[test]
     fn test_is_index() {
         assert!(StockCode::new(Market::SH, "000001").is_index()); // 上证指数
@@ -508,4 +1139,300 @@ mod tests {
         assert!(!StockCode::new(Market::HK, "00700").is_index()); // 腾讯
         assert!(!StockCode::new(Market::US, "AAPL").is_index()); // 苹果
     }
+
+    /// 指定美东时间的美股时段
+    fn session_et(y: i32, mo: u32, d: u32, h: u32, mi: u32) -> UsMarketSession {
+        let t = chrono_tz::America::New_York
+            .with_ymd_and_hms(y, mo, d, h, mi, 0)
+            .single()
+            .unwrap();
+        us_market_session_at(&t)
+    }
+
+    #[test]
+    fn test_watchlist_entry_cached_quote() {
+        let mut entry = WatchlistEntry {
+            code: StockCode::new(Market::HK, "00700"),
+            stock_id: 54047868453564,
+            name: "腾讯控股".to_string(),
+            cached_price: Some(606.0),
+            prev_close: Some(600.0),
+            sort_index: 0,
+        };
+        let q = entry.cached_quote();
+        assert_eq!(q.source, DataSource::Cache);
+        assert_eq!(q.last_price, 606.0);
+        assert_eq!(q.prev_close, 600.0);
+        assert!((q.change - 6.0).abs() < 1e-9);
+        assert!((q.change_pct - 1.0).abs() < 1e-9);
+
+        // 缺昨收时不计算涨跌
+        entry.prev_close = None;
+        let q = entry.cached_quote();
+        assert_eq!(q.change_pct, 0.0);
+    }
+
+    #[test]
+    fn test_us_session_weekday_boundaries() {
+        use UsMarketSession::*;
+        // 2024-03-12 周二，分界点左闭右开
+        assert_eq!(session_et(2024, 3, 12, 3, 59), Overnight);
+        assert_eq!(session_et(2024, 3, 12, 4, 0), PreMarket);
+        assert_eq!(session_et(2024, 3, 12, 9, 29), PreMarket);
+        assert_eq!(session_et(2024, 3, 12, 9, 30), Regular);
+        assert_eq!(session_et(2024, 3, 12, 15, 59), Regular);
+        assert_eq!(session_et(2024, 3, 12, 16, 0), AfterHours);
+        assert_eq!(session_et(2024, 3, 12, 19, 59), AfterHours);
+        assert_eq!(session_et(2024, 3, 12, 20, 0), Overnight);
+    }
+
+    #[test]
+    fn test_extended_change() {
+        let mut q = QuoteSnapshot::empty(StockCode::new(Market::US, "NVDA"), "英伟达".to_string());
+        q.last_price = 100.0;
+        q.extended_price = Some(108.0);
+        let (price, change, pct) = q.extended_change(UsMarketSession::AfterHours).unwrap();
+        assert_eq!(price, 108.0);
+        assert!((change - 8.0).abs() < 1e-9);
+        assert!((pct - 8.0).abs() < 1e-9);
+        // 盘中 / 非美股 / 扩展价未变动 → None
+        assert!(q.extended_change(UsMarketSession::Regular).is_none());
+        q.extended_price = Some(100.0);
+        assert!(q.extended_change(UsMarketSession::AfterHours).is_none());
+        q.extended_price = Some(108.0);
+        q.code.market = Market::HK;
+        assert!(q.extended_change(UsMarketSession::AfterHours).is_none());
+    }
+
+    #[test]
+    fn test_asia_sessions() {
+        use AsiaMarketSession::*;
+        // 2024-03-12 周二，港股与 A 股同为 UTC+8
+        let at = |h: u32, mi: u32| Utc.with_ymd_and_hms(2024, 3, 12, h, mi, 0).unwrap() - chrono::Duration::hours(8);
+        assert_eq!(hk_market_session_at(&at(9, 29)), Closed);
+        assert_eq!(hk_market_session_at(&at(9, 30)), Open);
+        assert_eq!(hk_market_session_at(&at(11, 45)), Open);
+        assert_eq!(cn_market_session_at(&at(11, 45)), LunchBreak);
+        assert_eq!(hk_market_session_at(&at(12, 0)), LunchBreak);
+        assert_eq!(hk_market_session_at(&at(13, 0)), Open);
+        assert_eq!(cn_market_session_at(&at(15, 0)), Closed);
+        assert_eq!(hk_market_session_at(&at(15, 59)), Open);
+        assert_eq!(hk_market_session_at(&at(16, 0)), Closed);
+        // 2024-03-16 周六
+        let sat = Utc.with_ymd_and_hms(2024, 3, 16, 2, 0, 0).unwrap();
+        assert_eq!(hk_market_session_at(&sat), Closed);
+    }
+
+    #[test]
+    fn test_market_session_all_markets() {
+        use MarketSession::*;
+        // 2024-03-12 周二 UTC 02:30 = 港/沪/新 10:30，美东 22:30（夜盘）
+        let tue = Utc.with_ymd_and_hms(2024, 3, 12, 2, 30, 0).unwrap();
+        assert_eq!(market_session_at(Market::HK, &tue), Open);
+        assert_eq!(market_session_at(Market::SH, &tue), Open);
+        assert_eq!(market_session_at(Market::SG, &tue), Open);
+        assert_eq!(market_session_at(Market::US, &tue), Extended);
+        assert_eq!(market_session_at(Market::FX, &tue), Open);
+
+        // 本地 12:30：新加坡、A 股午休
+        let lunch = Utc.with_ymd_and_hms(2024, 3, 12, 4, 30, 0).unwrap();
+        assert_eq!(market_session_at(Market::SG, &lunch), LunchBreak);
+        assert_eq!(market_session_at(Market::SZ, &lunch), LunchBreak);
+
+        // 本地 16:30：港股、A 股已收市，新加坡下午盘到 17:00
+        let late = Utc.with_ymd_and_hms(2024, 3, 12, 8, 30, 0).unwrap();
+        assert_eq!(market_session_at(Market::HK, &late), Closed);
+        assert_eq!(market_session_at(Market::SH, &late), Closed);
+        assert_eq!(market_session_at(Market::SG, &late), Open);
+        assert!(!is_regular_session_at(Market::HK, &late));
+        assert!(is_regular_session_at(Market::SG, &late));
+
+        // 外汇周末休市：周五 17:00 ET 起至周日 17:00 ET
+        let et = |d: u32, h: u32| {
+            chrono_tz::America::New_York
+                .with_ymd_and_hms(2024, 3, d, h, 0, 0)
+                .unwrap()
+        };
+        assert_eq!(market_session_at(Market::FX, &et(15, 16)), Open);
+        assert_eq!(market_session_at(Market::FX, &et(15, 17)), Closed);
+        assert_eq!(market_session_at(Market::FX, &et(16, 12)), Closed);
+        assert_eq!(market_session_at(Market::FX, &et(17, 16)), Closed);
+        assert_eq!(market_session_at(Market::FX, &et(17, 17)), Open);
+
+        assert_eq!(market_session_at(Market::Unknown, &et(16, 12)), Open);
+    }
+
+    #[test]
+    fn test_market_is_open_hk_lunch_boundaries() {
+        // 2024-03-12 周二，分界点左闭右开
+        let hkt = |h: u32, mi: u32, s: u32| {
+            chrono_tz::Asia::Hong_Kong
+                .with_ymd_and_hms(2024, 3, 12, h, mi, s)
+                .unwrap()
+        };
+        assert!(market_is_open(Market::HK, &hkt(11, 59, 59)));
+        assert!(!market_is_open(Market::HK, &hkt(12, 0, 0)));
+        assert!(!market_is_open(Market::HK, &hkt(12, 59, 59)));
+        assert!(market_is_open(Market::HK, &hkt(13, 0, 0)));
+        // 同一时刻美股夜盘仍在交易
+        assert!(market_is_open(Market::US, &hkt(12, 30, 0)));
+        assert!(!is_regular_session_at(Market::US, &hkt(12, 30, 0)));
+    }
+
+    #[test]
+    fn test_half_day_sessions() {
+        use AsiaMarketSession::*;
+        let d = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        // 全局日历只设置一次，日期选其他测试未用到的交易日
+        set_half_days(vec![
+            HalfDay {
+                market: Market::HK,
+                date: d(2024, 12, 24),
+                close_hhmm: 1200,
+            },
+            HalfDay {
+                market: Market::SZ,
+                date: d(2024, 12, 31),
+                close_hhmm: 1430,
+            },
+            HalfDay {
+                market: Market::US,
+                date: d(2024, 11, 29),
+                close_hhmm: 1300,
+            },
+        ]);
+
+        // 港股半日市：只有上午，12:00 起休市（不是午休）
+        let hkt = |m: u32, day: u32, h: u32, mi: u32| {
+            Utc.with_ymd_and_hms(2024, m, day, h, mi, 0).unwrap() - chrono::Duration::hours(8)
+        };
+        assert_eq!(hk_market_session_at(&hkt(12, 24, 11, 59)), Open);
+        assert_eq!(hk_market_session_at(&hkt(12, 24, 12, 0)), Closed);
+        assert_eq!(hk_market_session_at(&hkt(12, 24, 14, 0)), Closed);
+        // 同日 A 股不受影响
+        assert_eq!(cn_market_session_at(&hkt(12, 24, 14, 0)), Open);
+        // A 股（配置 SZ 对整个 A 股生效）下午提前收盘
+        assert_eq!(cn_market_session_at(&hkt(12, 31, 12, 0)), LunchBreak);
+        assert_eq!(cn_market_session_at(&hkt(12, 31, 14, 29)), Open);
+        assert_eq!(cn_market_session_at(&hkt(12, 31, 14, 30)), Closed);
+        assert_eq!(hk_market_session_at(&hkt(12, 31, 14, 30)), Open);
+
+        // 美股感恩节次日 13:00 收盘，盘后提前开始，20:00 起照常夜盘
+        assert_eq!(session_et(2024, 11, 29, 12, 59), UsMarketSession::Regular);
+        assert_eq!(session_et(2024, 11, 29, 13, 0), UsMarketSession::AfterHours);
+        assert_eq!(session_et(2024, 11, 29, 17, 0), UsMarketSession::AfterHours);
+        assert_eq!(session_et(2024, 11, 29, 20, 0), UsMarketSession::Overnight);
+        // 提前收盘后的盘后价照常替代收盘价
+        let mut q = QuoteSnapshot::empty(StockCode::new(Market::US, "AAPL"), "苹果".to_string());
+        q.last_price = 100.0;
+        q.extended_price = Some(101.0);
+        q.timestamp = chrono_tz::America::New_York
+            .with_ymd_and_hms(2024, 11, 29, 14, 0, 0)
+            .unwrap()
+            .with_timezone(&Local);
+        let session = us_market_session_at(&q.timestamp);
+        assert_eq!(session, UsMarketSession::AfterHours);
+        assert_eq!(q.extended_change(session).map(|(price, _, _)| price), Some(101.0));
+        assert_eq!(early_close_hhmm(&[Market::US], d(2024, 11, 29)), Some(1300));
+        assert_eq!(early_close_hhmm(&[Market::HK], d(2024, 11, 29)), None);
+    }
+
+    #[test]
+    fn test_us_session_dst_uses_et_not_fixed_offset() {
+        // 同为 UTC 13:30：DST 前 (EST, UTC-5) 是 08:30 盘前，DST 后 (EDT, UTC-4) 是 09:30 开盘
+        let before = Utc.with_ymd_and_hms(2024, 3, 8, 13, 30, 0).unwrap();
+        let after = Utc.with_ymd_and_hms(2024, 3, 11, 13, 30, 0).unwrap();
+        assert_eq!(us_market_session_at(&before), UsMarketSession::PreMarket);
+        assert_eq!(us_market_session_at(&after), UsMarketSession::Regular);
+
+        // 11 月 DST 结束：同为 UTC 20:00，EDT 是 16:00 盘后，EST 是 15:00 盘中
+        let nov_edt = Utc.with_ymd_and_hms(2024, 11, 1, 20, 0, 0).unwrap();
+        let nov_est = Utc.with_ymd_and_hms(2024, 11, 4, 20, 0, 0).unwrap();
+        assert_eq!(us_market_session_at(&nov_edt), UsMarketSession::AfterHours);
+        assert_eq!(us_market_session_at(&nov_est), UsMarketSession::Regular);
+    }
+
+    #[test]
+    fn test_us_session_weekend() {
+        use UsMarketSession::*;
+        // 周五晚 → 周六凌晨为周五夜盘延续
+        assert_eq!(session_et(2024, 3, 15, 21, 0), Overnight);
+        assert_eq!(session_et(2024, 3, 16, 3, 59), Overnight);
+        assert_eq!(session_et(2024, 3, 16, 4, 0), Closed);
+        assert_eq!(session_et(2024, 3, 16, 21, 0), Closed);
+        assert_eq!(session_et(2024, 3, 17, 12, 0), Closed);
+        // 周日 20:00 起为周一夜盘
+        assert_eq!(session_et(2024, 3, 17, 19, 59), Closed);
+        assert_eq!(session_et(2024, 3, 17, 20, 0), Overnight);
+    }
+
+    #[test]
+    fn test_us_session_holidays() {
+        use UsMarketSession::*;
+        assert_eq!(session_et(2024, 12, 25, 10, 0), Closed); // 圣诞节
+        assert_eq!(session_et(2024, 3, 29, 12, 0), Closed); // 耶稣受难日
+
+        // 感恩节：凌晨为前一交易日夜盘延续，白天休市
+        assert_eq!(session_et(2024, 11, 28, 2, 0), Overnight);
+        assert_eq!(session_et(2024, 11, 28, 10, 0), Closed);
+        // 劳动节晚间为次日夜盘
+        assert_eq!(session_et(2024, 9, 2, 12, 0), Closed);
+        assert_eq!(session_et(2024, 9, 2, 20, 0), Overnight);
+        // 节假日晚间若次日也非交易日，不进入夜盘（耶稣受难日 → 周六）
+        assert_eq!(session_et(2024, 3, 29, 20, 0), Closed);
+    }
+
+    #[test]
+    fn test_is_us_market_holiday() {
+        let d = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        assert!(is_us_market_holiday(d(2024, 1, 1))); // 元旦
+        assert!(is_us_market_holiday(d(2024, 1, 15))); // MLK
+        assert!(is_us_market_holiday(d(2024, 2, 19))); // 总统日
+        assert!(is_us_market_holiday(d(2024, 3, 29))); // 耶稣受难日
+        assert!(is_us_market_holiday(d(2025, 4, 18))); // 耶稣受难日
+        assert!(is_us_market_holiday(d(2024, 5, 27))); // 阵亡将士纪念日
+        assert!(is_us_market_holiday(d(2022, 6, 20))); // 六月节周日 → 周一补休
+        assert!(is_us_market_holiday(d(2026, 7, 3))); // 独立日周六 → 周五补休
+        assert!(is_us_market_holiday(d(2024, 9, 2))); // 劳动节
+        assert!(is_us_market_holiday(d(2023, 1, 2))); // 元旦周日 → 周一补休
+        assert!(!is_us_market_holiday(d(2021, 12, 31))); // 元旦周六不补休
+        assert!(!is_us_market_holiday(d(2021, 6, 18))); // 六月节 2022 年起
+        assert!(!is_us_market_holiday(d(2024, 3, 12)));
+    }
+
+    #[test]
+    fn test_extended_label() {
+        assert_eq!(UsMarketSession::PreMarket.extended_label(), "盘前");
+        assert_eq!(UsMarketSession::Regular.extended_label(), "盘前");
+        assert_eq!(UsMarketSession::AfterHours.extended_label(), "盘后");
+        assert_eq!(UsMarketSession::Overnight.extended_label(), "夜盘");
+        assert_eq!(UsMarketSession::Closed.extended_label(), "夜盘");
+    }
+
+    #[test]
+    fn test_kline_type_futu_values() {
+        let values: Vec<i32> = [
+            KlineType::Min1,
+            KlineType::Min5,
+            KlineType::Min15,
+            KlineType::Min30,
+            KlineType::Min60,
+            KlineType::Day,
+            KlineType::Week,
+            KlineType::Month,
+        ]
+        .into_iter()
+        .map(KlineType::futu_kl_type)
+        .collect();
+        assert_eq!(values, [1, 6, 7, 8, 9, 2, 3, 4]);
+        assert!(KlineType::Min5.is_intraday());
+        assert!(!KlineType::Week.is_intraday());
+    }
+
+    #[test]
+    fn test_connection_state_label() {
+        assert_eq!(ConnectionState::Connected.to_string(), "已连接");
+        assert_eq!(ConnectionState::Reconnecting { attempt: 0 }.to_string(), "重连中");
+        assert_eq!(ConnectionState::Reconnecting { attempt: 2 }.to_string(), "重连中(第3次)");
+    }
 }
@@ -3,6 +3,9 @@
 //! 大部分操作通过后台 AX API 完成（表单填写、按钮点击、弹窗验证）。
 //! 导航和 AXIncrementor 输入需要短暂激活窗口（前台 CGEvent 点击/粘贴）。
 //! 安全不变量：验价通过后才点击确认，任何步骤失败自动清理弹窗。
+//! 模拟模式（dry run）同样走完导航、填单与验价，最后取消确认弹窗而不提交。
+
+use std::path::PathBuf;
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
@@ -10,6 +13,8 @@ use tracing::{debug, info, warn};
 
 use crate::futu::accessibility::AccessibilityReader;
 use crate::futu::ax::{self, Element};
+use crate::futu::window_focus::activate_app;
+use crate::trading::journal::{self, OrderRecord};
 
 /// 交易方向
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -28,7 +33,7 @@ impl std::fmt::Display for OrderSide {
 }
 
 /// 交易市场
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum TradingMarket {
     /// 港股（通过港股通）
     HK,
@@ -80,7 +85,7 @@ impl std::fmt::Display for TradingMarket {
 }
 
 /// 委托请求
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OrderRequest {
     /// 股票代码（如 "00700"、"600519"）
     pub stock_code: String,
@@ -95,7 +100,7 @@ pub struct OrderRequest {
 }
 
 /// 委托结果
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OrderResult {
     pub success: bool,
     pub message: String,
@@ -107,6 +112,10 @@ pub struct OrderResult {
 /// 交易执行器
 pub struct TradingExecutor {
     app_pid: i32,
+    /// 模拟模式：验价通过后取消弹窗，不真正提交委托
+    dry_run: bool,
+    /// 委托日志路径（None 不落盘）
+    journal: Option<PathBuf>,
 }
 
 impl TradingExecutor {
@@ -124,7 +133,23 @@ impl TradingExecutor {
         let _window = prepare_trading_window(pid).context("无法获取财富通窗口")?;
 
         info!("TradingExecutor initialized, PID={}", pid);
-        Ok(Self { app_pid: pid })
+        Ok(Self {
+            app_pid: pid,
+            dry_run: false,
+            journal: None,
+        })
+    }
+
+    /// 设置模拟模式
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// 设置委托日志路径，每次委托的请求与结果追加一行
+    pub fn with_journal(mut self, path: Option<PathBuf>) -> Self {
+        self.journal = path;
+        self
     }
 
     /// 获取交易客户端 PID
@@ -132,13 +157,19 @@ impl TradingExecutor {
         self.app_pid
     }
 
+    /// 是否为模拟模式
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
     /// 执行委托
     pub async fn execute_order(&self, req: &OrderRequest) -> Result<OrderResult> {
         let timestamp = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
         let prec = req.market.price_decimals();
         let currency = req.market.currency();
         info!(
-            "开始执行委托: {} {} {} {} 股 @ {:.prec$} {}",
+            "开始执行委托{}: {} {} {} {} 股 @ {:.prec$} {}",
+            if self.dry_run { "（模拟）" } else { "" },
             req.market,
             req.side,
             req.stock_code,
@@ -155,38 +186,60 @@ impl TradingExecutor {
         let quantity = req.quantity;
         let side = req.side;
         let market = req.market;
-
-        let result =
-            tokio::task::spawn_blocking(move || execute_order_sync(pid, &stock_code, price, quantity, side, market))
-                .await
-                .map_err(|e| anyhow::anyhow!("spawn_blocking failed: {}", e))?;
-
-        match result {
+        let dry_run = self.dry_run;
+
+        let (reading, result) = tokio::task::spawn_blocking(move || {
+            let mut reading = DialogReading::default();
+            let result = execute_order_sync(pid, &stock_code, price, quantity, side, market, dry_run, &mut reading);
+            (reading, result)
+        })
+        .await
+        .map_err(|e| anyhow::anyhow!("spawn_blocking failed: {}", e))?;
+
+        // verified_* 记录 AX 实际从确认弹窗读到的内容（未读到弹窗为 None），而非请求值
+        let result = match result {
             Ok(msg) => {
                 info!("委托成功: {}", msg);
-                Ok(OrderResult {
+                OrderResult {
                     success: true,
                     message: msg,
-                    verified_price: Some(price),
-                    verified_code: Some(req.stock_code.clone()),
+                    verified_price: reading.price,
+                    verified_code: reading.code,
                     timestamp,
-                })
+                }
             }
             Err(e) => {
                 warn!("委托失败: {}", e);
-                Ok(OrderResult {
+                OrderResult {
                     success: false,
                     message: format!("{}", e),
-                    verified_price: None,
-                    verified_code: None,
+                    verified_price: reading.price,
+                    verified_code: reading.code,
                     timestamp,
-                })
+                }
+            }
+        };
+
+        // 日志写入失败只告警，不影响委托结果
+        if let Some(path) = &self.journal {
+            let record = OrderRecord {
+                request: req.clone(),
+                result: result.clone(),
+                dry_run: self.dry_run,
+            };
+            if let Err(e) = journal::append(path, &record) {
+                warn!("{:#}", e);
             }
         }
+        Ok(result)
     }
 }
 
 /// 同步执行委托流程（在 spawn_blocking 中运行）
+///
+/// `dry_run` 时验价通过后取消确认弹窗，跳过 T7/T8。
+/// 读到确认弹窗后将 AX 识别出的代码/价格写入 `reading`（无论后续成败），供委托日志审计。
+#[allow(clippy::too_many_arguments)]
 fn execute_order_sync(
     pid: i32,
     stock_code: &str,
@@ -194,6 +247,8 @@ fn execute_order_sync(
     quantity: u32,
     side: OrderSide,
     market: TradingMarket,
+    dry_run: bool,
+    reading: &mut DialogReading,
 ) -> Result<String> {
     let window = prepare_trading_window(pid).context("无法获取交易窗口")?;
 
@@ -268,14 +323,29 @@ fn execute_order_sync(
     debug!("确认弹窗已出现");
 
     // T6: AX 验价 — 从弹窗 AXStaticText 中提取并比对代码和价格
-    let verified = verify_dialog_content(&confirm_dialog, stock_code, price, market)?;
-    if !verified {
+    *reading = verify_dialog_content(&confirm_dialog, stock_code, price, market);
+    if !reading.matches(stock_code) {
         // 价格不匹配，取消
         try_cancel_dialog(&confirm_dialog);
         anyhow::bail!("AX 验价失败：弹窗内容与预期不符");
     }
     debug!("验价通过");
 
+    if dry_run {
+        // 弹窗已验价、处于可提交状态：必须确认已关闭，否则误按回车就会真实下单
+        if !try_cancel_dialog(&confirm_dialog) || !wait_for_dialog_closed(pid, &confirm_dialog, 1500) {
+            anyhow::bail!("模拟委托取消失败：确认弹窗可能仍然打开，请手动关闭，切勿按回车");
+        }
+        info!(
+            "模拟委托（未提交）: {} {} {} 股 @ {} {}",
+            side, stock_code, quantity, price_str, currency
+        );
+        return Ok(format!(
+            "模拟委托（未提交）: {} {} {} 股 @ {} {}",
+            side, stock_code, quantity, price_str, currency
+        ));
+    }
+
     // T7: 点击确认按钮
     let confirm_btn = find_confirm_button(&confirm_dialog).context("未找到确认按钮")?;
     confirm_btn.click().context("点击确认按钮失败")?;
@@ -341,34 +411,6 @@ fn prepare_trading_window(pid: i32) -> Result<Element> {
     Ok(window)
 }
 
-/// 激活应用到前台（处理跨桌面/Space 场景）
-fn activate_app(pid: i32) {
-    #[repr(C)]
-    #[derive(Copy, Clone)]
-    struct ProcessSerialNumber {
-        high: u32,
-        low: u32,
-    }
-
-    extern "C" {
-        fn GetProcessForPID(pid: i32, psn: *mut ProcessSerialNumber) -> i32;
-        fn SetFrontProcess(psn: *const ProcessSerialNumber) -> i32;
-    }
-
-    unsafe {
-        let mut psn = ProcessSerialNumber { high: 0, low: 0 };
-        if GetProcessForPID(pid, &mut psn) == 0 {
-            let status = SetFrontProcess(&psn);
-            if status != 0 {
-                debug!("SetFrontProcess failed: {}", status);
-            }
-        } else {
-            debug!("GetProcessForPID({}) failed", pid);
-        }
-    }
-    std::thread::sleep(std::time::Duration::from_millis(200));
-}
-
 /// 通过状态栏图标菜单恢复主窗口
 ///
 /// AX 路径: AXApplication → AXExtrasMenuBar → AXMenuBarItem → 坐标点击
@@ -588,7 +630,21 @@ fn wait_for_confirm_dialog(pid: i32, timeout_ms: u64) -> Option<Element> {
     }
 }
 
-/// 从弹窗中验证内容（代码 + 价格）
+/// AX 从确认弹窗中读到的代码与价格（未读到为 None）
+#[derive(Debug, Clone, Default, PartialEq)]
+struct DialogReading {
+    code: Option<String>,
+    price: Option<f64>,
+}
+
+impl DialogReading {
+    /// 代码与预期一致且读到了预期价格
+    fn matches(&self, expected_code: &str) -> bool {
+        self.code.as_deref() == Some(expected_code) && self.price.is_some()
+    }
+}
+
+/// 从弹窗中读取并验证内容（代码 + 价格）
 ///
 /// 通过 AX 读取弹窗中的文本元素，检查是否包含预期代码和价格。
 fn verify_dialog_content(
@@ -596,7 +652,7 @@ fn verify_dialog_content(
     expected_code: &str,
     expected_price: f64,
     market: TradingMarket,
-) -> Result<bool> {
+) -> DialogReading {
     // 收集弹窗中所有 AXStaticText 的文本
     let texts = dialog.find_all(|e| e.role().as_deref() == Some("AXStaticText"), 8);
 
@@ -611,33 +667,46 @@ fn verify_dialog_content(
 
     debug!("弹窗文本: {}", all_text);
 
-    // 验证代码
-    let code_found = all_text.contains(expected_code);
-    if !code_found {
+    let reading = read_dialog_text(&all_text, expected_code, expected_price, market);
+    if reading.code.as_deref() != Some(expected_code) {
         warn!("弹窗中未找到代码 '{}', 文本: {}", expected_code, all_text);
-        return Ok(false);
+    } else if reading.price.is_none() {
+        warn!(
+            "弹窗中未找到价格 {:.prec$}, 文本: {}",
+            expected_price,
+            all_text,
+            prec = market.price_decimals()
+        );
     }
+    reading
+}
 
-    // 验证价格（允许小数格式差异：检查 market 精度 + 相邻精度）
-    let prec = market.price_decimals();
-    let price_str = format!("{:.prec$}", expected_price, prec = prec);
-    let price_str_alt = if prec >= 1 {
-        format!("{:.prec$}", expected_price, prec = prec - 1)
+/// 从弹窗文本中识别代码与价格
+///
+/// 代码：包含预期代码则取之，否则取文本中第一个同长度的数字串（弹窗实际显示的代码）。
+/// 价格：允许小数格式差异，依次检查 market 精度、少一位、多一位的格式，取匹配到的那个。
+fn read_dialog_text(all_text: &str, expected_code: &str, expected_price: f64, market: TradingMarket) -> DialogReading {
+    let code = if all_text.contains(expected_code) {
+        Some(expected_code.to_string())
     } else {
-        format!("{:.1}", expected_price)
+        all_text
+            .split(|c: char| !c.is_ascii_digit())
+            .find(|token| token.len() == expected_code.len())
+            .map(str::to_string)
     };
-    let price_str_extra = format!("{:.prec$}", expected_price, prec = prec + 1);
-    let price_found =
-        all_text.contains(&price_str) || all_text.contains(&price_str_alt) || all_text.contains(&price_str_extra);
-    if !price_found {
-        warn!(
-            "弹窗中未找到价格 '{}' 或 '{}', 文本: {}",
-            price_str, price_str_alt, all_text
-        );
-        return Ok(false);
-    }
 
-    Ok(true)
+    let prec = market.price_decimals();
+    let candidates = [
+        format!("{:.prec$}", expected_price, prec = prec),
+        format!("{:.prec$}", expected_price, prec = prec.checked_sub(1).unwrap_or(1)),
+        format!("{:.prec$}", expected_price, prec = prec + 1),
+    ];
+    let price = candidates
+        .iter()
+        .find(|p| all_text.contains(p.as_str()))
+        .and_then(|p| p.parse().ok());
+
+    DialogReading { code, price }
 }
 
 /// 检查是否出现错误弹窗（"委托失败"/"关闭(C)" 等）
@@ -691,8 +760,8 @@ fn check_error_dialog(pid: i32) -> Option<String> {
     None
 }
 
-/// 尝试取消弹窗（找取消/关闭按钮并点击）
-fn try_cancel_dialog(dialog: &Element) {
+/// 尝试取消弹窗（找取消/关闭按钮并点击），返回是否点击成功
+fn try_cancel_dialog(dialog: &Element) -> bool {
     // 尝试找"取消"按钮
     let cancel_labels = ["取消", "关闭", "Cancel", "Close"];
     for label in &cancel_labels {
@@ -700,12 +769,33 @@ fn try_cancel_dialog(dialog: &Element) {
             |e| e.role().as_deref() == Some("AXButton") && e.title().map_or(false, |t| t.contains(label)),
             5,
         ) {
-            let _ = btn.click();
+            if let Err(e) = btn.click() {
+                warn!("点击 '{}' 失败: {:?}", label, e);
+                return false;
+            }
             debug!("已点击 '{}' 关闭弹窗", label);
-            return;
+            return true;
         }
     }
     warn!("未找到弹窗取消按钮，弹窗可能仍然打开");
+    false
+}
+
+/// 等待确认弹窗关闭，超时返回 false
+///
+/// 弹窗元素已失效（读不到 AXRole），或所有窗口中已没有含确认按钮的窗口，视为已关闭。
+fn wait_for_dialog_closed(pid: i32, dialog: &Element, timeout_ms: u64) -> bool {
+    let start = std::time::Instant::now();
+    let timeout = std::time::Duration::from_millis(timeout_ms);
+    loop {
+        if dialog.role().is_none() || wait_for_confirm_dialog(pid, 0).is_none() {
+            return true;
+        }
+        if start.elapsed() >= timeout {
+            return false;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(100));
+    }
 }
 
 /// 在弹窗中找到确认/委托按钮
@@ -777,3 +867,40 @@ pub fn get_cft5_main_window(app: &ax::Application) -> Result<Element> {
     // 没找到标准窗口，返回第一个窗口
     windows.into_iter().next().context("No windows found")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_dialog_text() {
+        // 代码与价格均匹配（港股 3 位小数）
+        let text = "证券代码 00700 腾讯控股 委托价格 388.200 委托数量 100 ";
+        let reading = read_dialog_text(text, "00700", 388.2, TradingMarket::HK);
+        assert_eq!(reading.code.as_deref(), Some("00700"));
+        assert_eq!(reading.price, Some(388.2));
+        assert!(reading.matches("00700"));
+
+        // 弹窗价格少一位小数也算匹配
+        let reading = read_dialog_text("600519 贵州茅台 价格 1650.5 ", "600519", 1650.5, TradingMarket::CN);
+        assert_eq!(reading.price, Some(1650.5));
+        assert!(reading.matches("600519"));
+
+        // 弹窗显示的是另一只股票：记录实际看到的代码
+        let reading = read_dialog_text("证券代码 09988 委托价格 388.200 ", "00700", 388.2, TradingMarket::HK);
+        assert_eq!(reading.code.as_deref(), Some("09988"));
+        assert!(!reading.matches("00700"));
+
+        // 价格不符：代码读到，价格为 None
+        let reading = read_dialog_text("证券代码 00700 委托价格 389.000 ", "00700", 388.2, TradingMarket::HK);
+        assert_eq!(reading.code.as_deref(), Some("00700"));
+        assert_eq!(reading.price, None);
+        assert!(!reading.matches("00700"));
+
+        // 空文本
+        assert_eq!(
+            read_dialog_text("", "00700", 388.2, TradingMarket::HK),
+            DialogReading::default()
+        );
+    }
+}
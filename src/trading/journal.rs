@@ -0,0 +1,131 @@
+//! 委托日志：每次委托的请求与结果以 JSON 行追加到 `mcp.order_log_path`，供 `qtrade orders` 复盘
+//!
+//! 每行是请求字段与 `OrderResult` 展平后的一条记录，可直接比对委托价与 AX 验价读到的
+//! `verified_price` / `verified_code`；文件只追加不改写，跨多次运行累积。
+
+use std::collections::VecDeque;
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use super::executor::{OrderRequest, OrderResult};
+
+/// 一条委托记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderRecord {
+    #[serde(flatten)]
+    pub request: OrderRequest,
+    #[serde(flatten)]
+    pub result: OrderResult,
+    /// 模拟委托（验价后取消，未提交）
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// 追加写入一条委托记录（追加模式打开，文件不存在时创建）
+pub fn append(path: &Path, record: &OrderRecord) -> Result<()> {
+    let mut line = serde_json::to_string(record)?;
+    line.push('\n');
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut f| f.write_all(line.as_bytes()))
+        .with_context(|| format!("写入委托日志 {} 失败", path.display()))
+}
+
+/// 读取最近 `count` 条委托（按时间正序）；文件不存在返回 `None`，无法解析的行跳过
+pub fn read_last(path: &Path, count: usize) -> Result<Option<Vec<OrderRecord>>> {
+    let file = match std::fs::File::open(path) {
+        Ok(f) => f,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e).with_context(|| format!("无法读取委托日志 {}", path.display())),
+    };
+
+    let mut last: VecDeque<OrderRecord> = VecDeque::with_capacity(count.min(1024));
+    for (i, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<OrderRecord>(&line) {
+            Ok(record) => {
+                if last.len() == count {
+                    last.pop_front();
+                }
+                if count > 0 {
+                    last.push_back(record);
+                }
+            }
+            Err(e) => warn!("委托日志第 {} 行无法解析，已跳过: {}", i + 1, e),
+        }
+    }
+    Ok(Some(last.into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trading::executor::{OrderSide, TradingMarket};
+
+    fn record(code: &str, success: bool) -> OrderRecord {
+        OrderRecord {
+            request: OrderRequest {
+                stock_code: code.to_string(),
+                price: 388.2,
+                quantity: 100,
+                side: OrderSide::Buy,
+                market: TradingMarket::infer(code).unwrap(),
+            },
+            result: OrderResult {
+                success,
+                message: if success { "委托已提交" } else { "AX 验价失败" }.to_string(),
+                verified_price: success.then_some(388.2),
+                verified_code: success.then(|| code.to_string()),
+                timestamp: "2025-01-15 10:30:00".to_string(),
+            },
+            dry_run: false,
+        }
+    }
+
+    #[test]
+    fn test_append_and_read_last() {
+        let path = std::env::temp_dir().join(format!("qtrade-test-orders-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        // 文件不存在
+        assert!(read_last(&path, 5).unwrap().is_none());
+
+        // 多次追加累积，损坏行跳过
+        append(&path, &record("00700", true)).unwrap();
+        append(&path, &record("09988", false)).unwrap();
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(b"not json\n")
+            .unwrap();
+        append(&path, &record("600519", true)).unwrap();
+
+        // 请求与结果字段展平在同一行
+        let first: serde_json::Value =
+            serde_json::from_str(std::fs::read_to_string(&path).unwrap().lines().next().unwrap()).unwrap();
+        assert_eq!(first["stock_code"], "00700");
+        assert_eq!(first["verified_price"], 388.2);
+
+        let last = read_last(&path, 2).unwrap().unwrap();
+        let codes: Vec<&str> = last.iter().map(|r| r.request.stock_code.as_str()).collect();
+        assert_eq!(codes, ["09988", "600519"]);
+        assert!(!last[0].result.success);
+        assert_eq!(last[0].result.verified_code, None);
+        assert_eq!(last[1].request.side, OrderSide::Buy);
+        assert_eq!(last[1].request.market, TradingMarket::CN);
+        assert_eq!(read_last(&path, 10).unwrap().unwrap().len(), 3);
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -1,12 +1,22 @@
 //! ratatui 终端仪表盘
 
-use std::collections::{HashMap, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
 use std::io;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 /// 最大最近提醒数量
 pub const MAX_RECENT_ALERTS: usize = 1000;
 
+/// 自动跟随后高亮该行的时长
+const FOLLOW_HIGHLIGHT: Duration = Duration::from_secs(2);
+
+/// 新触发 tick 信号的行加粗反显的时长（靠每秒的时钟重绘淡出）
+const SIGNAL_FLASH: Duration = Duration::from_secs(2);
+
+/// 详情弹窗走势图显示的日K收盘价根数
+const SPARKLINE_DAYS: usize = 60;
+
 use crossterm::event::{KeyCode, KeyEventKind};
 use crossterm::terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen};
 use crossterm::ExecutableCommand;
@@ -14,9 +24,14 @@ use ratatui::prelude::*;
 use ratatui::widgets::*;
 
 use chrono::{DateTime, Local};
+use tracing::warn;
 
+use crate::analysis::compare::PairComparison;
+use crate::analysis::daily::DailyAnalysisEngine;
+use crate::analysis::journal::SignalJournal;
 use crate::models::{
-    AlertEvent, Market, QuoteSnapshot, Sentiment, Signal, StockCode, TechnicalIndicators, TimedSignal,
+    AlertEvent, ConnectionState, DataSource, Market, MarketSession, QuoteSnapshot, Sentiment, Signal, StockCode,
+    TechnicalIndicators, TimedSignal,
 };
 
 /// 仪表盘状态
@@ -29,8 +44,16 @@ pub struct DashboardState {
     pub recent_alerts: VecDeque<AlertEvent>,
     /// 数据源状态
     pub source_name: String,
-    /// 数据源是否连接
-    pub source_connected: bool,
+    /// 数据源连接状态（OpenAPI 断线后显示重连中）
+    pub connection: ConnectionState,
+    /// 实时行情订阅成功的市场（仅 OpenAPI 有订阅，其余数据源为空）
+    pub subscribed_markets: HashSet<Market>,
+    /// 日K权限探测判定无权限的市场
+    pub no_permission_markets: HashSet<Market>,
+    /// 各数据源声明的行情延迟（data_source.latency_secs，仅含非 0 项）
+    pub source_latency: HashMap<DataSource, Duration>,
+    /// 行情超过此时长未刷新视为滞后（data_source.stale_after_secs，None 关闭）
+    pub stale_after: Option<Duration>,
     /// 上次更新时间
     pub last_update: Option<Instant>,
     /// 选中行
@@ -41,6 +64,8 @@ pub struct DashboardState {
     pub last_error: Option<String>,
     /// 是否显示技术指标
     pub show_indicators: bool,
+    /// 行情表格显示的列及顺序（ui.columns）
+    pub columns: Vec<Column>,
     /// 排序列
     pub sort_column: SortColumn,
     /// 排序方向
@@ -49,14 +74,131 @@ pub struct DashboardState {
     pub daily_indicators: HashMap<StockCode, TechnicalIndicators>,
     /// 日线信号
     pub daily_signals: HashMap<StockCode, Vec<TimedSignal>>,
+    /// 最近 SPARKLINE_DAYS 根日K收盘价（详情弹窗走势图）
+    pub daily_closes: HashMap<StockCode, Vec<f64>>,
     /// 是否显示日线信号
     pub show_daily_signals: bool,
+    /// 美股非盘中时段是否以扩展时段价格替代现价（关闭时显示收盘价 + 时段标注）
+    pub show_extended_hours: bool,
+    /// 涨跌幅显示的小数位数（ui.change_pct_decimals）
+    pub change_pct_decimals: usize,
     /// 日K线获取状态（显示在状态栏）
     pub daily_kline_status: String,
     /// Tick 信号（事件型，带触发时间）
     pub tick_signals: HashMap<StockCode, Vec<(Signal, DateTime<Local>)>>,
-    /// 每只股票最大日线信号数量（通常与 daily_kline_days 一致）
+    /// 每只股票最大日线信号数量（ui.max_daily_signals_per_stock，未配置时与 daily_kline_days 一致）
     pub max_daily_signals_per_stock: usize,
+    /// 因超出上限被截掉的日线信号数量（信号列显示 "+N"）
+    pub daily_signals_truncated: HashMap<StockCode, usize>,
+    /// 手动刷新已请求、尚未完成（状态栏显示「刷新中」）
+    pub refresh_pending: bool,
+    /// 本次运行触发的信号日志（x 键导出 CSV）
+    pub signal_journal: SignalJournal,
+    /// 状态栏提示（如导出结果），下次按键时清除
+    pub notice: Option<String>,
+    /// 对比基准（c 键标记，再次按 c 与当前选中股票对比）
+    pub compare_base: Option<StockCode>,
+    /// 正在显示的对比弹窗 (基准, 目标)
+    pub compare_pair: Option<(StockCode, StockCode)>,
+    /// 是否显示选中股票的详情弹窗（Enter 打开，Enter/Esc 关闭）
+    pub detail_open: bool,
+    /// 行筛选关键字（/ 键输入，代码或名称包含即显示，不区分大小写）
+    pub filter: String,
+    /// 是否正在输入筛选关键字
+    pub filter_editing: bool,
+    /// 暂停刷新（空格切换）：行情暂存到 `pending_quotes`，表格与选中行保持不动
+    pub paused: bool,
+    /// 暂停期间收到的行情（每只股票只保留最新一条），恢复时一次性应用
+    pending_quotes: Vec<QuoteSnapshot>,
+    /// 选中行自动跟随模式
+    pub follow_mode: FollowMode,
+    /// 手动按键后暂停自动跟随的时长
+    pub follow_pause: Duration,
+    /// 最近一次手动按键时间
+    last_key_at: Option<Instant>,
+    /// 最近一次自动跟随的股票及时间（用于短暂高亮）
+    follow_highlight: Option<(StockCode, Instant)>,
+}
+
+/// 自选股整体涨跌统计（汇总行）
+#[derive(Debug, Clone, PartialEq)]
+pub struct PortfolioSummary {
+    pub up: usize,
+    pub down: usize,
+    pub flat: usize,
+    /// 平均涨跌幅 (%)
+    pub avg_change_pct: f64,
+    /// 涨幅最大 / 最小的股票
+    pub strongest: (StockCode, String, f64),
+    pub weakest: (StockCode, String, f64),
+}
+
+/// 行情延迟标注（现价列）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LatencyNote {
+    /// 数据源本身为延迟行情（配置声明）
+    Delayed(Duration),
+    /// 行情已超过 `stale_after` 未刷新
+    Stale(Duration),
+    /// 所属市场午休 / 休市，价格不动属正常
+    Closed(MarketSession),
+}
+
+impl fmt::Display for LatencyNote {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LatencyNote::Delayed(d) => write!(f, "延{}", format_lag(*d)),
+            LatencyNote::Stale(d) => write!(f, "滞后{}", format_lag(*d)),
+            LatencyNote::Closed(MarketSession::LunchBreak) => write!(f, "📴午休"),
+            LatencyNote::Closed(_) => write!(f, "📴休市"),
+        }
+    }
+}
+
+/// 时长的简短中文表示："45秒" / "15分" / "2时"
+fn format_lag(d: Duration) -> String {
+    match d.as_secs() {
+        s if s < 60 => format!("{}秒", s),
+        s if s < 3600 => format!("{}分", s / 60),
+        s => format!("{}时", s / 3600),
+    }
+}
+
+/// 选中行自动跟随模式（对应 `ui.follow_action`）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FollowMode {
+    /// 不跟随
+    Off,
+    /// 跟随最近触发 tick 信号的股票
+    Signal,
+    /// 跟随本次更新涨跌幅变动最大的股票
+    Mover,
+}
+
+impl FollowMode {
+    /// 由配置字符串解析（未知值视为关闭，配置校验阶段已拦截）
+    pub fn from_config(value: &str) -> Self {
+        match value {
+            "signal" => Self::Signal,
+            "mover" => Self::Mover,
+            _ => Self::Off,
+        }
+    }
+}
+
+/// 按键处理结果（需要由主循环执行的动作）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputAction {
+    /// 无需额外动作（仅状态变更，重新渲染即可）
+    None,
+    /// 退出
+    Quit,
+    /// 立即拉取一次行情（跳过刷新间隔）
+    Refresh,
+    /// 导出信号日志 CSV
+    ExportSignals,
+    /// 强制重新探测市场权限（下一轮日K拉取前）
+    ReprobeMarkets,
 }
 
 /// 排序列
@@ -80,44 +222,350 @@ impl DashboardState {
             indicators: HashMap::new(),
             recent_alerts: VecDeque::with_capacity(MAX_RECENT_ALERTS),
             source_name: String::new(),
-            source_connected: false,
+            connection: ConnectionState::Disconnected,
+            subscribed_markets: HashSet::new(),
+            no_permission_markets: HashSet::new(),
+            source_latency: HashMap::new(),
+            stale_after: None,
             last_update: None,
             selected_row: 0,
             scroll_offset: 0,
             last_error: None,
             show_indicators: true,
+            columns: Column::ALL.to_vec(),
             sort_column: SortColumn::ChangePct,
             sort_ascending: false,
             daily_indicators: HashMap::new(),
             daily_signals: HashMap::new(),
+            daily_closes: HashMap::new(),
             show_daily_signals: true,
+            show_extended_hours: true,
+            change_pct_decimals: 2,
             daily_kline_status: String::new(),
             tick_signals: HashMap::new(),
             max_daily_signals_per_stock: max_daily_signals,
+            daily_signals_truncated: HashMap::new(),
+            refresh_pending: false,
+            signal_journal: SignalJournal::new(),
+            notice: None,
+            compare_base: None,
+            compare_pair: None,
+            detail_open: false,
+            filter: String::new(),
+            filter_editing: false,
+            paused: false,
+            pending_quotes: Vec::new(),
+            follow_mode: FollowMode::Off,
+            follow_pause: Duration::from_secs(5),
+            last_key_at: None,
+            follow_highlight: None,
+        }
+    }
+
+    /// c 键：无基准时标记当前行为对比基准；已有基准时与当前行打开对比弹窗
+    ///
+    /// 在基准行上再次按 c 取消标记
+    fn toggle_compare(&mut self) {
+        let Some(code) = self.quotes.get(self.selected_row).map(|q| q.code.clone()) else {
+            return;
+        };
+        match self.compare_base.take() {
+            None => {
+                self.notice = Some(format!("对比基准: {}，选择另一只股票后按 c", code.display_code()));
+                self.compare_base = Some(code);
+            }
+            Some(base) if base == code => {
+                self.notice = Some("已取消对比基准".to_string());
+            }
+            Some(base) => {
+                self.compare_pair = Some((base, code));
+            }
+        }
+    }
+
+    /// 该行是否通过筛选（代码或名称包含关键字，不区分大小写）
+    fn matches_filter(&self, q: &QuoteSnapshot) -> bool {
+        if self.filter.is_empty() {
+            return true;
+        }
+        let needle = self.filter.to_lowercase();
+        q.code.display_code().to_lowercase().contains(&needle) || q.name.to_lowercase().contains(&needle)
+    }
+
+    /// 选中行被筛掉时改选第一条可见行（筛选结果为空时保持不变）
+    fn clamp_selection_to_filter(&mut self) {
+        if self
+            .quotes
+            .get(self.selected_row)
+            .is_some_and(|q| self.matches_filter(q))
+        {
+            return;
+        }
+        if let Some(i) = self.quotes.iter().position(|q| self.matches_filter(q)) {
+            self.selected_row = i;
+        }
+    }
+
+    /// 空格键：暂停 / 恢复刷新，恢复时应用暂停期间暂存的行情
+    fn toggle_paused(&mut self) {
+        self.paused = !self.paused;
+        if !self.paused {
+            let pending = std::mem::take(&mut self.pending_quotes);
+            if !pending.is_empty() {
+                self.update_quotes(pending);
+            }
+        }
+    }
+
+    /// 清除筛选并退出输入模式
+    fn clear_filter(&mut self) {
+        self.filter.clear();
+        self.filter_editing = false;
+    }
+
+    /// ↑↓：在筛选可见的行之间移动选中行
+    fn move_selection(&mut self, down: bool) {
+        let next = if down {
+            (self.selected_row + 1..self.quotes.len()).find(|&i| self.matches_filter(&self.quotes[i]))
+        } else {
+            (0..self.selected_row.min(self.quotes.len()))
+                .rev()
+                .find(|&i| self.matches_filter(&self.quotes[i]))
+        };
+        if let Some(i) = next {
+            self.selected_row = i;
+        }
+    }
+
+    /// 该股票当前是否显示有信号（tick 信号，或开启日线信号显示时的日线信号）
+    fn has_signals(&self, code: &StockCode) -> bool {
+        self.tick_signals.get(code).is_some_and(|s| !s.is_empty())
+            || (self.show_daily_signals && self.daily_signals.get(code).is_some_and(|s| !s.is_empty()))
+    }
+
+    /// n / N 键：按当前显示顺序选中下一只 / 上一只有信号的股票（循环，跳过被筛掉的行）
+    fn jump_to_signal(&mut self, forward: bool) {
+        let n = self.quotes.len();
+        let target = (1..=n)
+            .map(|step| {
+                if forward {
+                    (self.selected_row + step) % n
+                } else {
+                    (self.selected_row + n - step) % n
+                }
+            })
+            .find(|&i| self.has_signals(&self.quotes[i].code) && self.matches_filter(&self.quotes[i]));
+        match target {
+            Some(i) => self.selected_row = i,
+            None => self.notice = Some("没有带信号的股票".to_string()),
+        }
+    }
+
+    /// 股票触发 tick 信号（signal 跟随模式下选中该行）
+    pub fn follow_signal(&mut self, code: &StockCode) {
+        if self.follow_mode == FollowMode::Signal {
+            self.follow(code);
+        }
+    }
+
+    /// 自动选中指定股票并短暂高亮；刷新暂停时、手动按键后的暂停期内、详情弹窗打开时、股票被筛掉时不跟随
+    fn follow(&mut self, code: &StockCode) {
+        if self.paused || self.detail_open || self.last_key_at.is_some_and(|t| t.elapsed() < self.follow_pause) {
+            return;
+        }
+        if let Some(idx) = self
+            .quotes
+            .iter()
+            .position(|q| &q.code == code && self.matches_filter(q))
+        {
+            self.selected_row = idx;
+            self.follow_highlight = Some((code.clone(), Instant::now()));
+        }
+    }
+
+    /// 该股票是否处于自动跟随后的高亮期
+    fn is_follow_highlighted(&self, code: &StockCode) -> bool {
+        matches!(&self.follow_highlight, Some((c, at)) if c == code && at.elapsed() < FOLLOW_HIGHLIGHT)
+    }
+
+    /// 该股票在 `now` 之前 `SIGNAL_FLASH` 内是否有新触发的 tick 信号
+    fn has_fresh_signal(&self, code: &StockCode, now: DateTime<Local>) -> bool {
+        self.tick_signals.get(code).is_some_and(|sigs| {
+            sigs.iter()
+                .any(|(_, at)| (now - *at).to_std().is_ok_and(|age| age < SIGNAL_FLASH))
+        })
+    }
+
+    /// 现价后的延迟标注：市场午休/休市优先（不动的价格不算滞后），其次实际超过 `stale_after` 未刷新，
+    /// 否则为数据源声明的延迟（缓存数据已灰显，不标注）
+    fn latency_note(&self, q: &QuoteSnapshot, now: DateTime<Local>) -> Option<LatencyNote> {
+        if q.source == DataSource::Cache {
+            return None;
+        }
+        if !crate::models::market_is_open(q.code.market, &now) {
+            return Some(LatencyNote::Closed(crate::models::market_session_at(
+                q.code.market,
+                &now,
+            )));
+        }
+        let age = (now - q.timestamp).to_std().unwrap_or_default();
+        if self.stale_after.is_some_and(|limit| age >= limit) {
+            return Some(LatencyNote::Stale(age));
+        }
+        self.source_latency.get(&q.source).map(|d| LatencyNote::Delayed(*d))
+    }
+
+    /// 从日线引擎同步自选股最近 SPARKLINE_DAYS 根日K收盘价
+    pub fn update_daily_closes(&mut self, engine: &DailyAnalysisEngine) {
+        self.daily_closes = self
+            .quotes
+            .iter()
+            .filter_map(|q| {
+                let klines = engine.get_klines(&q.code)?;
+                let recent = &klines[klines.len().saturating_sub(SPARKLINE_DAYS)..];
+                Some((q.code.clone(), recent.iter().map(|k| k.close).collect()))
+            })
+            .collect();
+    }
+
+    /// 写入日线信号（每只股票超出上限的旧信号被截掉，截掉数量记入 `daily_signals_truncated`）
+    pub fn set_daily_signals(&mut self, signals: HashMap<StockCode, Vec<TimedSignal>>) {
+        self.daily_signals = signals;
+        self.daily_signals_truncated.clear();
+        self.trim_daily_signals();
+    }
+
+    /// 按上限裁剪每只股票的日线信号（保留最新的）
+    fn trim_daily_signals(&mut self) {
+        let max = self.max_daily_signals_per_stock;
+        for (code, signals) in self.daily_signals.iter_mut() {
+            if signals.len() > max {
+                let dropped = signals.len() - max;
+                *signals = signals.split_off(dropped);
+                *self.daily_signals_truncated.entry(code.clone()).or_insert(0) += dropped;
+            }
+        }
+    }
+
+    /// 按市场统计股票数量，如 "港股5 美股4 新加坡1"（顺序同 `Market::all()`，数量为 0 的市场省略）
+    pub fn market_summary(&self) -> String {
+        let mut parts = Vec::new();
+        for market in Market::all().into_iter().chain([Market::Unknown]) {
+            let count = self.quotes.iter().filter(|q| q.code.market == market).count();
+            if count > 0 {
+                parts.push(format!("{}{}", market.label(), count));
+            }
+        }
+        parts.join(" ")
+    }
+
+    /// 各市场订阅/权限状态（如 "HK✓ SH✓ US✗无权限"），无订阅信息且无无权限市场时返回 None
+    ///
+    /// 列出自选股、订阅或权限探测涉及的市场；有订阅信息时未订阅成功的市场标 ✗
+    pub fn market_status(&self) -> Option<String> {
+        if self.subscribed_markets.is_empty() && self.no_permission_markets.is_empty() {
+            return None;
+        }
+        let parts: Vec<String> = Market::all()
+            .into_iter()
+            .filter(|m| {
+                self.subscribed_markets.contains(m)
+                    || self.no_permission_markets.contains(m)
+                    || self.quotes.iter().any(|q| q.code.market == *m)
+            })
+            .map(|m| {
+                if self.no_permission_markets.contains(&m) {
+                    format!("{}✗无权限", m)
+                } else if !self.subscribed_markets.is_empty() && !self.subscribed_markets.contains(&m) {
+                    format!("{}✗", m)
+                } else {
+                    format!("{}✓", m)
+                }
+            })
+            .collect();
+        Some(parts.join(" "))
+    }
+
+    /// 自选股涨跌统计（跳过仅有缓存数据的股票），无实时行情时返回 None
+    pub fn portfolio_summary(&self) -> Option<PortfolioSummary> {
+        let live: Vec<&QuoteSnapshot> = self.quotes.iter().filter(|q| q.source != DataSource::Cache).collect();
+        let first = live.first()?;
+        let mut summary = PortfolioSummary {
+            up: 0,
+            down: 0,
+            flat: 0,
+            avg_change_pct: 0.0,
+            strongest: (first.code.clone(), first.name.clone(), first.change_pct),
+            weakest: (first.code.clone(), first.name.clone(), first.change_pct),
+        };
+        for q in &live {
+            if q.change_pct > 0.0 {
+                summary.up += 1;
+            } else if q.change_pct < 0.0 {
+                summary.down += 1;
+            } else {
+                summary.flat += 1;
+            }
+            if q.change_pct > summary.strongest.2 {
+                summary.strongest = (q.code.clone(), q.name.clone(), q.change_pct);
+            }
+            if q.change_pct < summary.weakest.2 {
+                summary.weakest = (q.code.clone(), q.name.clone(), q.change_pct);
+            }
+        }
+        summary.avg_change_pct = live.iter().map(|q| q.change_pct).sum::<f64>() / live.len() as f64;
+        Some(summary)
+    }
+
+    /// 将当前日线信号记入信号日志（已记录过的跳过），返回新记录数
+    pub fn record_daily_signals(&mut self) -> usize {
+        let now = Local::now();
+        let mut added = 0;
+        for (code, sigs) in &self.daily_signals {
+            let (name, price) = self
+                .quotes
+                .iter()
+                .find(|q| &q.code == code)
+                .map(|q| (q.name.as_str(), q.last_price))
+                .unwrap_or(("", 0.0));
+            added += self.signal_journal.record_daily(code, name, sigs, price, now);
         }
+        added
     }
 
     /// 更新行情数据（按股票代码合并，不丢失未更新的股票）
     ///
-    /// 匹配规则：
-    /// 1. market + code 完全匹配 → 直接合并
-    /// 2. code 字符串相同，一方 market 为 Unknown → 视为同一只股票，采用非 Unknown 的 market
+    /// 匹配规则见 `find_merge_target`；Unknown 通配存在歧义的行情直接丢弃，不新增行
     pub fn update_quotes(&mut self, new_quotes: Vec<QuoteSnapshot>) {
+        if self.paused {
+            for q in new_quotes {
+                match self.pending_quotes.iter_mut().find(|p| p.code == q.code) {
+                    Some(p) => *p = q,
+                    None => self.pending_quotes.push(q),
+                }
+            }
+            return;
+        }
+        // 本次更新涨跌幅变动最大的股票（mover 跟随模式）
+        let mut biggest_move: Option<(StockCode, f64)> = None;
         if self.quotes.is_empty() {
             // 首次初始化，直接赋值
             self.quotes = new_quotes;
         } else {
             // 合并：更新已有的，添加新的
             for mut new_q in new_quotes {
-                // 查找匹配：优先精确匹配，其次 code 相同 + 一方 Unknown
-                let found = self.quotes.iter_mut().find(|q| {
-                    if q.code == new_q.code {
-                        return true;
+                let found = match find_merge_target(&self.quotes, &new_q.code) {
+                    MergeTarget::Existing(idx) => Some(&mut self.quotes[idx]),
+                    MergeTarget::New => None,
+                    // 同一代码字符串对应多个市场的股票，无法确定归属
+                    MergeTarget::Ambiguous => {
+                        warn!(
+                            "行情 {} 的市场未知，且自选股中有多只同代码股票，无法确定归属，已丢弃",
+                            new_q.code.code
+                        );
+                        continue;
                     }
-                    // code 字符串相同但 market 不同：Unknown 视为通配
-                    q.code.code == new_q.code.code
-                        && (q.code.market == Market::Unknown || new_q.code.market == Market::Unknown)
-                });
+                };
                 if let Some(existing) = found {
                     // 保留已有的中文名（API 返回的是英文名）
                     if !existing.name.is_empty() && new_q.name.is_empty() {
@@ -127,6 +575,10 @@ impl DashboardState {
                     if new_q.code.market == Market::Unknown && existing.code.market != Market::Unknown {
                         new_q.code.market = existing.code.market;
                     }
+                    let moved = (new_q.change_pct - existing.change_pct).abs();
+                    if moved > 0.0 && biggest_move.as_ref().is_none_or(|(_, m)| moved > *m) {
+                        biggest_move = Some((new_q.code.clone(), moved));
+                    }
                     *existing = new_q;
                 } else {
                     self.quotes.push(new_q);
@@ -135,6 +587,32 @@ impl DashboardState {
         }
         self.last_update = Some(Instant::now());
         self.sort_quotes();
+        if self.follow_mode == FollowMode::Mover {
+            if let Some((code, _)) = biggest_move {
+                self.follow(&code);
+            }
+        }
+    }
+
+    /// 用 plist 缓存价格刷新仍无实时数据（来源为 Cache）的股票，返回刷新数量
+    pub fn apply_cached_prices(&mut self, entries: &[crate::models::WatchlistEntry]) -> usize {
+        let by_code: HashMap<&StockCode, &crate::models::WatchlistEntry> =
+            entries.iter().map(|e| (&e.code, e)).collect();
+        let mut updated = 0;
+        for q in self.quotes.iter_mut() {
+            if q.source != crate::models::DataSource::Cache {
+                continue;
+            }
+            if let Some(entry) = by_code.get(&q.code) {
+                let mut fresh = entry.cached_quote();
+                if fresh.name.is_empty() {
+                    fresh.name = std::mem::take(&mut q.name);
+                }
+                *q = fresh;
+                updated += 1;
+            }
+        }
+        updated
     }
 
     /// 同步 watchlist 变更：移除已删股票、添加新增股票
@@ -145,31 +623,23 @@ impl DashboardState {
 
         // 移除不在新列表中的股票
         self.quotes.retain(|q| new_set.contains(&q.code));
+        self.pending_quotes.retain(|q| new_set.contains(&q.code));
         self.indicators.retain(|k, _| new_set.contains(k));
         self.daily_indicators.retain(|k, _| new_set.contains(k));
         self.daily_signals.retain(|k, _| new_set.contains(k));
+        self.daily_signals_truncated.retain(|k, _| new_set.contains(k));
         self.tick_signals.retain(|k, _| new_set.contains(k));
 
-        // 新增的股票追加空 QuoteSnapshot
+        // 新增的股票追加缓存快照（含缓存价格与昨收）
         let existing: HashSet<StockCode> = self.quotes.iter().map(|q| q.code.clone()).collect();
         for entry in new_entries {
             if !existing.contains(&entry.code) && new_set.contains(&entry.code) {
-                let mut q = QuoteSnapshot::empty(entry.code.clone(), entry.name.clone());
-                if let Some(price) = entry.cached_price {
-                    q.last_price = price;
-                }
-                self.quotes.push(q);
+                self.quotes.push(entry.cached_quote());
             }
         }
 
         // 清理每只股票过多的日线信号
-        for signals in self.daily_signals.values_mut() {
-            if signals.len() > self.max_daily_signals_per_stock {
-                // 保留最新的信号
-                let start = signals.len() - self.max_daily_signals_per_stock;
-                *signals = signals.split_off(start);
-            }
-        }
+        self.trim_daily_signals();
 
         // 防越界
         if !self.quotes.is_empty() {
@@ -244,6 +714,38 @@ impl DashboardState {
                 });
             }
         }
+        // 重排后选中位置上可能换成了被筛掉的行
+        self.clamp_selection_to_filter();
+    }
+}
+
+/// 行情合并目标
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MergeTarget {
+    /// 合并到已有行
+    Existing(usize),
+    /// 新增一行
+    New,
+    /// Unknown 通配匹配到多只股票（同代码不同市场），不合并也不新增
+    Ambiguous,
+}
+
+/// 查找新行情应合并到的已有行
+///
+/// 1. market + code 完全匹配 → 该行（同代码不同市场的股票互不干扰）
+/// 2. 无完全匹配时，code 字符串相同且一方 market 为 Unknown → 通配匹配，
+///    但仅在候选唯一时才合并，多个候选说明是不同市场的同代码股票
+fn find_merge_target(quotes: &[QuoteSnapshot], code: &StockCode) -> MergeTarget {
+    if let Some(idx) = quotes.iter().position(|q| &q.code == code) {
+        return MergeTarget::Existing(idx);
+    }
+    let mut candidates = quotes.iter().enumerate().filter(|(_, q)| {
+        q.code.code == code.code && (q.code.market == Market::Unknown || code.market == Market::Unknown)
+    });
+    match (candidates.next(), candidates.next()) {
+        (None, _) => MergeTarget::New,
+        (Some((idx, _)), None) => MergeTarget::Existing(idx),
+        (Some(_), Some(_)) => MergeTarget::Ambiguous,
     }
 }
 
@@ -266,12 +768,13 @@ pub fn restore_terminal() -> io::Result<()> {
 pub fn render(frame: &mut Frame, state: &DashboardState) {
     let area = frame.area();
 
-    // 布局：标题栏 + 主表格 + 提醒栏 + 状态栏
+    // 布局：标题栏 + 主表格 + 汇总行 + 提醒栏 + 状态栏
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
             Constraint::Length(3),  // 标题
             Constraint::Min(10),    // 主表格
+            Constraint::Length(1),  // 涨跌汇总
             Constraint::Length(10), // 提醒（8 条 + 2 行边框）
             Constraint::Length(1),  // 状态栏
         ])
@@ -283,181 +786,393 @@ pub fn render(frame: &mut Frame, state: &DashboardState) {
     // 主行情表格
     render_quote_table(frame, chunks[1], state);
 
+    // 涨跌汇总
+    render_summary(frame, chunks[2], state);
+
     // 提醒栏
-    render_alerts(frame, chunks[2], state);
+    render_alerts(frame, chunks[3], state);
 
     // 状态栏
-    render_status_bar(frame, chunks[3], state);
+    render_status_bar(frame, chunks[4], state);
+
+    // 双股对比弹窗（覆盖在表格上方）
+    if let Some((base, target)) = &state.compare_pair {
+        render_compare_popup(frame, chunks[1], state, base, target);
+    }
+
+    // 个股详情弹窗
+    if state.detail_open {
+        render_detail_popup(frame, chunks[1], state);
+    }
 }
 
-/// 渲染标题
+/// 渲染标题：应用名 + 各市场交易时段 + 时钟（主循环每秒重绘保持走时）
 fn render_title(frame: &mut Frame, area: Rect) {
+    use crate::models::{AsiaMarketSession, UsMarketSession};
+
+    let now = chrono::Utc::now();
+    let asia_color = |s: AsiaMarketSession| match s {
+        AsiaMarketSession::Open => Color::LightGreen,
+        AsiaMarketSession::LunchBreak => Color::Yellow,
+        AsiaMarketSession::Closed => Color::DarkGray,
+    };
+    let hk = crate::models::hk_market_session_at(&now);
+    let cn = crate::models::cn_market_session_at(&now);
+    let sg = crate::models::sg_market_session_at(&now);
+    let us = crate::models::us_market_session_at(&now);
+    let (us_text, us_color) = match us {
+        UsMarketSession::Regular => ("盘中", Color::LightGreen),
+        UsMarketSession::Closed => ("休", Color::DarkGray),
+        other => (other.extended_label(), Color::Yellow),
+    };
+
+    let sessions = Line::from(vec![
+        Span::raw("港 "),
+        Span::styled(hk.to_string(), Style::new().fg(asia_color(hk))),
+        Span::raw("  A "),
+        Span::styled(cn.to_string(), Style::new().fg(asia_color(cn))),
+        Span::raw("  新 "),
+        Span::styled(sg.to_string(), Style::new().fg(asia_color(sg))),
+        Span::raw("  美 "),
+        Span::styled(us_text, Style::new().fg(us_color)),
+        Span::raw("  "),
+        Span::styled(
+            crate::models::app_now().format("%H:%M:%S").to_string(),
+            Style::new().fg(Color::White).add_modifier(Modifier::BOLD),
+        ),
+        Span::raw(" "),
+    ])
+    .right_aligned();
+
     let title = Block::default()
         .title(" qtrade 量化盯盘系统 ")
         .title_alignment(Alignment::Center)
         .borders(Borders::ALL)
         .border_style(Style::default().fg(Color::Cyan));
+    let inner = title.inner(area);
     frame.render_widget(title, area);
+    frame.render_widget(Paragraph::new(sessions), inner);
+}
+
+/// 行情表格中一行的显示数据（按列生成单元格时共用）
+struct QuoteRow<'a> {
+    q: &'a QuoteSnapshot,
+    state: &'a DashboardState,
+    selected: bool,
+    now: DateTime<Local>,
+    /// 仅有 plist 缓存数据（未被 OCR/API 更新过）
+    is_stale: bool,
+    /// 现价 / 涨跌额 / 涨跌幅（美股非盘中时段可能为扩展时段价格）
+    price: f64,
+    change: f64,
+    change_pct: f64,
+    change_color: Color,
+    /// 关闭扩展价显示时，现价后标注的时段名
+    extended_note: Option<&'static str>,
+}
+
+impl<'a> QuoteRow<'a> {
+    fn new(q: &'a QuoteSnapshot, state: &'a DashboardState, selected: bool, now: DateTime<Local>) -> Self {
+        // 美股非盘中时段：extended_price 才是实价，涨跌相对收盘价重算
+        // 但盘前/盘后价格与收盘价相同（无盘前变动）时回退到显示收盘涨跌
+        // 关闭扩展价显示时保留收盘价，仅在价格后标注时段
+        let us_session = crate::models::us_market_session();
+        let extended = q.extended_change(us_session);
+        let has_extended = extended.is_some();
+        let use_extended = has_extended && state.show_extended_hours;
+        let extended_note = if has_extended && !state.show_extended_hours {
+            Some(us_session.extended_label())
+        } else {
+            None
+        };
+
+        let (price, change, change_pct) = match extended {
+            Some(ext) if use_extended => ext,
+            _ => (q.last_price, q.change, q.change_pct),
+        };
+
+        let change_color = match (change_pct > 0.0, change_pct < 0.0, selected) {
+            (true, _, true) => Color::LightRed,
+            (true, _, false) => Color::Red,
+            (_, true, true) => Color::LightGreen,
+            (_, true, false) => Color::Green,
+            (_, _, true) => Color::White,
+            _ => Color::Reset,
+        };
+
+        Self {
+            q,
+            state,
+            selected,
+            now,
+            is_stale: q.source == DataSource::Cache,
+            price,
+            change,
+            change_pct,
+            change_color,
+            extended_note,
+        }
+    }
+}
+
+/// 行情表格列（`ui.columns` 配置显示哪些列及顺序）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Column {
+    Code,
+    Name,
+    Price,
+    ChangePct,
+    Change,
+    Volume,
+    TurnoverRate,
+    Amplitude,
+    Signal,
+}
+
+impl Column {
+    /// 默认列顺序（全部列），与 `config::UI_COLUMNS` 逐项对应
+    pub const ALL: [Column; crate::config::UI_COLUMNS.len()] = [
+        Column::Code,
+        Column::Name,
+        Column::Price,
+        Column::ChangePct,
+        Column::Change,
+        Column::Volume,
+        Column::TurnoverRate,
+        Column::Amplitude,
+        Column::Signal,
+    ];
+
+    /// 配置名 → 列（按 `config::UI_COLUMNS` 中的位置取 `ALL` 对应项）
+    pub fn from_config(name: &str) -> Option<Self> {
+        crate::config::UI_COLUMNS
+            .iter()
+            .position(|&c| c == name)
+            .map(|i| Self::ALL[i])
+    }
+
+    /// 表头文字
+    pub fn header(self) -> &'static str {
+        match self {
+            Column::Code => "代码",
+            Column::Name => "名称",
+            Column::Price => "现价",
+            Column::ChangePct => "涨跌%",
+            Column::Change => "涨跌额",
+            Column::Volume => "成交量",
+            Column::TurnoverRate => "换手率%",
+            Column::Amplitude => "振幅%",
+            Column::Signal => "信号",
+        }
+    }
+
+    /// 可按此列排序时对应的排序列（表头显示排序箭头）
+    fn sort_column(self) -> Option<SortColumn> {
+        match self {
+            Column::Code => Some(SortColumn::Code),
+            Column::Name => Some(SortColumn::Name),
+            Column::Price => Some(SortColumn::Price),
+            Column::ChangePct => Some(SortColumn::ChangePct),
+            Column::Volume => Some(SortColumn::Volume),
+            Column::Change | Column::TurnoverRate | Column::Amplitude | Column::Signal => None,
+        }
+    }
+
+    /// 列宽（信号列占满剩余宽度）
+    fn width(self, change_pct_decimals: usize) -> Constraint {
+        match self {
+            Column::Code => Constraint::Length(12),
+            Column::Name => Constraint::Length(10),
+            Column::Price => Constraint::Length(14),
+            Column::ChangePct => Constraint::Length(change_pct_width(change_pct_decimals)),
+            Column::Change => Constraint::Length(9),
+            Column::Volume => Constraint::Length(10),
+            Column::TurnoverRate | Column::Amplitude => Constraint::Length(8),
+            Column::Signal => Constraint::Fill(1),
+        }
+    }
+
+    /// 生成单元格（Cell 只设 fg，不设 bg — bg 由 Row style 统一控制）
+    fn cell(self, row: &QuoteRow) -> Cell<'static> {
+        let q = row.q;
+        let state = row.state;
+        let stale = Style::new().fg(Color::DarkGray);
+        // 缓存数据带昨收时仍可显示涨跌（灰色，提示非实时），否则以灰色 "-" 代替虚假的 0%
+        let stale_has_change = q.last_price > 0.0 && q.prev_close > 0.0;
+        match self {
+            // 对比基准行：代码前加标记
+            Column::Code if state.compare_base.as_ref() == Some(&q.code) => {
+                Cell::from(format!("◆{}", q.code.display_code())).style(Style::new().fg(Color::Magenta))
+            }
+            Column::Code => Cell::from(q.code.display_code()),
+            Column::Name => Cell::from(q.name.clone()),
+            // 停牌：价格冻结，以 "停牌" 代替停牌前的价格与 0% 涨跌
+            Column::Price | Column::ChangePct if q.is_suspended => Cell::from("停牌").style(stale),
+            Column::Change if q.is_suspended => Cell::from("-").style(stale),
+            Column::Price if row.is_stale => {
+                let price = if q.last_price > 0.0 {
+                    format!("{:.2}", q.last_price)
+                } else {
+                    "-".to_string()
+                };
+                Cell::from(price).style(stale)
+            }
+            Column::Price => {
+                let note = state.latency_note(q, row.now);
+                // 休市/午休：最后成交价用中性色，与实时变动区分
+                let price_color = match note {
+                    Some(LatencyNote::Closed(_)) if row.selected => Color::White,
+                    Some(LatencyNote::Closed(_)) => Color::Reset,
+                    _ => row.change_color,
+                };
+                let mut spans = vec![Span::styled(format!("{:.2}", row.price), Style::new().fg(price_color))];
+                if let Some(note) = row.extended_note {
+                    spans.push(Span::styled(format!(" {}", note), Style::new().fg(Color::DarkGray)));
+                }
+                // 延迟行情 / 休市灰色标注，实际滞后黄色警示
+                match note {
+                    Some(note @ LatencyNote::Stale(_)) => {
+                        spans.push(Span::styled(format!(" {}", note), Style::new().fg(Color::Yellow)));
+                    }
+                    Some(note) => {
+                        spans.push(Span::styled(format!(" {}", note), Style::new().fg(Color::DarkGray)));
+                    }
+                    None => {}
+                }
+                Cell::from(Line::from(spans))
+            }
+            Column::ChangePct if row.is_stale && stale_has_change => {
+                Cell::from(format_change_pct(q.change_pct, state.change_pct_decimals)).style(stale)
+            }
+            Column::ChangePct if !row.is_stale => {
+                Cell::from(format_change_pct(row.change_pct, state.change_pct_decimals))
+                    .style(Style::new().fg(row.change_color))
+            }
+            Column::Change if row.is_stale && stale_has_change => Cell::from(format!("{:+.2}", q.change)).style(stale),
+            Column::Change if !row.is_stale => {
+                Cell::from(format!("{:+.2}", row.change)).style(Style::new().fg(row.change_color))
+            }
+            Column::Volume if !row.is_stale => Cell::from(format_volume(q.volume)),
+            Column::TurnoverRate if !row.is_stale => Cell::from(format!("{:.2}", q.turnover_rate)),
+            Column::Amplitude if !row.is_stale => Cell::from(format!("{:.2}", q.amplitude)),
+            Column::ChangePct | Column::Change | Column::Volume | Column::TurnoverRate | Column::Amplitude => {
+                Cell::from("-").style(stale)
+            }
+            Column::Signal => Cell::from(Line::from(signal_spans(state, &q.code, row.selected))),
+        }
+    }
+}
+
+/// 信号列：tick 信号（事件型，最新在前）+ 日线信号，均按情绪着色
+fn signal_spans(state: &DashboardState, code: &StockCode, selected: bool) -> Vec<Span<'static>> {
+    let mut spans: Vec<Span> = Vec::new();
+
+    // Tick 信号（事件型，按情绪着色）
+    if let Some(sigs) = state.tick_signals.get(code) {
+        for (sig, _at) in sigs.iter().rev() {
+            if !spans.is_empty() {
+                spans.push(Span::raw("  "));
+            }
+            let color = sentiment_color(sig.sentiment(), selected);
+            spans.push(Span::styled(
+                format!("[{}]{}", sig.sentiment(), sig),
+                Style::new().fg(color),
+            ));
+        }
+    }
+
+    // 日线信号（按情绪着色）
+    if state.show_daily_signals {
+        if let Some(sigs) = state.daily_signals.get(code) {
+            for s in sigs {
+                if !spans.is_empty() {
+                    spans.push(Span::raw("  "));
+                }
+                let color = sentiment_color(s.signal.sentiment(), selected);
+                spans.push(Span::styled(s.to_string(), Style::new().fg(color)));
+            }
+        }
+        if let Some(n) = state.daily_signals_truncated.get(code) {
+            spans.push(Span::styled(format!("  +{}", n), Style::new().fg(Color::DarkGray)));
+        }
+    }
+    spans
 }
 
-/// 渲染行情表格
+/// 渲染行情表格（列及顺序由 `state.columns` 决定）
 fn render_quote_table(frame: &mut Frame, area: Rect, state: &DashboardState) {
     // 根据当前排序列和方向生成带指示器的表头
     let sort_arrow = if state.sort_ascending { " ▲" } else { " ▼" };
-    let header_texts = [
-        ("代码", Some(SortColumn::Code)),
-        ("名称", Some(SortColumn::Name)),
-        ("现价", Some(SortColumn::Price)),
-        ("涨跌%", Some(SortColumn::ChangePct)),
-        ("涨跌额", None::<SortColumn>),
-        ("成交量", Some(SortColumn::Volume)),
-        ("换手率%", None::<SortColumn>),
-        ("振幅%", None::<SortColumn>),
-        ("信号", None::<SortColumn>),
-    ];
-    let header_cells = header_texts.iter().map(|(text, col)| {
-        let display = if *col == Some(state.sort_column) {
-            format!("{}{}", *text, sort_arrow)
+    let header_cells = state.columns.iter().map(|col| {
+        let display = if col.sort_column() == Some(state.sort_column) {
+            format!("{}{}", col.header(), sort_arrow)
         } else {
-            String::from(*text)
+            String::from(col.header())
         };
         Cell::from(display).style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))
     });
 
     let header = Row::new(header_cells).height(1);
 
+    let now = Local::now();
+    let closed_markets: Vec<Market> = Market::all()
+        .into_iter()
+        .filter(|m| crate::models::market_session(*m) == crate::models::MarketSession::Closed)
+        .collect();
     let rows: Vec<Row> = state
         .quotes
         .iter()
         .enumerate()
+        .filter(|(_, q)| state.matches_filter(q))
         .map(|(i, q)| {
             let selected = i == state.selected_row;
+            let row = QuoteRow::new(q, state, selected, now);
+            let cells: Vec<Cell> = state.columns.iter().map(|col| col.cell(&row)).collect();
 
-            // 美股非盘中时段：extended_price 才是实价，涨跌相对收盘价重算
-            // 但盘前/盘后价格与收盘价相同（无盘前变动）时回退到显示收盘涨跌
-            let use_extended = q.code.market == Market::US
-                && crate::models::us_market_session() != crate::models::UsMarketSession::Regular
-                && q.extended_price.is_some()
-                && (q.extended_price.unwrap() - q.last_price).abs() > 0.001;
-
-            let (display_price, display_change, display_change_pct) = if use_extended {
-                let ext = q.extended_price.unwrap();
-                let chg = ext - q.last_price;
-                let pct = if q.last_price > 0.0 {
-                    chg / q.last_price * 100.0
-                } else {
-                    0.0
-                };
-                (ext, chg, pct)
-            } else {
-                (q.last_price, q.change, q.change_pct)
-            };
-
-            let change_color = match (display_change_pct > 0.0, display_change_pct < 0.0, selected) {
-                (true, _, true) => Color::LightRed,
-                (true, _, false) => Color::Red,
-                (_, true, true) => Color::LightGreen,
-                (_, true, false) => Color::Green,
-                (_, _, true) => Color::White,
-                _ => Color::Reset,
-            };
-
-            let mut signal_spans: Vec<Span> = Vec::new();
-
-            // Tick 信号（事件型，按情绪着色）
-            if let Some(sigs) = state.tick_signals.get(&q.code) {
-                for (sig, _at) in sigs.iter().rev() {
-                    if !signal_spans.is_empty() {
-                        signal_spans.push(Span::raw("  "));
-                    }
-                    let color = sentiment_color(sig.sentiment(), selected);
-                    signal_spans.push(Span::styled(
-                        format!("[{}]{}", sig.sentiment(), sig),
-                        Style::new().fg(color),
-                    ));
-                }
-            }
-
-            // 日线信号（按情绪着色）
-            if state.show_daily_signals {
-                if let Some(sigs) = state.daily_signals.get(&q.code) {
-                    for s in sigs {
-                        if !signal_spans.is_empty() {
-                            signal_spans.push(Span::raw("  "));
-                        }
-                        let color = sentiment_color(s.signal.sentiment(), selected);
-                        signal_spans.push(Span::styled(s.to_string(), Style::new().fg(color)));
-                    }
-                }
-            }
-
-            // 仅有 plist 缓存数据（未被 OCR/API 更新过）→ 灰色 "-" 代替虚假的 0%
-            let is_stale = q.source == crate::models::DataSource::Cache;
-            let price_str = if is_stale && q.last_price > 0.0 {
-                format!("{:.2}", q.last_price)
-            } else if is_stale {
-                "-".to_string()
+            let row_style = if state.is_follow_highlighted(&q.code) {
+                Style::default().bg(Color::Blue).fg(Color::White)
+            } else if selected {
+                Style::default().bg(Color::DarkGray).fg(Color::White)
             } else {
-                format!("{:.2}", display_price)
+                Style::default()
             };
-            let stale_color = Color::DarkGray;
-
-            // Cell 只设 fg，不设 bg — bg 由 Row style 统一控制
-            let signal_cell = Cell::from(Line::from(signal_spans));
-
-            let cells = if is_stale {
-                vec![
-                    Cell::from(q.code.display_code()),
-                    Cell::from(q.name.clone()),
-                    Cell::from(price_str).style(Style::new().fg(stale_color)),
-                    Cell::from("-").style(Style::new().fg(stale_color)),
-                    Cell::from("-").style(Style::new().fg(stale_color)),
-                    Cell::from("-").style(Style::new().fg(stale_color)),
-                    Cell::from("-").style(Style::new().fg(stale_color)),
-                    Cell::from("-").style(Style::new().fg(stale_color)),
-                    signal_cell,
-                ]
+            // 所属市场休市 → 整行变暗，跨市场自选股一眼区分仍在交易的行
+            let row_style = if closed_markets.contains(&q.code.market) {
+                row_style.add_modifier(Modifier::DIM)
             } else {
-                vec![
-                    Cell::from(q.code.display_code()),
-                    Cell::from(q.name.clone()),
-                    Cell::from(price_str).style(Style::new().fg(change_color)),
-                    Cell::from(format!("{:+.2}%", display_change_pct)).style(Style::new().fg(change_color)),
-                    Cell::from(format!("{:+.2}", display_change)).style(Style::new().fg(change_color)),
-                    Cell::from(format_volume(q.volume)),
-                    Cell::from(format!("{:.2}", q.turnover_rate)),
-                    Cell::from(format!("{:.2}", q.amplitude)),
-                    signal_cell,
-                ]
+                row_style
             };
-
-            let row_style = if selected {
-                Style::default().bg(Color::DarkGray).fg(Color::White)
+            // 刚触发新信号 → 整行加粗反显，短暂吸引注意
+            let row_style = if state.has_fresh_signal(&q.code, now) {
+                row_style.add_modifier(Modifier::BOLD | Modifier::REVERSED)
             } else {
-                Style::default()
+                row_style
             };
 
             Row::new(cells).style(row_style)
         })
         .collect();
 
-    let widths = [
-        Constraint::Length(12),
-        Constraint::Length(10),
-        Constraint::Length(10),
-        Constraint::Length(9),
-        Constraint::Length(9),
-        Constraint::Length(10),
-        Constraint::Length(8),
-        Constraint::Length(8),
-        Constraint::Fill(1),
-    ];
+    let widths: Vec<Constraint> = state
+        .columns
+        .iter()
+        .map(|col| col.width(state.change_pct_decimals))
+        .collect();
 
     let table = Table::new(rows, widths)
         .header(header)
         .block(
             Block::default()
-                .title(format!(" 自选股行情 ({}) ", state.quotes.len()))
+                .title(if state.filter.is_empty() {
+                    format!(" 自选股行情 ({}) {} ", state.quotes.len(), state.market_summary())
+                } else {
+                    format!(
+                        " 自选股行情 ({}/{}) 筛选: {} ",
+                        state.quotes.iter().filter(|q| state.matches_filter(q)).count(),
+                        state.quotes.len(),
+                        state.filter
+                    )
+                })
                 .borders(Borders::ALL),
         )
         .row_highlight_style(Style::default().add_modifier(Modifier::BOLD));
@@ -465,6 +1180,284 @@ fn render_quote_table(frame: &mut Frame, area: Rect, state: &DashboardState) {
     frame.render_widget(table, area);
 }
 
+/// 渲染双股对比弹窗
+fn render_compare_popup(frame: &mut Frame, area: Rect, state: &DashboardState, base: &StockCode, target: &StockCode) {
+    let find = |code: &StockCode| state.quotes.iter().find(|q| &q.code == code);
+    let lines: Vec<Line> = match (find(base), find(target)) {
+        (Some(a), Some(b)) => {
+            let cmp = PairComparison::from_quotes(a, b);
+            let pct_span = |v: f64| {
+                let color = if v > 0.0 {
+                    Color::Red
+                } else if v < 0.0 {
+                    Color::Green
+                } else {
+                    Color::Reset
+                };
+                Span::styled(format!("{:+.2}%", v), Style::new().fg(color))
+            };
+            let ratio_str = |r: Option<f64>| r.map(|v| format!("{:.4}", v)).unwrap_or_else(|| "-".to_string());
+            vec![
+                Line::from(vec![
+                    Span::raw(format!("A {} {}  {:.2}  ", a.code.display_code(), a.name, a.last_price)),
+                    pct_span(cmp.change_pct_a),
+                ]),
+                Line::from(vec![
+                    Span::raw(format!("B {} {}  {:.2}  ", b.code.display_code(), b.name, b.last_price)),
+                    pct_span(cmp.change_pct_b),
+                ]),
+                Line::from(""),
+                Line::from(vec![Span::raw("相对表现 A-B: "), pct_span(cmp.relative_pct)]),
+                Line::from(format!("价格比 A/B: {}", ratio_str(cmp.price_ratio))),
+                Line::from(match cmp.ratio_change_pct() {
+                    Some(chg) => vec![
+                        Span::raw(format!("归一化比值: {}  较昨收 ", ratio_str(cmp.normalized_ratio))),
+                        pct_span(chg),
+                    ],
+                    None => vec![Span::raw("归一化比值: -（缺少昨收）")],
+                }),
+            ]
+        }
+        _ => vec![Line::from("对比股票已不在自选列表中")],
+    };
+
+    let width = 56.min(area.width);
+    let height = (lines.len() as u16 + 2).min(area.height);
+    let popup = Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    };
+    let widget = Paragraph::new(lines).block(
+        Block::default()
+            .title(" 双股对比 (c/Esc 关闭) ")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Magenta)),
+    );
+    frame.render_widget(Clear, popup);
+    frame.render_widget(widget, popup);
+}
+
+/// 渲染选中股票的详情弹窗：完整行情字段 + tick / 日线信号列表 + 日K收盘走势图
+fn render_detail_popup(frame: &mut Frame, area: Rect, state: &DashboardState) {
+    let mut lines: Vec<Line> = Vec::new();
+    let title = match state.quotes.get(state.selected_row) {
+        Some(q) => {
+            let color = if q.change_pct > 0.0 {
+                Color::Red
+            } else if q.change_pct < 0.0 {
+                Color::Green
+            } else {
+                Color::Reset
+            };
+            lines.push(Line::from(vec![
+                Span::raw(format!("现价 {:.2}  ", q.last_price)),
+                Span::styled(
+                    format!(
+                        "{:+.2}  {}",
+                        q.change,
+                        format_change_pct(q.change_pct, state.change_pct_decimals)
+                    ),
+                    Style::new().fg(color),
+                ),
+            ]));
+            lines.push(Line::from(format!(
+                "开盘 {:.2}  最高 {:.2}  最低 {:.2}  昨收 {:.2}",
+                q.open_price, q.high_price, q.low_price, q.prev_close
+            )));
+            lines.push(Line::from(format!(
+                "成交量 {}  成交额 {}",
+                format_volume(q.volume),
+                format_volume(q.turnover as u64)
+            )));
+            lines.push(Line::from(format!(
+                "振幅 {:.2}%  换手率 {:.2}%",
+                q.amplitude, q.turnover_rate
+            )));
+            if let (Some(price), Some(pct)) = (q.extended_price, q.extended_change_pct) {
+                lines.push(Line::from(format!("盘前/盘后 {:.2}  {:+.2}%", price, pct)));
+            }
+            lines.push(Line::from(Span::styled(
+                format!(
+                    "数据源 {}  更新于 {}",
+                    q.source,
+                    crate::models::to_app_time(&q.timestamp).format("%H:%M:%S")
+                ),
+                Style::new().fg(Color::DarkGray),
+            )));
+
+            // Tick 信号（最新在前，带触发时间）
+            lines.push(Line::from(""));
+            let ticks = state.tick_signals.get(&q.code).map(Vec::as_slice).unwrap_or_default();
+            lines.push(Line::styled(
+                format!("Tick 信号 ({})", ticks.len()),
+                Style::new().fg(Color::Yellow),
+            ));
+            for (sig, at) in ticks.iter().rev() {
+                lines.push(Line::styled(
+                    format!(
+                        "  {} [{}]{}",
+                        crate::models::to_app_time(at).format("%H:%M:%S"),
+                        sig.sentiment(),
+                        sig
+                    ),
+                    Style::new().fg(sentiment_color(sig.sentiment(), false)),
+                ));
+            }
+
+            // 日线信号（不受 d 键显示开关影响）
+            lines.push(Line::from(""));
+            let daily = state.daily_signals.get(&q.code).map(Vec::as_slice).unwrap_or_default();
+            lines.push(Line::styled(
+                format!("日线信号 ({})", daily.len()),
+                Style::new().fg(Color::Yellow),
+            ));
+            for s in daily {
+                lines.push(Line::styled(
+                    format!("  [{}]{}", s.signal.sentiment(), s.signal),
+                    Style::new().fg(sentiment_color(s.signal.sentiment(), false)),
+                ));
+            }
+            if let Some(n) = state.daily_signals_truncated.get(&q.code) {
+                lines.push(Line::styled(
+                    format!("  另有 {} 条超出显示上限", n),
+                    Style::new().fg(Color::DarkGray),
+                ));
+            }
+            format!(" {} {} (Enter/Esc 关闭) ", q.code.display_code(), q.name)
+        }
+        None => {
+            lines.push(Line::from("没有选中的股票"));
+            " 详情 (Enter/Esc 关闭) ".to_string()
+        }
+    };
+
+    // 日K收盘走势（至少两根才画），占弹窗底部：1 行标题 + SPARKLINE_HEIGHT 行图
+    const SPARKLINE_HEIGHT: u16 = 4;
+    let closes = state
+        .quotes
+        .get(state.selected_row)
+        .and_then(|q| state.daily_closes.get(&q.code))
+        .filter(|c| c.len() >= 2);
+    let chart_height = if closes.is_some() { SPARKLINE_HEIGHT + 2 } else { 0 };
+
+    let width = 64.min(area.width);
+    let height = (lines.len() as u16 + chart_height + 2).min(area.height);
+    let popup = Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    };
+    let block = Block::default()
+        .title(title)
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan));
+    let inner = block.inner(popup);
+    frame.render_widget(Clear, popup);
+    frame.render_widget(block, popup);
+
+    let [text_area, chart_area] = Layout::vertical([Constraint::Min(0), Constraint::Length(chart_height)]).areas(inner);
+    frame.render_widget(Paragraph::new(lines), text_area);
+
+    if let Some(closes) = closes {
+        let (first, last) = (closes[0], closes[closes.len() - 1]);
+        let net_pct = if first > 0.0 {
+            (last - first) / first * 100.0
+        } else {
+            0.0
+        };
+        let color = if last > first {
+            Color::Red
+        } else if last < first {
+            Color::Green
+        } else {
+            Color::Reset
+        };
+        let [label_area, spark_area] = Layout::vertical([Constraint::Length(2), Constraint::Min(0)]).areas(chart_area);
+        frame.render_widget(
+            Paragraph::new(vec![
+                Line::from(""),
+                Line::from(vec![
+                    Span::styled(format!("日K收盘 ({}日) ", closes.len()), Style::new().fg(Color::Yellow)),
+                    Span::styled(format!("{:+.2}%", net_pct), Style::new().fg(color)),
+                ]),
+            ]),
+            label_area,
+        );
+        frame.render_widget(
+            Sparkline::default()
+                .data(sparkline_data(closes))
+                .max(SPARKLINE_SCALE)
+                .style(Style::new().fg(color)),
+            spark_area,
+        );
+    }
+}
+
+/// 走势图纵轴刻度
+const SPARKLINE_SCALE: u64 = 100;
+
+/// 收盘价映射到 1..=SPARKLINE_SCALE（区间最低价仍留一格，避免最低点空白）
+fn sparkline_data(closes: &[f64]) -> Vec<u64> {
+    let min = closes.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = closes.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let range = max - min;
+    closes
+        .iter()
+        .map(|c| {
+            if range > 0.0 {
+                1 + ((c - min) / range * (SPARKLINE_SCALE - 1) as f64).round() as u64
+            } else {
+                SPARKLINE_SCALE / 2
+            }
+        })
+        .collect()
+}
+
+/// 渲染涨跌汇总行：上涨/下跌/平盘数、平均涨跌幅、最强/最弱股
+fn render_summary(frame: &mut Frame, area: Rect, state: &DashboardState) {
+    let Some(s) = state.portfolio_summary() else {
+        frame.render_widget(
+            Paragraph::new(" 汇总: 等待实时行情").style(Style::new().fg(Color::DarkGray)),
+            area,
+        );
+        return;
+    };
+    let pct_color = |v: f64| {
+        if v > 0.0 {
+            Color::Red
+        } else if v < 0.0 {
+            Color::Green
+        } else {
+            Color::Reset
+        }
+    };
+    let decimals = state.change_pct_decimals;
+    let mover = |label: &str, (code, name, pct): &(StockCode, String, f64)| {
+        vec![
+            Span::raw(format!(" | {} {} {} ", label, code.display_code(), name)),
+            Span::styled(format_change_pct(*pct, decimals), Style::new().fg(pct_color(*pct))),
+        ]
+    };
+
+    let mut spans = vec![
+        Span::raw(" 汇总: "),
+        Span::styled(format!("上涨 {}", s.up), Style::new().fg(Color::Red)),
+        Span::raw("  "),
+        Span::styled(format!("下跌 {}", s.down), Style::new().fg(Color::Green)),
+        Span::raw(format!("  平盘 {} | 平均 ", s.flat)),
+        Span::styled(
+            format_change_pct(s.avg_change_pct, decimals),
+            Style::new().fg(pct_color(s.avg_change_pct)),
+        ),
+    ];
+    spans.extend(mover("最强", &s.strongest));
+    spans.extend(mover("最弱", &s.weakest));
+    frame.render_widget(Paragraph::new(Line::from(spans)), area);
+}
+
 /// 渲染提醒栏
 fn render_alerts(frame: &mut Frame, area: Rect, state: &DashboardState) {
     let alerts: Vec<ListItem> = state
@@ -481,7 +1474,7 @@ fn render_alerts(frame: &mut Frame, area: Rect, state: &DashboardState) {
             let style = Style::default().fg(color);
             ListItem::new(format!(
                 "[{}] {} {}",
-                a.triggered_at.format("%H:%M:%S"),
+                crate::models::to_app_time(&a.triggered_at).format("%H:%M:%S"),
                 a.code,
                 a.message
             ))
@@ -502,6 +1495,7 @@ fn render_alerts(frame: &mut Frame, area: Rect, state: &DashboardState) {
 /// 渲染状态栏
 fn render_status_bar(frame: &mut Frame, area: Rect, state: &DashboardState) {
     let update_info = match state.last_update {
+        _ if state.refresh_pending => "刷新中…".to_string(),
         Some(t) => {
             let elapsed = t.elapsed().as_secs();
             if elapsed < 5 {
@@ -513,16 +1507,16 @@ fn render_status_bar(frame: &mut Frame, area: Rect, state: &DashboardState) {
         None => "未更新".to_string(),
     };
 
-    let conn_status = if state.source_connected {
-        "已连接"
-    } else {
-        "未连接"
+    let conn_status = state.connection.to_string();
+
+    let market_info = match state.market_status() {
+        Some(s) => format!(" | 市场: {}", s),
+        None => String::new(),
     };
 
+    // 按字符截断（错误信息常含中文，按字节切片会落在字符中间）
     let error_info = match &state.last_error {
-        Some(e) => {
-            format!(" | 错误: {}", if e.len() > 40 { &e[..40] } else { e })
-        }
+        Some(e) => format!(" | 错误: {}", e.chars().take(40).collect::<String>()),
         None => String::new(),
     };
 
@@ -532,9 +1526,39 @@ fn render_status_bar(frame: &mut Frame, area: Rect, state: &DashboardState) {
         format!(" | {}", state.daily_kline_status)
     };
 
+    let extended_info = if state.show_extended_hours {
+        ""
+    } else {
+        " | 美股显示收盘价"
+    };
+
+    let notice_info = match &state.notice {
+        Some(n) => format!(" | {}", n),
+        None => String::new(),
+    };
+
+    let paused_info = if state.paused { " | 已暂停(空格恢复)" } else { "" };
+
+    let filter_info = if state.filter_editing {
+        format!(" | 筛选: {}_ (Enter确认 Esc清除)", state.filter)
+    } else if !state.filter.is_empty() {
+        format!(" | 筛选: {} (Esc清除)", state.filter)
+    } else {
+        String::new()
+    };
+
     let status = format!(
-        " 数据源: {} ({}) | 更新: {}{}{} | ↑↓选择 s排序 d日线 q退出 ",
-        state.source_name, conn_status, update_info, error_info, daily_info
+        " 数据源: {} ({}){} | 更新: {}{}{}{}{}{}{} | ↑↓选择 Enter详情 /筛选 空格暂停 n/N信号 s排序 d日线 e盘外价 c对比 r刷新 x导出信号 q退出 ",
+        state.source_name,
+        conn_status,
+        market_info,
+        update_info,
+        error_info,
+        daily_info,
+        extended_info,
+        paused_info,
+        filter_info,
+        notice_info
     );
 
     let bar = Paragraph::new(status).style(Style::default().bg(Color::DarkGray).fg(Color::White));
@@ -542,24 +1566,60 @@ fn render_status_bar(frame: &mut Frame, area: Rect, state: &DashboardState) {
     frame.render_widget(bar, area);
 }
 
-/// 处理单个按键事件，返回 true 表示退出
-pub fn handle_key_event(state: &mut DashboardState, key: crossterm::event::KeyEvent) -> bool {
+/// 处理单个按键事件，返回需要主循环执行的动作
+pub fn handle_key_event(state: &mut DashboardState, key: crossterm::event::KeyEvent) -> InputAction {
     if key.kind != KeyEventKind::Press {
-        return false;
+        return InputAction::None;
     }
+    state.notice = None;
+    state.last_key_at = Some(Instant::now());
+    state.follow_highlight = None;
 
-    match key.code {
-        KeyCode::Char('q') | KeyCode::Esc => return true,
-        KeyCode::Up | KeyCode::Char('k') => {
-            if state.selected_row > 0 {
-                state.selected_row -= 1;
+    // 对比弹窗打开时，c / Esc 仅关闭弹窗
+    if state.compare_pair.is_some() && matches!(key.code, KeyCode::Char('c') | KeyCode::Esc) {
+        state.compare_pair = None;
+        return InputAction::None;
+    }
+    // 详情弹窗打开时，Enter / Esc 仅关闭弹窗（↑↓ 仍可切换股票）
+    if state.detail_open && matches!(key.code, KeyCode::Enter | KeyCode::Esc) {
+        state.detail_open = false;
+        return InputAction::None;
+    }
+
+    // 筛选输入模式：字符追加到关键字，Backspace 删除，Enter 确认，Esc 清除筛选
+    if state.filter_editing {
+        match key.code {
+            KeyCode::Char(c) => {
+                state.filter.push(c);
+                state.clamp_selection_to_filter();
             }
-        }
-        KeyCode::Down | KeyCode::Char('j') => {
-            if state.selected_row + 1 < state.quotes.len() {
-                state.selected_row += 1;
+            KeyCode::Backspace => {
+                state.filter.pop();
+                state.clamp_selection_to_filter();
             }
+            KeyCode::Enter => state.filter_editing = false,
+            KeyCode::Esc => state.clear_filter(),
+            KeyCode::Up => state.move_selection(false),
+            KeyCode::Down => state.move_selection(true),
+            _ => {}
+        }
+        return InputAction::None;
+    }
+
+    match key.code {
+        // 有筛选时 Esc 先清除筛选
+        KeyCode::Esc if !state.filter.is_empty() => state.clear_filter(),
+        KeyCode::Char('q') | KeyCode::Esc => return InputAction::Quit,
+        KeyCode::Char('/') => state.filter_editing = true,
+        KeyCode::Char(' ') => state.toggle_paused(),
+        KeyCode::Char('x') => return InputAction::ExportSignals,
+        KeyCode::Char('p') => return InputAction::ReprobeMarkets,
+        KeyCode::Char('r') => {
+            state.refresh_pending = true;
+            return InputAction::Refresh;
         }
+        KeyCode::Up | KeyCode::Char('k') => state.move_selection(false),
+        KeyCode::Down | KeyCode::Char('j') => state.move_selection(true),
         KeyCode::Char('s') => {
             // 切换排序列
             state.sort_column = match state.sort_column {
@@ -582,9 +1642,16 @@ pub fn handle_key_event(state: &mut DashboardState, key: crossterm::event::KeyEv
         KeyCode::Char('d') => {
             state.show_daily_signals = !state.show_daily_signals;
         }
+        KeyCode::Char('e') => {
+            state.show_extended_hours = !state.show_extended_hours;
+        }
+        KeyCode::Enter => state.detail_open = !state.quotes.is_empty(),
+        KeyCode::Char('c') => state.toggle_compare(),
+        KeyCode::Char('n') => state.jump_to_signal(true),
+        KeyCode::Char('N') => state.jump_to_signal(false),
         _ => {}
     }
-    false
+    InputAction::None
 }
 
 /// 根据情绪方向返回颜色
@@ -599,6 +1666,18 @@ fn sentiment_color(sentiment: Sentiment, selected: bool) -> Color {
     }
 }
 
+/// 格式化涨跌幅（带符号与 %）
+fn format_change_pct(pct: f64, decimals: usize) -> String {
+    format!("{:+.*}%", decimals, pct)
+}
+
+/// 涨跌幅列宽：随小数位数伸缩（2 位小数时为 9，与其他数值列对齐方式一致）
+fn change_pct_width(decimals: usize) -> u16 {
+    // 符号 + 两位整数 + 小数点 + % + 2 列间隔
+    let point = usize::from(decimals > 0);
+    (6 + point + decimals) as u16
+}
+
 /// 格式化成交量
 fn format_volume(vol: u64) -> String {
     if vol >= 100_000_000 {
@@ -609,3 +1688,416 @@ fn format_volume(vol: u64) -> String {
         format!("{}", vol)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AnalysisConfig;
+    use crate::models::DataSource;
+
+    fn make_quote(market: Market, code: &str, name: &str, change_pct: f64) -> QuoteSnapshot {
+        let mut q = QuoteSnapshot::empty(StockCode::new(market, code), name.to_string());
+        q.last_price = 10.0;
+        q.change_pct = change_pct;
+        q.source = DataSource::OpenApi;
+        q
+    }
+
+    #[test]
+    fn test_same_code_different_markets_stay_distinct() {
+        let mut state = DashboardState::new(5);
+        state.update_quotes(vec![
+            make_quote(Market::US, "C6L", "美股", 1.0),
+            make_quote(Market::SG, "C6L", "新加坡航空", 2.0),
+        ]);
+
+        // 各自按市场精确合并，不串行
+        state.update_quotes(vec![make_quote(Market::SG, "C6L", "", 3.0)]);
+        assert_eq!(state.quotes.len(), 2);
+        let sg = state.quotes.iter().find(|q| q.code.market == Market::SG).unwrap();
+        let us = state.quotes.iter().find(|q| q.code.market == Market::US).unwrap();
+        assert_eq!(sg.change_pct, 3.0);
+        assert_eq!(sg.name, "新加坡航空");
+        assert_eq!(us.change_pct, 1.0);
+
+        // Unknown 市场的同代码行情有歧义 → 丢弃，不覆盖任何一只也不新增行
+        state.update_quotes(vec![make_quote(Market::Unknown, "C6L", "", 9.0)]);
+        assert_eq!(state.quotes.len(), 2);
+        assert!(state.quotes.iter().all(|q| q.change_pct != 9.0));
+    }
+
+    #[test]
+    fn test_market_summary_counts_every_market() {
+        let mut state = DashboardState::new(5);
+        let quotes: Vec<QuoteSnapshot> = Market::all()
+            .into_iter()
+            .map(|m| make_quote(m, &format!("{}1", m), "", 0.0))
+            .collect();
+        state.update_quotes(quotes);
+        let summary = state.market_summary();
+        for market in Market::all() {
+            assert!(
+                summary.contains(&format!("{}1", market.label())),
+                "{} missing: {}",
+                market,
+                summary
+            );
+        }
+        assert!(summary.contains("新加坡1"));
+    }
+
+    #[test]
+    fn test_daily_signals_capped_on_update() {
+        let mut state = DashboardState::new(2);
+        let code = StockCode::new(Market::HK, "00700");
+        let sigs: Vec<TimedSignal> = [Signal::MacdGoldenCross, Signal::MsMacdBuy, Signal::MacdDeathCross]
+            .into_iter()
+            .map(|signal| TimedSignal {
+                signal,
+                timeframe: crate::models::Timeframe::Daily,
+            })
+            .collect();
+        state.set_daily_signals(HashMap::from([(code.clone(), sigs)]));
+
+        // 保留最新的 2 条，截掉数量可供显示 "+1"
+        let kept = &state.daily_signals[&code];
+        assert_eq!(kept.len(), 2);
+        assert!(matches!(kept[0].signal, Signal::MsMacdBuy));
+        assert_eq!(state.daily_signals_truncated.get(&code), Some(&1));
+
+        // 下次写入未超限 → 清除截断提示
+        state.set_daily_signals(HashMap::from([(code.clone(), Vec::new())]));
+        assert!(state.daily_signals_truncated.is_empty());
+    }
+
+    #[test]
+    fn test_unknown_wildcard_merges_single_candidate() {
+        let mut state = DashboardState::new(5);
+        state.update_quotes(vec![make_quote(Market::Unknown, "00001", "长和", 0.0)]);
+        state.update_quotes(vec![make_quote(Market::HK, "00001", "", 1.5)]);
+        assert_eq!(state.quotes.len(), 1);
+        assert_eq!(state.quotes[0].code.market, Market::HK);
+        assert_eq!(state.quotes[0].name, "长和");
+
+        // 已修正为 HK 后，另一市场的同代码股票新增为独立一行
+        state.update_quotes(vec![make_quote(Market::SH, "00001", "其他", 0.5)]);
+        assert_eq!(state.quotes.len(), 2);
+        assert_eq!(
+            find_merge_target(&state.quotes, &StockCode::new(Market::Unknown, "00001")),
+            MergeTarget::Ambiguous
+        );
+    }
+
+    #[test]
+    fn test_fresh_signal_flash_expires() {
+        let mut state = DashboardState::new(5);
+        let code = StockCode::new(Market::HK, "00700");
+        let now = Local::now();
+        state.tick_signals.insert(
+            code.clone(),
+            vec![(Signal::MacdGoldenCross, now - chrono::Duration::seconds(30))],
+        );
+        assert!(!state.has_fresh_signal(&code, now));
+
+        // 本轮新触发的信号在 SIGNAL_FLASH 内高亮，之后淡出
+        state
+            .tick_signals
+            .get_mut(&code)
+            .unwrap()
+            .push((Signal::MsMacdBuy, now));
+        assert!(state.has_fresh_signal(&code, now + chrono::Duration::seconds(1)));
+        assert!(!state.has_fresh_signal(&code, now + chrono::Duration::seconds(3)));
+        assert!(!state.has_fresh_signal(&StockCode::new(Market::HK, "09988"), now));
+    }
+
+    #[test]
+    fn test_jump_to_signal_wraps() {
+        let mut state = DashboardState::new(5);
+        state.sort_column = SortColumn::Code;
+        state.sort_ascending = true;
+        state.update_quotes(
+            ["00001", "00002", "00003", "00004"]
+                .iter()
+                .map(|c| make_quote(Market::HK, c, "", 0.0))
+                .collect(),
+        );
+        state.jump_to_signal(true);
+        assert_eq!(state.selected_row, 0);
+        assert_eq!(state.notice.as_deref(), Some("没有带信号的股票"));
+
+        let now = Local::now();
+        state
+            .tick_signals
+            .insert(StockCode::new(Market::HK, "00002"), vec![(Signal::MsMacdBuy, now)]);
+        state.daily_signals.insert(
+            StockCode::new(Market::HK, "00004"),
+            vec![TimedSignal {
+                signal: Signal::MacdGoldenCross,
+                timeframe: crate::models::Timeframe::Daily,
+            }],
+        );
+        state.jump_to_signal(true);
+        assert_eq!(state.selected_row, 1);
+        state.jump_to_signal(true);
+        assert_eq!(state.selected_row, 3);
+        state.jump_to_signal(true);
+        assert_eq!(state.selected_row, 1);
+        state.jump_to_signal(false);
+        assert_eq!(state.selected_row, 3);
+
+        // 隐藏日线信号后只在 tick 信号之间跳转
+        state.show_daily_signals = false;
+        state.jump_to_signal(false);
+        assert_eq!(state.selected_row, 1);
+        state.jump_to_signal(false);
+        assert_eq!(state.selected_row, 1);
+    }
+
+    #[test]
+    fn test_enter_toggles_detail_popup() {
+        use crossterm::event::{KeyEvent, KeyModifiers};
+        let press = |state: &mut DashboardState, code: KeyCode| {
+            handle_key_event(state, KeyEvent::new(code, KeyModifiers::NONE))
+        };
+
+        // 无行情时 Enter 不打开
+        let mut state = DashboardState::new(5);
+        press(&mut state, KeyCode::Enter);
+        assert!(!state.detail_open);
+
+        state.update_quotes(vec![make_quote(Market::HK, "00700", "腾讯控股", 1.0)]);
+        press(&mut state, KeyCode::Enter);
+        assert!(state.detail_open);
+        press(&mut state, KeyCode::Enter);
+        assert!(!state.detail_open);
+
+        // 弹窗打开时 Esc 只关闭弹窗，不退出
+        press(&mut state, KeyCode::Enter);
+        assert!(matches!(press(&mut state, KeyCode::Esc), InputAction::None));
+        assert!(!state.detail_open);
+        assert!(matches!(press(&mut state, KeyCode::Esc), InputAction::Quit));
+    }
+
+    #[test]
+    fn test_daily_closes_for_sparkline() {
+        use crate::models::DailyKline;
+
+        let code = StockCode::new(Market::HK, "00700");
+        let klines: Vec<DailyKline> = (0..80)
+            .map(|i| DailyKline {
+                open: 100.0,
+                close: 100.0 + i as f64,
+                high: 200.0,
+                low: 90.0,
+                volume: 1000,
+                turnover: 0.0,
+                date: format!("2024-{:02}-{:02}", i / 28 + 1, i % 28 + 1),
+            })
+            .collect();
+        let mut engine = DailyAnalysisEngine::new(&AnalysisConfig::default());
+        engine.update(HashMap::from([(code.clone(), klines)]));
+
+        // 只同步自选股，且只保留最近 SPARKLINE_DAYS 根
+        let mut state = DashboardState::new(5);
+        state.update_quotes(vec![make_quote(Market::HK, "00700", "腾讯控股", 1.0)]);
+        state.update_daily_closes(&engine);
+        let closes = &state.daily_closes[&code];
+        assert_eq!(closes.len(), SPARKLINE_DAYS);
+        assert_eq!(closes[0], 120.0);
+        assert_eq!(closes[SPARKLINE_DAYS - 1], 179.0);
+
+        // 最低价映射到 1，最高价映射到满刻度
+        let data = sparkline_data(closes);
+        assert_eq!(data[0], 1);
+        assert_eq!(data[SPARKLINE_DAYS - 1], SPARKLINE_SCALE);
+        assert_eq!(sparkline_data(&[5.0, 5.0]), [SPARKLINE_SCALE / 2; 2]);
+    }
+
+    #[test]
+    fn test_filter_limits_selection() {
+        use crossterm::event::{KeyEvent, KeyModifiers};
+        let press = |state: &mut DashboardState, code: KeyCode| {
+            handle_key_event(state, KeyEvent::new(code, KeyModifiers::NONE))
+        };
+
+        let mut state = DashboardState::new(5);
+        state.sort_column = SortColumn::Code;
+        state.sort_ascending = true;
+        state.update_quotes(vec![
+            make_quote(Market::HK, "00700", "腾讯控股", 0.0),
+            make_quote(Market::HK, "09988", "阿里巴巴-W", 0.0),
+            make_quote(Market::US, "BABA", "Alibaba", 0.0),
+        ]);
+        state.selected_row = 0;
+
+        // 输入 "ba"：不区分大小写匹配 "BABA" / "Alibaba"，选中行移到第一条可见行
+        press(&mut state, KeyCode::Char('/'));
+        press(&mut state, KeyCode::Char('b'));
+        press(&mut state, KeyCode::Char('a'));
+        assert!(state.filter_editing);
+        assert_eq!(state.filter, "ba");
+        assert_eq!(state.quotes[state.selected_row].code.code, "BABA");
+
+        // 确认后 ↑↓ 只在可见行间移动（被筛掉的行不可选中）
+        press(&mut state, KeyCode::Enter);
+        assert!(!state.filter_editing);
+        press(&mut state, KeyCode::Up);
+        assert_eq!(state.quotes[state.selected_row].code.code, "BABA");
+
+        // Esc 先清除筛选，不退出
+        assert!(matches!(press(&mut state, KeyCode::Esc), InputAction::None));
+        assert!(state.filter.is_empty());
+        press(&mut state, KeyCode::Up);
+        assert_eq!(state.quotes[state.selected_row].code.code, "09988");
+    }
+
+    #[test]
+    fn test_column_config_names() {
+        // 配置名与默认列顺序一一对应
+        let columns: Vec<Column> = crate::config::UI_COLUMNS
+            .iter()
+            .map(|name| Column::from_config(name).unwrap())
+            .collect();
+        assert_eq!(columns, Column::ALL);
+        assert_eq!(Column::from_config("vwap"), None);
+    }
+
+    #[test]
+    fn test_pause_buffers_quotes_until_resume() {
+        let mut state = DashboardState::new(5);
+        state.update_quotes(vec![make_quote(Market::HK, "00700", "腾讯控股", 1.0)]);
+
+        // 暂停期间行情只暂存，同一股票保留最新一条
+        state.toggle_paused();
+        state.update_quotes(vec![make_quote(Market::HK, "00700", "腾讯控股", 2.0)]);
+        state.update_quotes(vec![
+            make_quote(Market::HK, "00700", "腾讯控股", 3.0),
+            make_quote(Market::HK, "09988", "阿里巴巴-W", -1.0),
+        ]);
+        assert_eq!(state.quotes.len(), 1);
+        assert_eq!(state.quotes[0].change_pct, 1.0);
+        assert_eq!(state.pending_quotes.len(), 2);
+
+        // 恢复时一次性应用
+        state.toggle_paused();
+        assert!(state.pending_quotes.is_empty());
+        assert_eq!(state.quotes.len(), 2);
+        assert_eq!(state.quotes[0].change_pct, 3.0);
+    }
+
+    #[test]
+    fn test_follow_signal_skipped_while_paused() {
+        let mut state = DashboardState::new(5);
+        state.follow_mode = FollowMode::Signal;
+        state.update_quotes(vec![
+            make_quote(Market::HK, "00700", "腾讯控股", 3.0),
+            make_quote(Market::HK, "09988", "阿里巴巴-W", 1.0),
+        ]);
+        let target = state.quotes[1].code.clone();
+
+        // 暂停时画面冻结，信号不改变选中行
+        state.toggle_paused();
+        state.follow_signal(&target);
+        assert_eq!(state.selected_row, 0);
+        assert!(state.follow_highlight.is_none());
+
+        state.toggle_paused();
+        state.follow_signal(&target);
+        assert_eq!(state.selected_row, 1);
+    }
+
+    #[test]
+    fn test_portfolio_summary_skips_cached_quotes() {
+        let mut state = DashboardState::new(5);
+        assert_eq!(state.portfolio_summary(), None);
+
+        let mut cached = make_quote(Market::HK, "00005", "汇丰控股", 9.0);
+        cached.source = DataSource::Cache;
+        state.update_quotes(vec![
+            make_quote(Market::HK, "00700", "腾讯控股", 3.0),
+            make_quote(Market::HK, "09988", "阿里巴巴-W", -2.0),
+            make_quote(Market::HK, "03690", "美团-W", 0.0),
+            make_quote(Market::US, "AAPL", "苹果", 1.0),
+            cached,
+        ]);
+
+        let s = state.portfolio_summary().unwrap();
+        assert_eq!((s.up, s.down, s.flat), (2, 1, 1));
+        assert!((s.avg_change_pct - 0.5).abs() < 1e-9);
+        assert_eq!(s.strongest.0.code, "00700");
+        assert_eq!(s.weakest.0.code, "09988");
+    }
+
+    #[test]
+    fn test_market_status() {
+        let mut state = DashboardState::new(5);
+        state.update_quotes(vec![
+            make_quote(Market::HK, "00700", "腾讯控股", 1.0),
+            make_quote(Market::SH, "600519", "贵州茅台", 0.5),
+            make_quote(Market::US, "AAPL", "苹果", -1.0),
+        ]);
+        // OCR 等无订阅的数据源、且未探测到无权限市场 → 不显示
+        assert_eq!(state.market_status(), None);
+
+        state.subscribed_markets = [Market::HK, Market::SH].into_iter().collect();
+        assert_eq!(state.market_status().unwrap(), "HK✓ SH✓ US✗");
+
+        state.no_permission_markets.insert(Market::US);
+        assert_eq!(state.market_status().unwrap(), "HK✓ SH✓ US✗无权限");
+    }
+
+    #[test]
+    fn test_latency_note() {
+        use chrono::TimeZone;
+
+        let mut state = DashboardState::new(5);
+        // 港股交易时段内（2024-03-12 周二 10:30 HKT）
+        let now = chrono_tz::Asia::Hong_Kong
+            .with_ymd_and_hms(2024, 3, 12, 10, 30, 0)
+            .unwrap()
+            .with_timezone(&Local);
+        let mut q = make_quote(Market::HK, "00700", "腾讯控股", 1.0);
+        q.timestamp = now - chrono::Duration::seconds(90);
+        assert_eq!(state.latency_note(&q, now), None);
+
+        state
+            .source_latency
+            .insert(DataSource::OpenApi, Duration::from_secs(900));
+        assert_eq!(state.latency_note(&q, now).unwrap().to_string(), "延15分");
+
+        // 实际滞后优先于声明的延迟
+        state.stale_after = Some(Duration::from_secs(60));
+        assert_eq!(state.latency_note(&q, now).unwrap().to_string(), "滞后1分");
+        q.timestamp = now - chrono::Duration::seconds(5);
+        assert_eq!(
+            state.latency_note(&q, now),
+            Some(LatencyNote::Delayed(Duration::from_secs(900)))
+        );
+
+        // 午休 / 收市后价格不动不算滞后
+        let lunch = now + chrono::Duration::minutes(120);
+        q.timestamp = lunch - chrono::Duration::seconds(600);
+        assert_eq!(state.latency_note(&q, lunch).unwrap().to_string(), "📴午休");
+        let after_close = now + chrono::Duration::hours(6);
+        assert_eq!(state.latency_note(&q, after_close).unwrap().to_string(), "📴休市");
+
+        // 缓存数据已灰显，不再标注
+        q.source = DataSource::Cache;
+        q.timestamp = now - chrono::Duration::seconds(600);
+        assert_eq!(state.latency_note(&q, now), None);
+        assert_eq!(state.latency_note(&q, lunch), None);
+    }
+
+    #[test]
+    fn test_change_pct_precision_fits_column() {
+        assert_eq!(format_change_pct(1.23456, 2), "+1.23%");
+        assert_eq!(format_change_pct(-0.1234, 3), "-0.123%");
+        assert_eq!(format_change_pct(5.6, 0), "+6%");
+        assert_eq!(change_pct_width(2), 9);
+        for decimals in 0..=4 {
+            // 两位整数的涨跌幅仍与下一列留出间隔
+            let text = format_change_pct(-19.99, decimals);
+            assert!(text.len() + 2 <= change_pct_width(decimals) as usize, "{}", text);
+        }
+    }
+}
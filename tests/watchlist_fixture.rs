@@ -0,0 +1,69 @@
+//! 集成测试：`qtrade watchlist` 读取仓库内的富途数据目录样例
+//!
+//! `tests/fixtures/futu_data` 模拟富途牛牛本地数据目录：
+//! - `10001/watchstockContainer.dat`：二进制 plist 自选股（"港股" + "全部" 两个分组）
+//! - `StockDB/appdatav82.db`：`Stock (ID, zh)` 名称表
+
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+const FIXTURE_DATA_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/futu_data");
+
+/// 写入指向样例目录的临时配置文件（`extra` 追加到 `[futu]` 段）
+fn write_config(tag: &str, extra: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("qtrade-it-{}-{}", tag, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("config.toml");
+    std::fs::write(&path, format!("[futu]\ndata_path = {:?}\n{}", FIXTURE_DATA_PATH, extra)).unwrap();
+    path
+}
+
+/// 以指定配置运行 `qtrade watchlist`（关闭日志，stdout 只含列表）
+fn run_watchlist(config: &Path) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_qtrade"))
+        .arg("-c")
+        .arg(config)
+        .arg("watchlist")
+        .env("RUST_LOG", "off")
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn test_watchlist_command_reads_fixture() {
+    let config = write_config("watchlist", "");
+    let output = run_watchlist(&config);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with("自选股列表 (6 只):"), "{}", stdout);
+
+    // 每行: 序号 代码 名称 缓存价格
+    let rows: Vec<Vec<&str>> = stdout
+        .lines()
+        .filter(|l| l.starts_with(|c: char| c.is_ascii_digit()))
+        .map(|l| l.split_whitespace().collect())
+        .collect();
+    assert_eq!(
+        rows,
+        [
+            vec!["1", "HK.00700", "腾讯控股", "380.20"],
+            vec!["2", "SH.600519", "贵州茅台", "1680.50"],
+            vec!["3", "SZ.300750", "宁德时代", "190.12"],
+            vec!["4", "US.AAPL", "苹果", "190.50"],
+            vec!["5", "HK.800000", "恒生指数", "17650.32"],
+            vec!["6", "HK.09988", "-"],
+        ]
+    );
+
+    std::fs::remove_dir_all(config.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn test_watchlist_command_rejects_missing_user() {
+    let config = write_config("missing-user", "user_ids = [\"99999\"]\n");
+    let output = run_watchlist(&config);
+    assert!(!output.status.success());
+
+    std::fs::remove_dir_all(config.parent().unwrap()).unwrap();
+}